@@ -18,7 +18,7 @@ use crate::{
     frame::FrameTimer,
     gpu::GpuContext,
     input::{HotKey, KeypadTracker, keycode_to_button, keycode_to_hotkey},
-    windows::{GbaRenderer, Gui, WindowSurface, draw_splash},
+    windows::{CheatsWindow, GbaRenderer, Gui, RamSearchWindow, WindowSurface, draw_splash},
 };
 
 struct RunningContent {
@@ -26,6 +26,8 @@ struct RunningContent {
     renderer: GbaRenderer,
     last_frame: Option<Vec<u32>>,
     fps_timer: FrameTimer,
+    ram_search: RamSearchWindow,
+    cheats: CheatsWindow,
 }
 
 enum ScreenContent {
@@ -100,6 +102,8 @@ impl Application {
             renderer,
             last_frame: None,
             fps_timer,
+            ram_search: RamSearchWindow::new(),
+            cheats: CheatsWindow::new(),
         }))
     }
 
@@ -133,6 +137,8 @@ impl Application {
                     renderer,
                     last_frame: None,
                     fps_timer,
+                    ram_search: RamSearchWindow::new(),
+                    cheats: CheatsWindow::new(),
                 }));
 
                 let rom_name = std::path::Path::new(&rom_path)
@@ -194,6 +200,7 @@ impl Application {
         };
 
         let is_splash = matches!(state.content, ScreenContent::Splash);
+        let mut running = state.content.running_mut();
 
         // Egui must upload its textures and vertex/index buffers BEFORE the render pass
         // begins, because begin_render_pass borrows the encoder.
@@ -203,6 +210,32 @@ impl Application {
                 if is_splash {
                     draw_splash(ui);
                 }
+                if let Some(running) = running.as_mut()
+                    && running.ram_search.show()
+                {
+                    let view = running.emulator.ram_search.lock().ok();
+                    let command = running
+                        .ram_search
+                        .draw(ui.ctx(), view.as_ref().and_then(|view| view.as_ref()));
+                    drop(view);
+                    if let Some(command) = command
+                        && let Err(e) = running.emulator.commands.send(EmulatorCommand::RamSearch(command))
+                    {
+                        tracing::error!("emulator command dropped (thread gone?): {e}");
+                    }
+                }
+                if let Some(running) = running.as_mut()
+                    && running.cheats.show()
+                {
+                    let view = running.emulator.cheats.lock().ok();
+                    let command = running.cheats.draw(ui.ctx(), view.as_deref());
+                    drop(view);
+                    if let Some(command) = command
+                        && let Err(e) = running.emulator.commands.send(EmulatorCommand::Cheat(command))
+                    {
+                        tracing::error!("emulator command dropped (thread gone?): {e}");
+                    }
+                }
             });
 
         {
@@ -298,15 +331,14 @@ impl Application {
     }
 
     fn handle_hotkey(&mut self, code: KeyCode, state: ElementState) -> bool {
-        if state != ElementState::Pressed {
-            return false;
-        }
-
         let Some(hotkey) = keycode_to_hotkey(self.modifiers, code) else {
             return false;
         };
 
+        let pressed = state == ElementState::Pressed;
         match hotkey {
+            HotKey::Rewind => self.send_emulator_command(EmulatorCommand::Rewind(pressed)),
+            _ if !pressed => return false,
             HotKey::ToggleFps => {
                 if self.windows.values().any(|w| w.content.running().is_some()) {
                     for window_state in self.windows.values_mut() {
@@ -330,6 +362,20 @@ impl Application {
             }
             //TODO: add opt-in pause-on-minimize/unfocus config
             HotKey::ToggleMaxSpeed => self.send_emulator_command(EmulatorCommand::ToggleMaxSpeed),
+            HotKey::ToggleRamSearch => {
+                if let Some(state) = self.windows.values_mut().next()
+                    && let Some(running) = state.content.running_mut()
+                {
+                    *running.ram_search.show_mut() ^= true;
+                }
+            }
+            HotKey::ToggleCheats => {
+                if let Some(state) = self.windows.values_mut().next()
+                    && let Some(running) = state.content.running_mut()
+                {
+                    *running.cheats.show_mut() ^= true;
+                }
+            }
             HotKey::Reset => {
                 self.send_emulator_command(EmulatorCommand::Reset);
                 if let Some(state) = self.windows.values_mut().next()
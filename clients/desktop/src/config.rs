@@ -1,7 +1,11 @@
-use std::{fs, io, path::PathBuf};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use etcetera::{BaseStrategy, choose_base_strategy};
-use ironboyadvance::System;
+use ironboyadvance::{CheatFile, DEFAULT_FLUSH_INTERVAL, DirectorySaveStorage, System};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -24,6 +28,18 @@ pub struct Config {
     pub bios_path: Option<String>,
     pub gb_boot_rom: Option<String>,
     pub gbc_boot_rom: Option<String>,
+    pub saves_directory: Option<String>,
+    pub save_backups: Option<usize>,
+    pub save_flush_interval_ms: Option<u64>,
+    /// Localhost port gdb attaches to. Set from the command line for one session, so it is never saved.
+    #[serde(skip)]
+    pub gdb_port: Option<u16>,
+    /// Where the instruction trace goes, also only set from the command line.
+    #[serde(skip)]
+    pub trace_path: Option<PathBuf>,
+    /// Coverage bitmap the session adds to, written back when the emulator stops.
+    #[serde(skip)]
+    pub coverage_path: Option<PathBuf>,
 }
 
 impl Config {
@@ -62,6 +78,35 @@ impl Config {
             System::Gbc => self.gbc_boot_rom.as_deref(),
         }
     }
+
+    /// Saves go to the configured directory when there is one, otherwise next to the rom.
+    pub fn save_storage(&self, rom_path: &str) -> DirectorySaveStorage {
+        let storage = match &self.saves_directory {
+            Some(directory) => {
+                let name = Path::new(rom_path).file_stem().unwrap_or_default().to_string_lossy();
+                DirectorySaveStorage::new(directory, &name)
+            }
+            None => DirectorySaveStorage::beside_rom(rom_path),
+        };
+
+        let flush_interval = self
+            .save_flush_interval_ms
+            .map_or(DEFAULT_FLUSH_INTERVAL, Duration::from_millis);
+        storage
+            .with_flush_interval(flush_interval)
+            .with_backups(self.save_backups.unwrap_or(0))
+    }
+
+    /// Cheats are kept alongside the saves.
+    pub fn cheat_file(&self, rom_path: &str) -> CheatFile {
+        match &self.saves_directory {
+            Some(directory) => {
+                let name = Path::new(rom_path).file_stem().unwrap_or_default().to_string_lossy();
+                CheatFile::new(directory, &name)
+            }
+            None => CheatFile::beside_rom(rom_path),
+        }
+    }
 }
 
 fn config_path() -> Result<PathBuf, ConfigError> {
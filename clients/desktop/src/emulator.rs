@@ -1,39 +1,228 @@
 use std::{
     fs, io,
+    path::Path,
     sync::{
-        Arc,
+        Arc, Mutex,
         atomic::{AtomicU16, Ordering},
         mpsc::{self, Receiver, Sender, TryRecvError},
     },
-    thread,
+    thread::{self, JoinHandle},
 };
 
 use chrono::Local;
-use ironboyadvance::{BootError, boot, detect_system, system_info};
+use ironboyadvance::{
+    BootError, Cheat, CheatError, CheatFile, Coverage, Emulator, GdbStub, RamSearch, RewindBuffer, SearchFilter,
+    SearchOptions, SearchResult, SymbolTable, TraceOptions, Tracer, boot, detect_system, system_info,
+};
 use ringbuf::traits::Producer;
 
 use crate::{DesktopError, audio, config::Config, frame::FrameTimer, input::KEYPAD_IDLE};
 
+const REWIND_INTERVAL_FRAMES: usize = 2;
+const REWIND_CAPACITY_BYTES: usize = 256 * 1024 * 1024;
+/// Candidates the RAM search window lists, the rest are only counted.
+const RAM_SEARCH_RESULTS: usize = 500;
+
 fn current_unix_seconds() -> u64 {
     Local::now().naive_local().and_utc().timestamp().max(0) as u64
 }
 
+/// The rom's cheat file as it was read, with the user's changes merged in. Entries the system rejects stay
+/// in the file, and the flags written back are the ones the user set rather than what the engine turned off.
+struct CheatList {
+    file: CheatFile,
+    cheats: Vec<Cheat>,
+    /// The entry in `cheats` behind each of the system's cheats.
+    entries: Vec<usize>,
+    /// Whether the file could be read, so a broken file is not overwritten unless the cheat list is changed.
+    readable: bool,
+}
+
+impl CheatList {
+    fn new(file: CheatFile) -> Self {
+        CheatList {
+            file,
+            cheats: Vec::new(),
+            entries: Vec::new(),
+            readable: false,
+        }
+    }
+
+    /// Adds the cheats stored for the rom to the system.
+    fn load(&mut self, system: &mut dyn Emulator) {
+        self.entries.clear();
+        self.cheats = match self.file.load() {
+            Ok(cheats) => cheats,
+            Err(e) => {
+                tracing::error!("failed to read cheats {}: {e}", self.file.path().display());
+                self.readable = false;
+                return;
+            }
+        };
+        self.readable = true;
+        for (entry, cheat) in self.cheats.iter().enumerate() {
+            match system.add_cheat(cheat.clone()) {
+                Ok(_) => self.entries.push(entry),
+                Err(e) => tracing::error!("skipping cheat {}: {e}", cheat.name),
+            }
+        }
+    }
+
+    fn store(&self) {
+        if !self.readable {
+            return;
+        }
+        if let Err(e) = self.file.store(&self.cheats) {
+            tracing::error!("failed to write cheats {}: {e}", self.file.path().display());
+        }
+    }
+
+    /// Changes the cheat list and writes it back to the cheat file, so the change outlives the session.
+    fn change(&mut self, system: &mut dyn Emulator, command: CheatCommand) -> Result<(), CheatError> {
+        match command {
+            CheatCommand::Add(cheat) => {
+                system.add_cheat(cheat.clone())?;
+                self.cheats.push(cheat);
+                self.entries.push(self.cheats.len() - 1);
+            }
+            CheatCommand::Remove(index) => {
+                system.remove_cheat(index)?;
+                let removed = self.entries.remove(index);
+                self.cheats.remove(removed);
+                self.entries
+                    .iter_mut()
+                    .filter(|entry| **entry > removed)
+                    .for_each(|entry| *entry -= 1);
+            }
+            CheatCommand::SetEnabled(index, enabled) => {
+                system.set_cheat_enabled(index, enabled)?;
+                self.cheats[self.entries[index]].enabled = enabled;
+            }
+        }
+        self.readable = true;
+        self.store();
+        Ok(())
+    }
+}
+
+fn load_symbols(rom_path: &str) -> SymbolTable {
+    let Some(path) = SymbolTable::beside_rom(rom_path) else {
+        return SymbolTable::default();
+    };
+    SymbolTable::load(&path).unwrap_or_else(|e| {
+        tracing::error!("failed to read symbols {}: {e}", path.display());
+        SymbolTable::default()
+    })
+}
+
+fn load_coverage(path: &Path, rom_size: usize) -> Coverage {
+    match path.exists() {
+        true => Coverage::load(path, rom_size).unwrap_or_else(|e| {
+            tracing::error!("failed to read coverage {}: {e}", path.display());
+            Coverage::new(rom_size)
+        }),
+        false => Coverage::new(rom_size),
+    }
+}
+
+/// Writes out the trace and coverage taken from a system that is shutting down.
+fn finish_recording(tracer: Option<Tracer>, coverage: Option<Coverage>, config: &Config) {
+    if let Some(tracer) = tracer
+        && let Err(e) = tracer.finish()
+    {
+        tracing::error!("failed to finish trace: {e}");
+    }
+    if let (Some(coverage), Some(path)) = (coverage, &config.coverage_path)
+        && let Err(e) = coverage.save(path)
+    {
+        tracing::error!("failed to save coverage {}: {e}", path.display());
+    }
+}
+
+pub enum RamSearchCommand {
+    Start(SearchOptions),
+    Filter(SearchFilter),
+    Stop,
+}
+
+pub enum CheatCommand {
+    Add(Cheat),
+    Remove(usize),
+    SetEnabled(usize, bool),
+}
+
 pub enum EmulatorCommand {
     Reset,
     TogglePause,
     ToggleMaxSpeed,
+    Rewind(bool),
+    RamSearch(RamSearchCommand),
+    Cheat(CheatCommand),
+    Quit,
+}
+
+/// What the cheats window shows, refreshed whenever the cheat list changes.
+pub struct CheatsView {
+    pub cheats: Vec<Cheat>,
+    pub error: Option<String>,
+}
+
+fn publish_cheats(view: &Mutex<CheatsView>, system: &dyn Emulator, error: Option<String>) {
+    if let Ok(mut view) = view.lock() {
+        *view = CheatsView {
+            cheats: system.cheats().to_vec(),
+            error,
+        };
+    }
+}
+
+/// What the RAM search window shows, refreshed every frame while a search is running.
+pub struct RamSearchView {
+    pub options: SearchOptions,
+    pub candidates: usize,
+    pub snapshots: usize,
+    pub results: Vec<SearchResult>,
+}
+
+fn publish_ram_search(view: &Mutex<Option<RamSearchView>>, search: Option<&RamSearch>, system: &dyn Emulator) {
+    let latest = search.map(|search| RamSearchView {
+        options: search.options(),
+        candidates: search.candidates().len(),
+        snapshots: search.snapshot_count(),
+        results: search.results(system, RAM_SEARCH_RESULTS),
+    });
+    if let Ok(mut view) = view.lock() {
+        *view = latest;
+    }
 }
 
 pub struct EmulatorHandle {
     pub keypad: Arc<AtomicU16>,
     pub frames: Receiver<Vec<u32>>,
     pub commands: Sender<EmulatorCommand>,
+    pub ram_search: Arc<Mutex<Option<RamSearchView>>>,
+    pub cheats: Arc<Mutex<CheatsView>>,
     pub viewport_width: usize,
     pub viewport_height: usize,
     pub fps: f32,
+    thread: Option<JoinHandle<()>>,
     _audio_stream: Option<cpal::Stream>,
 }
 
+/// Stops the emulator thread and waits for it, so the save file, cheats, trace and coverage it writes on
+/// the way out are on disk before the window closes or another rom takes its place.
+impl Drop for EmulatorHandle {
+    fn drop(&mut self) {
+        // A thread that already stopped has dropped its receiver, which is fine, there is nothing to stop.
+        let _ = self.commands.send(EmulatorCommand::Quit);
+        if let Some(thread) = self.thread.take()
+            && thread.join().is_err()
+        {
+            tracing::error!("emulator thread panicked");
+        }
+    }
+}
+
 pub(crate) fn read_rom(path: &str) -> io::Result<Vec<u8>> {
     fs::read(path)
 }
@@ -68,14 +257,56 @@ pub fn spawn(
         None => (None, None, None),
     };
 
+    let rom_size = rom_buffer.len();
     let emu_keypad = keypad.clone();
-    thread::spawn(move || {
-        let mut system = boot(kind, &rom_path, rom_buffer, bios_buffer, current_unix_seconds(), show_logs)
-            .unwrap_or_else(|e| panic!("failed to initialize emulator: {e}"));
+    let ram_search_view = Arc::new(Mutex::new(None));
+    let emu_ram_search_view = ram_search_view.clone();
+    let cheats_view = Arc::new(Mutex::new(CheatsView {
+        cheats: Vec::new(),
+        error: None,
+    }));
+    let emu_cheats_view = cheats_view.clone();
+    let thread = thread::spawn(move || {
+        let mut system = boot(
+            kind,
+            rom_buffer,
+            bios_buffer,
+            Box::new(config.save_storage(&rom_path)),
+            current_unix_seconds(),
+            show_logs,
+        )
+        .unwrap_or_else(|e| panic!("failed to initialize emulator: {e}"));
+        let mut cheat_list = CheatList::new(config.cheat_file(&rom_path));
+        cheat_list.load(system.as_mut());
+        publish_cheats(&emu_cheats_view, system.as_ref(), None);
+        if let Some(path) = &config.trace_path {
+            match Tracer::create(path, TraceOptions::default()) {
+                Ok(tracer) => {
+                    system.start_trace(tracer.with_symbols(load_symbols(&rom_path)));
+                }
+                Err(e) => tracing::error!("failed to create trace {}: {e}", path.display()),
+            }
+        }
+        if let Some(path) = &config.coverage_path {
+            system.start_coverage(load_coverage(path, rom_size));
+        }
         let mut overshoot = 0;
         let mut frame_timer = FrameTimer::new(fps);
         let mut paused = false;
         let mut turbo = false;
+        let mut rewinding = false;
+        let mut rewind = RewindBuffer::new(REWIND_INTERVAL_FRAMES, REWIND_CAPACITY_BYTES);
+        let mut ram_search: Option<RamSearch> = None;
+        let mut gdb = config.gdb_port.and_then(|port| match GdbStub::bind(port) {
+            Ok(stub) => {
+                tracing::info!("waiting for gdb on localhost:{}", stub.port());
+                Some(stub)
+            }
+            Err(e) => {
+                tracing::error!("failed to start gdb stub on port {port}: {e}");
+                None
+            }
+        });
 
         'frame: loop {
             'commands: loop {
@@ -88,6 +319,22 @@ pub fn spawn(
                         turbo = !turbo;
                         tracing::info!("max_speed {}", if turbo { "on" } else { "off" });
                     }
+                    Ok(EmulatorCommand::Rewind(held)) => rewinding = held,
+                    Ok(EmulatorCommand::RamSearch(command)) => match command {
+                        RamSearchCommand::Start(options) => ram_search = Some(RamSearch::new(system.as_ref(), options)),
+                        RamSearchCommand::Filter(filter) => {
+                            if let Some(search) = ram_search.as_mut()
+                                && let Err(e) = search.filter(system.as_ref(), filter)
+                            {
+                                tracing::error!("ram search filter failed: {e}");
+                            }
+                        }
+                        RamSearchCommand::Stop => ram_search = None,
+                    },
+                    Ok(EmulatorCommand::Cheat(command)) => {
+                        let error = cheat_list.change(system.as_mut(), command).err();
+                        publish_cheats(&emu_cheats_view, system.as_ref(), error.map(|e| e.to_string()));
+                    }
                     Ok(EmulatorCommand::Reset) => {
                         let rom = match read_rom(&rom_path) {
                             Ok(bytes) => bytes,
@@ -107,22 +354,72 @@ pub fn spawn(
                                 continue 'commands;
                             }
                         };
-                        match boot(reset_kind, &rom_path, rom, bios, current_unix_seconds(), show_logs) {
-                            Ok(new_system) => {
-                                system = new_system;
-                                overshoot = 0;
-                                frame_timer = FrameTimer::new(fps);
-                                tracing::info!("emulator reset");
+                        // The old system hands its last save writes to the save file as it drops, so it has to go
+                        // before the new one reads the save back.
+                        let tracer = system.stop_trace();
+                        let coverage = system.stop_coverage();
+                        drop(system);
+                        system = match boot(
+                            reset_kind,
+                            rom,
+                            bios,
+                            Box::new(config.save_storage(&rom_path)),
+                            current_unix_seconds(),
+                            show_logs,
+                        ) {
+                            Ok(new_system) => new_system,
+                            Err(e) => {
+                                tracing::error!("reset failed building emulator: {e}");
+                                finish_recording(tracer, coverage, &config);
+                                return;
                             }
-                            Err(e) => tracing::error!("reset failed building emulator: {e}"),
+                        };
+                        if let Some(tracer) = tracer {
+                            system.start_trace(tracer);
+                        }
+                        if let Some(coverage) = coverage {
+                            system.start_coverage(coverage);
                         }
+                        cheat_list.load(system.as_mut());
+                        publish_cheats(&emu_cheats_view, system.as_ref(), None);
+                        rewind.clear();
+                        overshoot = 0;
+                        frame_timer = FrameTimer::new(fps);
+                        tracing::info!("emulator reset");
                     }
+                    Ok(EmulatorCommand::Quit) => break 'frame,
                     Err(TryRecvError::Empty) => break 'commands,
-                    Err(TryRecvError::Disconnected) => return,
+                    Err(TryRecvError::Disconnected) => break 'frame,
                 }
             }
 
-            if !paused {
+            // gdb keeps the system halted between its stops and continues.
+            let halted = match gdb.as_mut().map(|stub| stub.poll(system.as_mut())) {
+                Some(Ok(running)) => !running,
+                Some(Err(e)) => {
+                    tracing::error!("gdb stub stopped: {e}");
+                    gdb = None;
+                    false
+                }
+                None => false,
+            };
+            let paused = paused || halted;
+
+            if !paused && rewinding {
+                match rewind.rewind(system.as_mut()) {
+                    Ok(true) => {
+                        overshoot = 0;
+                        if frame_tx.send(system.frame_buffer().to_vec()).is_err() {
+                            break 'frame;
+                        }
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        tracing::error!("rewind failed: {e}");
+                        rewind.clear();
+                    }
+                }
+            } else if !paused {
                 system.handle_pressed_buttons(emu_keypad.load(Ordering::Relaxed));
                 overshoot = system.run(cycles_per_frame, overshoot);
 
@@ -135,26 +432,107 @@ pub fn spawn(
                     }
                 }
                 system.clear_audio_buffer();
+                rewind.frame_completed(system.as_ref());
 
                 if frame_tx.send(system.frame_buffer().to_vec()).is_err() {
                     break 'frame;
                 }
             }
 
+            publish_ram_search(&emu_ram_search_view, ram_search.as_ref(), system.as_ref());
+
             if !turbo || paused {
                 frame_timer.slow_frame();
             }
             frame_timer.count_frame();
         }
+
+        cheat_list.store();
+        finish_recording(system.stop_trace(), system.stop_coverage(), &config);
     });
 
     Ok(EmulatorHandle {
         keypad,
         frames: frame_rx,
         commands: command_tx,
+        ram_search: ram_search_view,
+        cheats: cheats_view,
         viewport_width,
         viewport_height,
         fps,
+        thread: Some(thread),
         _audio_stream: audio_stream,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use ironboyadvance::{CheatFormat, MemorySaveStorage, System};
+
+    use super::*;
+
+    fn gba() -> Box<dyn Emulator> {
+        boot(
+            System::Gba,
+            vec![0; 0x400],
+            Vec::new(),
+            Box::new(MemorySaveStorage::new()),
+            0,
+            false,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn cheat_changes_are_written_back_to_the_cheat_file() {
+        let directory = std::env::temp_dir().join(format!("ironboyadvance-cheats-{}", std::process::id()));
+        let cheat_file = CheatFile::new(&directory, "game");
+        let mut cheat_list = CheatList::new(CheatFile::new(&directory, "game"));
+        let mut system = gba();
+        cheat_list.load(system.as_mut());
+        assert!(cheat_list.readable, "a missing file reads as no cheats");
+
+        let cheat = Cheat::new("Fill", CheatFormat::CodeBreaker, "82000000 0063\n");
+        cheat_list.change(system.as_mut(), CheatCommand::Add(cheat.clone())).unwrap();
+        cheat_list.change(system.as_mut(), CheatCommand::Add(cheat.clone())).unwrap();
+        cheat_list
+            .change(system.as_mut(), CheatCommand::SetEnabled(0, false))
+            .unwrap();
+        cheat_list.change(system.as_mut(), CheatCommand::Remove(1)).unwrap();
+        assert!(cheat_list.change(system.as_mut(), CheatCommand::Remove(5)).is_err());
+
+        let mut next_session = gba();
+        let mut next_list = CheatList::new(CheatFile::new(&directory, "game"));
+        next_list.load(next_session.as_mut());
+        assert!(next_list.readable);
+        assert_eq!(next_session.cheats(), system.cheats());
+        assert!(!next_session.cheats()[0].enabled);
+        assert_eq!(cheat_file.load().unwrap(), system.cheats());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn rejected_cheats_and_engine_flags_are_not_written_back() {
+        let directory = std::env::temp_dir().join(format!("ironboyadvance-rejected-cheats-{}", std::process::id()));
+        let cheat_file = CheatFile::new(&directory, "game");
+        let unsupported = Cheat::new("Genie", CheatFormat::GameGenie, "ABC-DEF-GHI\n");
+        let fill = Cheat::new("Fill", CheatFormat::CodeBreaker, "82000000 0063\n");
+        let infinite = Cheat::new("Infinite", CheatFormat::CodeBreaker, "82000002 0099\n");
+        cheat_file
+            .store(&[fill.clone(), unsupported.clone(), infinite.clone()])
+            .unwrap();
+
+        let mut cheat_list = CheatList::new(CheatFile::new(&directory, "game"));
+        let mut system = gba();
+        cheat_list.load(system.as_mut());
+        assert_eq!(system.cheats(), [fill.clone(), infinite.clone()]);
+
+        // Turning the cheat off behind the list's back stands in for the engine disabling it.
+        system.set_cheat_enabled(1, false).unwrap();
+        cheat_list.change(system.as_mut(), CheatCommand::Remove(0)).unwrap();
+        cheat_list.change(system.as_mut(), CheatCommand::Add(fill.clone())).unwrap();
+        cheat_list.store();
+        assert_eq!(cheat_file.load().unwrap(), [unsupported, infinite, fill]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
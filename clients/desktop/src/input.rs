@@ -6,14 +6,17 @@ use std::sync::{
 use ironboyadvance::KeypadButton;
 use winit::keyboard::{KeyCode, ModifiersState};
 
-pub const KEYPAD_IDLE: u16 = 0x03FF;
+pub use ironboyadvance::KEYPAD_IDLE;
 
 pub enum HotKey {
     Reset,
     TogglePause,
     ToggleMaxSpeed,
+    Rewind,
     ToggleFps,
     Screenshot,
+    ToggleRamSearch,
+    ToggleCheats,
 }
 
 pub fn keycode_to_hotkey(modifiers: ModifiersState, code: KeyCode) -> Option<HotKey> {
@@ -22,8 +25,11 @@ pub fn keycode_to_hotkey(modifiers: ModifiersState, code: KeyCode) -> Option<Hot
         (true, KeyCode::KeyR) => Some(HotKey::Reset),
         (true, KeyCode::KeyP) => Some(HotKey::TogglePause),
         (false, KeyCode::F2) => Some(HotKey::ToggleMaxSpeed),
+        (false, KeyCode::Backquote) => Some(HotKey::Rewind),
         (false, KeyCode::F3) => Some(HotKey::ToggleFps),
         (false, KeyCode::F4) => Some(HotKey::Screenshot),
+        (false, KeyCode::F5) => Some(HotKey::ToggleRamSearch),
+        (false, KeyCode::F6) => Some(HotKey::ToggleCheats),
         _ => None,
     }
 }
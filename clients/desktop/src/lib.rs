@@ -1,4 +1,7 @@
-use std::{io, path::Path};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
 use winit::event_loop::EventLoop;
 
@@ -32,10 +35,20 @@ pub enum DesktopError {
     BootError(#[from] ironboyadvance::BootError),
 }
 
-pub fn run(rom_path: Option<String>, bios_path: Option<String>, show_logs: bool) -> Result<(), DesktopError> {
+pub fn run(
+    rom_path: Option<String>,
+    bios_path: Option<String>,
+    show_logs: bool,
+    gdb_port: Option<u16>,
+    trace_path: Option<PathBuf>,
+    coverage_path: Option<PathBuf>,
+) -> Result<(), DesktopError> {
     let _log_guard = if show_logs { Some(initialize_logger()) } else { None };
 
     let mut config = Config::load().unwrap_or_default();
+    config.gdb_port = gdb_port;
+    config.trace_path = trace_path;
+    config.coverage_path = coverage_path;
 
     let rom_buffer = rom_path.as_deref().map(emulator::read_rom).transpose()?;
     let kind = rom_buffer.as_deref().and_then(detect_system);
@@ -65,5 +78,5 @@ pub fn run(rom_path: Option<String>, bios_path: Option<String>, show_logs: bool)
     let event_loop = EventLoop::new()?;
     event_loop.run_app(&mut app)?;
 
-    std::process::exit(0);
+    Ok(())
 }
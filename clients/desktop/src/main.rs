@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::{ArgAction, Parser};
 
 #[derive(Parser)]
@@ -9,10 +11,19 @@ struct DesktopCli {
     bios: Option<String>,
     #[arg(short, long, action = ArgAction::SetTrue, required = false)]
     logs: bool,
+    /// Wait for gdb on this localhost port, e.g. `target remote :2345` from arm-none-eabi-gdb.
+    #[arg(long)]
+    gdb: Option<u16>,
+    /// Log every executed instruction to this gzip compressed file.
+    #[arg(long)]
+    trace: Option<PathBuf>,
+    /// Record which rom bytes run as code or are read as data, adding to this bitmap if it exists.
+    #[arg(long)]
+    coverage: Option<PathBuf>,
 }
 
 fn main() -> Result<(), desktop::DesktopError> {
     let cli = DesktopCli::parse();
-    desktop::run(cli.rom, cli.bios, cli.logs)?;
+    desktop::run(cli.rom, cli.bios, cli.logs, cli.gdb, cli.trace, cli.coverage)?;
     Ok(())
 }
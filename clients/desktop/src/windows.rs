@@ -1,10 +1,14 @@
+mod cheats;
 mod gba;
 mod gui;
 mod overlay;
+mod ram_search;
 mod splash;
 mod surface;
 
+pub use cheats::CheatsWindow;
 pub use gba::GbaRenderer;
 pub use gui::Gui;
+pub use ram_search::RamSearchWindow;
 pub use splash::draw_splash;
 pub use surface::WindowSurface;
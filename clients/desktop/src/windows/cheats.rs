@@ -0,0 +1,80 @@
+use egui::{Color32, ComboBox, Grid, RichText, ScrollArea, TextEdit, Window};
+use getset::{CopyGetters, MutGetters};
+use ironboyadvance::{Cheat, CheatFormat};
+
+use crate::emulator::{CheatCommand, CheatsView};
+
+/// Lists the game's cheats and adds new ones. The emulator thread applies the changes and writes them to
+/// the cheat file, this only shows the latest [`CheatsView`].
+#[derive(CopyGetters, MutGetters)]
+pub struct CheatsWindow {
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    show: bool,
+    name: String,
+    format: CheatFormat,
+    code: String,
+}
+
+impl CheatsWindow {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            name: String::new(),
+            format: CheatFormat::GameShark,
+            code: String::new(),
+        }
+    }
+
+    /// Draws the window, returning the change its controls asked for.
+    pub fn draw(&mut self, ctx: &egui::Context, view: Option<&CheatsView>) -> Option<CheatCommand> {
+        let mut command = None;
+        let mut show = self.show;
+        Window::new("Cheats").open(&mut show).show(ctx, |ui| {
+            if let Some(view) = view {
+                ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    Grid::new("cheats_list").striped(true).show(ui, |ui| {
+                        for (index, cheat) in view.cheats.iter().enumerate() {
+                            let mut enabled = cheat.enabled;
+                            if ui.checkbox(&mut enabled, &cheat.name).changed() {
+                                command = Some(CheatCommand::SetEnabled(index, enabled));
+                            }
+                            ui.label(cheat.format.name());
+                            if ui.button("Remove").clicked() {
+                                command = Some(CheatCommand::Remove(index));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+                if let Some(error) = &view.error {
+                    ui.label(RichText::new(error).color(Color32::RED));
+                }
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut self.name).hint_text("name").desired_width(140.0));
+                ComboBox::from_id_salt("cheat_format")
+                    .selected_text(self.format.name())
+                    .show_ui(ui, |ui| {
+                        for format in CheatFormat::ALL {
+                            ui.selectable_value(&mut self.format, format, format.name());
+                        }
+                    });
+            });
+            ui.add(
+                TextEdit::multiline(&mut self.code)
+                    .hint_text("one code per line")
+                    .code_editor(),
+            );
+            let ready = !self.name.trim().is_empty() && !self.code.trim().is_empty();
+            if ui.add_enabled(ready, egui::Button::new("Add")).clicked() {
+                let cheat = Cheat::new(self.name.trim(), self.format, std::mem::take(&mut self.code));
+                self.name.clear();
+                command = Some(CheatCommand::Add(cheat));
+            }
+        });
+        self.show = show;
+        command
+    }
+}
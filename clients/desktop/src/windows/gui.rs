@@ -65,7 +65,7 @@ impl Gui {
         queue: &wgpu::Queue,
         encoder: &mut wgpu::CommandEncoder,
         screen: &ScreenDescriptor,
-        content: impl Fn(&mut egui::Ui),
+        mut content: impl FnMut(&mut egui::Ui),
     ) -> PreparedFrame {
         let raw_input = self.state.take_egui_input(window);
         let fps_overlay = &self.fps_overlay;
@@ -0,0 +1,184 @@
+use egui::{Color32, ComboBox, Grid, RichText, ScrollArea, TextEdit, Window};
+use getset::{CopyGetters, MutGetters};
+use ironboyadvance::{Comparison, MemoryAccessWidth, SearchFilter, SearchOptions};
+
+use crate::emulator::{RamSearchCommand, RamSearchView};
+
+const WIDTHS: [(MemoryAccessWidth, &str); 3] = [
+    (MemoryAccessWidth::Byte, "8-bit"),
+    (MemoryAccessWidth::HalfWord, "16-bit"),
+    (MemoryAccessWidth::Word, "32-bit"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FilterKind {
+    Value,
+    Previous,
+    ChangedBy,
+    Snapshot,
+}
+
+impl FilterKind {
+    const ALL: [FilterKind; 4] = [
+        FilterKind::Value,
+        FilterKind::Previous,
+        FilterKind::ChangedBy,
+        FilterKind::Snapshot,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            FilterKind::Value => "value",
+            FilterKind::Previous => "previous value",
+            FilterKind::ChangedBy => "changed by",
+            FilterKind::Snapshot => "snapshot",
+        }
+    }
+}
+
+/// Cheat finder over work RAM. The search itself runs on the emulator thread, this only edits the
+/// filters and shows the latest [`RamSearchView`].
+#[derive(CopyGetters, MutGetters)]
+pub struct RamSearchWindow {
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    show: bool,
+    options: SearchOptions,
+    kind: FilterKind,
+    comparison: Comparison,
+    operand: String,
+    error: Option<String>,
+}
+
+impl RamSearchWindow {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            options: SearchOptions::default(),
+            kind: FilterKind::Value,
+            comparison: Comparison::Equal,
+            operand: String::new(),
+            error: None,
+        }
+    }
+
+    /// Draws the window, returning the command its buttons asked for.
+    pub fn draw(&mut self, ctx: &egui::Context, view: Option<&RamSearchView>) -> Option<RamSearchCommand> {
+        let mut command = None;
+        let mut show = self.show;
+        Window::new("RAM search").open(&mut show).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let width_name = |width| WIDTHS.iter().find(|(w, _)| *w == width).map_or("", |(_, name)| name);
+                ComboBox::from_id_salt("ram_search_width")
+                    .selected_text(width_name(self.options.width))
+                    .show_ui(ui, |ui| {
+                        for (width, name) in WIDTHS {
+                            ui.selectable_value(&mut self.options.width, width, name);
+                        }
+                    });
+                ui.checkbox(&mut self.options.signed, "signed");
+                ui.checkbox(&mut self.options.aligned, "aligned");
+                if ui.button("New search").clicked() {
+                    command = Some(RamSearchCommand::Start(self.options));
+                }
+                if view.is_some() && ui.button("Clear").clicked() {
+                    command = Some(RamSearchCommand::Stop);
+                }
+            });
+
+            let Some(view) = view else {
+                ui.label("Start a search to snapshot RAM.");
+                return;
+            };
+            ui.label(format!("{} candidates, {} snapshots", view.candidates, view.snapshots));
+
+            ui.horizontal(|ui| {
+                ComboBox::from_id_salt("ram_search_filter")
+                    .selected_text(self.kind.label())
+                    .show_ui(ui, |ui| {
+                        for kind in FilterKind::ALL {
+                            ui.selectable_value(&mut self.kind, kind, kind.label());
+                        }
+                    });
+                if self.kind != FilterKind::ChangedBy {
+                    ComboBox::from_id_salt("ram_search_comparison")
+                        .selected_text(self.comparison.symbol())
+                        .show_ui(ui, |ui| {
+                            for comparison in Comparison::ALL {
+                                ui.selectable_value(&mut self.comparison, comparison, comparison.symbol());
+                            }
+                        });
+                }
+                if self.kind != FilterKind::Previous {
+                    ui.add(TextEdit::singleline(&mut self.operand).desired_width(80.0));
+                }
+                if ui.button("Filter").clicked() {
+                    match self.filter() {
+                        Ok(filter) => {
+                            self.error = None;
+                            command = Some(RamSearchCommand::Filter(filter));
+                        }
+                        Err(e) => self.error = Some(e),
+                    }
+                }
+            });
+            if let Some(error) = &self.error {
+                ui.label(RichText::new(error).color(Color32::RED));
+            }
+
+            let digits = view.options.size() as usize * 2;
+            ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                Grid::new("ram_search_results").striped(true).show(ui, |ui| {
+                    ui.strong("Address");
+                    ui.strong("Value");
+                    ui.strong("Previous");
+                    ui.end_row();
+                    for result in &view.results {
+                        ui.monospace(format!("{:08X}", result.address));
+                        ui.monospace(format!("{} (0x{:0digits$X})", result.value, mask(result.value, digits)));
+                        ui.monospace(result.previous.to_string());
+                        ui.end_row();
+                    }
+                });
+                if view.candidates > view.results.len() {
+                    ui.label(format!("{} more not shown", view.candidates - view.results.len()));
+                }
+            });
+        });
+        self.show = show;
+        command
+    }
+
+    fn filter(&self) -> Result<SearchFilter, String> {
+        let operand = || parse_number(&self.operand).ok_or_else(|| format!("not a number: {}", self.operand));
+        Ok(match self.kind {
+            FilterKind::Value => SearchFilter::Value(self.comparison, operand()?),
+            FilterKind::Previous => SearchFilter::Previous(self.comparison),
+            FilterKind::ChangedBy => SearchFilter::ChangedBy(operand()?),
+            FilterKind::Snapshot => {
+                let index = usize::try_from(operand()?).map_err(|_| "snapshots are numbered from 0".to_string())?;
+                SearchFilter::Snapshot(index, self.comparison)
+            }
+        })
+    }
+}
+
+/// Decimal, or hex with a `0x` prefix, either one optionally negative.
+fn parse_number(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => digits.parse().ok()?,
+    };
+    match negative {
+        true => Some(-value),
+        false => Some(value),
+    }
+}
+
+fn mask(value: i64, digits: usize) -> u64 {
+    value as u64 & (u64::MAX >> (64 - digits * 4))
+}
@@ -0,0 +1,517 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    thread,
+    time::Duration,
+};
+
+use clap::Parser;
+use ironboyadvance::{
+    Architecture, BootError, BreakReason, Coverage, CoverageError, CoverageKind, DirectorySaveStorage, GdbError, GdbStub,
+    Headless, InstructionSet, MemorySaveStorage, Movie, MovieError, MovieSession, ProfileGrouping, Profiler,
+    SaveConvertError, SaveConvertOptions, SaveFormat, SaveStorage, SymbolError, SymbolTable, System, TraceFormat,
+    TraceOptions, TraceTrigger, Tracer, export_save, import_save, linear_sweep, write_coverage_disassembly,
+};
+use thiserror::Error;
+
+const DEFAULT_FRAMES: usize = 600;
+const DUMP_ROW_LENGTH: usize = 16;
+const PROFILE_REPORT_LENGTH: usize = 20;
+const GDB_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+#[derive(Error, Debug)]
+enum HeadlessError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to boot emulator: {0}")]
+    Boot(#[from] BootError),
+    #[error("Failed to play movie: {0}")]
+    Movie(#[from] MovieError),
+    #[error("Failed to write screenshot: {0}")]
+    Screenshot(#[from] image::ImageError),
+    #[error("Failed to convert save: {0}")]
+    SaveConvert(#[from] SaveConvertError),
+    #[error("GDB stub failed: {0}")]
+    Gdb(#[from] GdbError),
+    #[error("Failed to load symbols: {0}")]
+    Symbols(#[from] SymbolError),
+    #[error("Failed to load coverage: {0}")]
+    Coverage(#[from] CoverageError),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MemoryCondition {
+    address: u32,
+    value: u8,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MemoryRange {
+    start: u32,
+    length: u32,
+}
+
+#[derive(Parser)]
+#[command(name = "Iron Boy Advance Headless")]
+struct HeadlessCli {
+    #[arg(short, long)]
+    rom: String,
+    #[arg(short, long)]
+    bios: Option<String>,
+    /// Frames to run, or the most frames to wait for an --until condition. Defaults to the movie length
+    /// when playing a movie.
+    #[arg(short, long)]
+    frames: Option<usize>,
+    /// Stop once the serial output contains this text.
+    #[arg(long)]
+    until_serial: Option<String>,
+    /// Stop once the byte at ADDRESS equals VALUE, e.g. 0x03000000=0x01.
+    #[arg(long, value_parser = parse_memory_condition)]
+    until_memory: Option<MemoryCondition>,
+    /// Play back an input movie recorded on the same rom.
+    #[arg(short, long)]
+    movie: Option<PathBuf>,
+    /// Keep battery saves in this directory. Without it saves only live in memory for the run.
+    #[arg(long)]
+    saves: Option<PathBuf>,
+    /// Start from a save made by another emulator or a cheat device (.sav, .gsv, .sps), replacing the
+    /// existing save.
+    #[arg(long)]
+    import_save: Option<PathBuf>,
+    /// Write the save at the end of the run in the format matching the file extension.
+    #[arg(long)]
+    export_save: Option<PathBuf>,
+    /// Reverse the byte order of EEPROM blocks when importing or exporting.
+    #[arg(long)]
+    swap_eeprom_blocks: bool,
+    /// Unix time the real-time clock starts at. Movies use the time they were recorded with.
+    #[arg(long, default_value_t = 0)]
+    unix_seconds: u64,
+    /// Write the final frame to this PNG file.
+    #[arg(short, long)]
+    screenshot: Option<PathBuf>,
+    /// Print the serial output.
+    #[arg(long)]
+    serial: bool,
+    /// Print every cpu register.
+    #[arg(long)]
+    registers: bool,
+    /// Print the calls and exceptions the cpu is inside of at the end of the run, naming them after symbols.
+    #[arg(long)]
+    backtrace: bool,
+    /// Print a hex dump of START:LENGTH, e.g. 0x02000000:0x100. May be repeated.
+    #[arg(long, value_parser = parse_memory_range)]
+    dump: Vec<MemoryRange>,
+    /// Wait for gdb on this localhost port and run until it detaches, instead of for a number of frames.
+    #[arg(long)]
+    gdb: Option<u16>,
+    /// Log every executed instruction to this gzip compressed file.
+    #[arg(long)]
+    trace: Option<PathBuf>,
+    /// Trace line layout: full, or reference to match mGBA on the GBA and Gameboy Doctor on the Game Boy.
+    #[arg(long, value_parser = parse_trace_format, default_value = "full")]
+    trace_format: TraceFormat,
+    /// Start tracing at pc:ADDRESS or frame:N instead of the first instruction.
+    #[arg(long, value_parser = parse_trace_trigger)]
+    trace_start: Option<TraceTrigger>,
+    /// Stop tracing at pc:ADDRESS or frame:N.
+    #[arg(long, value_parser = parse_trace_trigger)]
+    trace_stop: Option<TraceTrigger>,
+    /// Profile where the cycles go and write the collapsed stacks to this file for a flame graph.
+    #[arg(long)]
+    profile: Option<PathBuf>,
+    /// Charge cycles to every function on the call-stack, or to the symbols containing the pc.
+    #[arg(long, value_parser = parse_profile_grouping, default_value = "call-stack")]
+    profile_by: ProfileGrouping,
+    /// Record which rom bytes run as code or are read as data into this bitmap, adding to it if it exists.
+    #[arg(long)]
+    coverage: Option<PathBuf>,
+    /// Write the rom, disassembled where it ran as code, annotated with how the run used it.
+    #[arg(long)]
+    coverage_disassembly: Option<PathBuf>,
+    /// Load symbols from an .elf or RGBDS .sym file. Defaults to one named after the rom next to it.
+    #[arg(long)]
+    symbols: Option<PathBuf>,
+    /// Stop at a breakpoint on an address, label or label+offset, e.g. main or main+0x10. May be repeated.
+    #[arg(long = "break")]
+    breakpoints: Vec<String>,
+    /// Print the disassembly of LOCATION:LENGTH at the end of the run, e.g. main:0x40. May be repeated.
+    #[arg(long)]
+    disassemble: Vec<String>,
+}
+
+fn parse_number(text: &str) -> Result<u32, String> {
+    let text = text.trim();
+    let parsed = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|error| format!("invalid number {text:?}: {error}"))
+}
+
+fn parse_memory_condition(text: &str) -> Result<MemoryCondition, String> {
+    let (address, value) = text.split_once('=').ok_or("expected ADDRESS=VALUE")?;
+    let value = parse_number(value)?;
+    Ok(MemoryCondition {
+        address: parse_number(address)?,
+        value: u8::try_from(value).map_err(|_| format!("value {value:#X} does not fit in a byte"))?,
+    })
+}
+
+fn parse_memory_range(text: &str) -> Result<MemoryRange, String> {
+    let (start, length) = text.split_once(':').ok_or("expected START:LENGTH")?;
+    Ok(MemoryRange {
+        start: parse_number(start)?,
+        length: parse_number(length)?,
+    })
+}
+
+fn parse_trace_format(text: &str) -> Result<TraceFormat, String> {
+    TraceFormat::from_name(text).ok_or_else(|| format!("unknown trace format {text:?}"))
+}
+
+fn parse_profile_grouping(text: &str) -> Result<ProfileGrouping, String> {
+    ProfileGrouping::from_name(text).ok_or_else(|| format!("unknown profile grouping {text:?}"))
+}
+
+fn parse_trace_trigger(text: &str) -> Result<TraceTrigger, String> {
+    match text.split_once(':') {
+        Some(("pc", address)) => Ok(TraceTrigger::Pc(parse_number(address)?)),
+        Some(("frame", frame)) => Ok(TraceTrigger::Frame(parse_number(frame)? as u64)),
+        _ => Err("expected pc:ADDRESS or frame:N".to_string()),
+    }
+}
+
+fn dump_memory(headless: &Headless, range: MemoryRange) {
+    println!(
+        "memory {:#010X}..{:#010X}:",
+        range.start,
+        range.start.wrapping_add(range.length)
+    );
+    for row in (0..range.length).step_by(DUMP_ROW_LENGTH) {
+        let address = range.start.wrapping_add(row);
+        let bytes: Vec<String> = (row..range.length.min(row + DUMP_ROW_LENGTH as u32))
+            .map(|offset| format!("{:02X}", headless.read_memory(range.start.wrapping_add(offset))))
+            .collect();
+        println!("{address:08X}: {}", bytes.join(" "));
+    }
+}
+
+fn load_symbols(cli: &HeadlessCli) -> Result<SymbolTable, HeadlessError> {
+    match cli.symbols.clone().or_else(|| SymbolTable::beside_rom(&cli.rom)) {
+        Some(path) => Ok(SymbolTable::load(path)?),
+        None => Ok(SymbolTable::default()),
+    }
+}
+
+fn describe_address(symbols: &SymbolTable, address: u32) -> String {
+    match symbols.describe(address) {
+        Some(name) => format!("{address:#010X} <{name}>"),
+        None => format!("{address:#010X}"),
+    }
+}
+
+fn describe_break(symbols: &SymbolTable, reason: BreakReason) -> String {
+    match reason {
+        BreakReason::Breakpoint { address } => format!("breakpoint at {}", describe_address(symbols, address)),
+        BreakReason::Watchpoint { address, access, value } => {
+            format!("{access:?} of {value:#X} at {}", describe_address(symbols, address))
+        }
+        BreakReason::Interrupt(bit) => format!("interrupt {bit}"),
+        BreakReason::Dma(channel) => format!("dma {channel}"),
+        BreakReason::Step => "step".to_string(),
+    }
+}
+
+fn print_profile(profiler: &Profiler) {
+    println!(
+        "profile: {} cycles, {} in DMA, {} halted",
+        profiler.total_cycles(),
+        profiler.dma_cycles(),
+        profiler.halt_cycles()
+    );
+    println!("{:>12} {:>12}  function", "self", "inclusive");
+    for entry in profiler.report().iter().take(PROFILE_REPORT_LENGTH) {
+        println!("{:>12} {:>12}  {}", entry.self_cycles, entry.inclusive_cycles, entry.name);
+    }
+}
+
+fn disassemble(headless: &Headless, symbols: &SymbolTable, range: &str) -> Result<(), HeadlessError> {
+    let invalid = || SymbolError::InvalidAddress(range.to_string());
+    let (location, length) = range.rsplit_once(':').ok_or_else(invalid)?;
+    let start = symbols.resolve(location)?;
+    let length = parse_number(length).map_err(|_| invalid())?;
+    let architecture = match headless.kind() {
+        System::Gb | System::Gbc => Architecture::Sm83,
+        System::Gba => match symbols.symbol_at(start, None).and_then(|symbol| symbol.instruction_set) {
+            Some(InstructionSet::Thumb) => Architecture::Thumb,
+            _ => Architecture::Arm,
+        },
+    };
+
+    let code: Vec<u8> = (0..length)
+        .map(|offset| headless.read_memory(start.wrapping_add(offset)))
+        .collect();
+    println!("disassembly {start:#010X}..{:#010X}:", start.wrapping_add(length));
+    for mut instruction in linear_sweep(&code, start, architecture) {
+        if let Some(symbol) = symbols.symbol_at(instruction.address, None) {
+            println!("{}:", symbol.name);
+        }
+        symbols.annotate(&mut instruction);
+        println!("{:08X}: {}", instruction.address, instruction.text);
+    }
+    Ok(())
+}
+
+/// Runs frames while gdb lets the system run, until it detaches.
+fn serve_gdb(headless: &mut Headless, port: u16) -> Result<(), HeadlessError> {
+    let mut stub = GdbStub::bind(port)?;
+    println!("waiting for gdb on localhost:{}", stub.port());
+    while !stub.attached() {
+        stub.poll(headless.system_mut())?;
+        thread::sleep(GDB_POLL_INTERVAL);
+    }
+    while stub.attached() {
+        match stub.poll(headless.system_mut())? {
+            true => headless.run_frame(),
+            false => thread::sleep(GDB_POLL_INTERVAL),
+        }
+    }
+    Ok(())
+}
+
+fn directory_storage(cli: &HeadlessCli) -> Option<DirectorySaveStorage> {
+    let name = Path::new(&cli.rom).file_stem().unwrap_or_default().to_string_lossy();
+    cli.saves
+        .as_ref()
+        .map(|directory| DirectorySaveStorage::new(directory, &name))
+}
+
+fn save_path(cli: &HeadlessCli) -> Option<PathBuf> {
+    directory_storage(cli).map(|storage| storage.path().to_path_buf())
+}
+
+fn save_storage(cli: &HeadlessCli, memory: &MemorySaveStorage) -> Box<dyn SaveStorage> {
+    match directory_storage(cli) {
+        Some(storage) => Box::new(storage),
+        None => Box::new(memory.clone()),
+    }
+}
+
+fn save_convert_options(cli: &HeadlessCli) -> SaveConvertOptions {
+    SaveConvertOptions {
+        unix_seconds: cli.unix_seconds,
+        swap_eeprom_blocks: cli.swap_eeprom_blocks,
+    }
+}
+
+fn import(cli: &HeadlessCli, rom: &[u8]) -> Result<MemorySaveStorage, HeadlessError> {
+    let Some(import_path) = &cli.import_save else {
+        return Ok(MemorySaveStorage::new());
+    };
+
+    let save = import_save(rom, &fs::read(import_path)?, &save_convert_options(cli))?;
+    if let Some(path) = save_path(cli) {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, &save)?;
+    }
+    Ok(MemorySaveStorage::with_data(save))
+}
+
+fn export(cli: &HeadlessCli, rom: &[u8], memory: &MemorySaveStorage, path: &Path) -> Result<(), HeadlessError> {
+    let save = match save_path(cli) {
+        Some(save_path) => fs::read(save_path)?,
+        None => memory.data(),
+    };
+    let format = path
+        .extension()
+        .and_then(|extension| SaveFormat::from_extension(&extension.to_string_lossy()))
+        .unwrap_or(SaveFormat::Raw);
+    fs::write(path, export_save(rom, &save, format, &save_convert_options(cli))?)?;
+    Ok(())
+}
+
+fn run(cli: HeadlessCli) -> Result<bool, HeadlessError> {
+    let rom = fs::read(&cli.rom)?;
+    let bios = match &cli.bios {
+        Some(path) => fs::read(path)?,
+        None => Vec::new(),
+    };
+
+    let memory = import(&cli, &rom)?;
+    let movie = cli.movie.as_deref().map(Movie::load).transpose()?;
+    let (mut headless, mut session) = match movie {
+        Some(movie) => {
+            let system = movie.boot(rom.clone(), bios, save_storage(&cli, &memory), false)?;
+            (Headless::new(movie.system(), system), Some(MovieSession::play(movie)))
+        }
+        None => (
+            Headless::boot(rom.clone(), bios, save_storage(&cli, &memory), cli.unix_seconds)?,
+            None,
+        ),
+    };
+
+    let symbols = load_symbols(&cli)?;
+    for location in &cli.breakpoints {
+        headless
+            .system_mut()
+            .debugger_mut()
+            .add_breakpoint(symbols.breakpoint(location)?);
+    }
+
+    if let Some(path) = &cli.trace {
+        let options = TraceOptions {
+            format: cli.trace_format,
+            start: cli.trace_start,
+            stop: cli.trace_stop,
+        };
+        let tracer = Tracer::create(path, options)?.with_symbols(symbols.clone());
+        headless.system_mut().start_trace(tracer);
+    }
+
+    if cli.profile.is_some() {
+        let profiler = Profiler::new(cli.profile_by).with_symbols(symbols.clone());
+        headless.system_mut().start_profile(profiler);
+    }
+
+    if cli.coverage.is_some() || cli.coverage_disassembly.is_some() {
+        let coverage = match cli.coverage.as_ref().filter(|path| path.exists()) {
+            Some(path) => Coverage::load(path, rom.len())?,
+            None => Coverage::new(rom.len()),
+        };
+        headless.system_mut().start_coverage(coverage);
+    }
+
+    let frames = cli
+        .frames
+        .or(session.as_ref().map(|session| session.movie().frame_count()))
+        .unwrap_or(DEFAULT_FRAMES);
+    let has_condition = cli.until_serial.is_some() || cli.until_memory.is_some();
+    let condition_met = |headless: &Headless| {
+        let serial_met = cli
+            .until_serial
+            .as_deref()
+            .is_some_and(|text| headless.serial_text().contains(text));
+        let memory_met = cli
+            .until_memory
+            .is_some_and(|condition| headless.read_memory(condition.address) == condition.value);
+        serial_met || memory_met
+    };
+
+    let mut met = false;
+    let mut break_reason = None;
+    match cli.gdb {
+        Some(port) => serve_gdb(&mut headless, port)?,
+        None => {
+            while headless.frames() < frames && !met && break_reason.is_none() {
+                match session.as_mut() {
+                    Some(session) => headless.run_movie_frame(session),
+                    None => headless.run_frame(),
+                }
+                met = has_condition && condition_met(&headless);
+                break_reason = headless.system_mut().take_break_reason();
+            }
+        }
+    }
+
+    if let Some(tracer) = headless.system_mut().stop_trace() {
+        tracer.finish()?;
+    }
+
+    let profiler = headless.system_mut().stop_profile();
+    let coverage = headless.system_mut().stop_coverage();
+
+    println!("frames: {}", headless.frames());
+    println!("frame hash: {:#018X}", headless.frame_hash());
+    if let Some(session) = &session {
+        let movie = session.movie();
+        println!(
+            "movie: {} frames, {} rerecords, {} lag frames",
+            movie.frame_count(),
+            movie.rerecord_count(),
+            movie.lag_frame_count()
+        );
+    }
+    if has_condition {
+        println!("condition met: {met}");
+    }
+    if let Some(reason) = break_reason {
+        println!("stopped: {}", describe_break(&symbols, reason));
+    }
+
+    if cli.serial {
+        println!("serial:\n{}", headless.serial_text());
+    }
+    if cli.registers {
+        for register in headless.system().registers() {
+            println!(
+                "{}: {:#0width$X}",
+                register.name,
+                register.value,
+                width = register.bits.div_ceil(4) as usize + 2
+            );
+        }
+    }
+    if let Some((profiler, path)) = profiler.zip(cli.profile.as_ref()) {
+        print_profile(&profiler);
+        profiler.save_collapsed(path)?;
+    }
+    if let Some(coverage) = &coverage {
+        println!(
+            "coverage: {} bytes executed, {} read as data",
+            coverage.executed_count(),
+            coverage.count(CoverageKind::Data)
+        );
+        if let Some(path) = &cli.coverage {
+            coverage.save(path)?;
+        }
+        if let Some(path) = &cli.coverage_disassembly {
+            let writer = BufWriter::new(File::create(path)?);
+            write_coverage_disassembly(coverage, &rom, headless.kind(), &symbols, writer)?;
+        }
+    }
+    if cli.backtrace {
+        println!("backtrace:");
+        for line in headless.system().backtrace(Some(&symbols)) {
+            println!("{line}");
+        }
+    }
+    for range in &cli.dump {
+        dump_memory(&headless, *range);
+    }
+    for range in &cli.disassemble {
+        disassemble(&headless, &symbols, range)?;
+    }
+
+    if let Some(path) = &cli.screenshot {
+        image::save_buffer(
+            path,
+            &headless.frame_rgba(),
+            headless.viewport_width() as u32,
+            headless.viewport_height() as u32,
+            image::ColorType::Rgba8,
+        )?;
+    }
+
+    if let Some(path) = &cli.export_save {
+        drop(headless);
+        export(&cli, &rom, &memory, path)?;
+    }
+
+    Ok(met || !has_condition)
+}
+
+fn main() -> ExitCode {
+    match run(HeadlessCli::parse()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::io::{self, Write};
+
+use ironboyadvance_arm7tdmi::disassembler::{decode_arm_bytes, decode_thumb_bytes};
+use ironboyadvance_common::{
+    coverage::{Coverage, CoverageKind},
+    disassembly::{self, Decoder, Disassembly},
+    emulator::System,
+    symbols::SymbolTable,
+};
+use ironboyadvance_sm83::disassembler::decode_sm83_bytes;
+
+pub use ironboyadvance_arm7tdmi::disassembler::{disassemble_arm, disassemble_thumb};
+pub use ironboyadvance_sm83::disassembler::disassemble_sm83;
+
+/// The instruction set code is decoded as. A sweep stays in one set, so Thumb code reached through `BX`
+/// from ARM needs a sweep of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Architecture {
+    Arm,
+    Thumb,
+    Sm83,
+}
+
+impl Architecture {
+    fn decoder(self) -> Decoder<'static> {
+        match self {
+            Architecture::Arm => &decode_arm_bytes,
+            Architecture::Thumb => &decode_thumb_bytes,
+            Architecture::Sm83 => &decode_sm83_bytes,
+        }
+    }
+}
+
+const GBA_ROM_BASE: u32 = 0x0800_0000;
+const GB_ROM_BANK_SIZE: usize = 0x4000;
+
+/// Disassembles all of `code`, loaded at `base`, one instruction after another.
+pub fn linear_sweep(code: &[u8], base: u32, architecture: Architecture) -> Vec<Disassembly> {
+    disassembly::linear_sweep(code, base, architecture.decoder())
+}
+
+/// Disassembles the parts of `code`, loaded at `base`, that control flow reaches from `entry_points`.
+pub fn recursive_descent(code: &[u8], base: u32, entry_points: &[u32], architecture: Architecture) -> Vec<Disassembly> {
+    disassembly::recursive_descent(code, base, entry_points, architecture.decoder())
+}
+
+/// Where the byte at rom `offset` sits on the bus, with switchable Game Boy banks mapped at 0x4000.
+fn rom_address(system: System, offset: usize) -> u32 {
+    match system {
+        System::Gba => GBA_ROM_BASE + offset as u32,
+        System::Gb | System::Gbc => match offset < GB_ROM_BANK_SIZE {
+            true => offset as u32,
+            false => (GB_ROM_BANK_SIZE | (offset % GB_ROM_BANK_SIZE)) as u32,
+        },
+    }
+}
+
+/// Game Boy locations carry their bank, like `BB:AAAA` in a `.sym` file.
+fn rom_location(system: System, offset: usize) -> String {
+    match system {
+        System::Gba => format!("{:08X}", rom_address(system, offset)),
+        System::Gb | System::Gbc => format!("{:02X}:{:04X}", offset / GB_ROM_BANK_SIZE, rom_address(system, offset)),
+    }
+}
+
+/// The set the instruction starting at `offset` ran in. Bytes inside an instruction are not a start.
+fn code_at(coverage: &Coverage, offset: usize) -> Option<Architecture> {
+    match (
+        coverage.covers(offset, CoverageKind::Arm) && offset.is_multiple_of(4),
+        coverage.covers(offset, CoverageKind::Thumb) && offset.is_multiple_of(2),
+        coverage.covers(offset, CoverageKind::Sm83),
+    ) {
+        (true, _, _) => Some(Architecture::Arm),
+        (_, true, _) => Some(Architecture::Thumb),
+        (_, _, true) => Some(Architecture::Sm83),
+        _ => None,
+    }
+}
+
+fn executed(coverage: &Coverage, offset: usize) -> bool {
+    coverage.kinds(offset).iter().any(|kind| kind.executed())
+}
+
+/// Writes `rom` as a session used it. Code that ran is disassembled in the set it ran in and marked
+/// `A`, `T` or `S`, with `d` when it was also read as data. Every other stretch of bytes is one line,
+/// `D` when it was read as data and blank when nothing touched it.
+pub fn write_coverage_disassembly(
+    coverage: &Coverage,
+    rom: &[u8],
+    system: System,
+    symbols: &SymbolTable,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let percent = |bytes: usize| bytes as f64 * 100.0 / rom.len().max(1) as f64;
+    let (executed_bytes, data_bytes) = (coverage.executed_count(), coverage.count(CoverageKind::Data));
+    writeln!(
+        writer,
+        "; {executed_bytes} of {} bytes executed ({:.1}%), {data_bytes} read as data ({:.1}%)",
+        rom.len(),
+        percent(executed_bytes),
+        percent(data_bytes)
+    )?;
+
+    let mut offset = 0;
+    while offset < rom.len() {
+        let data = coverage.covers(offset, CoverageKind::Data);
+        if let Some(architecture) = code_at(coverage, offset) {
+            let address = rom_address(system, offset);
+            let bank = match system {
+                System::Gba => None,
+                System::Gb | System::Gbc => Some((offset / GB_ROM_BANK_SIZE) as u16),
+            };
+            if let Some(symbol) = symbols.symbol_at(address, bank) {
+                writeln!(writer, "{}:", symbol.name)?;
+            }
+            let mut instruction = architecture.decoder()(&rom[offset..], address);
+            symbols.annotate(&mut instruction);
+            let set = match architecture {
+                Architecture::Arm => 'A',
+                Architecture::Thumb => 'T',
+                Architecture::Sm83 => 'S',
+            };
+            let data = if data { 'd' } else { ' ' };
+            writeln!(writer, "{set}{data} {}: {}", rom_location(system, offset), instruction.text)?;
+            offset += instruction.size.max(1) as usize;
+        } else if executed(coverage, offset) {
+            offset += 1;
+        } else {
+            let end = (offset..rom.len())
+                .find(|&end| executed(coverage, end) || coverage.covers(end, CoverageKind::Data) != data)
+                .unwrap_or(rom.len());
+            let (marker, use_) = match data {
+                true => ('D', "read as data"),
+                false => (' ', "never reached"),
+            };
+            writeln!(
+                writer,
+                "{marker}  {}: {} bytes {use_}",
+                rom_location(system, offset),
+                end - offset
+            )?;
+            offset = end;
+        }
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recursive_descent_follows_thumb_calls() {
+        let code: Vec<u8> = [
+            0xF000, 0xF802, // 0x00: BL 0x08
+            0xE7FE, //         0x04: B 0x04
+            0xFFFF, //         0x06: data
+            0x2001, //         0x08: MOV r0,#1
+            0x4770, //         0x0A: BX lr
+        ]
+        .iter()
+        .flat_map(|halfword: &u16| halfword.to_le_bytes())
+        .collect();
+
+        let reachable: Vec<(u32, String)> = recursive_descent(&code, 0x08000000, &[0x08000000], Architecture::Thumb)
+            .into_iter()
+            .map(|instruction| (instruction.address, instruction.text))
+            .collect();
+        assert_eq!(reachable[0], (0x08000000, "BL 0x08000008".to_string()));
+        assert_eq!(
+            reachable.iter().map(|(address, _)| *address).collect::<Vec<_>>(),
+            [0x08000000, 0x08000004, 0x08000008, 0x0800000A]
+        );
+        assert_eq!(linear_sweep(&code, 0x08000000, Architecture::Thumb).len(), 5);
+    }
+
+    #[test]
+    fn coverage_disassembly_shows_code_data_and_gaps() {
+        let rom: Vec<u8> = [0x2001u16, 0x4770, 0x1234, 0x5678, 0xFFFF, 0xFFFF]
+            .iter()
+            .flat_map(|halfword| halfword.to_le_bytes())
+            .collect();
+        let mut coverage = Coverage::new(rom.len());
+        coverage.mark(0, 4, CoverageKind::Thumb);
+        coverage.mark(4, 4, CoverageKind::Data);
+
+        let mut output = Vec::new();
+        write_coverage_disassembly(&coverage, &rom, System::Gba, &SymbolTable::default(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "; 4 of 12 bytes executed (33.3%), 4 read as data (33.3%)",
+                "T  08000000: MOV r0,#1",
+                "T  08000002: BX r0,lr",
+                "D  08000004: 4 bytes read as data",
+                "   08000008: 4 bytes never reached",
+            ]
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    BootError, Emulator, KEYPAD_IDLE, MovieSession, SaveStorage, SnapshotError, System, boot, detect_system, system_info,
+};
+
+const FNV_OFFSET_BASIS: u64 = 14695981039346656037;
+const FNV_PRIME: u64 = 1099511628211;
+
+const LUMINANCE_RAMP: &[u8] = b"@%#*+=-:. ";
+
+/// Runs a system frame by frame with no window or audio device attached, for tests and tooling.
+pub struct Headless {
+    kind: System,
+    system: Box<dyn Emulator>,
+    viewport_width: usize,
+    viewport_height: usize,
+    cycles_per_frame: usize,
+    overshoot: usize,
+    frames: usize,
+}
+
+impl Headless {
+    pub fn new(kind: System, system: Box<dyn Emulator>) -> Headless {
+        let (viewport_width, viewport_height, _, _, cycles_per_frame) = system_info(kind);
+        Headless {
+            kind,
+            system,
+            viewport_width,
+            viewport_height,
+            cycles_per_frame,
+            overshoot: 0,
+            frames: 0,
+        }
+    }
+
+    pub fn boot(
+        rom: Vec<u8>,
+        bios: Vec<u8>,
+        save_storage: Box<dyn SaveStorage>,
+        unix_seconds: u64,
+    ) -> Result<Headless, BootError> {
+        let kind = detect_system(&rom).ok_or(BootError::UnknownFormat)?;
+        let system = boot(kind, rom, bios, save_storage, unix_seconds, false)?;
+        Ok(Headless::new(kind, system))
+    }
+
+    pub fn kind(&self) -> System {
+        self.kind
+    }
+
+    pub fn system(&self) -> &dyn Emulator {
+        self.system.as_ref()
+    }
+
+    pub fn system_mut(&mut self) -> &mut dyn Emulator {
+        self.system.as_mut()
+    }
+
+    pub fn viewport_width(&self) -> usize {
+        self.viewport_width
+    }
+
+    pub fn viewport_height(&self) -> usize {
+        self.viewport_height
+    }
+
+    /// Frames run since boot.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn run_frame(&mut self) {
+        self.overshoot = self.system.run(self.cycles_per_frame, self.overshoot);
+        self.system.clear_audio_buffer();
+        self.frames += 1;
+    }
+
+    pub fn run_frames(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.run_frame();
+        }
+    }
+
+    /// Runs one frame of a movie playback, with the keypad released once the movie runs out.
+    pub fn run_movie_frame(&mut self, session: &mut MovieSession) {
+        session.run_frame(self.system.as_mut(), KEYPAD_IDLE);
+        self.system.clear_audio_buffer();
+        self.frames += 1;
+    }
+
+    pub fn frame_buffer(&self) -> &[u32] {
+        self.system.frame_buffer()
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        self.system.save_state()
+    }
+
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), SnapshotError> {
+        self.system.load_state(state)
+    }
+
+    /// Reads a byte without side effects.
+    pub fn read_memory(&self, address: u32) -> u8 {
+        self.system.peek_8(address)
+    }
+
+    pub fn serial_output(&self) -> &[u8] {
+        self.system.serial_output()
+    }
+
+    pub fn serial_text(&self) -> String {
+        String::from_utf8_lossy(self.system.serial_output()).into_owned()
+    }
+
+    pub fn frame_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+        for pixel in self.system.frame_buffer() {
+            for byte in pixel.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+        hash
+    }
+
+    pub fn frame_rgba(&self) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.viewport_width * self.viewport_height * 4);
+        for pixel in self.system.frame_buffer() {
+            rgba.push(((pixel >> 16) & 0xFF) as u8);
+            rgba.push(((pixel >> 8) & 0xFF) as u8);
+            rgba.push((pixel & 0xFF) as u8);
+            rgba.push(0xFF);
+        }
+        rgba
+    }
+
+    pub fn frame_ascii(&self) -> String {
+        let frame_buffer = self.system.frame_buffer();
+        let mut rendered = String::new();
+
+        for y in (0..self.viewport_height).step_by(2) {
+            for x in 0..self.viewport_width {
+                let pixel = frame_buffer[y * self.viewport_width + x];
+                let red = (pixel >> 16) & 0xFF;
+                let green = (pixel >> 8) & 0xFF;
+                let blue = pixel & 0xFF;
+                let luminance = (red * 54 + green * 183 + blue * 19) >> 8;
+                let index = luminance as usize * (LUMINANCE_RAMP.len() - 1) / 0xFF;
+                rendered.push(LUMINANCE_RAMP[index] as char);
+            }
+            rendered.push('\n');
+        }
+
+        rendered
+    }
+}
@@ -1,11 +1,42 @@
-use std::path::PathBuf;
-
 use ironboyadvance_gba::{GameBoyAdvance, GbaError};
 use ironboyadvance_gbc::{GameBoyColor, GbcError};
 use thiserror::Error;
 
-pub use ironboyadvance_common::emulator::{Emulator, System, detect_system};
-pub use ironboyadvance_common::keypad::KeypadButton;
+mod disassembler;
+mod headless;
+mod movie;
+mod rewind;
+mod save_convert;
+
+pub use disassembler::{
+    Architecture, disassemble_arm, disassemble_sm83, disassemble_thumb, linear_sweep, recursive_descent,
+    write_coverage_disassembly,
+};
+pub use headless::Headless;
+pub use ironboyadvance_common::call_stack::{CallStack, Frame, FrameKind};
+pub use ironboyadvance_common::cheats::{Cheat, CheatError, CheatFile, CheatFileError, CheatFormat};
+pub use ironboyadvance_common::coverage::{COVERAGE_EXTENSION, Coverage, CoverageError, CoverageKind};
+pub use ironboyadvance_common::debugger::{
+    AccessKind, BreakReason, Breakpoint, Debugger, InstructionSet, WatchCondition, WatchKind, Watchpoint,
+};
+pub use ironboyadvance_common::disassembly::{Disassembly, Flow};
+pub use ironboyadvance_common::emulator::{Emulator, MemoryRegion, Register, System, SystemInspection, detect_system};
+pub use ironboyadvance_common::gdb::{DEFAULT_GDB_PORT, GdbError, GdbStub, GdbTarget};
+pub use ironboyadvance_common::keypad::{KEYPAD_IDLE, KeypadButton};
+pub use ironboyadvance_common::memory::MemoryAccessWidth;
+pub use ironboyadvance_common::profiler::{COLLAPSED_EXTENSION, ProfileEntry, ProfileGrouping, Profiler};
+pub use ironboyadvance_common::ram_search::{Comparison, RamSearch, SearchError, SearchFilter, SearchOptions, SearchResult};
+pub use ironboyadvance_common::save_format::{SaveConvertOptions, SaveFormat};
+pub use ironboyadvance_common::save_storage::{
+    DEFAULT_FLUSH_INTERVAL, DirectorySaveStorage, MemorySaveStorage, ReadOnlySaveStorage, SaveStorage,
+};
+pub use ironboyadvance_common::snapshot::SnapshotError;
+pub use ironboyadvance_common::symbols::{Symbol, SymbolError, SymbolKind, SymbolTable};
+pub use ironboyadvance_common::trace::{TRACE_EXTENSION, TraceFormat, TraceOptions, TraceTrigger, Tracer};
+pub use ironboyadvance_gba::{LINK_PLAYERS, LinkHub};
+pub use movie::{Movie, MovieError, MovieMode, MovieSession};
+pub use rewind::RewindBuffer;
+pub use save_convert::{SaveConvertError, export_save, import_save};
 
 #[derive(Error, Debug)]
 pub enum BootError {
@@ -15,6 +46,8 @@ pub enum BootError {
     Gbc(#[from] GbcError),
     #[error("unrecognized rom format")]
     UnknownFormat,
+    #[error("all {LINK_PLAYERS} link cable places are taken")]
+    LinkFull,
 }
 
 pub fn system_info(kind: System) -> (usize, usize, f32, u32, usize) {
@@ -38,26 +71,41 @@ pub fn system_info(kind: System) -> (usize, usize, f32, u32, usize) {
 
 pub fn boot(
     kind: System,
-    rom_path: &str,
     rom: Vec<u8>,
     bios: Vec<u8>,
+    save_storage: Box<dyn SaveStorage>,
     unix_seconds: u64,
     show_logs: bool,
 ) -> Result<Box<dyn Emulator>, BootError> {
     match kind {
         System::Gba => Ok(Box::new(GameBoyAdvance::new(
-            PathBuf::from(rom_path),
             rom,
             bios,
+            save_storage,
             unix_seconds,
             show_logs,
         )?)),
         System::Gb | System::Gbc => Ok(Box::new(GameBoyColor::new(
             kind,
-            PathBuf::from(rom_path),
             rom,
             bios,
+            save_storage,
+            unix_seconds,
             show_logs,
         )?)),
     }
 }
+
+/// Boots a GBA with its serial port plugged into `hub`, for linking several instances in one process.
+pub fn boot_linked(
+    rom: Vec<u8>,
+    bios: Vec<u8>,
+    save_storage: Box<dyn SaveStorage>,
+    unix_seconds: u64,
+    show_logs: bool,
+    hub: &LinkHub,
+) -> Result<Box<dyn Emulator>, BootError> {
+    let mut gba = GameBoyAdvance::new(rom, bios, save_storage, unix_seconds, show_logs)?;
+    gba.connect_link(hub).ok_or(BootError::LinkFull)?;
+    Ok(Box::new(gba))
+}
@@ -0,0 +1,342 @@
+use std::{fs, io, path::Path};
+
+use ironboyadvance_common::{
+    keypad::KEYPAD_IDLE,
+    snapshot::{StateReader, StateWriter, rom_hash},
+};
+use thiserror::Error;
+
+use crate::{BootError, Emulator, SaveStorage, SnapshotError, System, boot, system_info};
+
+pub const MOVIE_MAGIC: [u8; 4] = *b"IBAM";
+pub const MOVIE_VERSION: u16 = 1;
+
+#[derive(Error, Debug)]
+pub enum MovieError {
+    #[error("failed to access movie file: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid movie: {0}")]
+    Format(#[from] SnapshotError),
+    #[error("movie was recorded with a different rom")]
+    RomMismatch,
+    #[error("movie was recorded with a different bios")]
+    BiosMismatch,
+    #[error("failed to boot movie: {0}")]
+    Boot(#[from] BootError),
+}
+
+/// A recording of the keypad value fed to the system on every frame, along with everything needed to
+/// boot the system into the same state it was recorded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    system: System,
+    rom_hash: u64,
+    bios_hash: u64,
+    unix_seconds: u64,
+    rerecord_count: u32,
+    inputs: Vec<u16>,
+    lag: Vec<bool>,
+}
+
+impl Movie {
+    pub fn new(system: System, rom: &[u8], bios: &[u8], unix_seconds: u64) -> Self {
+        Movie {
+            system,
+            rom_hash: rom_hash(rom),
+            bios_hash: rom_hash(bios),
+            unix_seconds,
+            rerecord_count: 0,
+            inputs: Vec::new(),
+            lag: Vec::new(),
+        }
+    }
+
+    pub fn system(&self) -> System {
+        self.system
+    }
+
+    pub fn unix_seconds(&self) -> u64 {
+        self.unix_seconds
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn rerecord_count(&self) -> u32 {
+        self.rerecord_count
+    }
+
+    /// Frames on which the game never read the keypad.
+    pub fn lag_frame_count(&self) -> usize {
+        self.lag.iter().filter(|&&lag| lag).count()
+    }
+
+    pub fn input(&self, frame: usize) -> Option<u16> {
+        self.inputs.get(frame).copied()
+    }
+
+    pub fn is_lag_frame(&self, frame: usize) -> bool {
+        self.lag.get(frame).copied().unwrap_or(false)
+    }
+
+    fn push(&mut self, input: u16, lag: bool) {
+        self.inputs.push(input);
+        self.lag.push(lag);
+    }
+
+    fn truncate(&mut self, frame: usize) {
+        self.inputs.truncate(frame);
+        self.lag.truncate(frame);
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        writer.write(&MOVIE_MAGIC);
+        writer.write(&MOVIE_VERSION);
+        writer.write(&self.system);
+        writer.write(&self.rom_hash);
+        writer.write(&self.bios_hash);
+        writer.write(&self.unix_seconds);
+        writer.write(&self.rerecord_count);
+        writer.write(&self.inputs);
+        writer.write(&self.lag);
+        writer.finish()
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, SnapshotError> {
+        let mut reader = StateReader::new(data);
+        if reader.read::<[u8; 4]>()? != MOVIE_MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+
+        let version: u16 = reader.read()?;
+        if version != MOVIE_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let movie = Movie {
+            system: reader.read()?,
+            rom_hash: reader.read()?,
+            bios_hash: reader.read()?,
+            unix_seconds: reader.read()?,
+            rerecord_count: reader.read()?,
+            inputs: reader.read()?,
+            lag: reader.read()?,
+        };
+        reader.finish()?;
+
+        if movie.inputs.len() != movie.lag.len() {
+            return Err(SnapshotError::LengthMismatch {
+                expected: movie.inputs.len(),
+                found: movie.lag.len(),
+            });
+        }
+
+        Ok(movie)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MovieError> {
+        Ok(fs::write(path, self.encode())?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MovieError> {
+        Ok(Self::decode(&fs::read(path)?)?)
+    }
+
+    /// Boots the system the movie was recorded on, refusing a rom or bios that differs from the recording.
+    pub fn boot(
+        &self,
+        rom: Vec<u8>,
+        bios: Vec<u8>,
+        save_storage: Box<dyn SaveStorage>,
+        show_logs: bool,
+    ) -> Result<Box<dyn Emulator>, MovieError> {
+        if rom_hash(&rom) != self.rom_hash {
+            return Err(MovieError::RomMismatch);
+        }
+        if rom_hash(&bios) != self.bios_hash {
+            return Err(MovieError::BiosMismatch);
+        }
+        Ok(boot(self.system, rom, bios, save_storage, self.unix_seconds, show_logs)?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovieMode {
+    Recording,
+    Playback,
+}
+
+/// Drives a system one frame at a time on behalf of a movie, either appending the live input to it or
+/// feeding it the recorded input.
+pub struct MovieSession {
+    movie: Movie,
+    mode: MovieMode,
+    frame: usize,
+    cycles_per_frame: usize,
+    overshoot: usize,
+}
+
+impl MovieSession {
+    pub fn record(movie: Movie) -> Self {
+        Self::new(movie, MovieMode::Recording)
+    }
+
+    pub fn play(movie: Movie) -> Self {
+        Self::new(movie, MovieMode::Playback)
+    }
+
+    fn new(movie: Movie, mode: MovieMode) -> Self {
+        let (_, _, _, _, cycles_per_frame) = system_info(movie.system);
+        MovieSession {
+            movie,
+            mode,
+            frame: 0,
+            cycles_per_frame,
+            overshoot: 0,
+        }
+    }
+
+    pub fn movie(&self) -> &Movie {
+        &self.movie
+    }
+
+    pub fn into_movie(self) -> Movie {
+        self.movie
+    }
+
+    pub fn mode(&self) -> MovieMode {
+        self.mode
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn finished(&self) -> bool {
+        self.mode == MovieMode::Playback && self.frame >= self.movie.frame_count()
+    }
+
+    /// Switches a playback to recording from the current frame, discarding the rest of the movie.
+    pub fn resume_recording(&mut self) {
+        if self.mode == MovieMode::Playback {
+            self.mode = MovieMode::Recording;
+            self.movie.truncate(self.frame);
+            self.movie.rerecord_count += 1;
+        }
+    }
+
+    /// Runs one frame and returns the input it was given. While recording `live_input` is appended to the
+    /// movie, during playback it is ignored in favour of the recorded input, with the keypad released
+    /// once the movie runs out.
+    pub fn run_frame(&mut self, system: &mut dyn Emulator, live_input: u16) -> u16 {
+        let input = match self.mode {
+            MovieMode::Recording => live_input,
+            MovieMode::Playback => self.movie.input(self.frame).unwrap_or(KEYPAD_IDLE),
+        };
+
+        system.take_input_polled();
+        system.handle_pressed_buttons(input);
+        self.overshoot = system.run(self.cycles_per_frame, self.overshoot);
+        let lag = !system.take_input_polled();
+
+        if self.mode == MovieMode::Recording {
+            self.movie.push(input, lag);
+        }
+        self.frame += 1;
+        input
+    }
+
+    /// Save state of the system tagged with the movie position, so loading it lands on the same frame.
+    pub fn save_state(&self, system: &dyn Emulator) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        writer.write(&self.frame);
+        writer.write(&self.overshoot);
+        writer.write_bytes(&system.save_state());
+        writer.finish()
+    }
+
+    /// Loads a state made by [`MovieSession::save_state`]. While recording this rewinds the movie to the
+    /// state's frame and counts a rerecord.
+    pub fn load_state(&mut self, system: &mut dyn Emulator, state: &[u8]) -> Result<(), SnapshotError> {
+        let mut reader = StateReader::new(state);
+        let frame: usize = reader.read()?;
+        let overshoot: usize = reader.read()?;
+        let system_state = reader.read_bytes()?;
+        reader.finish()?;
+
+        if frame > self.movie.frame_count() {
+            return Err(SnapshotError::InvalidValue("movie frame"));
+        }
+        system.load_state(&system_state)?;
+
+        self.frame = frame;
+        self.overshoot = overshoot;
+        if self.mode == MovieMode::Recording {
+            self.movie.truncate(frame);
+            self.movie.rerecord_count += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemorySaveStorage;
+
+    fn movie() -> Movie {
+        let mut movie = Movie::new(System::Gba, &[1, 2, 3], &[], 1_700_000_000);
+        for frame in 0..10u16 {
+            movie.push(KEYPAD_IDLE & !frame, frame % 3 == 0);
+        }
+        movie
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let movie = movie();
+        let decoded = Movie::decode(&movie.encode()).unwrap();
+        assert_eq!(decoded, movie);
+        assert_eq!(decoded.frame_count(), 10);
+        assert_eq!(decoded.lag_frame_count(), 4);
+        assert_eq!(decoded.input(3), Some(KEYPAD_IDLE & !3));
+    }
+
+    #[test]
+    fn decode_rejects_bad_data() {
+        let mut data = movie().encode();
+        data[0] = b'X';
+        assert!(matches!(Movie::decode(&data), Err(SnapshotError::InvalidMagic)));
+
+        let data = movie().encode();
+        assert!(matches!(
+            Movie::decode(&data[..data.len() - 1]),
+            Err(SnapshotError::UnexpectedEnd)
+        ));
+    }
+
+    #[test]
+    fn boot_rejects_other_rom() {
+        let movie = movie();
+        assert!(matches!(
+            movie.boot(vec![1, 2, 4], vec![], Box::new(MemorySaveStorage::new()), false),
+            Err(MovieError::RomMismatch)
+        ));
+        assert!(matches!(
+            movie.boot(vec![1, 2, 3], vec![0], Box::new(MemorySaveStorage::new()), false),
+            Err(MovieError::BiosMismatch)
+        ));
+    }
+
+    #[test]
+    fn resume_recording_truncates_and_counts_rerecord() {
+        let mut session = MovieSession::play(movie());
+        session.frame = 4;
+        session.resume_recording();
+        assert_eq!(session.mode(), MovieMode::Recording);
+        assert_eq!(session.movie().frame_count(), 4);
+        assert_eq!(session.movie().rerecord_count(), 1);
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+
+use crate::{Emulator, SnapshotError};
+
+/// History of save states taken while the system runs. The newest state is kept whole, every older one
+/// is stored as an XOR + run-length delta against the state that followed it, and the oldest deltas are
+/// dropped once the buffer grows past its memory budget.
+pub struct RewindBuffer {
+    interval: usize,
+    capacity: usize,
+    frames_since_capture: usize,
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>,
+    delta_bytes: usize,
+}
+
+impl RewindBuffer {
+    pub fn new(interval: usize, capacity: usize) -> Self {
+        RewindBuffer {
+            interval: interval.max(1),
+            capacity,
+            frames_since_capture: 0,
+            latest: None,
+            deltas: VecDeque::new(),
+            delta_bytes: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.latest.iter().count() + self.deltas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    pub fn memory_used(&self) -> usize {
+        self.latest.as_ref().map_or(0, Vec::len) + self.delta_bytes
+    }
+
+    pub fn clear(&mut self) {
+        self.frames_since_capture = 0;
+        self.latest = None;
+        self.deltas.clear();
+        self.delta_bytes = 0;
+    }
+
+    /// Counts a finished frame, capturing the system every `interval` frames.
+    pub fn frame_completed(&mut self, system: &dyn Emulator) {
+        self.frames_since_capture += 1;
+        if self.frames_since_capture >= self.interval {
+            self.capture(system.save_state());
+        }
+    }
+
+    pub fn capture(&mut self, state: Vec<u8>) {
+        self.frames_since_capture = 0;
+
+        if let Some(previous) = self.latest.take() {
+            let delta = encode_delta(&state, &previous);
+            self.delta_bytes += delta.len();
+            self.deltas.push_back(delta);
+        }
+        self.latest = Some(state);
+
+        while self.memory_used() > self.capacity {
+            let Some(oldest) = self.deltas.pop_front() else {
+                break;
+            };
+            self.delta_bytes -= oldest.len();
+        }
+    }
+
+    /// Takes the newest state off the buffer. The oldest state is handed out without being removed, so
+    /// holding rewind parks the system at the start of the history.
+    pub fn step_back(&mut self) -> Option<Vec<u8>> {
+        self.frames_since_capture = 0;
+        let newest = self.latest.take()?;
+
+        self.latest = match self.deltas.pop_back() {
+            Some(delta) => {
+                self.delta_bytes -= delta.len();
+                Some(apply_delta(&newest, &delta))
+            }
+            None => Some(newest.clone()),
+        };
+
+        Some(newest)
+    }
+
+    /// Restores the system to the newest state in the buffer, returning `false` once there is nothing to
+    /// rewind to.
+    pub fn rewind(&mut self, system: &mut dyn Emulator) -> Result<bool, SnapshotError> {
+        match self.step_back() {
+            Some(state) => system.load_state(&state).map(|_| true),
+            None => Ok(false),
+        }
+    }
+}
+
+fn encode_delta(newer: &[u8], older: &[u8]) -> Vec<u8> {
+    let length = newer.len().max(older.len());
+    let xor = |index: usize| newer.get(index).copied().unwrap_or(0) ^ older.get(index).copied().unwrap_or(0);
+
+    let mut delta = Vec::new();
+    write_varint(&mut delta, older.len());
+
+    let mut index = 0;
+    while index < length {
+        let zeros_start = index;
+        while index < length && xor(index) == 0 {
+            index += 1;
+        }
+
+        let literal_start = index;
+        while index < length && xor(index) != 0 {
+            index += 1;
+        }
+
+        write_varint(&mut delta, literal_start - zeros_start);
+        write_varint(&mut delta, index - literal_start);
+        delta.extend((literal_start..index).map(xor));
+    }
+
+    delta
+}
+
+fn apply_delta(newer: &[u8], delta: &[u8]) -> Vec<u8> {
+    let mut position = 0;
+    let older_length = read_varint(delta, &mut position);
+
+    let mut older = newer.to_vec();
+    older.resize(older_length.max(newer.len()), 0);
+
+    let mut index = 0;
+    while position < delta.len() {
+        index += read_varint(delta, &mut position);
+        let literal_length = read_varint(delta, &mut position);
+        for (byte, xor) in older[index..index + literal_length]
+            .iter_mut()
+            .zip(&delta[position..position + literal_length])
+        {
+            *byte ^= xor;
+        }
+        index += literal_length;
+        position += literal_length;
+    }
+
+    older.truncate(older_length);
+    older
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn read_varint(buffer: &[u8], position: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = buffer[*position];
+        *position += 1;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_round_trip() {
+        let older = vec![1, 2, 3, 0, 0, 0, 7, 8, 9, 10];
+        let newer = vec![1, 2, 4, 0, 0, 0, 7, 8, 0, 10, 11, 12];
+        let delta = encode_delta(&newer, &older);
+        assert_eq!(apply_delta(&newer, &delta), older);
+
+        let delta = encode_delta(&older, &newer);
+        assert_eq!(apply_delta(&older, &delta), newer);
+    }
+
+    #[test]
+    fn identical_states_compress() {
+        let state = vec![0xAB; 0x10000];
+        let delta = encode_delta(&state, &state);
+        assert!(delta.len() < 8);
+        assert_eq!(apply_delta(&state, &delta), state);
+    }
+
+    #[test]
+    fn steps_back_through_history() {
+        let mut buffer = RewindBuffer::new(1, usize::MAX);
+        for frame in 0..5u8 {
+            let mut state = vec![0; 0x100];
+            state[frame as usize] = frame + 1;
+            buffer.capture(state);
+        }
+
+        for frame in (0..5u8).rev() {
+            let state = buffer.step_back().unwrap();
+            assert_eq!(state[frame as usize], frame + 1);
+        }
+        assert_eq!(buffer.len(), 1);
+        assert_eq!(buffer.step_back().unwrap()[0], 1);
+    }
+
+    #[test]
+    fn oldest_deltas_are_dropped_over_budget() {
+        let mut buffer = RewindBuffer::new(1, 0x140);
+        for frame in 0..32u8 {
+            let mut state = vec![0; 0x100];
+            state[0] = frame;
+            buffer.capture(state);
+        }
+
+        assert!(buffer.memory_used() <= 0x140);
+        let retained = buffer.len();
+        assert!(retained > 1 && retained < 32);
+        while buffer.len() > 1 {
+            buffer.step_back();
+        }
+        assert_eq!(buffer.step_back().unwrap()[0] as usize, 32 - retained);
+    }
+}
@@ -0,0 +1,35 @@
+use thiserror::Error;
+
+use crate::{SaveConvertOptions, SaveFormat, System, detect_system};
+
+#[derive(Error, Debug)]
+pub enum SaveConvertError {
+    #[error("invalid GBA save: {0}")]
+    Gba(#[from] ironboyadvance_gba::CartridgeError),
+    #[error("invalid GB/GBC save: {0}")]
+    Gbc(#[from] ironboyadvance_gbc::CartridgeError),
+    #[error("unrecognized rom format")]
+    UnknownFormat,
+}
+
+/// Converts a save shared from another emulator or a cheat device into the save this emulator keeps for
+/// `rom`, ready to hand to a [`crate::SaveStorage`].
+pub fn import_save(rom: &[u8], data: &[u8], options: &SaveConvertOptions) -> Result<Vec<u8>, SaveConvertError> {
+    match detect_system(rom).ok_or(SaveConvertError::UnknownFormat)? {
+        System::Gba => Ok(ironboyadvance_gba::import_save(rom, data, options)?),
+        System::Gb | System::Gbc => Ok(ironboyadvance_gbc::import_save(rom, data, options)?),
+    }
+}
+
+/// Converts the save this emulator keeps for `rom` into `format`, for use in other emulators.
+pub fn export_save(
+    rom: &[u8],
+    save: &[u8],
+    format: SaveFormat,
+    options: &SaveConvertOptions,
+) -> Result<Vec<u8>, SaveConvertError> {
+    match detect_system(rom).ok_or(SaveConvertError::UnknownFormat)? {
+        System::Gba => Ok(ironboyadvance_gba::export_save(rom, save, format, options)?),
+        System::Gb | System::Gbc => Ok(ironboyadvance_gbc::export_save(rom, save, format, options)?),
+    }
+}
@@ -1,11 +1,14 @@
 mod common;
 
-use common::Headless;
+use std::fs;
+
+use common::{TEST_UNIX_SECONDS, repo_path};
+use ironboyadvance::{Headless, KEYPAD_IDLE, MemorySaveStorage, Movie, MovieSession, RewindBuffer, SnapshotError, System};
 
 const ALL_TESTS_PASSED: u64 = 0xFD05_07D8_C680_90A5;
 
 fn assert_frame_hash(rom: &str, frames: usize, expected: u64) {
-    let mut headless = Headless::load(rom);
+    let mut headless = common::load(rom);
     headless.run_frames(frames);
     let hash = headless.frame_hash();
 
@@ -61,3 +64,81 @@ fn save_flash128() {
 fn unsafe_access() {
     assert_frame_hash("external/gba-tests/unsafe/unsafe.gba", 600, ALL_TESTS_PASSED);
 }
+
+#[test]
+fn save_state_round_trip() {
+    let mut headless = common::load("external/gba-tests/arm/arm.gba");
+    headless.run_frames(30);
+    let state = headless.save_state();
+
+    headless.run_frames(120);
+    let expected = headless.frame_hash();
+
+    headless.load_state(&state).expect("failed to load save state");
+    headless.run_frames(120);
+    assert_eq!(headless.frame_hash(), expected);
+}
+
+#[test]
+fn save_state_rejects_other_rom() {
+    let state = common::load("external/gba-tests/arm/arm.gba").save_state();
+    let mut headless = common::load("external/gba-tests/thumb/thumb.gba");
+    assert_eq!(headless.load_state(&state), Err(SnapshotError::RomMismatch));
+}
+
+#[test]
+fn rewind_restores_captured_frames() {
+    let mut headless = common::load("external/gba-tests/arm/arm.gba");
+    let mut rewind = RewindBuffer::new(1, usize::MAX);
+    let mut hashes = Vec::new();
+    for _ in 0..30 {
+        headless.run_frame();
+        rewind.capture(headless.save_state());
+        hashes.push(headless.frame_hash());
+    }
+
+    for expected in hashes.into_iter().rev() {
+        let state = rewind.step_back().expect("rewind buffer ran dry");
+        headless.load_state(&state).expect("failed to load rewind state");
+        assert_eq!(headless.frame_hash(), expected);
+    }
+}
+
+#[test]
+fn movie_playback_matches_recording() {
+    let rom_path = "external/gba-tests/arm/arm.gba";
+    let rom = fs::read(repo_path(rom_path)).expect("failed to read rom");
+
+    let mut headless = common::load(rom_path);
+    let mut session = MovieSession::record(Movie::new(System::Gba, &rom, &[], TEST_UNIX_SECONDS));
+    for frame in 0..120u16 {
+        session.run_frame(headless.system_mut(), KEYPAD_IDLE & !(frame % 0x400));
+    }
+    let expected = headless.frame_hash();
+    let movie = Movie::decode(&session.into_movie().encode()).expect("failed to decode movie");
+    assert_eq!(movie.frame_count(), 120);
+
+    let mut headless = common::load(rom_path);
+    let mut session = MovieSession::play(movie);
+    while !session.finished() {
+        headless.run_movie_frame(&mut session);
+    }
+    assert_eq!(headless.frame_hash(), expected);
+}
+
+#[test]
+fn battery_save_goes_to_injected_storage() {
+    let rom = fs::read(repo_path("external/gba-tests/save/sram.gba")).expect("failed to read rom");
+    let storage = MemorySaveStorage::new();
+
+    let mut headless =
+        Headless::boot(rom.clone(), Vec::new(), Box::new(storage.clone()), TEST_UNIX_SECONDS).expect("failed to boot");
+    headless.run_frames(600);
+    let saved = storage.data();
+    assert_eq!(saved.len(), 0x8000);
+
+    let mut headless =
+        Headless::boot(rom, Vec::new(), Box::new(storage.clone()), TEST_UNIX_SECONDS).expect("failed to reboot");
+    headless.run_frames(1);
+    assert_eq!(storage.data(), saved);
+}
@@ -1,6 +1,6 @@
 mod common;
 
-use common::Headless;
+use ironboyadvance::{Headless, SnapshotError};
 use ironboyadvance_gbc::{VIEWPORT_HEIGHT, VIEWPORT_WIDTH};
 
 const STATUS_ADDRESS: u32 = 0xA000;
@@ -87,7 +87,7 @@ fn run_until_done(headless: &mut Headless, max_frames: usize) -> Outcome {
 }
 
 fn assert_passed(rom: &str, max_frames: usize) {
-    let mut headless = Headless::load(rom);
+    let mut headless = common::load(rom);
 
     let failure = match run_until_done(&mut headless, max_frames) {
         Outcome::Status(0) => return,
@@ -153,3 +153,24 @@ fn oam_bug() {
 fn halt_bug() {
     assert_passed("external/gb-test-roms/halt_bug.gb", 1000);
 }
+
+#[test]
+fn save_state_round_trip() {
+    let mut headless = common::load("external/gb-test-roms/cpu_instrs/cpu_instrs.gb");
+    headless.run_frames(60);
+    let state = headless.save_state();
+
+    headless.run_frames(240);
+    let expected = headless.frame_hash();
+
+    headless.load_state(&state).expect("failed to load save state");
+    headless.run_frames(240);
+    assert_eq!(headless.frame_hash(), expected);
+}
+
+#[test]
+fn save_state_rejects_other_rom() {
+    let state = common::load("external/gb-test-roms/cpu_instrs/cpu_instrs.gb").save_state();
+    let mut headless = common::load("external/gb-test-roms/halt_bug.gb");
+    assert_eq!(headless.load_state(&state), Err(SnapshotError::RomMismatch));
+}
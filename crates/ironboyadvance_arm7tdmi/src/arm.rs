@@ -1,4 +1,4 @@
-use ironboyadvance_common::bits::BitOps;
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow};
 
 use crate::{
     Condition, CpuAction,
@@ -90,25 +90,46 @@ impl Instruction for ArmInstruction {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, address: u32) -> String {
         match self {
-            Self::DataProcessing(i) => i.disassemble(cpu),
-            Self::PsrTransfer(i) => i.disassemble(cpu),
-            Self::Multiply(i) => i.disassemble(cpu),
-            Self::MultiplyLong(i) => i.disassemble(cpu),
-            Self::SingleDataSwap(i) => i.disassemble(cpu),
-            Self::BranchAndExchange(i) => i.disassemble(cpu),
-            Self::HalfwordAndSignedDataTransfer(i) => i.disassemble(cpu),
-            Self::SingleDataTransfer(i) => i.disassemble(cpu),
-            Self::Undefined(i) => i.disassemble(cpu),
-            Self::BlockDataTransfer(i) => i.disassemble(cpu),
-            Self::BranchAndBranchWithLink(i) => i.disassemble(cpu),
-            Self::SoftwareInterrupt(i) => i.disassemble(cpu),
-            Self::CoprocessorDataOperation(i) => i.disassemble(cpu),
-            Self::CoprocessorDataTransfer(i) => i.disassemble(cpu),
-            Self::CoprocessorRegisterTransfer(i) => i.disassemble(cpu),
+            Self::DataProcessing(i) => i.disassemble(address),
+            Self::PsrTransfer(i) => i.disassemble(address),
+            Self::Multiply(i) => i.disassemble(address),
+            Self::MultiplyLong(i) => i.disassemble(address),
+            Self::SingleDataSwap(i) => i.disassemble(address),
+            Self::BranchAndExchange(i) => i.disassemble(address),
+            Self::HalfwordAndSignedDataTransfer(i) => i.disassemble(address),
+            Self::SingleDataTransfer(i) => i.disassemble(address),
+            Self::Undefined(i) => i.disassemble(address),
+            Self::BlockDataTransfer(i) => i.disassemble(address),
+            Self::BranchAndBranchWithLink(i) => i.disassemble(address),
+            Self::SoftwareInterrupt(i) => i.disassemble(address),
+            Self::CoprocessorDataOperation(i) => i.disassemble(address),
+            Self::CoprocessorDataTransfer(i) => i.disassemble(address),
+            Self::CoprocessorRegisterTransfer(i) => i.disassemble(address),
         }
     }
+
+    fn flow(&self, address: u32) -> Flow {
+        let flow = match self {
+            Self::DataProcessing(i) => i.flow(address),
+            Self::PsrTransfer(i) => i.flow(address),
+            Self::Multiply(i) => i.flow(address),
+            Self::MultiplyLong(i) => i.flow(address),
+            Self::SingleDataSwap(i) => i.flow(address),
+            Self::BranchAndExchange(i) => i.flow(address),
+            Self::HalfwordAndSignedDataTransfer(i) => i.flow(address),
+            Self::SingleDataTransfer(i) => i.flow(address),
+            Self::Undefined(i) => i.flow(address),
+            Self::BlockDataTransfer(i) => i.flow(address),
+            Self::BranchAndBranchWithLink(i) => i.flow(address),
+            Self::SoftwareInterrupt(i) => i.flow(address),
+            Self::CoprocessorDataOperation(i) => i.flow(address),
+            Self::CoprocessorDataTransfer(i) => i.flow(address),
+            Self::CoprocessorRegisterTransfer(i) => i.flow(address),
+        };
+        flow.with_condition(self.cond() != Condition::AL)
+    }
 }
 
 pub fn generate_arm_lut() -> [ArmInstructionFactory; 4096] {
@@ -119,7 +140,7 @@ pub fn generate_arm_lut() -> [ArmInstructionFactory; 4096] {
     arm_lut
 }
 
-fn decode_arm(index: u32) -> ArmInstructionFactory {
+pub(crate) fn decode_arm(index: u32) -> ArmInstructionFactory {
     let pattern = index & 0x0FFFFFFF;
     let set_flags = pattern.bit(20);
     let opcode = pattern.bits(21..=24);
@@ -1,5 +1,5 @@
 use getset::CopyGetters;
-use ironboyadvance_common::{bits::BitOps, memory::MemoryAccess};
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow, memory::MemoryAccess};
 
 use crate::{
     Condition, CpuAction, CpuMode, Register,
@@ -144,7 +144,7 @@ impl Instruction for BlockDataTransfer {
         action
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let cond = self.cond;
         let pre_index = self.pre_index;
         let add = self.add;
@@ -195,4 +195,11 @@ impl Instruction for BlockDataTransfer {
 
         format!("{} {}{},({}){}", mnemonic, rn, write_back, register_list, load_psr_force_user)
     }
+
+    fn flow(&self, _address: u32) -> Flow {
+        match self.load && self.register_list.bit(PC) {
+            true => Flow::Indirect { conditional: false },
+            false => Flow::Next,
+        }
+    }
 }
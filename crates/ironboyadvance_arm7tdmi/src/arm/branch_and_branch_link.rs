@@ -5,7 +5,10 @@ use crate::{
     cpu::{Arm7tdmiCpu, Instruction, LR},
     memory::MemoryInterface,
 };
-use ironboyadvance_common::bits::{BitOps, SignExtend};
+use ironboyadvance_common::{
+    bits::{BitOps, SignExtend},
+    disassembly::Flow,
+};
 
 #[derive(Debug, Clone, Copy, CopyGetters)]
 pub struct BranchAndBranchWithLink {
@@ -26,6 +29,14 @@ impl BranchAndBranchWithLink {
     }
 }
 
+impl BranchAndBranchWithLink {
+    /// r15 is two instructions ahead of the branch when the offset is added.
+    fn target(&self, address: u32) -> u32 {
+        let offset = self.offset.sign_extend(24) << 2;
+        address.wrapping_add(8).wrapping_add(offset as u32)
+    }
+}
+
 impl Instruction for BranchAndBranchWithLink {
     fn execute<I: MemoryInterface>(&self, cpu: &mut Arm7tdmiCpu<I>) -> CpuAction {
         if self.link {
@@ -38,10 +49,24 @@ impl Instruction for BranchAndBranchWithLink {
         CpuAction::PipelineFlush
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, address: u32) -> String {
         let cond = self.cond();
         let link = if self.link { "L" } else { "" };
-        let expression = self.offset;
-        format!("B{link}{cond} 0x{expression:08X}")
+        let target = self.target(address);
+        format!("B{link}{cond} 0x{target:08X}")
+    }
+
+    fn flow(&self, address: u32) -> Flow {
+        let target = self.target(address);
+        match self.link {
+            true => Flow::Call {
+                target,
+                conditional: false,
+            },
+            false => Flow::Jump {
+                target,
+                conditional: false,
+            },
+        }
     }
 }
@@ -1,5 +1,5 @@
 use getset::CopyGetters;
-use ironboyadvance_common::bits::BitOps;
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow};
 
 use crate::{
     Condition, CpuAction, CpuState, Register,
@@ -33,9 +33,13 @@ impl Instruction for BranchAndExchange {
         CpuAction::PipelineFlush
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let cond = self.cond();
         let rn = self.rn;
         format!("BX{cond} {rn}")
     }
+
+    fn flow(&self, _address: u32) -> Flow {
+        Flow::Indirect { conditional: false }
+    }
 }
@@ -1,5 +1,5 @@
 use getset::CopyGetters;
-use ironboyadvance_common::{bits::BitOps, memory::MemoryAccess};
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow, memory::MemoryAccess};
 
 use crate::{
     Condition, CpuAction, DataProcessingOpcode, Register,
@@ -131,7 +131,7 @@ impl Instruction for DataProcessing {
         cpu_action
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         use DataProcessingOpcode::*;
 
         let cond = self.cond;
@@ -165,4 +165,14 @@ impl Instruction for DataProcessing {
             _ => format!("{opcode}{cond}{s} {rd},{rn},{operand_2}"),
         }
     }
+
+    fn flow(&self, _address: u32) -> Flow {
+        use DataProcessingOpcode::*;
+
+        match self.opcode {
+            CMP | CMN | TEQ | TST => Flow::Next,
+            _ if self.rd == Register::R15 => Flow::Indirect { conditional: false },
+            _ => Flow::Next,
+        }
+    }
 }
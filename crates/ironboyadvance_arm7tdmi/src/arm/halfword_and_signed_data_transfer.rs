@@ -154,7 +154,7 @@ impl Instruction for HalfwordAndSignedDataTransfer {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let cond = self.cond;
         let pre_index = self.pre_index;
         let add = if self.add { "+" } else { "-" };
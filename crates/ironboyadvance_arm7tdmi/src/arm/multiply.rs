@@ -81,7 +81,7 @@ impl Instruction for Multiply {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let cond = self.cond;
         let s = if self.sets_flags { "S" } else { "" };
         let rd = self.rd;
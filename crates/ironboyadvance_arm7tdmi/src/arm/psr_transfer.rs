@@ -109,27 +109,15 @@ impl Instruction for PsrTransfer {
         CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::Sequential)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let cond = self.cond;
-        let is_spsr = self.is_spsr;
-        let psr = match is_spsr {
+        let psr = match self.is_spsr {
             false => "CPSR",
-            true => match cpu.cpsr().mode() {
-                CpuMode::User | CpuMode::System => "CPSR",
-                CpuMode::Fiq => "SPSR_fiq",
-                CpuMode::Supervisor => "SPSR_svc",
-                CpuMode::Abort => "SPSR_abt",
-                CpuMode::Irq => "SPSR_irq",
-                CpuMode::Undefined => "SPSR_und",
-                CpuMode::Invalid => panic!("invalid mode"),
-            },
+            true => "SPSR",
         };
 
         match self.is_mrs {
-            true => {
-                let rd = self.rd as usize;
-                format!("MRS{} {},{}", cond, rd, psr)
-            }
+            true => format!("MRS{} {},{}", cond, self.rd, psr),
             false => {
                 let operand = match self.is_immediate {
                     false => format!("{}", self.rm),
@@ -140,16 +128,12 @@ impl Instruction for PsrTransfer {
                         format!("0x{:08X}", expression)
                     }
                 };
-
-                match is_spsr {
-                    false => {
-                        if cpu.cpsr().mode() == CpuMode::User {
-                            return format!("MSR{} {}_flg,{}", cond, psr, operand);
-                        }
-                        format!("MSR{} {}_all,{}", cond, psr, operand)
-                    }
-                    true => format!("MSR{} {},{}", cond, psr, operand),
-                }
+                let fields = match self.psr_mask {
+                    0xFF000000 => "flg",
+                    0x000000FF => "ctl",
+                    _ => "all",
+                };
+                format!("MSR{} {}_{},{}", cond, psr, fields, operand)
             }
         }
     }
@@ -65,7 +65,7 @@ impl Instruction for SingleDataSwap {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let cond = self.cond;
         let byte = if self.byte { "B" } else { "" };
         let rd = self.rd;
@@ -1,5 +1,5 @@
 use getset::CopyGetters;
-use ironboyadvance_common::{bits::BitOps, memory::MemoryAccess};
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow, memory::MemoryAccess};
 
 use crate::{
     Condition, CpuAction, Register,
@@ -122,7 +122,7 @@ impl Instruction for SingleDataTransfer {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, address: u32) -> String {
         let cond = self.cond;
         let pre_index = self.pre_index;
         let t = if pre_index { "" } else { "T" };
@@ -131,8 +131,15 @@ impl Instruction for SingleDataTransfer {
         let rn = self.rn;
         let rd = self.rd;
         let immediate = self.immediate;
-        let address = match rd as usize == 15 {
-            true => format!("#{:08X}", immediate),
+        let address = match self.rn == Register::R15 && self.is_immediate && pre_index {
+            true => {
+                let base = address.wrapping_add(8);
+                let literal = match self.add {
+                    true => base.wrapping_add(immediate),
+                    false => base.wrapping_sub(immediate),
+                };
+                format!("[0x{literal:08X}]")
+            }
             false => {
                 let offset = match self.is_immediate {
                     true => match immediate {
@@ -159,4 +166,11 @@ impl Instruction for SingleDataTransfer {
             false => format!("STR{}{}{} {},{}", cond, byte, t, rd, address),
         }
     }
+
+    fn flow(&self, _address: u32) -> Flow {
+        match self.load && self.rd == Register::R15 {
+            true => Flow::Indirect { conditional: false },
+            false => Flow::Next,
+        }
+    }
 }
@@ -1,8 +1,8 @@
 use getset::CopyGetters;
-use ironboyadvance_common::{bits::BitOps, memory::MemoryAccess};
+use ironboyadvance_common::bits::BitOps;
 
 use crate::{
-    Condition, CpuAction, Exception,
+    Condition, CpuAction,
     cpu::{Arm7tdmiCpu, Instruction},
     memory::MemoryInterface,
 };
@@ -26,16 +26,10 @@ impl SoftwareInterrupt {
 
 impl Instruction for SoftwareInterrupt {
     fn execute<I: MemoryInterface>(&self, cpu: &mut Arm7tdmiCpu<I>) -> CpuAction {
-        match !cpu.bios_loaded() && cpu.bios_call(self.comment >> 16) {
-            true => CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::Sequential),
-            false => {
-                cpu.exception(Exception::SoftwareInterrupt);
-                CpuAction::PipelineFlush
-            }
-        }
+        cpu.software_interrupt(self.comment >> 16)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let cond = self.cond;
         let comment = self.comment;
         format!("SWI{} 0x{:08X}", cond, comment)
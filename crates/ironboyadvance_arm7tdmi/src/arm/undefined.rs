@@ -1,5 +1,5 @@
 use getset::CopyGetters;
-use ironboyadvance_common::bits::BitOps;
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow};
 
 use crate::{
     Condition, CpuAction, Exception,
@@ -28,7 +28,11 @@ impl Instruction for Undefined {
         CpuAction::PipelineFlush
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         "Undefined".into()
     }
+
+    fn flow(&self, _address: u32) -> Flow {
+        Flow::Invalid
+    }
 }
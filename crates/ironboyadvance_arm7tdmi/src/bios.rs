@@ -0,0 +1,865 @@
+use std::f64::consts::TAU;
+
+use ironboyadvance_common::{
+    bits::BitOps,
+    call_stack::CallStack,
+    memory::{MemoryAccess, MemoryAccessWidth},
+};
+
+use crate::{
+    CpuAction, CpuMode, CpuState, Exception,
+    cpu::{Arm7tdmiCpu, LR, SP},
+    memory::MemoryInterface,
+};
+
+const NON_SEQUENTIAL: u8 = MemoryAccess::NonSequential as u8;
+const SEQUENTIAL: u8 = MemoryAccess::Sequential as u8;
+
+const DISPCNT: u32 = 0x04000000;
+const IE: u32 = 0x04000200;
+const IF: u32 = 0x04000202;
+const IME: u32 = 0x04000208;
+const HALTCNT: u32 = 0x04000301;
+/// Interrupts the game's handler has acknowledged to the BIOS, which IntrWait waits on.
+const BIOS_INTERRUPT_FLAGS: u32 = 0x03007FF8;
+/// The byte SoftReset checks to restart from EWRAM instead of the rom.
+const SOFT_RESET_FLAG: u32 = 0x03007FFA;
+const BIOS_CHECKSUM: u32 = 0xBAAE187F;
+
+/// Memory RegisterRamReset clears for bits 0-4 of r0, leaving the top 0x200 bytes of IWRAM to the BIOS.
+const RESET_MEMORY: [(u32, u32); 5] = [
+    (0x02000000, 0x40000),
+    (0x03000000, 0x7E00),
+    (0x05000000, 0x400),
+    (0x06000000, 0x18000),
+    (0x07000000, 0x400),
+];
+
+/// High level emulation of the BIOS functions, for when no BIOS image is loaded. Each one works on the
+/// registers and memory the way the BIOS routine does, without running its code.
+impl<I: MemoryInterface> Arm7tdmiCpu<I> {
+    /// Runs SWI `function`, emulated when there is no BIOS, and otherwise by entering the BIOS.
+    pub(crate) fn software_interrupt(&mut self, function: u32) -> CpuAction {
+        let action = match self.bios_loaded() {
+            true => None,
+            false => self.bios_call(function),
+        };
+        action.unwrap_or_else(|| {
+            self.exception(Exception::SoftwareInterrupt);
+            CpuAction::PipelineFlush
+        })
+    }
+
+    fn bios_call(&mut self, function: u32) -> Option<CpuAction> {
+        match function {
+            0x00 => return Some(self.bios_soft_reset()),
+            0x01 => self.bios_register_ram_reset(),
+            0x02 => self.store_8(HALTCNT, 0, NON_SEQUENTIAL),
+            0x03 => self.store_8(HALTCNT, 0x80, NON_SEQUENTIAL),
+            0x04 => return Some(self.bios_interrupt_wait()),
+            0x05 => {
+                self.set_register(0, 1);
+                self.set_register(1, 1);
+                return Some(self.bios_interrupt_wait());
+            }
+            0x06 => self.bios_divide(),
+            0x08 => self.bios_square_root(),
+            0x09 => self.bios_arc_tan(),
+            0x0A => self.bios_arc_tan_2(),
+            0x0B => self.bios_cpu_set(),
+            0x0C => self.bios_cpu_fast_set(),
+            0x0D => {
+                self.set_register(0, BIOS_CHECKSUM);
+                self.set_register(1, 1);
+                self.set_register(3, 0x4000);
+            }
+            0x0E => self.bios_bg_affine_set(),
+            0x0F => self.bios_obj_affine_set(),
+            0x10 => self.bios_bit_unpack(),
+            0x11 => self.bios_lz77_uncompress(MemoryAccessWidth::Byte),
+            0x12 => self.bios_lz77_uncompress(MemoryAccessWidth::HalfWord),
+            0x13 => self.bios_huffman_uncompress(),
+            0x14 => self.bios_run_length_uncompress(MemoryAccessWidth::Byte),
+            0x15 => self.bios_run_length_uncompress(MemoryAccessWidth::HalfWord),
+            0x16 => self.bios_diff_8_unfilter(MemoryAccessWidth::Byte),
+            0x17 => self.bios_diff_8_unfilter(MemoryAccessWidth::HalfWord),
+            0x18 => self.bios_diff_16_unfilter(),
+            0x1F => self.bios_midi_key_to_frequency(),
+            _ => return None,
+        }
+        Some(CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::Sequential))
+    }
+
+    /// SoftReset: clears the top 0x200 bytes of IWRAM and puts the registers back the way a boot without a
+    /// BIOS leaves them, restarting at the rom, or at EWRAM when the byte at 0x03007FFA is set.
+    fn bios_soft_reset(&mut self) -> CpuAction {
+        let entry = match self.load_8(SOFT_RESET_FLAG, NON_SEQUENTIAL) {
+            0 => 0x08000000,
+            _ => 0x02000000,
+        };
+        for address in (0x03007E00..0x03008000).step_by(4) {
+            self.store_32(address, 0, SEQUENTIAL);
+        }
+
+        for (mode, stack) in [(CpuMode::Supervisor, 0x03007FE0), (CpuMode::Irq, 0x03007FA0)] {
+            self.set_register_in_mode(mode, SP, stack);
+            self.set_register_in_mode(mode, LR, 0);
+            self.set_spsr_in_mode(mode, 0);
+        }
+        let cpsr = self.cpsr_mut();
+        cpsr.set_mode(CpuMode::System);
+        cpsr.set_state(CpuState::Arm);
+        cpsr.set_irq_disable(false);
+        for index in 0..=12 {
+            self.set_register(index, 0);
+        }
+        self.set_register(SP, 0x03007F00);
+        self.set_register(LR, entry);
+        self.set_interrupt_wait(false);
+        self.set_call_stack(CallStack::new());
+        self.jump(entry);
+        CpuAction::PipelineFlush
+    }
+
+    /// RegisterRamReset: bits 0-4 of r0 clear EWRAM, IWRAM, palette, VRAM and OAM, bit 5 resets the serial
+    /// registers, bit 6 the sound registers and bit 7 the rest. The display is always left force blanked.
+    fn bios_register_ram_reset(&mut self) {
+        let flags = self.register(0);
+        self.store_16(DISPCNT, 0x80, NON_SEQUENTIAL);
+        for (bit, (start, size)) in RESET_MEMORY.into_iter().enumerate() {
+            if flags.bit(bit) {
+                self.bios_clear(start, size);
+            }
+        }
+        if flags.bit(5) {
+            self.bios_clear(0x04000120, 0x10);
+            self.store_16(0x04000134, 0x8000, NON_SEQUENTIAL);
+            self.bios_clear(0x04000140, 0x4);
+            self.bios_clear(0x04000150, 0xC);
+        }
+        if flags.bit(6) {
+            self.bios_clear(0x04000060, 0x28);
+            self.bios_clear(0x04000090, 0x18);
+        }
+        if flags.bit(7) {
+            self.bios_clear(0x04000004, 0x5C);
+            for affine in [0x04000020, 0x04000026, 0x04000030, 0x04000036] {
+                self.store_16(affine, 0x100, NON_SEQUENTIAL);
+            }
+            self.bios_clear(0x040000B0, 0x30);
+            self.bios_clear(0x04000100, 0x10);
+            self.store_16(IE, 0, NON_SEQUENTIAL);
+            self.store_16(IF, 0xFFFF, NON_SEQUENTIAL);
+            self.bios_clear(0x04000204, 0x8);
+        }
+    }
+
+    fn bios_clear(&mut self, start: u32, size: u32) {
+        for address in (start..start + size).step_by(4) {
+            self.store_32(address, 0, SEQUENTIAL);
+        }
+    }
+
+    /// IntrWait: waits for one of the interrupts in r1 to be acknowledged in the BIOS interrupt flags, which
+    /// the game's interrupt handler sets. With r0 set the flags already there are discarded first, so only a
+    /// new interrupt ends the wait. The BIOS halts in a loop, here the SWI halts and runs again once an
+    /// interrupt has woken the cpu and been handled.
+    fn bios_interrupt_wait(&mut self) -> CpuAction {
+        let wanted = self.register(1) as u16;
+        let mut flags = self.load_16(BIOS_INTERRUPT_FLAGS, NON_SEQUENTIAL) as u16;
+        if self.register(0) != 0 && !self.interrupt_wait() {
+            flags &= !wanted;
+            self.store_16(BIOS_INTERRUPT_FLAGS, flags, NON_SEQUENTIAL);
+        } else if flags & wanted != 0 {
+            self.store_16(BIOS_INTERRUPT_FLAGS, flags & !wanted, NON_SEQUENTIAL);
+            self.set_interrupt_wait(false);
+            return CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::Sequential);
+        }
+
+        self.store_16(IME, 1, NON_SEQUENTIAL);
+        self.store_8(HALTCNT, 0, NON_SEQUENTIAL);
+        self.set_interrupt_wait(true);
+        self.jump(self.instruction_address());
+        CpuAction::PipelineFlush
+    }
+
+    fn bios_divide(&mut self) {
+        let numerator = self.register(0) as i32;
+        let denominator = self.register(1) as i32;
+
+        let (quotient, remainder, absolute_quotient) = match denominator {
+            0 => match numerator < 0 {
+                true => (-1, numerator, 1),
+                false => (1, numerator, 1),
+            },
+            _ => {
+                let quotient = numerator.wrapping_div(denominator);
+                (quotient, numerator.wrapping_rem(denominator), quotient.wrapping_abs())
+            }
+        };
+
+        self.set_register(0, quotient as u32);
+        self.set_register(1, remainder as u32);
+        self.set_register(3, absolute_quotient as u32);
+    }
+
+    fn bios_square_root(&mut self) {
+        let value = self.register(0);
+        self.set_register(0, (value as f64).sqrt() as u32);
+    }
+
+    /// ArcTan: r0 is a tangent in 1.14 fixed point and comes back as an angle from -0x4000 to 0x4000 for
+    /// -pi/2 to pi/2, worked out with the BIOS's polynomial, whose last terms stay in r1 and r3.
+    fn bios_arc_tan(&mut self) {
+        let (angle, square, polynomial) = arc_tan(self.register(0) as i32);
+        self.set_register(0, angle as u32);
+        self.set_register(1, square as u32);
+        self.set_register(3, polynomial as u32);
+    }
+
+    /// ArcTan2: the angle of the point (r0, r1), signed 2.14 fixed point, from 0 to 0xFFFF for a full turn.
+    fn bios_arc_tan_2(&mut self) {
+        let x = self.register(0) as i16 as i32;
+        let y = self.register(1) as i16 as i32;
+        let axis = |angle| (angle, self.register(1) as i32);
+        let (angle, square) = match (x, y) {
+            (0.., 0) => axis(0),
+            (_, 0) => axis(0x8000),
+            (0, 0..) => axis(0x4000),
+            (0, _) => axis(0xC000),
+            _ => {
+                let (tangent, base, sign) = match (y >= 0, x >= 0) {
+                    (true, true) if x >= y => ((y << 14) / x, 0, 1),
+                    (true, false) if -x >= y => ((y << 14) / x, 0x8000, 1),
+                    (true, _) => ((x << 14) / y, 0x4000, -1),
+                    (false, false) if -x > -y => ((y << 14) / x, 0x8000, 1),
+                    (false, true) if x >= -y => ((y << 14) / x, 0x10000, 1),
+                    (false, _) => ((x << 14) / y, 0xC000, -1),
+                };
+                let (angle, square, _) = arc_tan(tangent);
+                (base + sign * angle, square)
+            }
+        };
+        self.set_register(0, angle as u32 & 0xFFFF);
+        self.set_register(1, square as u32);
+        self.set_register(3, 0x170);
+    }
+
+    /// BgAffineSet: turns r2 entries at r0 of a center in texture space (two 19.8 words), its position on
+    /// screen (two halfwords), a 8.8 scale for each axis and an angle into the BG2/BG3 parameters at r1,
+    /// pa-pd then the reference point.
+    fn bios_bg_affine_set(&mut self) {
+        let (mut source, mut destination) = (self.register(0), self.register(1));
+        for _ in 0..self.register(2) {
+            let origin_x = self.load_32(source, NON_SEQUENTIAL) as i32;
+            let origin_y = self.load_32(source.wrapping_add(4), SEQUENTIAL) as i32;
+            let center_x = self.load_16(source.wrapping_add(8), SEQUENTIAL) as i16 as i32;
+            let center_y = self.load_16(source.wrapping_add(10), SEQUENTIAL) as i16 as i32;
+            let scale_x = self.load_16(source.wrapping_add(12), SEQUENTIAL) as i16 as i32;
+            let scale_y = self.load_16(source.wrapping_add(14), SEQUENTIAL) as i16 as i32;
+            let angle = self.load_16(source.wrapping_add(16), SEQUENTIAL) as u16;
+            source = source.wrapping_add(20);
+
+            let [pa, pb, pc, pd] = affine_matrix(scale_x, scale_y, angle);
+            let x = origin_x.wrapping_sub(pa.wrapping_mul(center_x).wrapping_add(pb.wrapping_mul(center_y)));
+            let y = origin_y.wrapping_sub(pc.wrapping_mul(center_x).wrapping_add(pd.wrapping_mul(center_y)));
+            for (offset, parameter) in [pa, pb, pc, pd].into_iter().enumerate() {
+                self.store_16(destination.wrapping_add(offset as u32 * 2), parameter as u16, SEQUENTIAL);
+            }
+            self.store_32(destination.wrapping_add(8), x as u32, SEQUENTIAL);
+            self.store_32(destination.wrapping_add(12), y as u32, SEQUENTIAL);
+            destination = destination.wrapping_add(16);
+        }
+    }
+
+    /// ObjAffineSet: turns r2 entries at r0 of a 8.8 scale for each axis and an angle into pa-pd at r1, r3
+    /// bytes apart, 2 for a plain array and 8 to write straight into OAM.
+    fn bios_obj_affine_set(&mut self) {
+        let (mut source, mut destination, stride) = (self.register(0), self.register(1), self.register(3));
+        for _ in 0..self.register(2) {
+            let scale_x = self.load_16(source, NON_SEQUENTIAL) as i16 as i32;
+            let scale_y = self.load_16(source.wrapping_add(2), SEQUENTIAL) as i16 as i32;
+            let angle = self.load_16(source.wrapping_add(4), SEQUENTIAL) as u16;
+            source = source.wrapping_add(8);
+
+            for parameter in affine_matrix(scale_x, scale_y, angle) {
+                self.store_16(destination, parameter as u16, SEQUENTIAL);
+                destination = destination.wrapping_add(stride);
+            }
+        }
+    }
+
+    /// MidiKey2Freq: the sample rate that plays the sound at r0 as midi key r1, plus r2/256 of a semitone.
+    fn bios_midi_key_to_frequency(&mut self) {
+        let frequency = self.load_32(self.register(0).wrapping_add(4), NON_SEQUENTIAL) as f32;
+        let key = self.register(1) as f32 + self.register(2) as f32 / 256.0;
+        self.set_register(0, (frequency / ((180.0 - key) / 12.0).exp2()) as u32);
+    }
+
+    /// CpuSet: r2 holds the unit count in bits 0-20, bit 24 to fill with the first unit instead of copying
+    /// and bit 26 for words instead of halfwords. r0 and r1 end past what was read and written.
+    fn bios_cpu_set(&mut self) {
+        let control = self.register(2);
+        let count = control.bits(0..=20);
+        let width = match control.bit(26) {
+            true => MemoryAccessWidth::Word,
+            false => MemoryAccessWidth::HalfWord,
+        };
+        self.bios_copy(count, width, control.bit(24));
+    }
+
+    /// CpuFastSet: like CpuSet but always words, copied in blocks of eight so the count rounds up.
+    fn bios_cpu_fast_set(&mut self) {
+        let control = self.register(2);
+        let count = control.bits(0..=20).next_multiple_of(8);
+        self.bios_copy(count, MemoryAccessWidth::Word, control.bit(24));
+    }
+
+    fn bios_copy(&mut self, count: u32, width: MemoryAccessWidth, fill: bool) {
+        let unit = unit_size(width);
+        let source = self.register(0) & !(unit - 1);
+        let destination = self.register(1) & !(unit - 1);
+        if !readable_source(source) {
+            return;
+        }
+
+        let mut value = 0;
+        for index in 0..count {
+            let access = match index {
+                0 => NON_SEQUENTIAL,
+                _ => SEQUENTIAL,
+            };
+            if index == 0 || !fill {
+                value = self.load_unit(source.wrapping_add(index.wrapping_mul(unit)), width, access);
+            }
+            self.store_unit(destination.wrapping_add(index.wrapping_mul(unit)), value, width, access);
+        }
+
+        let read = match fill {
+            true => 0,
+            false => count.wrapping_mul(unit),
+        };
+        self.set_register(0, source.wrapping_add(read));
+        self.set_register(1, destination.wrapping_add(count.wrapping_mul(unit)));
+    }
+
+    /// BitUnPack: r2 points at the unpack info, the source length in bytes, the source and destination unit
+    /// widths in bits, and an offset added to every unit, or only non zero ones unless bit 31 is set.
+    fn bios_bit_unpack(&mut self) {
+        let (mut source, mut destination, info) = (self.register(0), self.register(1), self.register(2));
+        let length = self.load_16(info, NON_SEQUENTIAL);
+        let source_width = self.load_8(info.wrapping_add(2), SEQUENTIAL);
+        let destination_width = self.load_8(info.wrapping_add(3), SEQUENTIAL);
+        let offset = self.load_32(info.wrapping_add(4), SEQUENTIAL);
+        if !readable_source(source)
+            || ![1, 2, 4, 8].contains(&source_width)
+            || ![1, 2, 4, 8, 16, 32].contains(&destination_width)
+        {
+            return;
+        }
+
+        let source_mask = (1 << source_width) - 1;
+        let destination_mask = u32::MAX >> (32 - destination_width);
+        let (mut word, mut filled) = (0, 0);
+        for _ in 0..length {
+            let byte = self.load_8(source, SEQUENTIAL);
+            source = source.wrapping_add(1);
+            for shift in (0..8).step_by(source_width as usize) {
+                let mut unit = (byte >> shift) & source_mask;
+                if unit != 0 || offset.bit(31) {
+                    unit = unit.wrapping_add(offset.bits(0..=30));
+                }
+                word |= (unit & destination_mask) << filled;
+                filled += destination_width;
+                if filled == 32 {
+                    self.store_32(destination, word, SEQUENTIAL);
+                    destination = destination.wrapping_add(4);
+                    (word, filled) = (0, 0);
+                }
+            }
+        }
+        self.set_register(0, source);
+        self.set_register(1, destination);
+    }
+
+    /// LZ77UnCompWram/Vram: after each flag byte come eight blocks, first one in bit 7, that are either a
+    /// literal byte or a 3-18 byte run copied from up to 4096 bytes back in the output.
+    fn bios_lz77_uncompress(&mut self, width: MemoryAccessWidth) {
+        let Some((mut source, size)) = self.bios_uncompress_header() else {
+            return;
+        };
+
+        let mut output = Vec::with_capacity(size);
+        while output.len() < size {
+            let flags = self.load_8(source, SEQUENTIAL);
+            source = source.wrapping_add(1);
+            for block in (0..8).rev() {
+                if output.len() >= size {
+                    break;
+                }
+                match flags.bit(block) {
+                    true => {
+                        let high = self.load_8(source, SEQUENTIAL) as usize;
+                        let low = self.load_8(source.wrapping_add(1), SEQUENTIAL) as usize;
+                        source = source.wrapping_add(2);
+                        let displacement = ((high & 0xF) << 8 | low) + 1;
+                        for _ in 0..(high >> 4) + 3 {
+                            if output.len() >= size {
+                                break;
+                            }
+                            let byte = output.len().checked_sub(displacement).map_or(0, |index| output[index]);
+                            output.push(byte);
+                        }
+                    }
+                    false => {
+                        output.push(self.load_8(source, SEQUENTIAL) as u8);
+                        source = source.wrapping_add(1);
+                    }
+                }
+            }
+        }
+        self.bios_write_uncompressed(source, &output, width);
+    }
+
+    /// HuffUnComp: the header's low nibble is the data width, 4 or 8 bits. The tree follows it, a size byte
+    /// then nodes whose bits 0-5 are the offset to their children and bits 7 and 6 mark the left and right
+    /// child as data. After the tree comes the bitstream in words, read from bit 31 down. A walk from the root
+    /// longer than the tree has nodes has left a malformed tree, which stops the decoding where the BIOS
+    /// would wander on through memory.
+    fn bios_huffman_uncompress(&mut self) {
+        let header_address = self.register(0);
+        if !readable_source(header_address) {
+            return;
+        }
+
+        let header = self.load_32(header_address, NON_SEQUENTIAL);
+        let size = (header >> 8) as usize;
+        let data_width = match header & 0xF {
+            4 => 4,
+            _ => 8,
+        };
+        let tree_size = self.load_8(header_address.wrapping_add(4), SEQUENTIAL);
+        let root = header_address.wrapping_add(5);
+        let mut stream = header_address.wrapping_add(4 + (tree_size + 1) * 2);
+        let nodes = (tree_size + 1) * 2 - 1;
+        // Every unit of output takes at most one bit per node of the tree.
+        let symbols = (size * 8).div_ceil(data_width as usize);
+        let words = (symbols * nodes as usize).div_ceil(32);
+
+        let mut output = Vec::with_capacity(size + 3);
+        let (mut word, mut filled, mut depth) = (0u32, 0, 0);
+        let (mut node_address, mut node) = (root, self.load_8(root, SEQUENTIAL));
+        'stream: for _ in 0..words {
+            let bits = self.load_32(stream, SEQUENTIAL);
+            stream = stream.wrapping_add(4);
+            for bit in (0..32).rev() {
+                let right = bits.bit(bit);
+                let child = (node_address & !1).wrapping_add(node.bits(0..=5) * 2 + 2 + right as u32);
+                let is_data = match right {
+                    true => node.bit(6),
+                    false => node.bit(7),
+                };
+                match is_data {
+                    true => {
+                        let data = self.load_8(child, SEQUENTIAL) & ((1 << data_width) - 1);
+                        word |= data << filled;
+                        filled += data_width;
+                        depth = 0;
+                        (node_address, node) = (root, self.load_8(root, SEQUENTIAL));
+                        if filled == 32 {
+                            output.extend(word.to_le_bytes());
+                            (word, filled) = (0, 0);
+                            if output.len() >= size {
+                                break 'stream;
+                            }
+                        }
+                    }
+                    false => {
+                        depth += 1;
+                        if depth > nodes {
+                            break 'stream;
+                        }
+                        (node_address, node) = (child, self.load_8(child, SEQUENTIAL));
+                    }
+                }
+            }
+        }
+        self.bios_write_uncompressed(stream, &output, MemoryAccessWidth::Word);
+    }
+
+    /// RLUnCompWram/Vram: each flag byte starts either a run of one byte repeated 3-130 times, when bit 7 is
+    /// set, or 1-128 literal bytes.
+    fn bios_run_length_uncompress(&mut self, width: MemoryAccessWidth) {
+        let Some((mut source, size)) = self.bios_uncompress_header() else {
+            return;
+        };
+
+        let mut output = Vec::with_capacity(size + 130);
+        while output.len() < size {
+            let flag = self.load_8(source, SEQUENTIAL);
+            source = source.wrapping_add(1);
+            match flag.bit(7) {
+                true => {
+                    let byte = self.load_8(source, SEQUENTIAL) as u8;
+                    source = source.wrapping_add(1);
+                    output.extend(std::iter::repeat_n(byte, flag.bits(0..=6) as usize + 3));
+                }
+                false => {
+                    for _ in 0..flag.bits(0..=6) + 1 {
+                        output.push(self.load_8(source, SEQUENTIAL) as u8);
+                        source = source.wrapping_add(1);
+                    }
+                }
+            }
+        }
+        output.truncate(size);
+        self.bios_write_uncompressed(source, &output, width);
+    }
+
+    /// Diff8bitUnFilterWram/Vram: every byte after the first is the difference to the one before it.
+    fn bios_diff_8_unfilter(&mut self, width: MemoryAccessWidth) {
+        let Some((mut source, size)) = self.bios_uncompress_header() else {
+            return;
+        };
+
+        let mut output = Vec::with_capacity(size);
+        let mut value = 0u8;
+        while output.len() < size {
+            value = value.wrapping_add(self.load_8(source, SEQUENTIAL) as u8);
+            source = source.wrapping_add(1);
+            output.push(value);
+        }
+        self.bios_write_uncompressed(source, &output, width);
+    }
+
+    /// Diff16bitUnFilter: the halfword version of Diff8bitUnFilter.
+    fn bios_diff_16_unfilter(&mut self) {
+        let Some((mut source, size)) = self.bios_uncompress_header() else {
+            return;
+        };
+
+        let mut output = Vec::with_capacity(size + 1);
+        let mut value = 0u16;
+        while output.len() < size {
+            value = value.wrapping_add(self.load_16(source, SEQUENTIAL) as u16);
+            source = source.wrapping_add(2);
+            output.extend(value.to_le_bytes());
+        }
+        self.bios_write_uncompressed(source, &output, MemoryAccessWidth::HalfWord);
+    }
+
+    /// Reads the header word every compressed format starts with, returning the address after it and the
+    /// uncompressed size, or None when the source is inside the BIOS, which refuses to read itself.
+    fn bios_uncompress_header(&mut self) -> Option<(u32, usize)> {
+        let source = self.register(0);
+        if !readable_source(source) {
+            return None;
+        }
+        let header = self.load_32(source, NON_SEQUENTIAL);
+        Some((source.wrapping_add(4), (header >> 8) as usize))
+    }
+
+    /// Stores uncompressed data at r1 in units of `width`, the VRAM variants writing halfwords since VRAM
+    /// ignores byte stores. A trailing part unit is never written, as the BIOS only stores whole ones. r0 and
+    /// r1 end past what was read and written and r3 is cleared.
+    fn bios_write_uncompressed(&mut self, source_end: u32, output: &[u8], width: MemoryAccessWidth) {
+        let mut destination = self.register(1);
+        for (index, chunk) in output.chunks_exact(unit_size(width) as usize).enumerate() {
+            let value = chunk.iter().rev().fold(0, |value, &byte| value << 8 | byte as u32);
+            let access = match index {
+                0 => NON_SEQUENTIAL,
+                _ => SEQUENTIAL,
+            };
+            self.store_unit(destination, value, width, access);
+            destination = destination.wrapping_add(chunk.len() as u32);
+        }
+        self.set_register(0, source_end);
+        self.set_register(1, destination);
+        self.set_register(3, 0);
+    }
+
+    fn load_unit(&mut self, address: u32, width: MemoryAccessWidth, access: u8) -> u32 {
+        match width {
+            MemoryAccessWidth::Byte => self.load_8(address, access),
+            MemoryAccessWidth::HalfWord => self.load_16(address, access),
+            MemoryAccessWidth::Word => self.load_32(address, access),
+        }
+    }
+
+    fn store_unit(&mut self, address: u32, value: u32, width: MemoryAccessWidth, access: u8) {
+        match width {
+            MemoryAccessWidth::Byte => self.store_8(address, value as u8, access),
+            MemoryAccessWidth::HalfWord => self.store_16(address, value as u16, access),
+            MemoryAccessWidth::Word => self.store_32(address, value, access),
+        }
+    }
+}
+
+/// The BIOS's arctangent polynomial of a 1.14 tangent, with the squared tangent and polynomial it leaves in
+/// r1 and r3.
+fn arc_tan(tangent: i32) -> (i32, i32, i32) {
+    let square = -(tangent.wrapping_mul(tangent) >> 14);
+    let polynomial = [0x390, 0x91C, 0xFB6, 0x16AA, 0x2081, 0x3651, 0xA2F9]
+        .into_iter()
+        .fold(0xA9, |polynomial, term| ((polynomial * square) >> 14) + term);
+    (tangent.wrapping_mul(polynomial) >> 16, square, polynomial)
+}
+
+/// Sine of the BIOS angle `index`, a 256th of a turn, in 1.14 fixed point as in the BIOS's sine table.
+fn sine(index: u16) -> i32 {
+    (((index & 0xFF) as f64 * TAU / 256.0).sin() * 16384.0).round() as i32
+}
+
+/// pa, pb, pc and pd in 8.8 fixed point scaling by `scale_x` and `scale_y` and rotating by the top byte of
+/// `angle`.
+fn affine_matrix(scale_x: i32, scale_y: i32, angle: u16) -> [i32; 4] {
+    let index = angle >> 8;
+    let (sin, cos) = (sine(index), sine(index + 64));
+    [
+        (scale_x * cos) >> 14,
+        -(scale_x * sin) >> 14,
+        (scale_y * sin) >> 14,
+        (scale_y * cos) >> 14,
+    ]
+}
+
+fn unit_size(width: MemoryAccessWidth) -> u32 {
+    match width {
+        MemoryAccessWidth::Byte => 1,
+        MemoryAccessWidth::HalfWord => 2,
+        MemoryAccessWidth::Word => 4,
+    }
+}
+
+/// The BIOS copy and decompression functions do nothing when asked to read the BIOS itself.
+fn readable_source(address: u32) -> bool {
+    address & 0x0E00_0000 != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::memory::CpuContext;
+
+    use super::*;
+
+    const RAM: u32 = 0x02000000;
+
+    struct Ram {
+        memory: Vec<u8>,
+        cpu_context: CpuContext,
+    }
+
+    impl Ram {
+        fn offset(address: u32) -> usize {
+            (address & 0xFFFF) as usize
+        }
+
+        fn bytes(&self, address: u32, length: usize) -> &[u8] {
+            &self.memory[Self::offset(address)..Self::offset(address) + length]
+        }
+    }
+
+    impl MemoryInterface for Ram {
+        fn load_8(&mut self, address: u32, _access_pattern: u8) -> u32 {
+            self.memory[Self::offset(address)] as u32
+        }
+
+        fn load_16(&mut self, address: u32, _access_pattern: u8) -> u32 {
+            let address = address & !1;
+            self.load_8(address, 0) | self.load_8(address + 1, 0) << 8
+        }
+
+        fn load_32(&mut self, address: u32, _access_pattern: u8) -> u32 {
+            let address = address & !3;
+            self.load_16(address, 0) | self.load_16(address + 2, 0) << 16
+        }
+
+        fn store_8(&mut self, address: u32, value: u8, _access_pattern: u8) {
+            self.memory[Self::offset(address)] = value;
+        }
+
+        fn store_16(&mut self, address: u32, value: u16, _access_pattern: u8) {
+            let address = address & !1;
+            self.memory[Self::offset(address)..Self::offset(address) + 2].copy_from_slice(&value.to_le_bytes());
+        }
+
+        fn store_32(&mut self, address: u32, value: u32, _access_pattern: u8) {
+            let address = address & !3;
+            self.memory[Self::offset(address)..Self::offset(address) + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        fn idle_cycle(&mut self) {}
+
+        fn cpu_context_mut(&mut self) -> &mut CpuContext {
+            &mut self.cpu_context
+        }
+    }
+
+    /// Places `data` at 0x02000000 and calls BIOS `function` with the given registers from r0 up.
+    fn call<const N: usize>(function: u32, data: &[u8], registers: [u32; N]) -> Arm7tdmiCpu<Ram> {
+        let mut memory = vec![0; 0x10000];
+        memory[..data.len()].copy_from_slice(data);
+        let mut cpu = Arm7tdmiCpu::new(
+            Ram {
+                memory,
+                cpu_context: CpuContext::default(),
+            },
+            false,
+            false,
+        );
+        for (index, value) in registers.into_iter().enumerate() {
+            cpu.set_register(index, value);
+        }
+        assert!(cpu.bios_call(function).is_some());
+        cpu
+    }
+
+    fn header(kind: u32, size: u32) -> Vec<u8> {
+        (size << 8 | kind).to_le_bytes().to_vec()
+    }
+
+    #[test]
+    fn cpu_set_copies_and_fills() {
+        let data = [1, 2, 3, 4, 5, 6, 7, 8];
+        let cpu = call(0x0B, &data, [RAM + 1, RAM + 0x100, 3]);
+        assert_eq!(
+            cpu.bus().bytes(RAM + 0x100, 8),
+            [1, 2, 3, 4, 5, 6, 0, 0],
+            "halfwords, aligned down"
+        );
+        assert_eq!((cpu.register(0), cpu.register(1)), (RAM + 6, RAM + 0x106));
+
+        let cpu = call(0x0B, &data, [RAM, RAM + 0x100, 1 << 26 | 1 << 24 | 2]);
+        assert_eq!(cpu.bus().bytes(RAM + 0x100, 12), [1, 2, 3, 4, 1, 2, 3, 4, 0, 0, 0, 0]);
+        assert_eq!(
+            (cpu.register(0), cpu.register(1)),
+            (RAM, RAM + 0x108),
+            "a fill keeps reading r0"
+        );
+
+        let cpu = call(0x0C, &data, [RAM, RAM + 0x100, 1 << 24 | 1]);
+        assert_eq!(
+            cpu.bus().bytes(RAM + 0x11C, 8),
+            [1, 2, 3, 4, 0, 0, 0, 0],
+            "rounds up to eight words"
+        );
+        assert_eq!(cpu.register(1), RAM + 0x120);
+
+        let cpu = call(0x0B, &data, [0x100, RAM + 0x100, 4]);
+        assert_eq!(cpu.bus().bytes(RAM + 0x100, 8), [0; 8], "refuses to copy out of the BIOS");
+
+        let cpu = call(0x0B, &data, [RAM, 0xFFFFFFFC, 1 << 26 | 2]);
+        assert_eq!(cpu.register(1), 4, "the destination wraps around the address space");
+    }
+
+    #[test]
+    fn lz77_and_run_length_uncompress() {
+        let mut data = header(0x10, 10);
+        data.extend([0b0001_0000, b'a', b'b', b'c', 0x30, 0x02, b'X']);
+        for function in [0x11, 0x12] {
+            let cpu = call(function, &data, [RAM, RAM + 0x100, 0]);
+            assert_eq!(cpu.bus().bytes(RAM + 0x100, 11), b"abcabcabcX\0");
+            assert_eq!((cpu.register(0), cpu.register(1)), (RAM + 11, RAM + 0x10A));
+        }
+
+        let mut data = header(0x30, 7);
+        data.extend([0x82, b'a', 0x01, b'b', b'c']);
+        let cpu = call(0x14, &data, [RAM, RAM + 0x100, 0]);
+        assert_eq!(cpu.bus().bytes(RAM + 0x100, 8), b"aaaaabc\0");
+        let cpu = call(0x15, &data, [RAM, RAM + 0x100, 0]);
+        assert_eq!(cpu.bus().bytes(RAM + 0x100, 8), b"aaaaab\0\0", "VRAM never gets the odd byte");
+        assert_eq!(cpu.register(1), RAM + 0x106);
+    }
+
+    #[test]
+    fn huffman_uncompress_walks_the_tree() {
+        let mut data = header(0x28, 4);
+        data.extend([1, 0xC0, b'A', b'B']);
+        data.extend(0x6000_0000u32.to_le_bytes());
+        let cpu = call(0x13, &data, [RAM, RAM + 0x100, 0]);
+        assert_eq!(cpu.bus().bytes(RAM + 0x100, 4), b"ABBA");
+        assert_eq!((cpu.register(0), cpu.register(1)), (RAM + 12, RAM + 0x104));
+
+        let mut data = header(0x24, 4);
+        data.extend([1, 0xC0, 0x3, 0xC]);
+        data.extend(0xAAAA_AAAAu32.to_le_bytes());
+        let cpu = call(0x13, &data, [RAM, RAM + 0x100, 0]);
+        assert_eq!(
+            cpu.bus().bytes(RAM + 0x100, 4),
+            [0x3C; 4],
+            "packs 4 bit data low nibble first"
+        );
+    }
+
+    #[test]
+    fn huffman_uncompress_stops_on_a_tree_without_data() {
+        let mut data = header(0x28, 0xFFFFFF);
+        data.extend([1, 0x00, 0x00, 0x00]);
+        data.extend(0u32.to_le_bytes());
+        let cpu = call(0x13, &data, [RAM, RAM + 0x100, 0]);
+        assert_eq!(cpu.bus().bytes(RAM + 0x100, 4), [0; 4]);
+        assert_eq!((cpu.register(0), cpu.register(1)), (RAM + 12, RAM + 0x100));
+    }
+
+    #[test]
+    fn unfilters_and_unpacks_bits() {
+        let mut data = header(0x81, 4);
+        data.extend([1, 1, 2, 0xFF]);
+        let cpu = call(0x16, &data, [RAM, RAM + 0x100, 0]);
+        assert_eq!(cpu.bus().bytes(RAM + 0x100, 4), [1, 2, 4, 3]);
+
+        let mut data = header(0x82, 4);
+        data.extend([0x00, 0x01, 0x01, 0x00]);
+        let cpu = call(0x18, &data, [RAM, RAM + 0x100, 0]);
+        assert_eq!(cpu.bus().bytes(RAM + 0x100, 4), [0x00, 0x01, 0x01, 0x01]);
+
+        let data = [0b1110_0100, 0, 0, 0, 1, 0, 2, 8, 0x10, 0, 0, 0];
+        let cpu = call(0x10, &data, [RAM, RAM + 0x100, RAM + 4]);
+        assert_eq!(cpu.bus().bytes(RAM + 0x100, 4), [0, 0x11, 0x12, 0x13], "zero stays zero");
+        assert_eq!((cpu.register(0), cpu.register(1)), (RAM + 1, RAM + 0x104));
+    }
+
+    #[test]
+    fn arc_tangents_cover_every_quadrant() {
+        let angle = |x: i32, y: i32| call(0x0A, &[], [x as u32, y as u32, 0]).register(0);
+        assert_eq!(angle(0x4000, 0), 0);
+        assert_eq!(angle(0, -0x4000), 0xC000);
+        for (x, y, expected) in [(1, 1, 0x2000), (-1, 1, 0x6000), (-1, -1, 0xA000), (1, -1, 0xE000)] {
+            let angle = angle(x * 0x1000, y * 0x1000);
+            assert!(angle.abs_diff(expected) <= 8, "({x}, {y}) is at {angle:#X}");
+        }
+
+        let cpu = call(0x09, &[], [0x4000, 0, 0]);
+        assert!(
+            cpu.register(0).abs_diff(0x2000) <= 8,
+            "arctan(1) is pi/4, not {:#X}",
+            cpu.register(0)
+        );
+        assert_eq!(cpu.register(1), (-0x4000i32) as u32);
+    }
+
+    #[test]
+    fn affine_sets_scale_and_rotate() {
+        let mut data = Vec::new();
+        data.extend(0x1000i32.to_le_bytes());
+        data.extend(0x2000i32.to_le_bytes());
+        for halfword in [120i16, 80, 0x200, 0x100, 0x4000] {
+            data.extend(halfword.to_le_bytes());
+        }
+        let cpu = call(0x0E, &data, [RAM, RAM + 0x100, 1]);
+        let parameters: Vec<i16> = cpu
+            .bus()
+            .bytes(RAM + 0x100, 8)
+            .chunks(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        assert_eq!(parameters, [0, -0x200, 0x100, 0], "a quarter turn");
+        assert_eq!(cpu.bus().bytes(RAM + 0x108, 8), [0x00, 0xB0, 0, 0, 0x00, 0xA8, 0xFF, 0xFF]);
+
+        let data = [0x00, 0x01, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let cpu = call(0x0F, &data, [RAM, RAM + 0x100, 1, 8]);
+        assert_eq!(cpu.bus().bytes(RAM + 0x100, 2), [0x00, 0x01]);
+        assert_eq!(cpu.bus().bytes(RAM + 0x108, 2), [0x00, 0x00]);
+        assert_eq!(cpu.bus().bytes(RAM + 0x118, 2), [0x80, 0x00], "pd lands 8 bytes after pc");
+    }
+}
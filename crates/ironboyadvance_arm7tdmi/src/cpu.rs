@@ -10,6 +10,12 @@ use crate::{
 };
 
 use super::{CpuMode, CpuState, psr::ProgramStatusRegister};
+use ironboyadvance_common::{
+    call_stack::{CallStack, Frame, FrameKind},
+    disassembly::Flow,
+    snapshot::{Snapshot, SnapshotError, StateReader, StateWriter},
+    trace::TraceFormat,
+};
 
 pub(crate) const SP: usize = 13;
 pub(crate) const LR: usize = 14;
@@ -23,7 +29,13 @@ pub enum LastInstruction {
 
 pub(crate) trait Instruction {
     fn execute<I: MemoryInterface>(&self, cpu: &mut Arm7tdmiCpu<I>) -> CpuAction;
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Arm7tdmiCpu<I>) -> String;
+    /// Disassembles the instruction as if it sat at `address`, which resolves pc relative operands.
+    fn disassemble(&self, address: u32) -> String;
+
+    /// Where execution can go after the instruction at `address`.
+    fn flow(&self, _address: u32) -> Flow {
+        Flow::Next
+    }
 }
 
 #[derive(Getters, MutGetters, Setters)]
@@ -45,9 +57,14 @@ pub struct Arm7tdmiCpu<I: MemoryInterface> {
     arm_lut: [ArmInstructionFactory; 4096],
     thumb_lut: [ThumbInstructionFactory; 1024],
     last_instruction: Option<LastInstruction>,
+    #[getset(get = "pub")]
+    call_stack: CallStack,
     show_logs: bool,
     #[getset(skip)]
     bios_loaded: bool,
+    /// Set while an emulated IntrWait is halted, so running the SWI again on waking keeps the flags it
+    /// already discarded.
+    interrupt_wait: bool,
 }
 
 impl<I: MemoryInterface> MemoryInterface for Arm7tdmiCpu<I> {
@@ -101,8 +118,10 @@ impl<I: MemoryInterface> Arm7tdmiCpu<I> {
             arm_lut: [|v| ArmInstruction::Undefined(arm::undefined::Undefined::new(v)); 4096],
             thumb_lut: [|v| ThumbInstruction::Undefined(thumb::undefined::Undefined::new(v)); 1024],
             last_instruction: None,
+            call_stack: CallStack::new(),
             show_logs,
             bios_loaded,
+            interrupt_wait: false,
         };
 
         cpu.arm_lut = generate_arm_lut();
@@ -146,7 +165,7 @@ impl<I: MemoryInterface> Arm7tdmiCpu<I> {
                 self.last_instruction = Some(LastInstruction::Arm(instruction));
 
                 if self.show_logs {
-                    debug!("{}", instruction.disassemble(self));
+                    debug!("{}", instruction.disassemble(self.instruction_address()));
                 }
 
                 let condition = instruction.cond();
@@ -155,12 +174,13 @@ impl<I: MemoryInterface> Arm7tdmiCpu<I> {
                     self.next_memory_access = MemoryAccess::Instruction | MemoryAccess::Sequential;
                     return;
                 }
+                let address = self.instruction_address();
                 match instruction.execute(self) {
                     CpuAction::Advance(memory_access) => {
                         self.advance_pc_arm();
                         self.next_memory_access = memory_access;
                     }
-                    CpuAction::PipelineFlush => {}
+                    CpuAction::PipelineFlush => self.track_jump(instruction.flow(address), address, 4),
                 };
             }
             CpuState::Thumb => {
@@ -173,20 +193,49 @@ impl<I: MemoryInterface> Arm7tdmiCpu<I> {
                 self.last_instruction = Some(LastInstruction::Thumb(instruction));
 
                 if self.show_logs {
-                    debug!("{}", instruction.disassemble(self));
+                    debug!("{}", instruction.disassemble(self.instruction_address()));
                 }
 
+                let address = self.instruction_address();
                 match instruction.execute(self) {
                     CpuAction::Advance(memory_access) => {
                         self.advance_pc_thumb();
                         self.next_memory_access = memory_access;
                     }
-                    CpuAction::PipelineFlush => {}
+                    CpuAction::PipelineFlush => self.track_jump(instruction.flow(address), address, 2),
                 };
             }
         }
     }
 
+    /// Keeps the call stack in step with a jump the instruction at `address` took. Landing on a return
+    /// address unwinds to that frame, while BL and jumps that leave the link register pointing just past
+    /// themselves (`MOV LR, PC` followed by `BX`) are calls. SWIs and undefined instructions push their
+    /// frame on entering the exception.
+    fn track_jump(&mut self, flow: Flow, address: u32, size: u32) {
+        let target = self.instruction_address();
+        let return_address = address.wrapping_add(size);
+        match flow {
+            Flow::Call { .. } => self.call_stack.push(Frame {
+                kind: FrameKind::Call,
+                call_site: address,
+                target,
+                return_address,
+            }),
+            Flow::Indirect { .. } => {
+                if !self.call_stack.returned_to(target) && self.register(LR) & !0x1 == return_address {
+                    self.call_stack.push(Frame {
+                        kind: FrameKind::Call,
+                        call_site: address,
+                        target,
+                        return_address,
+                    });
+                }
+            }
+            Flow::Next | Flow::Jump { .. } | Flow::Invalid => {}
+        }
+    }
+
     #[inline]
     pub(crate) fn is_condition_met(&self, condition: Condition) -> bool {
         use Condition::*;
@@ -214,6 +263,163 @@ impl<I: MemoryInterface> Arm7tdmiCpu<I> {
         self.general_registers[PC]
     }
 
+    pub fn cpu_state(&self) -> CpuState {
+        self.cpsr.state()
+    }
+
+    /// Address of the instruction the next `cycle` executes, r15 running two instructions ahead of it.
+    pub fn instruction_address(&self) -> u32 {
+        match self.cpsr.state() {
+            CpuState::Arm => self.general_registers[PC].wrapping_sub(8),
+            CpuState::Thumb => self.general_registers[PC].wrapping_sub(4),
+        }
+    }
+
+    pub fn stack_pointer(&self) -> u32 {
+        self.register(SP)
+    }
+
+    pub fn link_register(&self) -> u32 {
+        self.register(LR)
+    }
+
+    /// Where execution continues once the next instruction returns, when it is a BL or SWI.
+    pub fn call_return_address(&self) -> Option<u32> {
+        let address = self.instruction_address();
+        let instruction = self.pipeline[0];
+        match self.cpsr.state() {
+            CpuState::Arm => match instruction & 0x0F000000 {
+                0x0B000000 | 0x0F000000 => Some(address.wrapping_add(4)),
+                _ => None,
+            },
+            CpuState::Thumb => match instruction & 0xFF00 {
+                // BL is a prefix and suffix pair, so stepping over the prefix skips both halves.
+                0xF000..=0xF7FF => Some(address.wrapping_add(4)),
+                0xF800..=0xFFFF | 0xDF00 => Some(address.wrapping_add(2)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Disassembles the instruction the next `cycle` executes, which is already in the pipeline.
+    pub fn disassemble_next(&self) -> String {
+        let instruction = self.pipeline[0];
+        let address = self.instruction_address();
+        match self.cpsr.state() {
+            CpuState::Arm => {
+                let lut_index = ((instruction >> 16) & 0x0FF0) | ((instruction >> 4) & 0x000F);
+                (self.arm_lut[lut_index as usize])(instruction).disassemble(address)
+            }
+            CpuState::Thumb => (self.thumb_lut[instruction as usize >> 6])(instruction as u16).disassemble(address),
+        }
+    }
+
+    /// Formats the instruction about to execute for an instruction trace. The reference format follows
+    /// mGBA's `trace` output: every register, the cpsr, then the address, opcode and disassembly.
+    pub fn trace_line(&self, format: TraceFormat, timestamp: u64) -> String {
+        let address = self.instruction_address();
+        let opcode = match self.cpsr.state() {
+            CpuState::Arm => format!("{:08X}", self.pipeline[0]),
+            CpuState::Thumb => format!("{:04X}", self.pipeline[0] as u16),
+        };
+        let disassembly = self.disassemble_next();
+        let registers: Vec<String> = (0..16).map(|index| format!("{:08X}", self.register(index))).collect();
+        match format {
+            TraceFormat::Reference => format!(
+                "{} cpsr: {:08X} | {address:08X}:  {opcode}\t{disassembly}",
+                registers.join(" "),
+                self.cpsr_bits()
+            ),
+            TraceFormat::Full => format!(
+                "{timestamp:>12} {address:08X} {:<5} {} {opcode:<8} {disassembly:<32} {} cpsr={:08X}",
+                self.cpsr.state().to_string(),
+                self.cpsr.mode(),
+                registers.join(" "),
+                self.cpsr_bits()
+            ),
+        }
+    }
+
+    pub fn mode(&self) -> CpuMode {
+        self.cpsr.mode()
+    }
+
+    pub fn cpsr_bits(&self) -> u32 {
+        self.cpsr.into_bits()
+    }
+
+    /// Replaces the CPSR for a debugger, refilling the pipeline when the T bit switches instruction set.
+    /// Invalid mode bits keep the current mode, since every banked register lookup depends on it.
+    pub fn set_cpsr_bits(&mut self, value: u32) {
+        let address = self.instruction_address();
+        let state = self.cpsr.state();
+        let mode = self.cpsr.mode();
+        self.cpsr = ProgramStatusRegister::from_bits_with_defaults(value);
+        if self.cpsr.mode() == CpuMode::Invalid {
+            self.cpsr.set_mode(mode);
+        }
+        if self.cpsr.state() != state {
+            self.jump(address);
+        }
+    }
+
+    /// Register r0-r14 as `mode` sees it, whatever the current mode is.
+    pub fn register_in_mode(&self, mode: CpuMode, index: usize) -> u32 {
+        match (mode, index) {
+            (CpuMode::Fiq, 8..=14) => self.banked_registers_fiq[index - 8],
+            (CpuMode::Supervisor, 13 | 14) => self.banked_registers_svc[index - 13],
+            (CpuMode::Abort, 13 | 14) => self.banked_registers_abt[index - 13],
+            (CpuMode::Irq, 13 | 14) => self.banked_registers_irq[index - 13],
+            (CpuMode::Undefined, 13 | 14) => self.banked_registers_und[index - 13],
+            (_, 0..=14) => self.general_registers[index],
+            _ => panic!("Index out of range"),
+        }
+    }
+
+    pub fn set_register_in_mode(&mut self, mode: CpuMode, index: usize, value: u32) {
+        match (mode, index) {
+            (CpuMode::Fiq, 8..=14) => self.banked_registers_fiq[index - 8] = value,
+            (CpuMode::Supervisor, 13 | 14) => self.banked_registers_svc[index - 13] = value,
+            (CpuMode::Abort, 13 | 14) => self.banked_registers_abt[index - 13] = value,
+            (CpuMode::Irq, 13 | 14) => self.banked_registers_irq[index - 13] = value,
+            (CpuMode::Undefined, 13 | 14) => self.banked_registers_und[index - 13] = value,
+            (_, 0..=14) => self.general_registers[index] = value,
+            _ => panic!("Index out of range"),
+        }
+    }
+
+    /// The SPSR of an exception mode. User and System mode have none.
+    pub fn spsr_in_mode(&self, mode: CpuMode) -> Option<u32> {
+        Self::spsr_index(mode).map(|index| self.spsrs[index].into_bits())
+    }
+
+    pub fn set_spsr_in_mode(&mut self, mode: CpuMode, value: u32) {
+        if let Some(index) = Self::spsr_index(mode) {
+            self.spsrs[index] = ProgramStatusRegister::from_bits_with_defaults(value);
+        }
+    }
+
+    fn spsr_index(mode: CpuMode) -> Option<usize> {
+        match mode {
+            CpuMode::Fiq => Some(0),
+            CpuMode::Supervisor => Some(1),
+            CpuMode::Abort => Some(2),
+            CpuMode::Irq => Some(3),
+            CpuMode::Undefined => Some(4),
+            CpuMode::User | CpuMode::System | CpuMode::Invalid => None,
+        }
+    }
+
+    /// Continues execution at `address` in the current instruction set, as a debugger setting the pc does.
+    pub fn jump(&mut self, address: u32) {
+        let address = match self.cpsr.state() {
+            CpuState::Arm => address & !0x3,
+            CpuState::Thumb => address & !0x1,
+        };
+        self.set_pc(address);
+        self.pipeline_flush();
+    }
+
     pub(crate) fn set_pc(&mut self, value: u32) {
         self.general_registers[PC] = value;
     }
@@ -245,7 +451,7 @@ impl<I: MemoryInterface> Arm7tdmiCpu<I> {
             CpuState::Thumb => {
                 self.pipeline[0] = self.load_16(
                     self.general_registers[PC],
-                    MemoryAccess::Instruction | MemoryAccess::Sequential,
+                    MemoryAccess::Instruction | MemoryAccess::NonSequential,
                 );
                 self.advance_pc_thumb();
                 self.pipeline[1] = self.load_16(
@@ -335,6 +541,7 @@ impl<I: MemoryInterface> Arm7tdmiCpu<I> {
     }
 
     pub(crate) fn exception(&mut self, exception: Exception) {
+        let call_site = self.instruction_address();
         let (mode, disable_irq, disable_fiq) = match exception {
             Exception::Reset => (CpuMode::Supervisor, true, true),
             Exception::Undefined => (CpuMode::Undefined, true, false),
@@ -359,7 +566,28 @@ impl<I: MemoryInterface> Arm7tdmiCpu<I> {
         };
         self.set_register(LR, return_pc);
         self.cpsr.set_state(CpuState::Arm);
-        self.set_pc(exception as u32);
+
+        // Interrupts return to the instruction they interrupted, the others to the one after the cause.
+        let (kind, return_address) = match &exception {
+            Exception::Reset => {
+                self.call_stack.clear();
+                (None, call_site)
+            }
+            Exception::Undefined => (Some(FrameKind::Undefined), return_pc),
+            Exception::SoftwareInterrupt => (Some(FrameKind::SoftwareInterrupt), return_pc),
+            Exception::Irq | Exception::Fiq => (Some(FrameKind::Interrupt), call_site),
+        };
+        let vector = exception as u32;
+        if let Some(kind) = kind {
+            self.call_stack.push(Frame {
+                kind,
+                call_site,
+                target: vector,
+                return_address,
+            });
+        }
+
+        self.set_pc(vector);
         self.pipeline_flush();
     }
 
@@ -376,38 +604,38 @@ impl<I: MemoryInterface> Arm7tdmiCpu<I> {
     pub(crate) fn bios_loaded(&self) -> bool {
         self.bios_loaded
     }
+}
 
-    pub(crate) fn bios_call(&mut self, function: u32) -> bool {
-        match function {
-            0x06 => self.bios_divide(),
-            0x08 => self.bios_square_root(),
-            _ => return false,
-        }
-        true
-    }
-
-    fn bios_divide(&mut self) {
-        let numerator = self.register(0) as i32;
-        let denominator = self.register(1) as i32;
-
-        let (quotient, remainder, absolute_quotient) = match denominator {
-            0 => match numerator < 0 {
-                true => (-1, numerator, 1),
-                false => (1, numerator, 1),
-            },
-            _ => {
-                let quotient = numerator.wrapping_div(denominator);
-                (quotient, numerator.wrapping_rem(denominator), quotient.wrapping_abs())
-            }
-        };
-
-        self.set_register(0, quotient as u32);
-        self.set_register(1, remainder as u32);
-        self.set_register(3, absolute_quotient as u32);
+impl<I: MemoryInterface + Snapshot> Snapshot for Arm7tdmiCpu<I> {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.general_registers);
+        writer.write(&self.banked_registers_fiq);
+        writer.write(&self.banked_registers_svc);
+        writer.write(&self.banked_registers_abt);
+        writer.write(&self.banked_registers_irq);
+        writer.write(&self.banked_registers_und);
+        writer.write(&self.spsrs.map(ProgramStatusRegister::into_bits));
+        writer.write(&self.cpsr.into_bits());
+        writer.write(&self.pipeline);
+        writer.write(&self.next_memory_access);
+        writer.write(&self.interrupt_wait);
+        writer.save(&self.bus);
     }
 
-    fn bios_square_root(&mut self) {
-        let value = self.register(0);
-        self.set_register(0, (value as f64).sqrt() as u32);
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.general_registers = reader.read()?;
+        self.banked_registers_fiq = reader.read()?;
+        self.banked_registers_svc = reader.read()?;
+        self.banked_registers_abt = reader.read()?;
+        self.banked_registers_irq = reader.read()?;
+        self.banked_registers_und = reader.read()?;
+        self.spsrs = reader.read::<[u32; 5]>()?.map(ProgramStatusRegister::from_bits);
+        self.cpsr = ProgramStatusRegister::from_bits(reader.read()?);
+        self.pipeline = reader.read()?;
+        self.next_memory_access = reader.read()?;
+        self.interrupt_wait = reader.read()?;
+        self.last_instruction = None;
+        self.call_stack.clear();
+        reader.load(&mut self.bus)
     }
 }
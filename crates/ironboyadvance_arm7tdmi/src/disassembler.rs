@@ -0,0 +1,116 @@
+use ironboyadvance_common::disassembly::{Disassembly, Flow};
+
+use crate::{
+    arm::decode_arm,
+    cpu::Instruction,
+    thumb::{ThumbInstruction, decode_thumb},
+};
+
+const ARM_DECODE_MASK: u32 = 0x0FF000F0;
+const THUMB_DECODE_MASK: u16 = 0xFFC0;
+
+/// Disassembles the ARM instruction `opcode` as if it sat at `address`.
+pub fn disassemble_arm(opcode: u32, address: u32) -> Disassembly {
+    let instruction = decode_arm(opcode & ARM_DECODE_MASK)(opcode);
+    Disassembly {
+        address,
+        size: 4,
+        text: instruction.disassemble(address),
+        flow: instruction.flow(address),
+    }
+}
+
+fn decode_thumb_instruction(opcode: u16) -> ThumbInstruction {
+    decode_thumb(opcode & THUMB_DECODE_MASK)(opcode)
+}
+
+/// Disassembles the Thumb instruction in the low halfword of `opcode` as if it sat at `address`. The high
+/// halfword is the one after it, which completes a `BL` prefix into a single four byte call.
+pub fn disassemble_thumb(opcode: u32, address: u32) -> Disassembly {
+    let instruction = decode_thumb_instruction(opcode as u16);
+    if let ThumbInstruction::LongBranchWithLink(prefix) = instruction
+        && let ThumbInstruction::LongBranchWithLink(suffix) = decode_thumb_instruction((opcode >> 16) as u16)
+        && let Some(target) = prefix.pair_target(&suffix, address)
+    {
+        return Disassembly {
+            address,
+            size: 4,
+            text: format!("BL 0x{target:08X}"),
+            flow: Flow::Call {
+                target,
+                conditional: false,
+            },
+        };
+    }
+
+    Disassembly {
+        address,
+        size: 2,
+        text: instruction.disassemble(address),
+        flow: instruction.flow(address),
+    }
+}
+
+fn read_word(bytes: &[u8]) -> u32 {
+    let mut word = [0; 4];
+    let length = bytes.len().min(4);
+    word[..length].copy_from_slice(&bytes[..length]);
+    u32::from_le_bytes(word)
+}
+
+/// Decodes the ARM instruction at the start of `bytes`, for the sweeps in
+/// [`disassembly`](ironboyadvance_common::disassembly).
+pub fn decode_arm_bytes(bytes: &[u8], address: u32) -> Disassembly {
+    disassemble_arm(read_word(bytes), address)
+}
+
+/// Decodes the Thumb instruction at the start of `bytes`, pairing up `BL` halves.
+pub fn decode_thumb_bytes(bytes: &[u8], address: u32) -> Disassembly {
+    disassemble_thumb(read_word(bytes), address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_branch_targets_and_literals() {
+        let branch = disassemble_arm(0xEAFFFFFE, 0x08000000);
+        assert_eq!(branch.text, "B 0x08000000");
+        assert_eq!(
+            branch.flow,
+            Flow::Jump {
+                target: 0x08000000,
+                conditional: false
+            }
+        );
+
+        let call = disassemble_arm(0x1B000010, 0x08000100);
+        assert_eq!(call.text, "BLNE 0x08000148");
+        assert!(call.flow.falls_through());
+
+        assert_eq!(disassemble_arm(0xE59F0004, 0x08000010).text, "LDR r0,[0x0800001C]");
+        assert_eq!(disassemble_thumb(0x4801, 0x08000102).text, "LDR r0,[0x08000108]");
+        assert_eq!(
+            disassemble_arm(0xE12FFF1E, 0x08000000).flow,
+            Flow::Indirect { conditional: false }
+        );
+    }
+
+    #[test]
+    fn pairs_thumb_long_branch_with_link() {
+        let call = disassemble_thumb(0xF800_F000, 0x08000200);
+        assert_eq!(call.size, 4);
+        assert_eq!(call.text, "BL 0x08000204");
+
+        let backwards = disassemble_thumb(0xFFFE_F7FF, 0x08000200);
+        assert_eq!(backwards.flow.target(), Some(0x08000200));
+
+        let lone_prefix = disassemble_thumb(0x4770_F000, 0x08000200);
+        assert_eq!(lone_prefix.size, 2);
+        assert_eq!(
+            disassemble_thumb(0xBD00, 0x08000300).flow,
+            Flow::Indirect { conditional: false }
+        );
+    }
+}
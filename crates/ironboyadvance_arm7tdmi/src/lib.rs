@@ -4,7 +4,9 @@ use std::fmt;
 mod alu;
 mod arm;
 mod barrel_shifter;
+mod bios;
 pub mod cpu;
+pub mod disassembler;
 pub mod memory;
 mod psr;
 mod test;
@@ -20,7 +22,7 @@ pub(crate) enum CpuAction {
 
 #[bitflag(u8)]
 #[derive(Debug, PartialEq, Eq)]
-pub(crate) enum CpuMode {
+pub enum CpuMode {
     User = 0b10000,
     Fiq = 0b10001,
     Irq = 0b10010,
@@ -1,3 +1,5 @@
+use ironboyadvance_common::disassembly::Flow;
+
 use crate::{
     CpuAction,
     cpu::{Arm7tdmiCpu, Instruction},
@@ -90,28 +92,54 @@ impl Instruction for ThumbInstruction {
     }
 
     #[inline]
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, address: u32) -> String {
+        match self {
+            Self::MoveShiftedRegister(i) => i.disassemble(address),
+            Self::AddSubtract(i) => i.disassemble(address),
+            Self::MoveCompareAddSubtractImmediate(i) => i.disassemble(address),
+            Self::AluOperations(i) => i.disassemble(address),
+            Self::HiRegisterOperationsBranchExchange(i) => i.disassemble(address),
+            Self::PcRelativeLoad(i) => i.disassemble(address),
+            Self::LoadStoreRegisterOffset(i) => i.disassemble(address),
+            Self::LoadStoreSignExtendedByteHalfword(i) => i.disassemble(address),
+            Self::LoadStoreImmediateOffset(i) => i.disassemble(address),
+            Self::LoadStoreHalfword(i) => i.disassemble(address),
+            Self::SpRelativeLoadStore(i) => i.disassemble(address),
+            Self::LoadAddress(i) => i.disassemble(address),
+            Self::AddOffsetToSp(i) => i.disassemble(address),
+            Self::PushPopRegisters(i) => i.disassemble(address),
+            Self::MultipleLoadStore(i) => i.disassemble(address),
+            Self::ConditionalBranch(i) => i.disassemble(address),
+            Self::SoftwareInterrupt(i) => i.disassemble(address),
+            Self::UnconditionalBranch(i) => i.disassemble(address),
+            Self::LongBranchWithLink(i) => i.disassemble(address),
+            Self::Undefined(i) => i.disassemble(address),
+        }
+    }
+
+    #[inline]
+    fn flow(&self, address: u32) -> Flow {
         match self {
-            Self::MoveShiftedRegister(i) => i.disassemble(cpu),
-            Self::AddSubtract(i) => i.disassemble(cpu),
-            Self::MoveCompareAddSubtractImmediate(i) => i.disassemble(cpu),
-            Self::AluOperations(i) => i.disassemble(cpu),
-            Self::HiRegisterOperationsBranchExchange(i) => i.disassemble(cpu),
-            Self::PcRelativeLoad(i) => i.disassemble(cpu),
-            Self::LoadStoreRegisterOffset(i) => i.disassemble(cpu),
-            Self::LoadStoreSignExtendedByteHalfword(i) => i.disassemble(cpu),
-            Self::LoadStoreImmediateOffset(i) => i.disassemble(cpu),
-            Self::LoadStoreHalfword(i) => i.disassemble(cpu),
-            Self::SpRelativeLoadStore(i) => i.disassemble(cpu),
-            Self::LoadAddress(i) => i.disassemble(cpu),
-            Self::AddOffsetToSp(i) => i.disassemble(cpu),
-            Self::PushPopRegisters(i) => i.disassemble(cpu),
-            Self::MultipleLoadStore(i) => i.disassemble(cpu),
-            Self::ConditionalBranch(i) => i.disassemble(cpu),
-            Self::SoftwareInterrupt(i) => i.disassemble(cpu),
-            Self::UnconditionalBranch(i) => i.disassemble(cpu),
-            Self::LongBranchWithLink(i) => i.disassemble(cpu),
-            Self::Undefined(i) => i.disassemble(cpu),
+            Self::MoveShiftedRegister(i) => i.flow(address),
+            Self::AddSubtract(i) => i.flow(address),
+            Self::MoveCompareAddSubtractImmediate(i) => i.flow(address),
+            Self::AluOperations(i) => i.flow(address),
+            Self::HiRegisterOperationsBranchExchange(i) => i.flow(address),
+            Self::PcRelativeLoad(i) => i.flow(address),
+            Self::LoadStoreRegisterOffset(i) => i.flow(address),
+            Self::LoadStoreSignExtendedByteHalfword(i) => i.flow(address),
+            Self::LoadStoreImmediateOffset(i) => i.flow(address),
+            Self::LoadStoreHalfword(i) => i.flow(address),
+            Self::SpRelativeLoadStore(i) => i.flow(address),
+            Self::LoadAddress(i) => i.flow(address),
+            Self::AddOffsetToSp(i) => i.flow(address),
+            Self::PushPopRegisters(i) => i.flow(address),
+            Self::MultipleLoadStore(i) => i.flow(address),
+            Self::ConditionalBranch(i) => i.flow(address),
+            Self::SoftwareInterrupt(i) => i.flow(address),
+            Self::UnconditionalBranch(i) => i.flow(address),
+            Self::LongBranchWithLink(i) => i.flow(address),
+            Self::Undefined(i) => i.flow(address),
         }
     }
 }
@@ -124,7 +152,7 @@ pub fn generate_thumb_lut() -> [ThumbInstructionFactory; 1024] {
     thumb_lut
 }
 
-fn decode_thumb(instruction: u16) -> ThumbInstructionFactory {
+pub(crate) fn decode_thumb(instruction: u16) -> ThumbInstructionFactory {
     if instruction & 0xF800 < 0x1800 {
         |value| ThumbInstruction::MoveShiftedRegister(MoveShiftedRegister::new(value))
     } else if instruction & 0xF800 == 0x1800 {
@@ -34,7 +34,7 @@ impl Instruction for AddOffsetToSp {
         CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::Sequential)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let offset = self.offset;
         let signed = if self.signed { "-" } else { "" };
         format!("ADD sp, {}{}", signed, offset)
@@ -49,7 +49,7 @@ impl Instruction for AddSubtract {
         CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::Sequential)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let rs = self.rs;
         let rd = self.rd;
         let is_immediate = self.is_immediate;
@@ -115,7 +115,7 @@ impl Instruction for AluOperations {
         access
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let rd = self.rd;
         let rs = self.rs;
         let opcode = AluOperationsOpcode::from(self.opcode);
@@ -1,5 +1,6 @@
 use ironboyadvance_common::{
     bits::{BitOps, SignExtend},
+    disassembly::Flow,
     memory::MemoryAccess,
 };
 
@@ -25,6 +26,13 @@ impl ConditionalBranch {
     }
 }
 
+impl ConditionalBranch {
+    fn target(&self, address: u32) -> u32 {
+        let offset = self.offset.sign_extend(8) << 1;
+        address.wrapping_add(4).wrapping_add(offset as u32)
+    }
+}
+
 impl Instruction for ConditionalBranch {
     fn execute<I: MemoryInterface>(&self, cpu: &mut Arm7tdmiCpu<I>) -> CpuAction {
         let condition = self.cond;
@@ -38,9 +46,16 @@ impl Instruction for ConditionalBranch {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, address: u32) -> String {
         let cond = self.cond;
-        let offset = self.offset;
-        format!("B{} #{}", cond, offset)
+        let target = self.target(address);
+        format!("B{cond} 0x{target:08X}")
+    }
+
+    fn flow(&self, address: u32) -> Flow {
+        Flow::Jump {
+            target: self.target(address),
+            conditional: true,
+        }
     }
 }
@@ -1,4 +1,4 @@
-use ironboyadvance_common::{bits::BitOps, memory::MemoryAccess};
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow, memory::MemoryAccess};
 
 use crate::{
     CpuAction, CpuState, HiRegOpsBxOpcode, HiRegister, LoRegister,
@@ -85,7 +85,7 @@ impl Instruction for HiRegisterOperationsBranchExchange {
         action
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let destination = match self.h1 {
             true => self.hd.to_string(),
             false => self.rd.to_string(),
@@ -99,4 +99,13 @@ impl Instruction for HiRegisterOperationsBranchExchange {
         let opcode = HiRegOpsBxOpcode::from(self.opcode);
         format!("{} {},{}", opcode, destination, source)
     }
+
+    fn flow(&self, _address: u32) -> Flow {
+        let writes_pc = self.h1 && self.hd == HiRegister::R15;
+        match HiRegOpsBxOpcode::from(self.opcode) {
+            HiRegOpsBxOpcode::BX => Flow::Indirect { conditional: false },
+            HiRegOpsBxOpcode::ADD | HiRegOpsBxOpcode::MOV if writes_pc => Flow::Indirect { conditional: false },
+            _ => Flow::Next,
+        }
+    }
 }
@@ -36,10 +36,15 @@ impl Instruction for LoadAddress {
         CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::Sequential)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
-        let offset = self.offset;
+    fn disassemble(&self, address: u32) -> String {
+        let offset = self.offset * 4;
         let rd = self.rd;
-        let sp = if self.sp { "sp" } else { "pc" };
-        format!("ADD {},{},{}", rd, sp, offset)
+        match self.sp {
+            true => format!("ADD {rd},sp,#{offset}"),
+            false => {
+                let value = (address.wrapping_add(4) & !0x2).wrapping_add(offset as u32);
+                format!("ADD {rd},pc,#{offset} ; 0x{value:08X}")
+            }
+        }
     }
 }
@@ -47,7 +47,7 @@ impl Instruction for LoadStoreHalfword {
         CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::NonSequential)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let offset = self.offset;
         let rb = self.rb;
         let rd = self.rd;
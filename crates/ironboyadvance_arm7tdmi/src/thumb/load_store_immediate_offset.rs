@@ -61,7 +61,7 @@ impl Instruction for LoadStoreImmediateOffset {
         CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::NonSequential)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let byte = if self.byte { "B" } else { "" };
         let offset = self.offset;
         let rb = self.rb;
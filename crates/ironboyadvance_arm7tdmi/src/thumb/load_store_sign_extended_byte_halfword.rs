@@ -62,7 +62,7 @@ impl Instruction for LoadStoreSignExtendedByteHalfword {
         CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::NonSequential)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let ro = self.ro;
         let rb = self.rb;
         let rd = self.rd;
@@ -1,5 +1,6 @@
 use ironboyadvance_common::{
     bits::{BitOps, SignExtend},
+    disassembly::Flow,
     memory::MemoryAccess,
 };
 
@@ -23,6 +24,18 @@ impl LongBranchWithLink {
             high: value.bit(11),
         }
     }
+
+    /// Where a prefix at `address` followed by `suffix` branches to, when they form a pair.
+    pub(crate) fn pair_target(&self, suffix: &LongBranchWithLink, address: u32) -> Option<u32> {
+        match !self.high && suffix.high {
+            true => {
+                let upper = self.offset.sign_extend(11) << 12;
+                let lower = (suffix.offset as u32) << 1;
+                Some(address.wrapping_add(4).wrapping_add(upper as u32).wrapping_add(lower))
+            }
+            false => None,
+        }
+    }
 }
 
 impl Instruction for LongBranchWithLink {
@@ -44,9 +57,17 @@ impl Instruction for LongBranchWithLink {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let offset = self.offset;
         let high = if self.high { "hi" } else { "lo" };
         format!("BL #{}({})", offset, high)
     }
+
+    /// A suffix on its own branches relative to the link register, which only the prefix sets.
+    fn flow(&self, _address: u32) -> Flow {
+        match self.high {
+            true => Flow::Indirect { conditional: false },
+            false => Flow::Next,
+        }
+    }
 }
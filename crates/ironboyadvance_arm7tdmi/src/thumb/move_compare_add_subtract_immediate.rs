@@ -46,7 +46,7 @@ impl Instruction for MoveCompareAddSubtractImmediate {
         CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::Sequential)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let rd = self.rd;
         let offset = self.offset;
         let opcode = MovCmpAddSubImmediateOpcode::from(self.opcode);
@@ -50,7 +50,7 @@ impl Instruction for MoveShiftedRegister {
         CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::Sequential)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let shift_type = ShiftType::from(self.opcode);
         let offset5 = self.offset;
         let rs = self.rs;
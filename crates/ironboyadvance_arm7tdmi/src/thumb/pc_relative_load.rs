@@ -32,9 +32,10 @@ impl Instruction for PcRelativeLoad {
         CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::NonSequential)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    /// The pc reads word aligned, four bytes past the load.
+    fn disassemble(&self, address: u32) -> String {
         let rd = self.rd;
-        let offset = self.offset;
-        format!("LDR {},[PC, #{}]", rd, offset)
+        let literal = (address.wrapping_add(4) & !0x2).wrapping_add((self.offset << 2) as u32);
+        format!("LDR {rd},[0x{literal:08X}]")
     }
 }
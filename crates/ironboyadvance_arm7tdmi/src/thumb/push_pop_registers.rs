@@ -1,4 +1,4 @@
-use ironboyadvance_common::{bits::BitOps, memory::MemoryAccess};
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow, memory::MemoryAccess};
 
 use crate::{
     CpuAction, LoRegister,
@@ -92,7 +92,7 @@ impl Instruction for PushPopRegisters {
         CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::NonSequential)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let load = self.load;
         let store_lr_load_pc = self.store_lr_load_pc;
         let register_list: Vec<usize> = (0..8).filter(|&i| (self.register_list_bits >> i) & 1 == 1).collect();
@@ -109,4 +109,11 @@ impl Instruction for PushPopRegisters {
             (true, true) => format!("POP {{{},pc}}", register_list_str),
         }
     }
+
+    fn flow(&self, _address: u32) -> Flow {
+        match self.load && self.store_lr_load_pc {
+            true => Flow::Indirect { conditional: false },
+            false => Flow::Next,
+        }
+    }
 }
@@ -1,9 +1,9 @@
 use crate::{
-    CpuAction, Exception,
+    CpuAction,
     cpu::{Arm7tdmiCpu, Instruction},
     memory::MemoryInterface,
 };
-use ironboyadvance_common::{bits::BitOps, memory::MemoryAccess};
+use ironboyadvance_common::bits::BitOps;
 
 #[derive(Debug, Clone, Copy)]
 pub struct SoftwareInterrupt {
@@ -21,16 +21,10 @@ impl SoftwareInterrupt {
 
 impl Instruction for SoftwareInterrupt {
     fn execute<I: MemoryInterface>(&self, cpu: &mut Arm7tdmiCpu<I>) -> CpuAction {
-        match !cpu.bios_loaded() && cpu.bios_call(self.offset as u32) {
-            true => CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::Sequential),
-            false => {
-                cpu.exception(Exception::SoftwareInterrupt);
-                CpuAction::PipelineFlush
-            }
-        }
+        cpu.software_interrupt(self.offset as u32)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let offset = self.offset;
         format!("SWI #{}", offset)
     }
@@ -45,7 +45,7 @@ impl Instruction for SpRelativeLoadStore {
         CpuAction::Advance(MemoryAccess::Instruction | MemoryAccess::NonSequential)
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         let offset = self.offset;
         let rd = self.rd;
         match self.load {
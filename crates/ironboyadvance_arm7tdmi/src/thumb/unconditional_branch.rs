@@ -3,7 +3,10 @@ use crate::{
     cpu::{Arm7tdmiCpu, Instruction},
     memory::MemoryInterface,
 };
-use ironboyadvance_common::bits::{BitOps, SignExtend};
+use ironboyadvance_common::{
+    bits::{BitOps, SignExtend},
+    disassembly::Flow,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct UnconditionalBranch {
@@ -19,6 +22,13 @@ impl UnconditionalBranch {
     }
 }
 
+impl UnconditionalBranch {
+    fn target(&self, address: u32) -> u32 {
+        let offset = self.offset.sign_extend(11) << 1;
+        address.wrapping_add(4).wrapping_add(offset as u32)
+    }
+}
+
 impl Instruction for UnconditionalBranch {
     fn execute<I: MemoryInterface>(&self, cpu: &mut Arm7tdmiCpu<I>) -> CpuAction {
         let offset = self.offset.sign_extend(11) << 1;
@@ -27,8 +37,15 @@ impl Instruction for UnconditionalBranch {
         CpuAction::PipelineFlush
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
-        let offset = self.offset;
-        format!("B #{}", offset)
+    fn disassemble(&self, address: u32) -> String {
+        let target = self.target(address);
+        format!("B 0x{target:08X}")
+    }
+
+    fn flow(&self, address: u32) -> Flow {
+        Flow::Jump {
+            target: self.target(address),
+            conditional: false,
+        }
     }
 }
@@ -1,3 +1,5 @@
+use ironboyadvance_common::disassembly::Flow;
+
 use crate::{
     CpuAction, Exception,
     cpu::{Arm7tdmiCpu, Instruction},
@@ -19,7 +21,11 @@ impl Instruction for Undefined {
         CpuAction::PipelineFlush
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Arm7tdmiCpu<I>) -> String {
+    fn disassemble(&self, _address: u32) -> String {
         "Undefined".into()
     }
+
+    fn flow(&self, _address: u32) -> Flow {
+        Flow::Invalid
+    }
 }
@@ -0,0 +1,147 @@
+use crate::symbols::SymbolTable;
+
+/// Frames kept before the oldest are dropped, so code that never returns (a jump out of an interrupt
+/// handler, a longjmp) cannot grow the stack forever.
+pub const MAX_DEPTH: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    Call,
+    Interrupt,
+    SoftwareInterrupt,
+    Undefined,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub kind: FrameKind,
+    /// Address of the call, or of the instruction an exception interrupted.
+    pub call_site: u32,
+    pub target: u32,
+    /// Where execution lands when the frame returns.
+    pub return_address: u32,
+}
+
+/// Shadow call stack built from the calls, returns and exceptions the cpu executes, independent of what
+/// the game keeps on its own stack.
+#[derive(Debug, Clone, Default)]
+pub struct CallStack {
+    frames: Vec<Frame>,
+}
+
+impl CallStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        if self.frames.len() == MAX_DEPTH {
+            self.frames.remove(0);
+        }
+        self.frames.push(frame);
+    }
+
+    /// Pops the innermost frame returning to `address` along with every frame above it, which the code
+    /// left without returning. Returns false, leaving the stack alone, when no frame returns there.
+    pub fn returned_to(&mut self, address: u32) -> bool {
+        match self.frames.iter().rposition(|frame| frame.return_address == address) {
+            Some(index) => {
+                self.frames.truncate(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Frames from the outermost to the innermost.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// One line per frame in gdb's style, innermost first, starting with the instruction at `pc`.
+    pub fn backtrace(&self, pc: u32, symbols: Option<&SymbolTable>) -> Vec<String> {
+        let describe = |address: u32| match symbols.and_then(|symbols| symbols.describe(address)) {
+            Some(symbol) => format!("0x{address:08X} <{symbol}>"),
+            None => format!("0x{address:08X}"),
+        };
+        let mut lines = vec![format!("#0  {}", describe(pc))];
+        for (index, frame) in self.frames.iter().rev().enumerate() {
+            let note = match frame.kind {
+                FrameKind::Call => "",
+                FrameKind::Interrupt => " (interrupt)",
+                FrameKind::SoftwareInterrupt => " (software interrupt)",
+                FrameKind::Undefined => " (undefined instruction)",
+            };
+            lines.push(format!("#{:<2} {}{note}", index + 1, describe(frame.call_site)));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::symbols::{Symbol, SymbolKind};
+
+    use super::*;
+
+    fn call(call_site: u32, target: u32) -> Frame {
+        Frame {
+            kind: FrameKind::Call,
+            call_site,
+            target,
+            return_address: call_site + 4,
+        }
+    }
+
+    #[test]
+    fn returns_unwind_to_the_matching_frame() {
+        let mut stack = CallStack::new();
+        stack.push(call(0x100, 0x200));
+        stack.push(call(0x210, 0x300));
+        stack.push(call(0x310, 0x400));
+
+        assert!(!stack.returned_to(0x500));
+        assert_eq!(stack.depth(), 3);
+        assert!(stack.returned_to(0x214), "returning past a frame drops it too");
+        assert_eq!(stack.frames(), &[call(0x100, 0x200)]);
+
+        for index in 0..MAX_DEPTH as u32 + 1 {
+            stack.push(call(index * 8, 0));
+        }
+        assert_eq!(stack.depth(), MAX_DEPTH);
+        assert_eq!(stack.frames()[0], call(8, 0));
+    }
+
+    #[test]
+    fn prints_a_backtrace_with_symbols() {
+        let symbols = SymbolTable::new(vec![Symbol {
+            name: "Main".to_string(),
+            address: 0x100,
+            size: 0x100,
+            bank: None,
+            kind: SymbolKind::Function,
+            instruction_set: None,
+        }]);
+        let mut stack = CallStack::new();
+        stack.push(call(0x110, 0x4000));
+        stack.push(Frame {
+            kind: FrameKind::Interrupt,
+            call_site: 0x4010,
+            target: 0x18,
+            return_address: 0x4010,
+        });
+
+        assert_eq!(
+            stack.backtrace(0x20, Some(&symbols)),
+            vec!["#0  0x00000020", "#1  0x00004010 (interrupt)", "#2  0x00000110 <Main+0x10>"]
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+pub const CHEAT_EXTENSION: &str = "cht";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CheatError {
+    #[error("invalid cheat code on line {line}: {reason}")]
+    InvalidCode { line: usize, reason: &'static str },
+    #[error("unsupported cheat code on line {line}: {reason}")]
+    UnsupportedCode { line: usize, reason: &'static str },
+    #[error("{0:?} codes are not supported by this system")]
+    UnsupportedFormat(CheatFormat),
+    #[error("no cheat at index {0}")]
+    UnknownCheat(usize),
+}
+
+#[derive(Error, Debug)]
+pub enum CheatFileError {
+    #[error("Cheat file I/O failed: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid cheat file on line {line}: {reason}")]
+    InvalidFile { line: usize, reason: &'static str },
+}
+
+/// Cheat devices whose codes can be entered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatFormat {
+    /// GBA GameShark and Action Replay v1/v2, or Game Boy GameShark.
+    GameShark,
+    ActionReplayV3,
+    CodeBreaker,
+    /// Game Boy Game Genie.
+    GameGenie,
+}
+
+impl CheatFormat {
+    pub const ALL: [CheatFormat; 4] = [
+        CheatFormat::GameShark,
+        CheatFormat::ActionReplayV3,
+        CheatFormat::CodeBreaker,
+        CheatFormat::GameGenie,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CheatFormat::GameShark => "GameShark",
+            CheatFormat::ActionReplayV3 => "ActionReplayV3",
+            CheatFormat::CodeBreaker => "CodeBreaker",
+            CheatFormat::GameGenie => "GameGenie",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CheatFormat> {
+        CheatFormat::ALL.into_iter().find(|format| format.name() == name)
+    }
+}
+
+/// A cheat as the user entered it. `code` holds one device code per line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cheat {
+    pub name: String,
+    pub format: CheatFormat,
+    pub code: String,
+    pub enabled: bool,
+}
+
+impl Cheat {
+    pub fn new(name: impl Into<String>, format: CheatFormat, code: impl Into<String>) -> Self {
+        Cheat {
+            name: name.into(),
+            format,
+            code: code.into(),
+            enabled: true,
+        }
+    }
+}
+
+/// Keeps a game's cheats in a `.cht` text file, one section per cheat:
+///
+/// ```text
+/// [Infinite lives]
+/// format = GameShark
+/// enabled = true
+/// 010238CD
+/// ```
+pub struct CheatFile {
+    path: PathBuf,
+}
+
+impl CheatFile {
+    pub fn new(directory: impl AsRef<Path>, name: &str) -> Self {
+        Self::at(directory.as_ref().join(format!("{name}.{CHEAT_EXTENSION}")))
+    }
+
+    /// Cheats next to the rom, e.g. `game.gb` keeps its cheats in `game.cht`.
+    pub fn beside_rom(rom_path: impl AsRef<Path>) -> Self {
+        Self::at(rom_path.as_ref().with_extension(CHEAT_EXTENSION))
+    }
+
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the stored cheats, or none when the file does not exist yet.
+    pub fn load(&self) -> Result<Vec<Cheat>, CheatFileError> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => parse_cheat_file(&contents),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Replaces the stored cheats, writing through a temporary file so a crash keeps the old list.
+    pub fn store(&self, cheats: &[Cheat]) -> io::Result<()> {
+        if let Some(directory) = self.path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)?;
+        }
+
+        let mut temporary_name = self.path.clone().into_os_string();
+        temporary_name.push(".tmp");
+        let temporary_path = PathBuf::from(temporary_name);
+        fs::write(&temporary_path, format_cheat_file(cheats))?;
+        fs::rename(&temporary_path, &self.path)
+    }
+}
+
+fn format_cheat_file(cheats: &[Cheat]) -> String {
+    let mut contents = String::new();
+    for cheat in cheats {
+        contents.push_str(&format!(
+            "[{}]\nformat = {}\nenabled = {}\n",
+            cheat.name,
+            cheat.format.name(),
+            cheat.enabled
+        ));
+        for line in cheat.code.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+        contents.push('\n');
+    }
+    contents
+}
+
+fn parse_cheat_file(contents: &str) -> Result<Vec<Cheat>, CheatFileError> {
+    let mut cheats: Vec<Cheat> = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        let invalid = |reason| CheatFileError::InvalidFile { line: index + 1, reason };
+
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            cheats.push(Cheat::new(name, CheatFormat::GameShark, String::new()));
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let cheat = cheats.last_mut().ok_or_else(|| invalid("code before the first cheat name"))?;
+        match line.split_once('=').map(|(key, value)| (key.trim(), value.trim())) {
+            Some(("format", format)) => {
+                cheat.format = CheatFormat::from_name(format).ok_or_else(|| invalid("unknown cheat format"))?;
+            }
+            Some(("enabled", enabled)) => {
+                cheat.enabled = enabled.parse().map_err(|_| invalid("enabled must be true or false"))?;
+            }
+            Some(_) => return Err(invalid("unknown cheat setting")),
+            None => {
+                cheat.code.push_str(line);
+                cheat.code.push('\n');
+            }
+        }
+    }
+    Ok(cheats)
+}
+
+/// Splits a code into its lines with spaces and `-`/`:` separators removed, skipping blank lines. Line
+/// numbers start at 1 for error reporting.
+pub fn code_lines(code: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    code.lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let digits = line
+                .chars()
+                .filter(|character| !character.is_whitespace() && !matches!(character, '-' | ':'))
+                .collect::<String>();
+            (index + 1, digits)
+        })
+        .filter(|(_, digits)| !digits.is_empty())
+}
+
+/// Parses a line of hex digits into its leading and trailing words, the split falling after
+/// `first_digits`.
+pub fn parse_code_line(
+    line: usize,
+    digits: &str,
+    first_digits: usize,
+    total_digits: usize,
+) -> Result<(u32, u32), CheatError> {
+    let invalid = CheatError::InvalidCode {
+        line,
+        reason: "unexpected length or non hex digit",
+    };
+    if digits.len() != total_digits || !digits.chars().all(|character| character.is_ascii_hexdigit()) {
+        return Err(invalid);
+    }
+
+    let first = u32::from_str_radix(&digits[..first_digits], 16).map_err(|_| invalid.clone())?;
+    let second = u32::from_str_radix(&digits[first_digits..], 16).map_err(|_| invalid)?;
+    Ok((first, second))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_lines_strip_separators_and_blank_lines() {
+        let lines: Vec<_> = code_lines("0123ABCD 4567EF01\n\n  3200-0000:FF ").collect();
+        assert_eq!(
+            lines,
+            vec![(1, "0123ABCD4567EF01".to_string()), (3, "32000000FF".to_string())]
+        );
+        assert_eq!(parse_code_line(1, &lines[0].1, 8, 16), Ok((0x0123ABCD, 0x4567EF01)));
+        assert!(parse_code_line(3, &lines[1].1, 8, 16).is_err());
+        assert!(parse_code_line(1, "0123ABCZ4567EF01", 8, 16).is_err());
+    }
+
+    #[test]
+    fn cheat_files_round_trip() {
+        let mut disabled = Cheat::new("Walk through walls", CheatFormat::GameGenie, "00A-17B-C49\n3E1-0AF");
+        disabled.enabled = false;
+        let cheats = vec![Cheat::new("Infinite lives", CheatFormat::GameShark, "010238CD"), disabled];
+
+        let path = std::env::temp_dir().join(format!("ironboyadvance-cheats-{}", std::process::id()));
+        let file = CheatFile::new(&path, "game");
+        assert_eq!(file.load().unwrap(), Vec::new());
+        file.store(&cheats).unwrap();
+        let loaded = file.load().unwrap();
+        fs::remove_dir_all(&path).unwrap();
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].code, "010238CD\n");
+        assert_eq!((loaded[1].format, loaded[1].enabled), (CheatFormat::GameGenie, false));
+        assert_eq!(code_lines(&loaded[1].code).count(), 2);
+        assert!(matches!(
+            parse_cheat_file("010238CD"),
+            Err(CheatFileError::InvalidFile { line: 1, .. })
+        ));
+        assert!(parse_cheat_file("[x]\nformat = Pokefinder").is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use std::{fs, io, path::Path};
+
+use thiserror::Error;
+
+pub const COVERAGE_EXTENSION: &str = "cov";
+
+#[derive(Error, Debug)]
+pub enum CoverageError {
+    #[error("Coverage file I/O failed: {0}")]
+    Io(#[from] io::Error),
+    #[error("coverage map covers {found} bytes but the rom has {expected}")]
+    SizeMismatch { expected: usize, found: usize },
+}
+
+/// How a rom byte was used. A byte can be more than one, like a table the code also reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageKind {
+    Arm,
+    Thumb,
+    Sm83,
+    Data,
+}
+
+impl CoverageKind {
+    const ALL: [CoverageKind; 4] = [CoverageKind::Arm, CoverageKind::Thumb, CoverageKind::Sm83, CoverageKind::Data];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    pub fn executed(self) -> bool {
+        self != CoverageKind::Data
+    }
+}
+
+/// Which rom bytes ran as code and which were read as data. The bitmap holds one byte of
+/// [`CoverageKind`] bits per rom byte, bit 0 for ARM up to bit 3 for data, so it lines up with the rom
+/// file itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    bitmap: Vec<u8>,
+}
+
+impl Coverage {
+    pub fn new(rom_size: usize) -> Coverage {
+        Coverage {
+            bitmap: vec![0; rom_size],
+        }
+    }
+
+    /// Reads a bitmap saved by an earlier session, so coverage adds up across play sessions.
+    pub fn load(path: impl AsRef<Path>, rom_size: usize) -> Result<Coverage, CoverageError> {
+        let bitmap = fs::read(path)?;
+        match bitmap.len() == rom_size {
+            true => Ok(Coverage { bitmap }),
+            false => Err(CoverageError::SizeMismatch {
+                expected: rom_size,
+                found: bitmap.len(),
+            }),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, &self.bitmap)
+    }
+
+    pub fn bitmap(&self) -> &[u8] {
+        &self.bitmap
+    }
+
+    pub fn rom_size(&self) -> usize {
+        self.bitmap.len()
+    }
+
+    /// Marks `length` bytes from rom `offset` on, ignoring any past the end of the rom.
+    pub fn mark(&mut self, offset: usize, length: usize, kind: CoverageKind) {
+        let end = offset.saturating_add(length).min(self.bitmap.len());
+        if let Some(bytes) = self.bitmap.get_mut(offset..end) {
+            bytes.iter_mut().for_each(|byte| *byte |= kind.bit());
+        }
+    }
+
+    pub fn covers(&self, offset: usize, kind: CoverageKind) -> bool {
+        self.bitmap.get(offset).is_some_and(|byte| byte & kind.bit() != 0)
+    }
+
+    /// Every way the byte at `offset` was used.
+    pub fn kinds(&self, offset: usize) -> Vec<CoverageKind> {
+        CoverageKind::ALL
+            .into_iter()
+            .filter(|kind| self.covers(offset, *kind))
+            .collect()
+    }
+
+    /// Bytes marked as `kind`.
+    pub fn count(&self, kind: CoverageKind) -> usize {
+        self.bitmap.iter().filter(|byte| *byte & kind.bit() != 0).count()
+    }
+
+    /// Bytes that ran as code in any instruction set.
+    pub fn executed_count(&self) -> usize {
+        let code = CoverageKind::Arm.bit() | CoverageKind::Thumb.bit() | CoverageKind::Sm83.bit();
+        self.bitmap.iter().filter(|byte| *byte & code != 0).count()
+    }
+
+    /// Adds the bytes `other` covers. Maps of different sized roms are left alone.
+    pub fn merge(&mut self, other: &Coverage) -> Result<(), CoverageError> {
+        if other.bitmap.len() != self.bitmap.len() {
+            return Err(CoverageError::SizeMismatch {
+                expected: self.bitmap.len(),
+                found: other.bitmap.len(),
+            });
+        }
+        self.bitmap
+            .iter_mut()
+            .zip(&other.bitmap)
+            .for_each(|(byte, other)| *byte |= other);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_merges_and_round_trips() {
+        let mut coverage = Coverage::new(16);
+        coverage.mark(0, 4, CoverageKind::Arm);
+        coverage.mark(2, 2, CoverageKind::Data);
+        coverage.mark(14, 4, CoverageKind::Thumb);
+
+        assert_eq!(coverage.kinds(2), vec![CoverageKind::Arm, CoverageKind::Data]);
+        assert_eq!(coverage.count(CoverageKind::Thumb), 2, "marks stop at the end of the rom");
+        assert_eq!(coverage.executed_count(), 6);
+
+        let mut other = Coverage::new(16);
+        other.mark(8, 1, CoverageKind::Sm83);
+        coverage.merge(&other).unwrap();
+        assert!(coverage.covers(8, CoverageKind::Sm83));
+        assert!(coverage.merge(&Coverage::new(8)).is_err());
+
+        let path = std::env::temp_dir().join(format!("ironboyadvance-coverage-{}.cov", std::process::id()));
+        coverage.save(&path).unwrap();
+        assert_eq!(Coverage::load(&path, 16).unwrap(), coverage);
+        assert!(matches!(
+            Coverage::load(&path, 32),
+            Err(CoverageError::SizeMismatch { expected: 32, found: 16 })
+        ));
+        fs::remove_file(path).unwrap();
+    }
+}
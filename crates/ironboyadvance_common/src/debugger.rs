@@ -0,0 +1,357 @@
+/// Instruction set a breakpoint is limited to. Only the ARM7TDMI switches between sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionSet {
+    Arm,
+    Thumb,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub address: u32,
+    /// Fires in either set when `None`.
+    pub instruction_set: Option<InstructionSet>,
+}
+
+impl Breakpoint {
+    pub fn new(address: u32) -> Self {
+        Breakpoint {
+            address,
+            instruction_set: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    Access,
+}
+
+/// Value the access must read or write for a watchpoint to fire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchCondition {
+    Any,
+    Equal(u32),
+    NotEqual(u32),
+    Below(u32),
+    Above(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub address: u32,
+    pub length: u32,
+    pub kind: WatchKind,
+    pub condition: WatchCondition,
+}
+
+impl Watchpoint {
+    fn matches(&self, address: u32, width: u32, access: AccessKind, value: u32) -> bool {
+        let kind = matches!(
+            (self.kind, access),
+            (WatchKind::Access, _) | (WatchKind::Read, AccessKind::Read) | (WatchKind::Write, AccessKind::Write)
+        );
+        let overlaps = address < self.address.saturating_add(self.length) && self.address < address.saturating_add(width);
+        let condition = match self.condition {
+            WatchCondition::Any => true,
+            WatchCondition::Equal(expected) => value == expected,
+            WatchCondition::NotEqual(expected) => value != expected,
+            WatchCondition::Below(limit) => value < limit,
+            WatchCondition::Above(limit) => value > limit,
+        };
+        kind && overlaps && condition
+    }
+}
+
+/// Why the system stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakReason {
+    /// About to execute the instruction at `address`.
+    Breakpoint {
+        address: u32,
+    },
+    Watchpoint {
+        address: u32,
+        access: AccessKind,
+        value: u32,
+    },
+    /// The interrupt with this IF bit was raised.
+    Interrupt(u8),
+    /// The DMA channel started a transfer.
+    Dma(u8),
+    /// A step, step over, step out or run to cursor finished.
+    Step,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Instruction,
+    /// Runs until `address` is about to execute with the stack pointer at or above `stack_pointer`, so a
+    /// recursive call to the same function does not end the step early.
+    Until {
+        address: u32,
+        stack_pointer: Option<u32>,
+    },
+}
+
+/// Breakpoints, watchpoints and stepping shared by both cpu cores. The system reports instructions,
+/// memory accesses, interrupts and DMA starts as they happen and stops once one of them asks it to.
+#[derive(Debug, Default)]
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    interrupt_breaks: u32,
+    dma_breaks: u32,
+    step: Option<Step>,
+    pending: Option<BreakReason>,
+    /// Set after stopping in front of an instruction, so resuming executes it.
+    resuming: bool,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    /// Removes every breakpoint at `address`, returning whether there was one.
+    pub fn remove_breakpoint(&mut self, address: u32) -> bool {
+        let count = self.breakpoints.len();
+        self.breakpoints.retain(|breakpoint| breakpoint.address != address);
+        self.breakpoints.len() != count
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        if !self.watchpoints.contains(&watchpoint) {
+            self.watchpoints.push(watchpoint);
+        }
+    }
+
+    /// Removes every watchpoint covering `address`, returning whether there was one.
+    pub fn remove_watchpoint(&mut self, address: u32) -> bool {
+        let count = self.watchpoints.len();
+        self.watchpoints.retain(|watchpoint| {
+            !(watchpoint.address..watchpoint.address.saturating_add(watchpoint.length)).contains(&address)
+        });
+        self.watchpoints.len() != count
+    }
+
+    /// Stops when the interrupt with IF bit `bit` is raised.
+    pub fn set_interrupt_break(&mut self, bit: u8, enabled: bool) {
+        match enabled {
+            true => self.interrupt_breaks |= 1 << bit,
+            false => self.interrupt_breaks &= !(1 << bit),
+        }
+    }
+
+    pub fn set_dma_break(&mut self, channel: u8, enabled: bool) {
+        match enabled {
+            true => self.dma_breaks |= 1 << channel,
+            false => self.dma_breaks &= !(1 << channel),
+        }
+    }
+
+    /// Stops after the next instruction.
+    pub fn step_instruction(&mut self) {
+        self.step = Some(Step::Instruction);
+    }
+
+    /// Stops in front of `address` once the stack pointer is back at `stack_pointer` or above. Step over
+    /// and step out are built on this by the systems, which know where calls return to.
+    pub fn step_until(&mut self, address: u32, stack_pointer: Option<u32>) {
+        self.step = Some(Step::Until { address, stack_pointer });
+    }
+
+    pub fn run_to(&mut self, address: u32) {
+        self.step_until(address, None);
+    }
+
+    pub fn cancel_step(&mut self) {
+        self.step = None;
+    }
+
+    /// Called before the instruction at `address` executes. Returns why it must not execute yet.
+    pub fn before_instruction(
+        &mut self,
+        address: u32,
+        instruction_set: Option<InstructionSet>,
+        stack_pointer: u32,
+    ) -> Option<BreakReason> {
+        if std::mem::take(&mut self.resuming) {
+            return None;
+        }
+
+        let reason = match self.step {
+            Some(Step::Until {
+                address: target,
+                stack_pointer: minimum,
+            }) if target == address && minimum.is_none_or(|minimum| stack_pointer >= minimum) => {
+                self.step = None;
+                Some(BreakReason::Step)
+            }
+            _ => self
+                .breakpoints
+                .iter()
+                .any(|breakpoint| {
+                    breakpoint.address == address
+                        && breakpoint.instruction_set.is_none_or(|set| Some(set) == instruction_set)
+                })
+                .then_some(BreakReason::Breakpoint { address }),
+        };
+        self.resuming = reason.is_some();
+        reason
+    }
+
+    /// Called once the system has finished a cycle, whether or not it executed an instruction. The
+    /// instruction already ran, so the next one is checked as usual when the system resumes.
+    pub fn after_cycle(&mut self, executed_instruction: bool) -> Option<BreakReason> {
+        match (self.pending.take(), self.step) {
+            (Some(reason), _) => Some(reason),
+            (None, Some(Step::Instruction)) if executed_instruction => {
+                self.step = None;
+                Some(BreakReason::Step)
+            }
+            (None, _) => None,
+        }
+    }
+
+    #[inline]
+    pub fn memory_access(&mut self, address: u32, width: u32, access: AccessKind, value: u32) {
+        if self.watchpoints.is_empty() || self.pending.is_some() {
+            return;
+        }
+
+        if self
+            .watchpoints
+            .iter()
+            .any(|watchpoint| watchpoint.matches(address, width, access, value))
+        {
+            self.pending = Some(BreakReason::Watchpoint { address, access, value });
+        }
+    }
+
+    pub fn interrupt_raised(&mut self, bit: u8) {
+        if self.interrupt_breaks & (1 << bit) != 0 && self.pending.is_none() {
+            self.pending = Some(BreakReason::Interrupt(bit));
+        }
+    }
+
+    pub fn dma_started(&mut self, channel: u8) {
+        if self.dma_breaks & (1 << channel) != 0 && self.pending.is_none() {
+            self.pending = Some(BreakReason::Dma(channel));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breakpoints_stop_once_and_respect_instruction_set() {
+        let mut debugger = Debugger::new();
+        debugger.add_breakpoint(Breakpoint {
+            address: 0x08000100,
+            instruction_set: Some(InstructionSet::Thumb),
+        });
+
+        assert_eq!(debugger.before_instruction(0x08000100, Some(InstructionSet::Arm), 0), None);
+        assert_eq!(
+            debugger.before_instruction(0x08000100, Some(InstructionSet::Thumb), 0),
+            Some(BreakReason::Breakpoint { address: 0x08000100 })
+        );
+        assert_eq!(
+            debugger.before_instruction(0x08000100, Some(InstructionSet::Thumb), 0),
+            None,
+            "resuming runs the instruction"
+        );
+        assert_eq!(debugger.after_cycle(true), None);
+        assert!(debugger.remove_breakpoint(0x08000100));
+        assert!(!debugger.remove_breakpoint(0x08000100));
+    }
+
+    #[test]
+    fn watchpoints_match_kind_range_and_value() {
+        let mut debugger = Debugger::new();
+        debugger.add_watchpoint(Watchpoint {
+            address: 0xC000,
+            length: 2,
+            kind: WatchKind::Write,
+            condition: WatchCondition::Equal(3),
+        });
+
+        debugger.memory_access(0xC001, 1, AccessKind::Read, 3);
+        debugger.memory_access(0xC001, 1, AccessKind::Write, 2);
+        debugger.memory_access(0xC002, 1, AccessKind::Write, 3);
+        assert_eq!(debugger.after_cycle(true), None);
+
+        debugger.memory_access(0xBFFF, 2, AccessKind::Write, 3);
+        assert_eq!(
+            debugger.after_cycle(true),
+            Some(BreakReason::Watchpoint {
+                address: 0xBFFF,
+                access: AccessKind::Write,
+                value: 3,
+            })
+        );
+        assert!(debugger.remove_watchpoint(0xC001));
+    }
+
+    #[test]
+    fn steps_interrupts_and_dma() {
+        let mut debugger = Debugger::new();
+        debugger.step_instruction();
+        assert_eq!(debugger.after_cycle(false), None, "halted cycles do not finish a step");
+        assert_eq!(debugger.after_cycle(true), Some(BreakReason::Step));
+        debugger.add_breakpoint(Breakpoint {
+            address: 0x0150,
+            instruction_set: None,
+        });
+        assert_eq!(
+            debugger.before_instruction(0x0150, None, 0xFFFE),
+            Some(BreakReason::Breakpoint { address: 0x0150 }),
+            "a step stops after its instruction, so the next one still hits breakpoints"
+        );
+
+        let mut debugger = Debugger::new();
+        debugger.step_until(0x0150, Some(0xFFFE));
+        assert_eq!(
+            debugger.before_instruction(0x0150, None, 0xFFFC),
+            None,
+            "still inside a recursive call"
+        );
+        assert_eq!(debugger.before_instruction(0x0150, None, 0xFFFE), Some(BreakReason::Step));
+
+        debugger.set_interrupt_break(0, true);
+        debugger.set_dma_break(3, true);
+        debugger.interrupt_raised(1);
+        debugger.dma_started(3);
+        debugger.interrupt_raised(0);
+        assert_eq!(debugger.after_cycle(false), Some(BreakReason::Dma(3)));
+        debugger.interrupt_raised(0);
+        assert_eq!(debugger.after_cycle(false), Some(BreakReason::Interrupt(0)));
+        debugger.set_interrupt_break(0, false);
+        debugger.interrupt_raised(0);
+        assert_eq!(debugger.after_cycle(false), None);
+    }
+}
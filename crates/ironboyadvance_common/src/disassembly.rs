@@ -0,0 +1,141 @@
+use std::collections::{BTreeMap, VecDeque};
+
+/// Where execution can go after an instruction, which is what recursive descent follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Falls through to the next instruction.
+    Next,
+    /// Branches to `target`, also falling through when the branch is conditional.
+    Jump { target: u32, conditional: bool },
+    /// Calls `target` and carries on after it once it returns.
+    Call { target: u32, conditional: bool },
+    /// Returns, or jumps to an address that is only known at run time.
+    Indirect { conditional: bool },
+    /// Not a valid instruction, so the bytes are most likely data.
+    Invalid,
+}
+
+impl Flow {
+    /// The same flow under a condition, for instruction sets where any instruction can be conditional.
+    pub fn with_condition(self, conditional: bool) -> Flow {
+        match self {
+            Flow::Jump { target, .. } => Flow::Jump { target, conditional },
+            Flow::Call { target, .. } => Flow::Call { target, conditional },
+            Flow::Indirect { .. } => Flow::Indirect { conditional },
+            flow => flow,
+        }
+    }
+
+    /// Whether the instruction after this one can execute next.
+    pub fn falls_through(&self) -> bool {
+        match self {
+            Flow::Next | Flow::Call { .. } => true,
+            Flow::Jump { conditional, .. } | Flow::Indirect { conditional } => *conditional,
+            Flow::Invalid => false,
+        }
+    }
+
+    pub fn target(&self) -> Option<u32> {
+        match self {
+            Flow::Jump { target, .. } | Flow::Call { target, .. } => Some(*target),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disassembly {
+    pub address: u32,
+    /// Length of the instruction in bytes.
+    pub size: u32,
+    pub text: String,
+    pub flow: Flow,
+}
+
+/// Decodes the instruction at the start of `bytes`, which sits at the given address. Decoders pad
+/// `bytes` with zeros when it is shorter than the instruction.
+pub type Decoder<'a> = &'a dyn Fn(&[u8], u32) -> Disassembly;
+
+fn instruction_at(code: &[u8], base: u32, address: u32, decode: Decoder) -> Option<Disassembly> {
+    let offset = address.checked_sub(base)? as usize;
+    let bytes = code.get(offset..).filter(|bytes| !bytes.is_empty())?;
+    let disassembly = decode(bytes, address);
+    (offset + disassembly.size as usize <= code.len()).then_some(disassembly)
+}
+
+/// Decodes every instruction from the start of `code` to its end, treating data as instructions too.
+pub fn linear_sweep(code: &[u8], base: u32, decode: Decoder) -> Vec<Disassembly> {
+    let mut instructions = Vec::new();
+    let mut address = base;
+    while let Some(disassembly) = instruction_at(code, base, address, decode) {
+        address = address.wrapping_add(disassembly.size);
+        instructions.push(disassembly);
+    }
+    instructions
+}
+
+/// Decodes only what is reachable from `entry_points`, following branches and calls and stopping at
+/// returns, indirect jumps and invalid instructions. Returns the instructions in address order.
+pub fn recursive_descent(code: &[u8], base: u32, entry_points: &[u32], decode: Decoder) -> Vec<Disassembly> {
+    let mut instructions = BTreeMap::new();
+    let mut pending: VecDeque<u32> = entry_points.iter().copied().collect();
+    while let Some(mut address) = pending.pop_front() {
+        while !instructions.contains_key(&address)
+            && let Some(disassembly) = instruction_at(code, base, address, decode)
+        {
+            let flow = disassembly.flow;
+            let next = address.wrapping_add(disassembly.size);
+            instructions.insert(address, disassembly);
+            if let Some(target) = flow.target() {
+                pending.push_back(target);
+            }
+            if !flow.falls_through() {
+                break;
+            }
+            address = next;
+        }
+    }
+    instructions.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One byte instructions: 0x00 falls through, 0x1n jumps to n, 0x2n conditionally jumps to n and
+    /// 0xFF returns.
+    fn decode(bytes: &[u8], address: u32) -> Disassembly {
+        let flow = match bytes[0] {
+            0x00 => Flow::Next,
+            0xFF => Flow::Indirect { conditional: false },
+            byte if byte >> 4 == 1 => Flow::Jump {
+                target: (byte & 0xF) as u32,
+                conditional: false,
+            },
+            byte if byte >> 4 == 2 => Flow::Jump {
+                target: (byte & 0xF) as u32,
+                conditional: true,
+            },
+            _ => Flow::Invalid,
+        };
+        Disassembly {
+            address,
+            size: 1,
+            text: format!("{:02X}", bytes[0]),
+            flow,
+        }
+    }
+
+    #[test]
+    fn recursive_descent_skips_unreachable_bytes() {
+        let code = [0x00, 0x24, 0x16, 0xEE, 0xFF, 0xEE, 0x00, 0xFF];
+        let linear: Vec<u32> = linear_sweep(&code, 0, &decode).iter().map(|i| i.address).collect();
+        assert_eq!(linear, (0..8).collect::<Vec<_>>());
+
+        let reachable: Vec<u32> = recursive_descent(&code, 0, &[0], &decode)
+            .iter()
+            .map(|instruction| instruction.address)
+            .collect();
+        assert_eq!(reachable, [0, 1, 2, 4, 6, 7]);
+    }
+}
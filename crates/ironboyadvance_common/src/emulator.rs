@@ -1,11 +1,145 @@
+use crate::{
+    call_stack::CallStack,
+    cheats::{Cheat, CheatError},
+    coverage::Coverage,
+    debugger::{BreakReason, Debugger},
+    gdb::GdbTarget,
+    profiler::Profiler,
+    snapshot::SnapshotError,
+    symbols::SymbolTable,
+    trace::Tracer,
+};
+
+/// One register of a cpu's register file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Register {
+    pub name: &'static str,
+    pub value: u32,
+    /// Width of the register, e.g. 8 for the SM83's `a` or 1 for its interrupt master enable.
+    pub bits: u8,
+}
+
+/// A named block of the address space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub name: &'static str,
+    pub start: u32,
+    /// Bytes of memory backing the region.
+    pub size: u32,
+    /// Bytes of address space from `start` the region repeats through every `size` bytes. Equal to `size`
+    /// when the region is not mirrored.
+    pub span: u32,
+    /// Start of another region this one shows the contents of, like the Game Boy's echo RAM.
+    pub mirror_of: Option<u32>,
+}
+
+impl MemoryRegion {
+    pub const fn new(name: &'static str, start: u32, size: u32) -> MemoryRegion {
+        MemoryRegion {
+            name,
+            start,
+            size,
+            span: size,
+            mirror_of: None,
+        }
+    }
+
+    pub const fn mirrored(self, span: u32) -> MemoryRegion {
+        MemoryRegion { span, ..self }
+    }
+
+    pub const fn mirror_of(self, start: u32) -> MemoryRegion {
+        MemoryRegion {
+            mirror_of: Some(start),
+            ..self
+        }
+    }
+
+    pub fn contains(&self, address: u32) -> bool {
+        address.wrapping_sub(self.start) < self.span
+    }
+
+    /// How many times the region shows up in its span, counting itself.
+    pub fn mirrors(&self) -> u32 {
+        self.span / self.size.max(1)
+    }
+}
+
+/// Looks at and changes a system from the outside, for debuggers, memory viewers, RAM search and scripts.
+/// None of it advances the system.
 pub trait SystemInspection {
     fn serial_output(&self) -> &[u8] {
         &[]
     }
 
-    fn read_memory(&self, _address: u32) -> u8 {
+    /// Reads a byte the way the cpu would, but without side effects. Nothing latches open bus values,
+    /// acknowledges a read or clocks a serial transfer, and the scheduler does not move.
+    fn peek_8(&self, _address: u32) -> u8 {
         0xFF
     }
+
+    fn peek_16(&self, address: u32) -> u16 {
+        u16::from_le_bytes([self.peek_8(address), self.peek_8(address.wrapping_add(1))])
+    }
+
+    fn peek_32(&self, address: u32) -> u32 {
+        u32::from_le_bytes([0, 1, 2, 3].map(|offset| self.peek_8(address.wrapping_add(offset))))
+    }
+
+    /// Writes exactly one byte. Memory that only takes wider writes is written around it, and memory the
+    /// cpu cannot write, like rom and the BIOS, is patched where the system supports it. I/O registers
+    /// take the write like a cpu store would.
+    fn poke_8(&mut self, _address: u32, _value: u8) {}
+
+    fn poke_16(&mut self, address: u32, value: u16) {
+        for (offset, byte) in value.to_le_bytes().into_iter().enumerate() {
+            self.poke_8(address.wrapping_add(offset as u32), byte);
+        }
+    }
+
+    fn poke_32(&mut self, address: u32, value: u32) {
+        for (offset, byte) in value.to_le_bytes().into_iter().enumerate() {
+            self.poke_8(address.wrapping_add(offset as u32), byte);
+        }
+    }
+
+    /// Every register of the cpu, including status registers and registers banked away in other modes.
+    fn registers(&self) -> Vec<Register> {
+        Vec::new()
+    }
+
+    /// Reads a register by the name [`SystemInspection::registers`] lists it under.
+    fn register(&self, name: &str) -> Option<u32> {
+        self.registers()
+            .into_iter()
+            .find(|register| register.name.eq_ignore_ascii_case(name))
+            .map(|register| register.value)
+    }
+
+    /// Writes a register by name, returning false when the cpu has no such register.
+    fn set_register(&mut self, _name: &str, _value: u32) -> bool {
+        false
+    }
+
+    fn memory_map(&self) -> Vec<MemoryRegion> {
+        Vec::new()
+    }
+
+    /// The RAM games keep their state in, which a [`RamSearch`](crate::ram_search::RamSearch) looks through.
+    fn ram_regions(&self) -> Vec<MemoryRegion> {
+        Vec::new()
+    }
+
+    /// Calls and exceptions the cpu is currently inside of, as far as the cpu has seen them happen.
+    fn call_stack(&self) -> Option<&CallStack> {
+        None
+    }
+
+    /// The call stack innermost first, starting at the instruction about to execute.
+    fn backtrace(&self, symbols: Option<&SymbolTable>) -> Vec<String> {
+        let pc = self.register("pc").unwrap_or_default();
+        self.call_stack().cloned().unwrap_or_default().backtrace(pc, symbols)
+    }
 }
 
 pub trait Emulator: SystemInspection {
@@ -14,6 +148,68 @@ pub trait Emulator: SystemInspection {
     fn audio_buffer(&self) -> &[(f32, f32)];
     fn clear_audio_buffer(&mut self);
     fn handle_pressed_buttons(&mut self, input: u16);
+    /// Reports whether the game read the keypad since the previous call, clearing the flag.
+    fn take_input_polled(&mut self) -> bool;
+    fn save_state(&self) -> Vec<u8>;
+    fn load_state(&mut self, state: &[u8]) -> Result<(), SnapshotError>;
+
+    /// Cheats applied while the system runs, in the order they were added.
+    fn cheats(&self) -> &[Cheat] {
+        &[]
+    }
+
+    /// Parses and adds a cheat, returning its index in [`Emulator::cheats`].
+    fn add_cheat(&mut self, cheat: Cheat) -> Result<usize, CheatError> {
+        Err(CheatError::UnsupportedFormat(cheat.format))
+    }
+
+    fn remove_cheat(&mut self, index: usize) -> Result<Cheat, CheatError> {
+        Err(CheatError::UnknownCheat(index))
+    }
+
+    fn set_cheat_enabled(&mut self, index: usize, _enabled: bool) -> Result<(), CheatError> {
+        Err(CheatError::UnknownCheat(index))
+    }
+
+    fn debugger(&self) -> &Debugger;
+
+    /// Breakpoints, watchpoints and stepping. `run` returns early once the debugger stops the system.
+    fn debugger_mut(&mut self) -> &mut Debugger;
+
+    /// Why the last `run` returned early, if it did.
+    fn take_break_reason(&mut self) -> Option<BreakReason>;
+
+    /// Steps one instruction, running a call to completion instead of stepping into it.
+    fn step_over(&mut self);
+
+    /// Runs until the current function returns to its caller.
+    fn step_out(&mut self);
+
+    /// Registers and memory for a [`GdbStub`](crate::gdb::GdbStub), on systems gdb can debug.
+    fn gdb_target(&mut self) -> Option<&mut dyn GdbTarget> {
+        None
+    }
+
+    /// Logs every executed instruction to `tracer`, returning the tracer it replaces. Each `run` that
+    /// reaches its cycle target counts as a frame for the tracer's triggers.
+    fn start_trace(&mut self, tracer: Tracer) -> Option<Tracer>;
+
+    /// Removes the tracer so the caller can [`Tracer::finish`] it.
+    fn stop_trace(&mut self) -> Option<Tracer>;
+
+    /// Charges the cycles of every instruction and halted step to `profiler`, returning the profiler it
+    /// replaces.
+    fn start_profile(&mut self, profiler: Profiler) -> Option<Profiler>;
+
+    /// Removes the profiler so the caller can report on it.
+    fn stop_profile(&mut self) -> Option<Profiler>;
+
+    /// Records the rom bytes that execute or are read as data into `coverage`, returning the map it
+    /// replaces.
+    fn start_coverage(&mut self, coverage: Coverage) -> Option<Coverage>;
+
+    /// Removes the coverage map so the caller can save it.
+    fn stop_coverage(&mut self) -> Option<Coverage>;
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
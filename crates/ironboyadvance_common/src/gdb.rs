@@ -0,0 +1,551 @@
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+};
+
+use thiserror::Error;
+
+use crate::{
+    debugger::{AccessKind, BreakReason, Breakpoint, InstructionSet, WatchCondition, WatchKind, Watchpoint},
+    emulator::Emulator,
+};
+
+pub const DEFAULT_GDB_PORT: u16 = 2345;
+
+const PACKET_SIZE: usize = 0x4000;
+const INTERRUPT: u8 = 0x03;
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+#[derive(Error, Debug)]
+pub enum GdbError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("this system cannot be debugged with gdb")]
+    UnsupportedSystem,
+}
+
+/// Registers and memory writes a system exposes to gdb. Registers are numbered in the order the target
+/// description lists them.
+pub trait GdbTarget {
+    /// The `target.xml` gdb reads to learn the register layout.
+    fn target_description(&self) -> &'static str;
+    fn register_count(&self) -> usize;
+    fn read_register(&self, index: usize) -> u32;
+    fn write_register(&mut self, index: usize, value: u32);
+    fn write_memory(&mut self, address: u32, value: u8);
+    /// Continues execution at `address`, for `c` and `s` packets that carry one.
+    fn jump(&mut self, address: u32);
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Incoming {
+    Interrupt,
+    Packet(String),
+    Corrupt,
+}
+
+struct Connection {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+    acknowledge: bool,
+    /// Breakpoint and watchpoint addresses gdb inserted, removed again if it goes away without doing so.
+    inserted: Vec<(u8, u32)>,
+}
+
+/// A gdb remote serial protocol server on a localhost port. The frontend calls [`GdbStub::poll`] between
+/// frames and only runs the system while it returns true, so a halted system keeps the window responsive.
+pub struct GdbStub {
+    listener: TcpListener,
+    connection: Option<Connection>,
+    running: bool,
+}
+
+impl GdbStub {
+    pub fn bind(port: u16) -> Result<GdbStub, GdbError> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        Ok(GdbStub {
+            listener,
+            connection: None,
+            running: true,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.listener.local_addr().map_or(0, |address| address.port())
+    }
+
+    pub fn attached(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Accepts gdb, answers its packets and reports why the system stopped. Returns whether the system
+    /// should run; it halts as soon as gdb attaches and stays halted until gdb continues or steps it.
+    pub fn poll(&mut self, system: &mut dyn Emulator) -> Result<bool, GdbError> {
+        if self.connection.is_none() {
+            self.accept(system)?;
+        }
+        if self.connection.is_none() {
+            return Ok(true);
+        }
+
+        if self.running
+            && let Some(reason) = system.take_break_reason()
+        {
+            self.running = false;
+            self.send(&stop_reply(reason))?;
+        }
+
+        match self.receive() {
+            Ok(true) => {}
+            Ok(false) => {
+                self.disconnect(system);
+                return Ok(true);
+            }
+            Err(error) if error.kind() == ErrorKind::ConnectionReset => {
+                self.disconnect(system);
+                return Ok(true);
+            }
+            Err(error) => return Err(error.into()),
+        }
+
+        while let Some(connection) = &mut self.connection
+            && let Some(incoming) = next_incoming(&mut connection.buffer)
+        {
+            match incoming {
+                Incoming::Interrupt if self.running => {
+                    self.running = false;
+                    system.debugger_mut().cancel_step();
+                    self.send(&format!("S{SIGINT:02x}"))?;
+                }
+                Incoming::Interrupt => {}
+                Incoming::Corrupt => connection.stream.write_all(b"-")?,
+                Incoming::Packet(packet) => {
+                    if connection.acknowledge {
+                        connection.stream.write_all(b"+")?;
+                    }
+                    if let Some(reply) = self.handle(system, &packet)? {
+                        self.send(&reply)?;
+                    }
+                    if packet == "QStartNoAckMode"
+                        && let Some(connection) = &mut self.connection
+                    {
+                        connection.acknowledge = false;
+                    }
+                }
+            }
+        }
+
+        Ok(self.running)
+    }
+
+    fn accept(&mut self, system: &mut dyn Emulator) -> Result<(), GdbError> {
+        let stream = match self.listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(error) if error.kind() == ErrorKind::WouldBlock => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+        if system.gdb_target().is_none() {
+            return Err(GdbError::UnsupportedSystem);
+        }
+        stream.set_nonblocking(false)?;
+        stream.set_nodelay(true)?;
+        self.connection = Some(Connection {
+            stream,
+            buffer: Vec::new(),
+            acknowledge: true,
+            inserted: Vec::new(),
+        });
+        self.running = false;
+        system.debugger_mut().cancel_step();
+        Ok(())
+    }
+
+    fn disconnect(&mut self, system: &mut dyn Emulator) {
+        if let Some(connection) = self.connection.take() {
+            let debugger = system.debugger_mut();
+            for (kind, address) in connection.inserted {
+                match kind {
+                    0 | 1 => debugger.remove_breakpoint(address),
+                    _ => debugger.remove_watchpoint(address),
+                };
+            }
+            debugger.cancel_step();
+        }
+        self.running = true;
+    }
+
+    /// Reads whatever gdb has sent without blocking. Returns false once gdb closed the connection.
+    fn receive(&mut self) -> io::Result<bool> {
+        let Some(connection) = &mut self.connection else {
+            return Ok(true);
+        };
+        let mut chunk = [0; 1024];
+        connection.stream.set_nonblocking(true)?;
+        let result = loop {
+            match connection.stream.read(&mut chunk) {
+                Ok(0) => break Ok(false),
+                Ok(length) => connection.buffer.extend_from_slice(&chunk[..length]),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break Ok(true),
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => break Err(error),
+            }
+        };
+        connection.stream.set_nonblocking(false)?;
+        result
+    }
+
+    fn send(&mut self, reply: &str) -> io::Result<()> {
+        match &mut self.connection {
+            Some(connection) => connection.stream.write_all(&frame(reply)),
+            None => Ok(()),
+        }
+    }
+
+    /// Answers one packet. Returns `None` for packets answered later, like `c` once the system stops.
+    fn handle(&mut self, system: &mut dyn Emulator, packet: &str) -> Result<Option<String>, GdbError> {
+        let reply = match packet.as_bytes().first() {
+            Some(b'?') => format!("S{SIGTRAP:02x}"),
+            Some(b'g' | b'G' | b'p' | b'P' | b'M') => {
+                let target = system.gdb_target().ok_or(GdbError::UnsupportedSystem)?;
+                access_target(target, packet)
+            }
+            Some(b'm') => match parse_range(&packet[1..]) {
+                Some((address, length)) => (0..length.min(PACKET_SIZE as u32 / 2))
+                    .map(|offset| format!("{:02x}", system.peek_8(address.wrapping_add(offset))))
+                    .collect(),
+                None => "E01".to_string(),
+            },
+            Some(b'Z') => self.insert(system, &packet[1..]),
+            Some(b'z') => self.remove(system, &packet[1..]),
+            Some(b'c' | b'C') => return self.resume(system, packet, false),
+            Some(b's' | b'S') => return self.resume(system, packet, true),
+            Some(b'H' | b'T') => "OK".to_string(),
+            Some(b'D') => {
+                self.send("OK")?;
+                self.disconnect(system);
+                return Ok(None);
+            }
+            Some(b'k') => {
+                self.disconnect(system);
+                return Ok(None);
+            }
+            _ if packet.starts_with("vCont;") => return self.resume(system, packet, false),
+            _ if packet == "vKill" => {
+                self.send("OK")?;
+                self.disconnect(system);
+                return Ok(None);
+            }
+            _ => self.query(system, packet),
+        };
+        Ok(Some(reply))
+    }
+
+    fn query(&mut self, system: &mut dyn Emulator, packet: &str) -> String {
+        let Some(target) = system.gdb_target() else {
+            return String::new();
+        };
+        match packet {
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            "QStartNoAckMode" => "OK".to_string(),
+            "vCont?" => "vCont;c;C;s;S".to_string(),
+            _ if packet.starts_with("qSupported") => {
+                format!("PacketSize={PACKET_SIZE:x};qXfer:features:read+;QStartNoAckMode+")
+            }
+            _ => match packet.strip_prefix("qXfer:features:read:target.xml:") {
+                Some(range) => match parse_range(range) {
+                    Some((offset, length)) => {
+                        let description = target.target_description().as_bytes();
+                        let start = (offset as usize).min(description.len());
+                        let end = start.saturating_add(length as usize).min(description.len());
+                        let more = match end < description.len() {
+                            true => 'm',
+                            false => 'l',
+                        };
+                        format!("{more}{}", String::from_utf8_lossy(&description[start..end]))
+                    }
+                    None => "E01".to_string(),
+                },
+                None => String::new(),
+            },
+        }
+    }
+
+    /// Handles `c`, `s`, their signal variants and `vCont`, which gdb uses when it saw `vCont?`.
+    fn resume(&mut self, system: &mut dyn Emulator, packet: &str, step: bool) -> Result<Option<String>, GdbError> {
+        let (step, address) = match packet.strip_prefix("vCont;") {
+            Some(actions) => match actions.as_bytes().first() {
+                Some(b'c' | b'C') => (false, None),
+                Some(b's' | b'S') => (true, None),
+                _ => return Ok(Some("E01".to_string())),
+            },
+            None => {
+                // `C` and `S` carry a signal to deliver before the address, which the system has no use for.
+                let arguments = &packet[1..];
+                let address = match packet.as_bytes()[0] {
+                    b'C' | b'S' => arguments.split_once(';').map(|(_, address)| address),
+                    _ => Some(arguments).filter(|address| !address.is_empty()),
+                };
+                (step, address.and_then(parse_hex))
+            }
+        };
+
+        if let Some(address) = address
+            && let Some(target) = system.gdb_target()
+        {
+            target.jump(address);
+        }
+        let debugger = system.debugger_mut();
+        match step {
+            true => debugger.step_instruction(),
+            false => debugger.cancel_step(),
+        }
+        self.running = true;
+        Ok(None)
+    }
+
+    /// `Z0` and `Z1` pass the instruction length as the kind, which tells ARM and Thumb breakpoints apart.
+    /// `Z2` to `Z4` pass the watched length.
+    fn insert(&mut self, system: &mut dyn Emulator, arguments: &str) -> String {
+        let Some((kind, address, length)) = parse_point(arguments) else {
+            return "E01".to_string();
+        };
+        let debugger = system.debugger_mut();
+        match kind {
+            0 | 1 => debugger.add_breakpoint(Breakpoint {
+                address,
+                instruction_set: match length {
+                    2 => Some(InstructionSet::Thumb),
+                    4 => Some(InstructionSet::Arm),
+                    _ => None,
+                },
+            }),
+            2..=4 => debugger.add_watchpoint(Watchpoint {
+                address,
+                length: length.max(1),
+                kind: match kind {
+                    2 => WatchKind::Write,
+                    3 => WatchKind::Read,
+                    _ => WatchKind::Access,
+                },
+                condition: WatchCondition::Any,
+            }),
+            _ => return String::new(),
+        }
+        if let Some(connection) = &mut self.connection {
+            connection.inserted.push((kind, address));
+        }
+        "OK".to_string()
+    }
+
+    fn remove(&mut self, system: &mut dyn Emulator, arguments: &str) -> String {
+        let Some((kind, address, _)) = parse_point(arguments) else {
+            return "E01".to_string();
+        };
+        let debugger = system.debugger_mut();
+        match kind {
+            0 | 1 => debugger.remove_breakpoint(address),
+            2..=4 => debugger.remove_watchpoint(address),
+            _ => return String::new(),
+        };
+        if let Some(connection) = &mut self.connection {
+            connection.inserted.retain(|&point| point != (kind, address));
+        }
+        "OK".to_string()
+    }
+}
+
+/// Answers the register packets and memory writes.
+fn access_target(target: &mut dyn GdbTarget, packet: &str) -> String {
+    let arguments = &packet[1..];
+    match packet.as_bytes()[0] {
+        b'g' => (0..target.register_count())
+            .map(|index| encode_register(target.read_register(index)))
+            .collect(),
+        b'G' => {
+            for index in 0..target.register_count().min(arguments.len() / 8) {
+                if let Some(value) = decode_register(&arguments[index * 8..index * 8 + 8]) {
+                    target.write_register(index, value);
+                }
+            }
+            "OK".to_string()
+        }
+        b'p' => match parse_hex(arguments) {
+            Some(index) if (index as usize) < target.register_count() => {
+                encode_register(target.read_register(index as usize))
+            }
+            _ => "E01".to_string(),
+        },
+        b'P' => {
+            let register = arguments.split_once('=').and_then(|(index, value)| {
+                let index = parse_hex(index)? as usize;
+                (index < target.register_count()).then_some((index, decode_register(value)?))
+            });
+            match register {
+                Some((index, value)) => {
+                    target.write_register(index, value);
+                    "OK".to_string()
+                }
+                None => "E01".to_string(),
+            }
+        }
+        _ => {
+            let write = arguments.split_once(':').and_then(|(range, data)| {
+                let (address, length) = parse_range(range)?;
+                let bytes = decode_bytes(data)?;
+                (bytes.len() == length as usize).then_some((address, bytes))
+            });
+            match write {
+                Some((address, bytes)) => {
+                    for (offset, value) in bytes.into_iter().enumerate() {
+                        target.write_memory(address.wrapping_add(offset as u32), value);
+                    }
+                    "OK".to_string()
+                }
+                None => "E01".to_string(),
+            }
+        }
+    }
+}
+
+fn stop_reply(reason: BreakReason) -> String {
+    match reason {
+        BreakReason::Watchpoint {
+            address,
+            access: AccessKind::Write,
+            ..
+        } => format!("T{SIGTRAP:02x}watch:{address:x};"),
+        BreakReason::Watchpoint {
+            address,
+            access: AccessKind::Read,
+            ..
+        } => format!("T{SIGTRAP:02x}rwatch:{address:x};"),
+        _ => format!("S{SIGTRAP:02x}"),
+    }
+}
+
+/// Takes the next interrupt or complete packet off the front of `buffer`, skipping acknowledgements.
+fn next_incoming(buffer: &mut Vec<u8>) -> Option<Incoming> {
+    loop {
+        match buffer.first()? {
+            b'$' => break,
+            &INTERRUPT => {
+                buffer.remove(0);
+                return Some(Incoming::Interrupt);
+            }
+            _ => {
+                buffer.remove(0);
+            }
+        }
+    }
+
+    let end = buffer.iter().position(|&byte| byte == b'#')?;
+    if buffer.len() < end + 3 {
+        return None;
+    }
+    let packet: Vec<u8> = buffer.drain(..end + 3).collect();
+    let data = &packet[1..end];
+    let expected = std::str::from_utf8(&packet[end + 1..])
+        .ok()
+        .and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+    match expected == Some(checksum(data)) {
+        true => Some(Incoming::Packet(String::from_utf8_lossy(data).into_owned())),
+        false => Some(Incoming::Corrupt),
+    }
+}
+
+/// Wraps `reply` as `$data#checksum`, escaping the bytes the framing reserves.
+fn frame(reply: &str) -> Vec<u8> {
+    let mut data = Vec::with_capacity(reply.len());
+    for byte in reply.bytes() {
+        match byte {
+            b'#' | b'$' | b'}' | b'*' => data.extend_from_slice(&[b'}', byte ^ 0x20]),
+            _ => data.push(byte),
+        }
+    }
+    let mut packet = Vec::with_capacity(data.len() + 4);
+    packet.push(b'$');
+    packet.extend_from_slice(&data);
+    packet.extend_from_slice(format!("#{:02x}", checksum(&data)).as_bytes());
+    packet
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+fn parse_hex(text: &str) -> Option<u32> {
+    u32::from_str_radix(text, 16).ok()
+}
+
+/// Parses `address,length`.
+fn parse_range(text: &str) -> Option<(u32, u32)> {
+    let (address, length) = text.split_once(',')?;
+    Some((parse_hex(address)?, parse_hex(length)?))
+}
+
+/// Parses the `type,address,kind` of a `Z` or `z` packet, ignoring any condition list after the kind.
+fn parse_point(text: &str) -> Option<(u8, u32, u32)> {
+    let (kind, range) = text.split_once(',')?;
+    let range = range.split(';').next()?;
+    let (address, length) = parse_range(range)?;
+    Some((kind.parse().ok()?, address, length))
+}
+
+/// Registers travel in target byte order, which is little endian on both systems.
+fn encode_register(value: u32) -> String {
+    format!("{:08x}", value.swap_bytes())
+}
+
+fn decode_register(text: &str) -> Option<u32> {
+    (text.len() == 8).then(|| parse_hex(text)).flatten().map(u32::swap_bytes)
+}
+
+fn decode_bytes(text: &str) -> Option<Vec<u8>> {
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets_are_framed_and_checked() {
+        assert_eq!(frame("OK"), b"$OK#9a");
+        assert_eq!(frame("a}b"), b"$a}]b#9d");
+
+        let mut buffer = b"+$g#67\x03$m8000000,4#ff$qC".to_vec();
+        assert_eq!(next_incoming(&mut buffer), Some(Incoming::Packet("g".to_string())));
+        assert_eq!(next_incoming(&mut buffer), Some(Incoming::Interrupt));
+        assert_eq!(next_incoming(&mut buffer), Some(Incoming::Corrupt));
+        assert_eq!(next_incoming(&mut buffer), None, "waits for the rest of the packet");
+        buffer.extend_from_slice(b"#b4");
+        assert_eq!(next_incoming(&mut buffer), Some(Incoming::Packet("qC".to_string())));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn registers_and_points_are_decoded() {
+        assert_eq!(encode_register(0x08000104), "04010008");
+        assert_eq!(decode_register("04010008"), Some(0x08000104));
+        assert_eq!(decode_register("0401"), None);
+        assert_eq!(decode_bytes("00ff10"), Some(vec![0x00, 0xFF, 0x10]));
+        assert_eq!(parse_point("0,80001a4,2"), Some((0, 0x080001A4, 2)));
+        assert_eq!(parse_point("2,3000000,4;X1,0"), Some((2, 0x03000000, 4)));
+        assert_eq!(
+            stop_reply(BreakReason::Watchpoint {
+                address: 0x03000010,
+                access: AccessKind::Write,
+                value: 0,
+            }),
+            "T05watch:3000010;"
+        );
+    }
+}
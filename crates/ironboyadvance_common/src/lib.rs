@@ -1,6 +1,19 @@
 pub mod bits;
+pub mod call_stack;
+pub mod cheats;
+pub mod coverage;
+pub mod debugger;
+pub mod disassembly;
 pub mod emulator;
+pub mod gdb;
 pub mod keypad;
 pub mod memory;
+pub mod profiler;
+pub mod ram_search;
 pub mod register_ops;
+pub mod save_format;
+pub mod save_storage;
 pub mod scheduler;
+pub mod snapshot;
+pub mod symbols;
+pub mod trace;
@@ -0,0 +1,296 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{call_stack::CallStack, symbols::SymbolTable};
+
+/// Extension flame graph tools like inferno and flamegraph.pl use for collapsed stacks.
+pub const COLLAPSED_EXTENSION: &str = "folded";
+
+/// What the profiler charges cycles to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProfileGrouping {
+    /// The functions on the shadow call stack, so a routine's cycles are split between its callers.
+    #[default]
+    CallStack,
+    /// Only the symbol containing the pc, whoever called it.
+    Symbols,
+}
+
+impl ProfileGrouping {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProfileGrouping::CallStack => "call-stack",
+            ProfileGrouping::Symbols => "symbols",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ProfileGrouping> {
+        [ProfileGrouping::CallStack, ProfileGrouping::Symbols]
+            .into_iter()
+            .find(|grouping| grouping.name().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Node {
+    /// Code outside every call the cpu was seen making.
+    Root,
+    Function(u32),
+    /// An address no symbol covers.
+    Unknown,
+    Dma,
+    Halt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileEntry {
+    pub name: String,
+    /// Cycles spent in the function itself.
+    pub self_cycles: u64,
+    /// Cycles spent in the function and everything below it, counting recursive calls once.
+    pub inclusive_cycles: u64,
+}
+
+/// Charges the cycles each instruction takes on the bus, wait states included, to the function running
+/// it. The system calls [`Profiler::enter`] before each instruction or halted step and
+/// [`Profiler::record`] with the cycles it took.
+pub struct Profiler {
+    grouping: ProfileGrouping,
+    symbols: SymbolTable,
+    stacks: HashMap<Vec<Node>, u64>,
+    current: Vec<Node>,
+    total_cycles: u64,
+    dma_cycles: u64,
+    halt_cycles: u64,
+}
+
+impl Profiler {
+    pub fn new(grouping: ProfileGrouping) -> Profiler {
+        Profiler {
+            grouping,
+            symbols: SymbolTable::default(),
+            stacks: HashMap::new(),
+            current: Vec::new(),
+            total_cycles: 0,
+            dma_cycles: 0,
+            halt_cycles: 0,
+        }
+    }
+
+    /// Names functions after `symbols`, which [`ProfileGrouping::Symbols`] also groups by.
+    pub fn with_symbols(mut self, symbols: SymbolTable) -> Profiler {
+        self.symbols = symbols;
+        self
+    }
+
+    pub fn grouping(&self) -> ProfileGrouping {
+        self.grouping
+    }
+
+    pub fn total_cycles(&self) -> u64 {
+        self.total_cycles
+    }
+
+    pub fn dma_cycles(&self) -> u64 {
+        self.dma_cycles
+    }
+
+    pub fn halt_cycles(&self) -> u64 {
+        self.halt_cycles
+    }
+
+    /// Picks the stack the next [`Profiler::record`] charges, with `pc` at the instruction about to run.
+    pub fn enter(&mut self, call_stack: &CallStack, pc: u32) {
+        self.current.clear();
+        match self.grouping {
+            ProfileGrouping::CallStack => {
+                self.current.push(Node::Root);
+                self.current
+                    .extend(call_stack.frames().iter().map(|frame| Node::Function(frame.target)));
+            }
+            ProfileGrouping::Symbols => self.current.push(match self.symbols.containing(pc) {
+                Some(symbol) => Node::Function(symbol.address),
+                None => Node::Unknown,
+            }),
+        }
+    }
+
+    /// Charges `cycles` to the stack picked by the last [`Profiler::enter`]. The `dma_cycles` among them
+    /// went to DMA transfers, and the rest to waiting when the cpu was `halted`.
+    pub fn record(&mut self, cycles: u64, dma_cycles: u64, halted: bool) {
+        let dma_cycles = dma_cycles.min(cycles);
+        let cpu_cycles = cycles - dma_cycles;
+        self.total_cycles += cycles;
+        self.dma_cycles += dma_cycles;
+        self.charge(Some(Node::Dma), dma_cycles);
+        match halted {
+            true => {
+                self.halt_cycles += cpu_cycles;
+                self.charge(Some(Node::Halt), cpu_cycles);
+            }
+            false => self.charge(None, cpu_cycles),
+        }
+    }
+
+    fn charge(&mut self, leaf: Option<Node>, cycles: u64) {
+        if cycles == 0 {
+            return;
+        }
+        self.current.extend(leaf);
+        match self.stacks.get_mut(self.current.as_slice()) {
+            Some(total) => *total += cycles,
+            None => {
+                self.stacks.insert(self.current.clone(), cycles);
+            }
+        }
+        if leaf.is_some() {
+            self.current.pop();
+        }
+    }
+
+    /// Self and inclusive cycles of every function, the most self cycles first.
+    pub fn report(&self) -> Vec<ProfileEntry> {
+        let mut totals: HashMap<Node, (u64, u64)> = HashMap::new();
+        for (stack, &cycles) in &self.stacks {
+            for (index, node) in stack.iter().enumerate() {
+                let total = totals.entry(*node).or_default();
+                if index == stack.len() - 1 {
+                    total.0 += cycles;
+                }
+                if !stack[..index].contains(node) {
+                    total.1 += cycles;
+                }
+            }
+        }
+
+        let mut entries: Vec<ProfileEntry> = totals
+            .into_iter()
+            .map(|(node, (self_cycles, inclusive_cycles))| ProfileEntry {
+                name: self.name(node),
+                self_cycles,
+                inclusive_cycles,
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.self_cycles
+                .cmp(&a.self_cycles)
+                .then(b.inclusive_cycles.cmp(&a.inclusive_cycles))
+                .then(a.name.cmp(&b.name))
+        });
+        entries
+    }
+
+    /// Writes one `outer;inner cycles` line per stack, the collapsed format flame graph tools read.
+    pub fn write_collapsed(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut lines: Vec<String> = self
+            .stacks
+            .iter()
+            .map(|(stack, cycles)| {
+                let names: Vec<String> = stack.iter().map(|&node| self.name(node)).collect();
+                format!("{} {cycles}", names.join(";"))
+            })
+            .collect();
+        lines.sort();
+        for line in lines {
+            writeln!(writer, "{line}")?;
+        }
+        writer.flush()
+    }
+
+    pub fn save_collapsed(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_collapsed(BufWriter::new(File::create(path)?))
+    }
+
+    fn name(&self, node: Node) -> String {
+        match node {
+            Node::Root => "[root]".to_string(),
+            Node::Unknown => "[unknown]".to_string(),
+            Node::Dma => "[DMA]".to_string(),
+            Node::Halt => "[halt]".to_string(),
+            Node::Function(address) => self.symbols.describe(address).unwrap_or_else(|| format!("0x{address:08X}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        call_stack::{Frame, FrameKind},
+        symbols::{Symbol, SymbolKind},
+    };
+
+    use super::*;
+
+    fn symbols() -> SymbolTable {
+        let symbol = |name: &str, address: u32| Symbol {
+            name: name.to_string(),
+            address,
+            size: 0x100,
+            bank: None,
+            kind: SymbolKind::Function,
+            instruction_set: None,
+        };
+        SymbolTable::new(vec![symbol("Main", 0x100), symbol("Draw", 0x200)])
+    }
+
+    #[test]
+    fn charges_call_stacks_and_collapses_them() {
+        let mut profiler = Profiler::new(ProfileGrouping::CallStack).with_symbols(symbols());
+        let mut call_stack = CallStack::new();
+        profiler.enter(&call_stack, 0x100);
+        profiler.record(4, 0, false);
+        call_stack.push(Frame {
+            kind: FrameKind::Call,
+            call_site: 0x104,
+            target: 0x200,
+            return_address: 0x108,
+        });
+        profiler.enter(&call_stack, 0x200);
+        profiler.record(10, 6, false);
+        profiler.enter(&call_stack, 0x210);
+        profiler.record(20, 0, true);
+
+        assert_eq!(
+            (profiler.total_cycles(), profiler.dma_cycles(), profiler.halt_cycles()),
+            (34, 6, 20)
+        );
+        let report = profiler.report();
+        let draw = report.iter().find(|entry| entry.name == "Draw").unwrap();
+        assert_eq!((draw.self_cycles, draw.inclusive_cycles), (4, 30));
+        let root = report.iter().find(|entry| entry.name == "[root]").unwrap();
+        assert_eq!((root.self_cycles, root.inclusive_cycles), (4, 34));
+        assert_eq!(report[0].name, "[halt]");
+
+        let mut collapsed = Vec::new();
+        profiler.write_collapsed(&mut collapsed).unwrap();
+        assert_eq!(
+            String::from_utf8(collapsed).unwrap(),
+            "[root] 4\n[root];Draw 4\n[root];Draw;[DMA] 6\n[root];Draw;[halt] 20\n"
+        );
+    }
+
+    #[test]
+    fn groups_by_the_symbol_containing_the_pc() {
+        let mut profiler = Profiler::new(ProfileGrouping::Symbols).with_symbols(symbols());
+        let call_stack = CallStack::new();
+        for (pc, cycles) in [(0x100, 2), (0x1F0, 3), (0x280, 5), (0x400, 7)] {
+            profiler.enter(&call_stack, pc);
+            profiler.record(cycles, 0, false);
+        }
+
+        let report: Vec<(String, u64)> = profiler
+            .report()
+            .into_iter()
+            .map(|entry| (entry.name, entry.self_cycles))
+            .collect();
+        assert_eq!(
+            report,
+            vec![("[unknown]".to_string(), 7), ("Draw".to_string(), 5), ("Main".to_string(), 5)]
+        );
+        assert_eq!(ProfileGrouping::from_name("Symbols"), Some(ProfileGrouping::Symbols));
+    }
+}
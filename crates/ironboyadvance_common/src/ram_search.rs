@@ -0,0 +1,375 @@
+use thiserror::Error;
+
+use crate::{
+    emulator::{MemoryRegion, SystemInspection},
+    memory::MemoryAccessWidth,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SearchError {
+    #[error("no snapshot {0}, the search has {1}")]
+    UnknownSnapshot(usize, usize),
+    #[error("snapshot {0} was dropped, only the first, the latest and explicit ones are kept")]
+    DroppedSnapshot(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessOrEqual,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    pub const ALL: [Comparison; 6] = [
+        Comparison::Equal,
+        Comparison::NotEqual,
+        Comparison::Less,
+        Comparison::Greater,
+        Comparison::LessOrEqual,
+        Comparison::GreaterOrEqual,
+    ];
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::Greater => ">",
+            Comparison::LessOrEqual => "<=",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+
+    fn holds(&self, value: i64, other: i64) -> bool {
+        match self {
+            Comparison::Equal => value == other,
+            Comparison::NotEqual => value != other,
+            Comparison::Less => value < other,
+            Comparison::Greater => value > other,
+            Comparison::LessOrEqual => value <= other,
+            Comparison::GreaterOrEqual => value >= other,
+        }
+    }
+}
+
+/// What a candidate's current value must satisfy to stay in the search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchFilter {
+    /// Compares the value to a constant.
+    Value(Comparison, i64),
+    /// Compares the value to what it was at the previous filter, e.g. `Previous(NotEqual)` for changed.
+    Previous(Comparison),
+    /// The value moved by exactly this much since the previous filter, wrapping at the search width, so
+    /// a negative amount means decreased.
+    ChangedBy(i64),
+    /// Compares the value to what it was in an earlier snapshot, 0 being the one the search started from.
+    Snapshot(usize, Comparison),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub width: MemoryAccessWidth,
+    pub signed: bool,
+    /// Only look at addresses that are a multiple of the width, the way the GBA's cpu stores them.
+    pub aligned: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            width: MemoryAccessWidth::Byte,
+            signed: false,
+            aligned: true,
+        }
+    }
+}
+
+impl SearchOptions {
+    pub fn size(&self) -> u32 {
+        match self.width {
+            MemoryAccessWidth::Byte => 1,
+            MemoryAccessWidth::HalfWord => 2,
+            MemoryAccessWidth::Word => 4,
+        }
+    }
+
+    /// Reads a little-endian value of the search width from `bytes`.
+    fn decode(&self, bytes: impl IntoIterator<Item = u8>) -> i64 {
+        let bits = self.size() * 8;
+        let value = bytes
+            .into_iter()
+            .take(self.size() as usize)
+            .enumerate()
+            .fold(0u64, |value, (index, byte)| value | (byte as u64) << (index * 8));
+        match self.signed {
+            true => ((value << (64 - bits)) as i64) >> (64 - bits),
+            false => value as i64,
+        }
+    }
+
+    fn wraps_to(&self, value: i64, other: i64) -> bool {
+        let mask = u64::MAX >> (64 - self.size() * 8);
+        (value as u64 ^ other as u64) & mask == 0
+    }
+}
+
+/// A candidate with its value now and at the previous filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchResult {
+    pub address: u32,
+    pub value: i64,
+    pub previous: i64,
+}
+
+/// The contents of every searched region at one point in time.
+#[derive(Debug, Clone)]
+struct Snapshot {
+    memory: Vec<Vec<u8>>,
+    kept: bool,
+}
+
+/// Narrows the system's work RAM down to the addresses holding a value, one filter at a time. Every
+/// filter compares memory as it is now and then keeps it as a snapshot later filters can compare to, until
+/// the next filter replaces it. The first snapshot and the ones taken with [`RamSearch::snapshot`] stay.
+#[derive(Debug, Clone)]
+pub struct RamSearch {
+    options: SearchOptions,
+    regions: Vec<MemoryRegion>,
+    snapshots: Vec<Option<Snapshot>>,
+    candidates: Vec<u32>,
+}
+
+impl RamSearch {
+    /// Starts a search over [`SystemInspection::ram_regions`], with every address a candidate.
+    pub fn new(system: &dyn SystemInspection, options: SearchOptions) -> RamSearch {
+        let regions = system.ram_regions();
+        let step = match options.aligned {
+            true => options.size(),
+            false => 1,
+        };
+        let candidates = regions
+            .iter()
+            .flat_map(|region| {
+                (region.start..region.start + region.size.saturating_sub(options.size() - 1)).step_by(step as usize)
+            })
+            .collect();
+        let mut search = RamSearch {
+            options,
+            regions,
+            snapshots: Vec::new(),
+            candidates,
+        };
+        search.snapshot(system);
+        search
+    }
+
+    pub fn options(&self) -> SearchOptions {
+        self.options
+    }
+
+    pub fn regions(&self) -> &[MemoryRegion] {
+        &self.regions
+    }
+
+    /// Addresses that passed every filter so far.
+    pub fn candidates(&self) -> &[u32] {
+        &self.candidates
+    }
+
+    /// How many snapshots were taken, including the dropped ones, so indexes stay the same.
+    pub fn snapshot_count(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Keeps memory as it is now without filtering, returning the snapshot's index.
+    pub fn snapshot(&mut self, system: &dyn SystemInspection) -> usize {
+        self.take_snapshot(system, true)
+    }
+
+    fn take_snapshot(&mut self, system: &dyn SystemInspection, kept: bool) -> usize {
+        let memory = self
+            .regions
+            .iter()
+            .map(|region| (0..region.size).map(|offset| system.peek_8(region.start + offset)).collect())
+            .collect();
+        self.snapshots.push(Some(Snapshot { memory, kept }));
+        self.snapshots.len() - 1
+    }
+
+    /// Drops the candidates failing `filter`, returning how many are left.
+    pub fn filter(&mut self, system: &dyn SystemInspection, filter: SearchFilter) -> Result<usize, SearchError> {
+        let earlier = match filter {
+            SearchFilter::Snapshot(index, _) => index,
+            _ => self.snapshots.len() - 1,
+        };
+        match self.snapshots.get(earlier) {
+            None => return Err(SearchError::UnknownSnapshot(earlier, self.snapshots.len())),
+            Some(None) => return Err(SearchError::DroppedSnapshot(earlier)),
+            Some(Some(_)) => {}
+        }
+
+        let previous = self.snapshots.len() - 1;
+        let now = self.take_snapshot(system, false);
+        let (options, regions) = (self.options, &self.regions);
+        let (earlier, now) = (Self::held(&self.snapshots, earlier), Self::held(&self.snapshots, now));
+        self.candidates.retain(|&address| {
+            let value = Self::stored(options, regions, now, address);
+            let other = Self::stored(options, regions, earlier, address);
+            match filter {
+                SearchFilter::Value(comparison, constant) => comparison.holds(value, constant),
+                SearchFilter::Previous(comparison) | SearchFilter::Snapshot(_, comparison) => comparison.holds(value, other),
+                SearchFilter::ChangedBy(amount) => options.wraps_to(value.wrapping_sub(other), amount),
+            }
+        });
+        if let Some(snapshot) = &self.snapshots[previous]
+            && !snapshot.kept
+        {
+            self.snapshots[previous] = None;
+        }
+        Ok(self.candidates.len())
+    }
+
+    /// The first `limit` candidates, reading their values live from the system.
+    pub fn results(&self, system: &dyn SystemInspection, limit: usize) -> Vec<SearchResult> {
+        let previous = Self::held(&self.snapshots, self.snapshots.len() - 1);
+        self.candidates
+            .iter()
+            .take(limit)
+            .map(|&address| SearchResult {
+                address,
+                value: self.value(system, address),
+                previous: Self::stored(self.options, &self.regions, previous, address),
+            })
+            .collect()
+    }
+
+    /// Reads the value at `address` now, at the search's width and signedness.
+    pub fn value(&self, system: &dyn SystemInspection, address: u32) -> i64 {
+        self.options
+            .decode((0..self.options.size()).map(|offset| system.peek_8(address.wrapping_add(offset))))
+    }
+
+    fn held(snapshots: &[Option<Snapshot>], index: usize) -> &Snapshot {
+        snapshots[index]
+            .as_ref()
+            .expect("the first and latest snapshots are never dropped")
+    }
+
+    fn stored(options: SearchOptions, regions: &[MemoryRegion], snapshot: &Snapshot, address: u32) -> i64 {
+        let index = regions
+            .iter()
+            .position(|region| address.wrapping_sub(region.start) < region.size)
+            .expect("candidates are inside the searched regions");
+        let offset = (address - regions[index].start) as usize;
+        options.decode(snapshot.memory[index][offset..].iter().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Ram(Vec<u8>);
+
+    impl SystemInspection for Ram {
+        fn peek_8(&self, address: u32) -> u8 {
+            self.0[(address - 0x100) as usize]
+        }
+
+        fn ram_regions(&self) -> Vec<MemoryRegion> {
+            vec![MemoryRegion::new("RAM", 0x100, self.0.len() as u32)]
+        }
+    }
+
+    #[test]
+    fn narrows_down_to_a_changing_value() {
+        let mut ram = Ram(vec![5, 5, 0, 9, 5, 0, 0, 0]);
+        let mut search = RamSearch::new(&ram, SearchOptions::default());
+        assert_eq!(search.filter(&ram, SearchFilter::Value(Comparison::Equal, 5)), Ok(3));
+
+        ram.0[1] = 6;
+        ram.0[4] = 4;
+        assert_eq!(search.filter(&ram, SearchFilter::Previous(Comparison::NotEqual)), Ok(2));
+        assert_eq!(search.filter(&ram, SearchFilter::ChangedBy(1)), Ok(0), "nothing moved since");
+
+        let mut search = RamSearch::new(&ram, SearchOptions::default());
+        ram.0[1] = 7;
+        ram.0[4] = 3;
+        assert_eq!(search.filter(&ram, SearchFilter::ChangedBy(1)), Ok(1));
+        ram.0[1] = 8;
+        assert_eq!(
+            search.results(&ram, 10),
+            vec![SearchResult {
+                address: 0x101,
+                value: 8,
+                previous: 7
+            }]
+        );
+        assert_eq!(search.filter(&ram, SearchFilter::Snapshot(0, Comparison::Greater)), Ok(1));
+        assert_eq!(
+            search.filter(&ram, SearchFilter::Snapshot(9, Comparison::Equal)),
+            Err(SearchError::UnknownSnapshot(9, 3))
+        );
+    }
+
+    #[test]
+    fn keeps_only_the_first_latest_and_explicit_snapshots() {
+        let mut ram = Ram(vec![1, 0, 0, 0, 0, 0, 0, 0]);
+        let mut search = RamSearch::new(&ram, SearchOptions::default());
+        for value in 2..6 {
+            ram.0[0] = value;
+            search.filter(&ram, SearchFilter::Previous(Comparison::NotEqual)).unwrap();
+        }
+        assert_eq!(search.snapshot_count(), 5);
+        let held = search.snapshots.iter().filter(|snapshot| snapshot.is_some()).count();
+        assert_eq!(held, 2, "filters replace the snapshot the one before them took");
+
+        assert_eq!(search.snapshot(&ram), 5);
+        ram.0[0] = 6;
+        assert_eq!(search.filter(&ram, SearchFilter::Previous(Comparison::Greater)), Ok(1));
+        ram.0[0] = 7;
+        assert_eq!(search.filter(&ram, SearchFilter::Snapshot(5, Comparison::Greater)), Ok(1));
+        assert_eq!(search.filter(&ram, SearchFilter::Snapshot(0, Comparison::Greater)), Ok(1));
+        assert_eq!(
+            search.filter(&ram, SearchFilter::Snapshot(2, Comparison::Equal)),
+            Err(SearchError::DroppedSnapshot(2))
+        );
+    }
+
+    #[test]
+    fn reads_signed_little_endian_values() {
+        let mut ram = Ram(vec![0xFF, 0xFF, 0x34, 0x12, 0, 0, 0, 0x80]);
+        let options = SearchOptions {
+            width: MemoryAccessWidth::HalfWord,
+            signed: true,
+            aligned: true,
+        };
+        let mut search = RamSearch::new(&ram, options);
+        assert_eq!(search.candidates(), &[0x100, 0x102, 0x104, 0x106]);
+        assert_eq!(search.filter(&ram, SearchFilter::Value(Comparison::Less, 0)), Ok(2));
+        assert_eq!(search.value(&ram, 0x100), -1);
+
+        ram.0[0] = 0;
+        ram.0[1] = 0;
+        assert_eq!(
+            search.filter(&ram, SearchFilter::ChangedBy(1)),
+            Ok(1),
+            "0xFFFF + 1 wraps to 0"
+        );
+
+        let unaligned = RamSearch::new(
+            &ram,
+            SearchOptions {
+                width: MemoryAccessWidth::Word,
+                signed: false,
+                aligned: false,
+            },
+        );
+        assert_eq!(unaligned.candidates().len(), 5);
+        assert_eq!(unaligned.value(&ram, 0x101), 0x0012_3400);
+    }
+}
@@ -0,0 +1,78 @@
+/// Offset of the `ADVSAVEG` magic in a GameShark SP save.
+pub const GAMESHARK_SP_MAGIC_OFFSET: usize = 4;
+pub const GAMESHARK_SP_MAGIC: &[u8] = b"ADVSAVEG";
+/// Offset of the `SharkPortSave` magic in an Action Replay / GameShark save, right after its length.
+pub const SHARKPORT_MAGIC_OFFSET: usize = 4;
+pub const SHARKPORT_MAGIC: &[u8] = b"SharkPortSave";
+
+/// Layouts saves are shared in. A raw save is the backup memory as the cartridge holds it, optionally
+/// followed by a clock block; the others wrap a raw save in a cheat device's container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveFormat {
+    Raw,
+    /// GameShark SP `.gsv`.
+    GameSharkSp,
+    /// Action Replay and GameShark `.sps`/`.xps`.
+    SharkPort,
+}
+
+impl SaveFormat {
+    /// Works out the format from the magic of the container formats, treating anything else as raw.
+    pub fn detect(data: &[u8]) -> SaveFormat {
+        let magic_at = |offset: usize, magic: &[u8]| data.get(offset..offset + magic.len()) == Some(magic);
+        match (
+            magic_at(GAMESHARK_SP_MAGIC_OFFSET, GAMESHARK_SP_MAGIC),
+            magic_at(SHARKPORT_MAGIC_OFFSET, SHARKPORT_MAGIC),
+        ) {
+            (true, _) => SaveFormat::GameSharkSp,
+            (_, true) => SaveFormat::SharkPort,
+            _ => SaveFormat::Raw,
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<SaveFormat> {
+        match extension.to_ascii_lowercase().as_str() {
+            "sav" | "srm" | "sa1" => Some(SaveFormat::Raw),
+            "gsv" => Some(SaveFormat::GameSharkSp),
+            "sps" | "xps" => Some(SaveFormat::SharkPort),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            SaveFormat::Raw => "sav",
+            SaveFormat::GameSharkSp => "gsv",
+            SaveFormat::SharkPort => "sps",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SaveConvertOptions {
+    /// Wall clock time the conversion happens at. Clock blocks record the time they were written so the
+    /// clock keeps running while the save sits on disk.
+    pub unix_seconds: u64,
+    /// Reverses the bytes of every 8 byte EEPROM block, for saves dumped by tools that store the blocks
+    /// little endian.
+    pub swap_eeprom_blocks: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_recognizes_container_magic() {
+        let mut gameshark_sp = vec![0; 0x440];
+        gameshark_sp[4..12].copy_from_slice(GAMESHARK_SP_MAGIC);
+        assert_eq!(SaveFormat::detect(&gameshark_sp), SaveFormat::GameSharkSp);
+
+        let mut sharkport = vec![0x0D, 0, 0, 0];
+        sharkport.extend_from_slice(SHARKPORT_MAGIC);
+        assert_eq!(SaveFormat::detect(&sharkport), SaveFormat::SharkPort);
+
+        assert_eq!(SaveFormat::detect(&[0xFF; 0x8000]), SaveFormat::Raw);
+        assert_eq!(SaveFormat::detect(&[]), SaveFormat::Raw);
+    }
+}
@@ -0,0 +1,332 @@
+use std::{
+    cell::RefCell,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+pub const SAVE_EXTENSION: &str = "sav";
+pub const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where a cartridge keeps its battery backed memory. The save is a flat byte image, so SRAM, Flash,
+/// EEPROM, MBC RAM and any RTC footer following them all live in the same storage.
+pub trait SaveStorage {
+    /// Returns the stored save, or an empty buffer when nothing has been saved yet.
+    fn load(&mut self) -> io::Result<Vec<u8>>;
+
+    /// Writes `data` at `offset`, growing the save when it is shorter.
+    fn store(&mut self, offset: usize, data: &[u8]) -> io::Result<()>;
+
+    /// Called at frame boundaries to make earlier stores durable. Storages may hold writes back until
+    /// then, or longer.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: SaveStorage + ?Sized> SaveStorage for Box<S> {
+    fn load(&mut self) -> io::Result<Vec<u8>> {
+        (**self).load()
+    }
+
+    fn store(&mut self, offset: usize, data: &[u8]) -> io::Result<()> {
+        (**self).store(offset, data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+}
+
+/// Storage handed to every part of a cartridge that persists data.
+pub type SharedSaveStorage = Rc<RefCell<Box<dyn SaveStorage>>>;
+
+/// Keeps the save in memory. Clones share the same buffer, so a caller can hold on to one to inspect the
+/// save written by the system it booted.
+#[derive(Clone, Default)]
+pub struct MemorySaveStorage {
+    data: Arc<Mutex<Vec<u8>>>,
+}
+
+impl MemorySaveStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_data(data: Vec<u8>) -> Self {
+        Self {
+            data: Arc::new(Mutex::new(data)),
+        }
+    }
+
+    pub fn data(&self) -> Vec<u8> {
+        self.data.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+}
+
+impl SaveStorage for MemorySaveStorage {
+    fn load(&mut self) -> io::Result<Vec<u8>> {
+        Ok(self.data())
+    }
+
+    fn store(&mut self, offset: usize, data: &[u8]) -> io::Result<()> {
+        let mut save = self.data.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let end = offset + data.len();
+        if save.len() < end {
+            save.resize(end, 0);
+        }
+        save[offset..end].copy_from_slice(data);
+        Ok(())
+    }
+}
+
+/// Keeps the save in a `.sav` file. Stores only touch an in-memory image; flushing writes the whole image
+/// to a temporary file and renames it over the save, so a crash never leaves a half written save behind.
+/// Flushes are held back until `flush_interval` has passed since the last one, and anything still
+/// pending is written on drop.
+pub struct DirectorySaveStorage {
+    path: PathBuf,
+    image: Option<Vec<u8>>,
+    dirty: bool,
+    flush_interval: Duration,
+    last_flush: Instant,
+    backups: usize,
+    backed_up: bool,
+}
+
+impl DirectorySaveStorage {
+    pub fn new(directory: impl AsRef<Path>, name: &str) -> Self {
+        Self::at(directory.as_ref().join(format!("{name}.{SAVE_EXTENSION}")))
+    }
+
+    /// Storage next to the rom, e.g. `game.gba` saves to `game.sav`.
+    pub fn beside_rom(rom_path: impl AsRef<Path>) -> Self {
+        Self::at(rom_path.as_ref().with_extension(SAVE_EXTENSION))
+    }
+
+    fn at(path: PathBuf) -> Self {
+        Self {
+            path,
+            image: None,
+            dirty: false,
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            last_flush: Instant::now(),
+            backups: 0,
+            backed_up: false,
+        }
+    }
+
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    /// Keeps up to `backups` copies of the save as it was before each session's first flush, newest in
+    /// `game.sav.bak`, older ones in `game.sav.bak1`, `game.sav.bak2` and so on.
+    pub fn with_backups(mut self, backups: usize) -> Self {
+        self.backups = backups;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn backup_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        match index {
+            0 => name.push(".bak"),
+            _ => name.push(format!(".bak{index}")),
+        }
+        PathBuf::from(name)
+    }
+
+    fn image(&mut self) -> io::Result<&mut Vec<u8>> {
+        if self.image.is_none() {
+            let data = match fs::read(&self.path) {
+                Ok(data) => data,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+                Err(error) => return Err(error),
+            };
+            self.image = Some(data);
+        }
+        Ok(self.image.as_mut().unwrap())
+    }
+
+    /// Writes any pending stores to disk right away, ignoring the flush interval.
+    pub fn persist(&mut self) -> io::Result<()> {
+        let Some(image) = self.image.as_ref().filter(|_| self.dirty) else {
+            return Ok(());
+        };
+
+        if let Some(directory) = self.path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)?;
+        }
+
+        let mut temporary_name = self.path.clone().into_os_string();
+        temporary_name.push(".tmp");
+        let temporary_path = PathBuf::from(temporary_name);
+        let mut file = File::create(&temporary_path)?;
+        file.write_all(image)?;
+        file.sync_all()?;
+        drop(file);
+
+        if !self.backed_up {
+            self.rotate_backups()?;
+            self.backed_up = true;
+        }
+        fs::rename(&temporary_path, &self.path)?;
+
+        self.dirty = false;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    fn rotate_backups(&self) -> io::Result<()> {
+        if self.backups == 0 || !self.path.exists() {
+            return Ok(());
+        }
+
+        for index in (1..self.backups).rev() {
+            let older = self.backup_path(index - 1);
+            if older.exists() {
+                fs::rename(&older, self.backup_path(index))?;
+            }
+        }
+        fs::copy(&self.path, self.backup_path(0))?;
+        Ok(())
+    }
+}
+
+impl SaveStorage for DirectorySaveStorage {
+    fn load(&mut self) -> io::Result<Vec<u8>> {
+        self.image().cloned()
+    }
+
+    fn store(&mut self, offset: usize, data: &[u8]) -> io::Result<()> {
+        let image = self.image()?;
+        let end = offset + data.len();
+        if image.len() < end {
+            image.resize(end, 0);
+        }
+        image[offset..end].copy_from_slice(data);
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.dirty && self.last_flush.elapsed() >= self.flush_interval {
+            true => self.persist(),
+            false => Ok(()),
+        }
+    }
+}
+
+impl Drop for DirectorySaveStorage {
+    fn drop(&mut self) {
+        let _ = self.persist();
+    }
+}
+
+/// Serves an existing save but drops every write, e.g. for replaying a movie against a known save.
+pub struct ReadOnlySaveStorage<S> {
+    inner: S,
+}
+
+impl<S: SaveStorage> ReadOnlySaveStorage<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S: SaveStorage> SaveStorage for ReadOnlySaveStorage<S> {
+    fn load(&mut self) -> io::Result<Vec<u8>> {
+        self.inner.load()
+    }
+
+    fn store(&mut self, _offset: usize, _data: &[u8]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn memory_storage_grows_and_shares() {
+        let storage = MemorySaveStorage::new();
+        let mut handle = storage.clone();
+        handle.store(4, &[1, 2]).unwrap();
+        handle.store(0, &[9]).unwrap();
+        assert_eq!(storage.data(), vec![9, 0, 0, 0, 1, 2]);
+    }
+
+    #[test]
+    fn read_only_storage_drops_writes() {
+        let inner = MemorySaveStorage::with_data(vec![1, 2, 3]);
+        let mut storage = ReadOnlySaveStorage::new(inner.clone());
+        storage.store(0, &[0xFF]).unwrap();
+        assert_eq!(storage.load().unwrap(), vec![1, 2, 3]);
+        assert_eq!(inner.data(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn directory_storage_round_trip() {
+        let directory = env::temp_dir().join(format!("ironboyadvance-save-storage-{}", std::process::id()));
+        let mut storage = DirectorySaveStorage::new(&directory, "game");
+        assert!(storage.load().unwrap().is_empty());
+
+        storage.store(0, &[0xAA; 4]).unwrap();
+        storage.store(2, &[0x55; 4]).unwrap();
+        assert_eq!(storage.path(), directory.join("game.sav"));
+        drop(storage);
+        assert_eq!(
+            DirectorySaveStorage::new(&directory, "game").load().unwrap(),
+            vec![0xAA, 0xAA, 0x55, 0x55, 0x55, 0x55]
+        );
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn directory_storage_holds_writes_until_flushed() {
+        let directory = env::temp_dir().join(format!("ironboyadvance-save-flush-{}", std::process::id()));
+        let mut storage = DirectorySaveStorage::new(&directory, "game").with_flush_interval(Duration::from_secs(3600));
+        storage.store(0, &[1, 2, 3]).unwrap();
+        storage.flush().unwrap();
+        assert!(!storage.path().exists(), "flush interval has not passed");
+
+        storage.persist().unwrap();
+        assert_eq!(fs::read(storage.path()).unwrap(), vec![1, 2, 3]);
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn directory_storage_rotates_backups_once_per_session() {
+        let directory = env::temp_dir().join(format!("ironboyadvance-save-backup-{}", std::process::id()));
+        for session in 1..=3u8 {
+            let mut storage = DirectorySaveStorage::new(&directory, "game")
+                .with_flush_interval(Duration::ZERO)
+                .with_backups(2);
+            for write in 0..3 {
+                storage.store(0, &[session, write]).unwrap();
+                storage.flush().unwrap();
+            }
+        }
+
+        let storage = DirectorySaveStorage::new(&directory, "game");
+        assert_eq!(fs::read(storage.path()).unwrap(), vec![3, 2]);
+        assert_eq!(fs::read(storage.backup_path(0)).unwrap(), vec![2, 2]);
+        assert_eq!(fs::read(storage.backup_path(1)).unwrap(), vec![1, 2]);
+        assert!(!storage.backup_path(2).exists());
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
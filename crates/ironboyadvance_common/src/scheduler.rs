@@ -117,6 +117,15 @@ impl<E: SystemEvent> Scheduler<E> {
     pub fn is_empty(&self) -> bool {
         self.events.is_empty()
     }
+
+    pub(crate) fn events(&self) -> &BinaryHeap<Event<E>> {
+        &self.events
+    }
+
+    pub(crate) fn restore(&mut self, time: usize, events: BinaryHeap<Event<E>>) {
+        self.time = time;
+        self.events = events;
+    }
 }
 
 impl<E: SystemEvent> Default for Scheduler<E> {
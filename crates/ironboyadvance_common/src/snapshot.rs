@@ -0,0 +1,458 @@
+use std::collections::{BinaryHeap, VecDeque};
+
+use thiserror::Error;
+
+use crate::{
+    emulator::System,
+    scheduler::{Event, Scheduler, SystemEvent},
+};
+
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"IBAS";
+pub const SNAPSHOT_VERSION: u16 = 5;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    #[error("Not a save state")]
+    InvalidMagic,
+    #[error("Unsupported save state version: {0}")]
+    UnsupportedVersion(u16),
+    #[error("Save state was created for a different system")]
+    SystemMismatch,
+    #[error("Save state was created for a different ROM")]
+    RomMismatch,
+    #[error("Save state ended unexpectedly")]
+    UnexpectedEnd,
+    #[error("Save state has trailing data")]
+    TrailingData,
+    #[error("Invalid {0} in save state")]
+    InvalidValue(&'static str),
+    #[error("Save state length mismatch: expected {expected}, found {found}")]
+    LengthMismatch { expected: usize, found: usize },
+}
+
+/// Restores a component in place, keeping anything shared with the rest of the system
+/// (scheduler handles, save files, lookup tables) untouched.
+pub trait Snapshot {
+    fn save_state(&self, writer: &mut StateWriter);
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError>;
+}
+
+/// A plain value that can be written to and rebuilt from a save state.
+pub trait StateValue: Sized {
+    fn write_state(&self, writer: &mut StateWriter);
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError>;
+}
+
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    rom.iter().fold(0xCBF2_9CE4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+fn system_tag(system: System) -> u8 {
+    match system {
+        System::Gba => 0,
+        System::Gbc => 1,
+        System::Gb => 2,
+    }
+}
+
+impl StateValue for System {
+    fn write_state(&self, writer: &mut StateWriter) {
+        writer.write(&system_tag(*self));
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        match reader.read::<u8>()? {
+            0 => Ok(System::Gba),
+            1 => Ok(System::Gbc),
+            2 => Ok(System::Gb),
+            _ => Err(SnapshotError::InvalidValue("system")),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct StateWriter {
+    buffer: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        Self { buffer: Vec::new() }
+    }
+
+    pub fn with_header(system: System, rom_hash: u64) -> Self {
+        let mut writer = Self::new();
+        writer.buffer.extend_from_slice(&SNAPSHOT_MAGIC);
+        writer.write(&SNAPSHOT_VERSION);
+        writer.write(&system_tag(system));
+        writer.write(&rom_hash);
+        writer
+    }
+
+    pub fn write<T: StateValue>(&mut self, value: &T) {
+        value.write_state(self);
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write(&bytes.len());
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn save<T: Snapshot + ?Sized>(&mut self, component: &T) {
+        component.save_state(self);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    pub fn with_header(data: &'a [u8], system: System, rom_hash: u64) -> Result<Self, SnapshotError> {
+        let mut reader = Self::new(data);
+        if reader.take(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+
+        let version: u16 = reader.read()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let tag: u8 = reader.read()?;
+        if tag != system_tag(system) {
+            return Err(SnapshotError::SystemMismatch);
+        }
+
+        let hash: u64 = reader.read()?;
+        if hash != rom_hash {
+            return Err(SnapshotError::RomMismatch);
+        }
+
+        Ok(reader)
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], SnapshotError> {
+        let end = self.position.checked_add(length).ok_or(SnapshotError::UnexpectedEnd)?;
+        let bytes = self.data.get(self.position..end).ok_or(SnapshotError::UnexpectedEnd)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
+    }
+
+    pub fn read<T: StateValue>(&mut self) -> Result<T, SnapshotError> {
+        T::read_state(self)
+    }
+
+    pub fn read_bytes(&mut self) -> Result<Vec<u8>, SnapshotError> {
+        let length: usize = self.read()?;
+        Ok(self.take(length)?.to_vec())
+    }
+
+    /// Reads a length prefixed byte block into a buffer of a fixed size, e.g. RAM.
+    pub fn read_bytes_into(&mut self, buffer: &mut [u8]) -> Result<(), SnapshotError> {
+        let length: usize = self.read()?;
+        if length != buffer.len() {
+            return Err(SnapshotError::LengthMismatch {
+                expected: buffer.len(),
+                found: length,
+            });
+        }
+        buffer.copy_from_slice(self.take(length)?);
+        Ok(())
+    }
+
+    pub fn load<T: Snapshot + ?Sized>(&mut self, component: &mut T) -> Result<(), SnapshotError> {
+        component.load_state(self)
+    }
+
+    pub fn finish(self) -> Result<(), SnapshotError> {
+        match self.position == self.data.len() {
+            true => Ok(()),
+            false => Err(SnapshotError::TrailingData),
+        }
+    }
+}
+
+macro_rules! impl_state_value_for_primitive {
+    ($($type:ty),*) => {
+        $(
+            impl StateValue for $type {
+                fn write_state(&self, writer: &mut StateWriter) {
+                    writer.buffer.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+                    Ok(<$type>::from_le_bytes(reader.take_array()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_state_value_for_primitive!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl StateValue for usize {
+    fn write_state(&self, writer: &mut StateWriter) {
+        writer.write(&(*self as u64));
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        let value: u64 = reader.read()?;
+        usize::try_from(value).map_err(|_| SnapshotError::InvalidValue("usize"))
+    }
+}
+
+impl StateValue for bool {
+    fn write_state(&self, writer: &mut StateWriter) {
+        writer.write(&(*self as u8));
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        match reader.read::<u8>()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SnapshotError::InvalidValue("bool")),
+        }
+    }
+}
+
+impl<T: StateValue, const N: usize> StateValue for [T; N] {
+    fn write_state(&self, writer: &mut StateWriter) {
+        self.iter().for_each(|value| writer.write(value));
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        let values = (0..N).map(|_| reader.read()).collect::<Result<Vec<T>, _>>()?;
+        values.try_into().map_err(|_| SnapshotError::InvalidValue("array"))
+    }
+}
+
+impl<T: StateValue> StateValue for Option<T> {
+    fn write_state(&self, writer: &mut StateWriter) {
+        match self {
+            Some(value) => {
+                writer.write(&true);
+                writer.write(value);
+            }
+            None => writer.write(&false),
+        }
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        match reader.read::<bool>()? {
+            true => Ok(Some(reader.read()?)),
+            false => Ok(None),
+        }
+    }
+}
+
+impl<T: StateValue> StateValue for Vec<T> {
+    fn write_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.len());
+        self.iter().for_each(|value| writer.write(value));
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        let length: usize = reader.read()?;
+        (0..length).map(|_| reader.read()).collect()
+    }
+}
+
+impl<T: StateValue> StateValue for VecDeque<T> {
+    fn write_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.len());
+        self.iter().for_each(|value| writer.write(value));
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        let length: usize = reader.read()?;
+        (0..length).map(|_| reader.read()).collect()
+    }
+}
+
+impl<A: StateValue, B: StateValue> StateValue for (A, B) {
+    fn write_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.0);
+        writer.write(&self.1);
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        Ok((reader.read()?, reader.read()?))
+    }
+}
+
+impl<E: SystemEvent + StateValue> Snapshot for Scheduler<E> {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.timestamp());
+        // The heap is written in its internal order so events that share a timestamp and
+        // priority are popped in the same order after a restore.
+        let events = self.events();
+        writer.write(&events.len());
+        for event in events.iter() {
+            writer.write(&event.event_type());
+            writer.write(&event.time());
+        }
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        let time: usize = reader.read()?;
+        let length: usize = reader.read()?;
+        let mut events = Vec::with_capacity(length.min(reader.data.len()));
+        for _ in 0..length {
+            let event_type: E = reader.read()?;
+            let event_time: usize = reader.read()?;
+            events.push(Event::new(event_type, event_time));
+        }
+        self.restore(time, BinaryHeap::from(events));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    enum TestEvent {
+        A,
+        B,
+    }
+
+    impl SystemEvent for TestEvent {
+        fn priority(&self) -> u8 {
+            0
+        }
+    }
+
+    impl StateValue for TestEvent {
+        fn write_state(&self, writer: &mut StateWriter) {
+            writer.write(&(*self as u8));
+        }
+
+        fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+            match reader.read::<u8>()? {
+                0 => Ok(TestEvent::A),
+                1 => Ok(TestEvent::B),
+                _ => Err(SnapshotError::InvalidValue("event")),
+            }
+        }
+    }
+
+    #[test]
+    fn primitives_round_trip() {
+        let mut writer = StateWriter::new();
+        writer.write(&0x12u8);
+        writer.write(&-2i16);
+        writer.write(&0xDEADBEEFu32);
+        writer.write(&usize::MAX);
+        writer.write(&true);
+        writer.write(&1.5f32);
+        writer.write(&[1u16, 2, 3]);
+        writer.write(&Some(7u8));
+        writer.write(&VecDeque::from(vec![-1i8, 2]));
+        writer.write_bytes(&[9, 8, 7]);
+        let data = writer.finish();
+
+        let mut reader = StateReader::new(&data);
+        assert_eq!(reader.read::<u8>(), Ok(0x12));
+        assert_eq!(reader.read::<i16>(), Ok(-2));
+        assert_eq!(reader.read::<u32>(), Ok(0xDEADBEEF));
+        assert_eq!(reader.read::<usize>(), Ok(usize::MAX));
+        assert_eq!(reader.read::<bool>(), Ok(true));
+        assert_eq!(reader.read::<f32>(), Ok(1.5));
+        assert_eq!(reader.read::<[u16; 3]>(), Ok([1, 2, 3]));
+        assert_eq!(reader.read::<Option<u8>>(), Ok(Some(7)));
+        assert_eq!(reader.read::<VecDeque<i8>>(), Ok(VecDeque::from(vec![-1, 2])));
+        let mut bytes = [0; 3];
+        assert_eq!(reader.read_bytes_into(&mut bytes), Ok(()));
+        assert_eq!(bytes, [9, 8, 7]);
+        assert_eq!(reader.finish(), Ok(()));
+    }
+
+    #[test]
+    fn truncated_data_is_rejected() {
+        let mut reader = StateReader::new(&[1, 2]);
+        assert_eq!(reader.read::<u32>(), Err(SnapshotError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn fixed_buffer_length_must_match() {
+        let mut writer = StateWriter::new();
+        writer.write_bytes(&[1, 2, 3]);
+        let data = writer.finish();
+
+        let mut buffer = [0; 4];
+        assert_eq!(
+            StateReader::new(&data).read_bytes_into(&mut buffer),
+            Err(SnapshotError::LengthMismatch { expected: 4, found: 3 })
+        );
+    }
+
+    #[test]
+    fn header_is_validated() {
+        let data = StateWriter::with_header(System::Gba, 42).finish();
+
+        assert!(StateReader::with_header(&data, System::Gba, 42).is_ok());
+        assert_eq!(
+            StateReader::with_header(&data, System::Gba, 43).err(),
+            Some(SnapshotError::RomMismatch)
+        );
+        assert_eq!(
+            StateReader::with_header(&data, System::Gbc, 42).err(),
+            Some(SnapshotError::SystemMismatch)
+        );
+        assert_eq!(
+            StateReader::with_header(&data[1..], System::Gba, 42).err(),
+            Some(SnapshotError::InvalidMagic)
+        );
+
+        let mut newer = data.clone();
+        newer[4] = 0xFF;
+        assert!(matches!(
+            StateReader::with_header(&newer, System::Gba, 42).err(),
+            Some(SnapshotError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn scheduler_round_trip_preserves_pop_order() {
+        let mut scheduler = Scheduler::new();
+        scheduler.schedule((TestEvent::B, 10));
+        scheduler.schedule((TestEvent::A, 10));
+        scheduler.schedule((TestEvent::A, 5));
+        scheduler.step(3);
+
+        let mut writer = StateWriter::new();
+        writer.save(&scheduler);
+        let data = writer.finish();
+
+        let mut restored = Scheduler::new();
+        restored.schedule((TestEvent::B, 1));
+        StateReader::new(&data).load(&mut restored).unwrap();
+
+        assert_eq!(restored.timestamp(), 3);
+        scheduler.step(20);
+        restored.step(20);
+        while let Some(event) = scheduler.pop() {
+            assert_eq!(restored.pop(), Some(event));
+        }
+        assert!(restored.is_empty());
+    }
+}
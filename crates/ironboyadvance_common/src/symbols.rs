@@ -0,0 +1,403 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::{
+    debugger::{Breakpoint, InstructionSet},
+    disassembly::Disassembly,
+};
+
+pub const ELF_EXTENSION: &str = "elf";
+pub const SYM_EXTENSION: &str = "sym";
+
+const ELF_MAGIC: [u8; 4] = [0x7F, b'E', b'L', b'F'];
+const ELF_CLASS_32: u8 = 1;
+const ELF_LITTLE_ENDIAN: u8 = 1;
+const SECTION_HEADER_SIZE: usize = 40;
+const SYMBOL_ENTRY_SIZE: usize = 16;
+const SHT_SYMTAB: u32 = 2;
+const STT_NOTYPE: u8 = 0;
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+const SHN_UNDEF: u16 = 0;
+
+#[derive(Error, Debug)]
+pub enum SymbolError {
+    #[error("Symbol file I/O failed: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid ELF file: {0}")]
+    InvalidElf(&'static str),
+    #[error("invalid symbol file on line {line}: {reason}")]
+    InvalidSymFile { line: usize, reason: &'static str },
+    #[error("unknown symbol {0:?}")]
+    UnknownSymbol(String),
+    #[error("invalid address {0:?}")]
+    InvalidAddress(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Object,
+    Label,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    /// Where the symbol starts, with the Thumb bit of ELF functions cleared.
+    pub address: u32,
+    /// Bytes the symbol covers, or 0 when the file does not say.
+    pub size: u32,
+    /// Rom bank of a Game Boy label.
+    pub bank: Option<u16>,
+    pub kind: SymbolKind,
+    /// The set an ELF function is written in.
+    pub instruction_set: Option<InstructionSet>,
+}
+
+/// Symbols from an ELF or `.sym` file, looked up by name or by address.
+#[derive(Debug, Default, Clone)]
+pub struct SymbolTable {
+    /// Sorted by address.
+    symbols: Vec<Symbol>,
+    by_name: HashMap<String, usize>,
+}
+
+impl SymbolTable {
+    pub fn new(mut symbols: Vec<Symbol>) -> SymbolTable {
+        symbols.sort_by_key(|symbol| (symbol.address, symbol.bank));
+        let mut by_name = HashMap::new();
+        for (index, symbol) in symbols.iter().enumerate() {
+            by_name.entry(symbol.name.clone()).or_insert(index);
+        }
+        SymbolTable { symbols, by_name }
+    }
+
+    /// Loads an ELF file, or a `.sym` file for anything that does not start like one.
+    pub fn load(path: impl AsRef<Path>) -> Result<SymbolTable, SymbolError> {
+        let bytes = fs::read(path)?;
+        match bytes.starts_with(&ELF_MAGIC) {
+            true => SymbolTable::parse_elf(&bytes),
+            false => SymbolTable::parse_sym(&String::from_utf8_lossy(&bytes)),
+        }
+    }
+
+    /// The symbol file next to the rom, e.g. `game.elf` for `game.gba` or `game.sym` for `game.gb`.
+    pub fn beside_rom(rom_path: impl AsRef<Path>) -> Option<PathBuf> {
+        [ELF_EXTENSION, SYM_EXTENSION]
+            .into_iter()
+            .map(|extension| rom_path.as_ref().with_extension(extension))
+            .find(|path| path.is_file())
+    }
+
+    /// Reads the function, object and label symbols of a 32 bit little endian ELF file. Mapping symbols
+    /// like `$t` and `$d` are skipped.
+    pub fn parse_elf(bytes: &[u8]) -> Result<SymbolTable, SymbolError> {
+        if !bytes.starts_with(&ELF_MAGIC) || bytes.get(4) != Some(&ELF_CLASS_32) || bytes.get(5) != Some(&ELF_LITTLE_ENDIAN)
+        {
+            return Err(SymbolError::InvalidElf("not a 32 bit little endian ELF file"));
+        }
+
+        let section_offset = read_u32(bytes, 0x20)? as usize;
+        let section_count = read_u16(bytes, 0x30)? as usize;
+        let section = |index: usize| -> Result<&[u8], SymbolError> {
+            let start = section_offset + index * SECTION_HEADER_SIZE;
+            bytes
+                .get(start..start + SECTION_HEADER_SIZE)
+                .ok_or(SymbolError::InvalidElf("section header out of bounds"))
+        };
+        let contents = |header: &[u8]| -> Result<&[u8], SymbolError> {
+            let offset = read_u32(header, 16)? as usize;
+            let size = read_u32(header, 20)? as usize;
+            bytes
+                .get(offset..offset + size)
+                .ok_or(SymbolError::InvalidElf("section contents out of bounds"))
+        };
+
+        let mut symbols = Vec::new();
+        for index in 0..section_count {
+            let header = section(index)?;
+            if read_u32(header, 4)? != SHT_SYMTAB {
+                continue;
+            }
+            let names = contents(section(read_u32(header, 24)? as usize)?)?;
+            for entry in contents(header)?.chunks_exact(SYMBOL_ENTRY_SIZE) {
+                if let Some(symbol) = elf_symbol(entry, names)? {
+                    symbols.push(symbol);
+                }
+            }
+        }
+        Ok(SymbolTable::new(symbols))
+    }
+
+    /// Reads an RGBDS or no$gmb symbol file, one `BANK:ADDRESS name` label per line with `;` comments.
+    pub fn parse_sym(contents: &str) -> Result<SymbolTable, SymbolError> {
+        let mut symbols = Vec::new();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.split(';').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |reason| SymbolError::InvalidSymFile { line: index + 1, reason };
+            let (location, name) = line
+                .split_once(char::is_whitespace)
+                .ok_or(invalid("expected BANK:ADDRESS NAME"))?;
+            let (bank, address) = location.split_once(':').ok_or(invalid("expected BANK:ADDRESS"))?;
+            symbols.push(Symbol {
+                name: name.trim().to_string(),
+                address: u16::from_str_radix(address, 16).map_err(|_| invalid("invalid address"))? as u32,
+                size: 0,
+                bank: Some(u16::from_str_radix(bank, 16).map_err(|_| invalid("invalid bank"))?),
+                kind: SymbolKind::Label,
+                instruction_set: None,
+            });
+        }
+        Ok(SymbolTable::new(symbols))
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Symbol> {
+        self.by_name.get(name).map(|&index| &self.symbols[index])
+    }
+
+    /// The symbol starting at `address`. Game Boy labels in another bank than `bank` are skipped when
+    /// it is given.
+    pub fn symbol_at(&self, address: u32, bank: Option<u16>) -> Option<&Symbol> {
+        let start = self.symbols.partition_point(|symbol| symbol.address < address);
+        self.symbols[start..]
+            .iter()
+            .take_while(|symbol| symbol.address == address)
+            .find(|symbol| bank.is_none() || symbol.bank.is_none() || symbol.bank == bank)
+    }
+
+    /// The closest symbol at or before `address` that covers it. Symbols with a size only cover that many
+    /// bytes, labels without one run up to the next symbol.
+    pub fn containing(&self, address: u32) -> Option<&Symbol> {
+        let end = self.symbols.partition_point(|symbol| symbol.address <= address);
+        let symbol = self.symbols[..end].last()?;
+        match symbol.size != 0 && address - symbol.address >= symbol.size {
+            true => None,
+            false => Some(symbol),
+        }
+    }
+
+    /// Names `address` as a symbol or an offset into the symbol containing it, e.g. `main+0x1C`.
+    pub fn describe(&self, address: u32) -> Option<String> {
+        let symbol = self.containing(address)?;
+        match address - symbol.address {
+            0 => Some(symbol.name.clone()),
+            offset => Some(format!("{}+{offset:#X}", symbol.name)),
+        }
+    }
+
+    /// Resolves a location written as a number (`0x08000120`, `4096`), a symbol name, a name with an
+    /// offset (`main+0x10`) or a Game Boy `BANK:ADDRESS`, whose bank is dropped.
+    pub fn resolve(&self, location: &str) -> Result<u32, SymbolError> {
+        let location = location.trim();
+        if let Some(address) = parse_number(location) {
+            return Ok(address);
+        }
+        if let Some((bank, address)) = location.split_once(':')
+            && u16::from_str_radix(bank, 16).is_ok()
+            && let Ok(address) = u16::from_str_radix(address, 16)
+        {
+            return Ok(address as u32);
+        }
+        let (name, offset) = match location.split_once('+') {
+            Some((name, offset)) => (
+                name.trim(),
+                parse_number(offset.trim()).ok_or_else(|| SymbolError::InvalidAddress(location.to_string()))?,
+            ),
+            None => (location, 0),
+        };
+        self.find(name)
+            .map(|symbol| symbol.address.wrapping_add(offset))
+            .ok_or_else(|| SymbolError::UnknownSymbol(name.to_string()))
+    }
+
+    /// A breakpoint on a location [`SymbolTable::resolve`] accepts. Breaking on a function keeps to the
+    /// instruction set it is written in.
+    pub fn breakpoint(&self, location: &str) -> Result<Breakpoint, SymbolError> {
+        let address = self.resolve(location)?;
+        Ok(Breakpoint {
+            address,
+            instruction_set: self.symbol_at(address, None).and_then(|symbol| symbol.instruction_set),
+        })
+    }
+
+    /// Names the target of a branch or call, e.g. `BL 0x08000148 <main>`.
+    pub fn annotate(&self, disassembly: &mut Disassembly) {
+        if let Some(name) = disassembly.flow.target().and_then(|target| self.describe(target)) {
+            disassembly.text.push_str(&format!(" <{name}>"));
+        }
+    }
+}
+
+fn parse_number(text: &str) -> Option<u32> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, SymbolError> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|field| u16::from_le_bytes([field[0], field[1]]))
+        .ok_or(SymbolError::InvalidElf("truncated header"))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, SymbolError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|field| u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+        .ok_or(SymbolError::InvalidElf("truncated header"))
+}
+
+/// Functions with bit 0 set are Thumb code, the address itself being halfword aligned.
+fn elf_symbol(entry: &[u8], names: &[u8]) -> Result<Option<Symbol>, SymbolError> {
+    let info = entry[12];
+    let kind = match info & 0xF {
+        STT_FUNC => SymbolKind::Function,
+        STT_OBJECT => SymbolKind::Object,
+        STT_NOTYPE => SymbolKind::Label,
+        _ => return Ok(None),
+    };
+    if read_u16(entry, 14)? == SHN_UNDEF {
+        return Ok(None);
+    }
+
+    let name_offset = read_u32(entry, 0)? as usize;
+    let name = names
+        .get(name_offset..)
+        .and_then(|names| names.split(|&byte| byte == 0).next())
+        .ok_or(SymbolError::InvalidElf("symbol name out of bounds"))?;
+    let name = String::from_utf8_lossy(name);
+    if name.is_empty() || name.starts_with('$') {
+        return Ok(None);
+    }
+
+    let value = read_u32(entry, 4)?;
+    let instruction_set = match (kind, value & 1 == 1) {
+        (SymbolKind::Function, true) => Some(InstructionSet::Thumb),
+        (SymbolKind::Function, false) => Some(InstructionSet::Arm),
+        _ => None,
+    };
+    Ok(Some(Symbol {
+        name: name.into_owned(),
+        address: match kind {
+            SymbolKind::Function => value & !1,
+            _ => value,
+        },
+        size: read_u32(entry, 8)?,
+        bank: None,
+        kind,
+        instruction_set,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An ELF file with only a null section, a string table and a symbol table.
+    fn elf(symbols: &[(&str, u32, u32, u8)]) -> Vec<u8> {
+        let mut names = vec![0u8];
+        let mut table = vec![0u8; SYMBOL_ENTRY_SIZE];
+        for &(name, value, size, info) in symbols {
+            table.extend((names.len() as u32).to_le_bytes());
+            table.extend(value.to_le_bytes());
+            table.extend(size.to_le_bytes());
+            table.extend([info, 0]);
+            table.extend(1u16.to_le_bytes());
+            names.extend(name.as_bytes());
+            names.push(0);
+        }
+
+        let mut file = vec![0u8; 0x34];
+        file[..4].copy_from_slice(&ELF_MAGIC);
+        file[4] = ELF_CLASS_32;
+        file[5] = ELF_LITTLE_ENDIAN;
+        let names_offset = file.len() as u32;
+        file.extend(&names);
+        let table_offset = file.len() as u32;
+        file.extend(&table);
+        let section_offset = file.len() as u32;
+        file[0x20..0x24].copy_from_slice(&section_offset.to_le_bytes());
+        file[0x30..0x32].copy_from_slice(&3u16.to_le_bytes());
+
+        let mut header = |kind: u32, offset: u32, size: u32, link: u32| {
+            let mut section = vec![0u8; SECTION_HEADER_SIZE];
+            section[4..8].copy_from_slice(&kind.to_le_bytes());
+            section[16..20].copy_from_slice(&offset.to_le_bytes());
+            section[20..24].copy_from_slice(&size.to_le_bytes());
+            section[24..28].copy_from_slice(&link.to_le_bytes());
+            file.extend(section);
+        };
+        header(0, 0, 0, 0);
+        header(3, names_offset, names.len() as u32, 0);
+        header(SHT_SYMTAB, table_offset, table.len() as u32, 1);
+        file
+    }
+
+    #[test]
+    fn reads_elf_functions_with_their_instruction_set() {
+        let file = elf(&[
+            ("main", 0x08000121, 0x20, 0x12),
+            ("irq_handler", 0x03000000, 0x40, 0x02),
+            ("player", 0x02000010, 8, 0x11),
+            ("$t", 0x08000120, 0, 0x00),
+        ]);
+        let symbols = SymbolTable::parse_elf(&file).unwrap();
+
+        assert_eq!(symbols.symbols().len(), 3);
+        let main = symbols.find("main").unwrap();
+        assert_eq!(
+            (main.address, main.instruction_set),
+            (0x08000120, Some(InstructionSet::Thumb))
+        );
+        assert_eq!(symbols.describe(0x08000130).as_deref(), Some("main+0x10"));
+        assert_eq!(symbols.describe(0x08000140), None);
+        assert_eq!(
+            symbols.breakpoint("main+4").unwrap(),
+            Breakpoint {
+                address: 0x08000124,
+                instruction_set: None
+            }
+        );
+        assert_eq!(
+            symbols.breakpoint("main").unwrap().instruction_set,
+            Some(InstructionSet::Thumb)
+        );
+        assert_eq!(symbols.resolve("player").unwrap(), 0x02000010);
+        assert!(matches!(symbols.resolve("missing"), Err(SymbolError::UnknownSymbol(_))));
+    }
+
+    #[test]
+    fn reads_banked_game_boy_labels() {
+        let symbols =
+            SymbolTable::parse_sym("; File generated by rgblink\n00:0150 Start\n01:4000 Level.load\n02:4000 Sound\n")
+                .unwrap();
+
+        assert_eq!(symbols.resolve("Level.load").unwrap(), 0x4000);
+        assert_eq!(symbols.resolve("01:4010").unwrap(), 0x4010);
+        assert_eq!(symbols.symbol_at(0x4000, Some(2)).unwrap().name, "Sound");
+        assert_eq!(symbols.symbol_at(0x0150, None).unwrap().name, "Start");
+        assert!(matches!(
+            SymbolTable::parse_sym("00:0150\n"),
+            Err(SymbolError::InvalidSymFile { line: 1, .. })
+        ));
+    }
+}
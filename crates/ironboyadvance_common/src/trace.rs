@@ -0,0 +1,224 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use flate2::{Compression, write::GzEncoder};
+
+use crate::symbols::SymbolTable;
+
+pub const TRACE_EXTENSION: &str = "log.gz";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraceFormat {
+    /// Cycle timestamp, pc, cpu state, opcode, disassembly and every register.
+    #[default]
+    Full,
+    /// The log layout of a reference emulator, mGBA's `trace` on the GBA and Gameboy Doctor's on the Game
+    /// Boy, so a trace diffs line for line against theirs.
+    Reference,
+}
+
+impl TraceFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TraceFormat::Full => "full",
+            TraceFormat::Reference => "reference",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TraceFormat> {
+        [TraceFormat::Full, TraceFormat::Reference]
+            .into_iter()
+            .find(|format| format.name().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceTrigger {
+    /// The instruction at this address is about to execute.
+    Pc(u32),
+    /// This many frames have run since tracing was set up.
+    Frame(u64),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TraceOptions {
+    pub format: TraceFormat,
+    /// Starts with the first instruction when `None`.
+    pub start: Option<TraceTrigger>,
+    /// Runs until the tracer is removed when `None`.
+    pub stop: Option<TraceTrigger>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TraceState {
+    Waiting,
+    Tracing,
+    Done,
+}
+
+enum TraceOutput {
+    Compressed(GzEncoder<BufWriter<File>>),
+    Plain(Box<dyn Write + Send>),
+}
+
+/// Writes one line per executed instruction. The system asks [`Tracer::should_trace`] before each
+/// instruction and formats the line itself, since only it knows the registers.
+pub struct Tracer {
+    output: TraceOutput,
+    options: TraceOptions,
+    state: TraceState,
+    frames: u64,
+    symbols: SymbolTable,
+    error: Option<io::Error>,
+}
+
+impl Tracer {
+    /// Writes a gzip compressed trace to `path`, which `zcat` or `zdiff` read back.
+    pub fn create(path: impl AsRef<Path>, options: TraceOptions) -> io::Result<Tracer> {
+        let file = BufWriter::new(File::create(path)?);
+        Ok(Tracer::with_output(
+            TraceOutput::Compressed(GzEncoder::new(file, Compression::fast())),
+            options,
+        ))
+    }
+
+    /// Writes an uncompressed trace to `writer`.
+    pub fn to_writer(writer: Box<dyn Write + Send>, options: TraceOptions) -> Tracer {
+        Tracer::with_output(TraceOutput::Plain(writer), options)
+    }
+
+    fn with_output(output: TraceOutput, options: TraceOptions) -> Tracer {
+        let mut tracer = Tracer {
+            output,
+            options,
+            state: TraceState::Waiting,
+            frames: 0,
+            symbols: SymbolTable::default(),
+            error: None,
+        };
+        if tracer.options.start.is_none_or(|start| start == TraceTrigger::Frame(0)) {
+            tracer.state = TraceState::Tracing;
+        }
+        tracer
+    }
+
+    /// Labels full format traces with these symbols.
+    pub fn with_symbols(mut self, symbols: SymbolTable) -> Tracer {
+        self.symbols = symbols;
+        self
+    }
+
+    pub fn format(&self) -> TraceFormat {
+        self.options.format
+    }
+
+    pub fn tracing(&self) -> bool {
+        self.state == TraceState::Tracing
+    }
+
+    /// Called before the instruction at `pc` executes. Returns whether to write a line for it.
+    pub fn should_trace(&mut self, pc: u32) -> bool {
+        match self.state {
+            TraceState::Waiting if self.options.start == Some(TraceTrigger::Pc(pc)) => self.state = TraceState::Tracing,
+            TraceState::Tracing if self.options.stop == Some(TraceTrigger::Pc(pc)) => self.state = TraceState::Done,
+            _ => {}
+        }
+        self.state == TraceState::Tracing
+    }
+
+    pub fn write_line(&mut self, line: &str) {
+        let writer: &mut dyn Write = match &mut self.output {
+            TraceOutput::Compressed(encoder) => encoder,
+            TraceOutput::Plain(writer) => writer,
+        };
+        if let Err(error) = writeln!(writer, "{line}") {
+            self.error = Some(error);
+            self.state = TraceState::Done;
+        }
+    }
+
+    /// Writes the line for the instruction at `pc`, preceded by a `label:` line when a symbol starts there.
+    /// Reference traces stay unlabelled so they still diff against the reference emulator's.
+    pub fn write_instruction(&mut self, pc: u32, line: &str) {
+        if self.options.format == TraceFormat::Full
+            && let Some(symbol) = self.symbols.symbol_at(pc, None)
+        {
+            let label = format!("{}:", symbol.name);
+            self.write_line(&label);
+        }
+        self.write_line(line);
+    }
+
+    /// Called once the system finished running a frame, to start or stop on frame triggers.
+    pub fn frame_completed(&mut self) {
+        self.frames += 1;
+        let frame = Some(TraceTrigger::Frame(self.frames));
+        match self.state {
+            TraceState::Waiting if self.options.start == frame => self.state = TraceState::Tracing,
+            TraceState::Tracing if self.options.stop == frame => self.state = TraceState::Done,
+            _ => {}
+        }
+    }
+
+    /// Flushes the trace, writing the end of the gzip stream. Reports the first write that failed.
+    pub fn finish(self) -> io::Result<()> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        match self.output {
+            TraceOutput::Compressed(encoder) => encoder.finish()?.flush(),
+            TraceOutput::Plain(mut writer) => writer.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buffer);
+            Ok(buffer.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn triggers_start_and_stop_tracing() {
+        let buffer = SharedBuffer::default();
+        let mut tracer = Tracer::to_writer(
+            Box::new(buffer.clone()),
+            TraceOptions {
+                format: TraceFormat::Reference,
+                start: Some(TraceTrigger::Pc(0x0150)),
+                stop: Some(TraceTrigger::Frame(2)),
+            },
+        );
+
+        for pc in [0x0100, 0x0150, 0x0151] {
+            if tracer.should_trace(pc) {
+                tracer.write_line(&format!("{pc:04X}"));
+            }
+        }
+        tracer.frame_completed();
+        assert!(tracer.should_trace(0x0100));
+        tracer.frame_completed();
+        assert!(!tracer.should_trace(0x0150), "stopped traces do not restart");
+        tracer.finish().unwrap();
+
+        assert_eq!(String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(), "0150\n0151\n");
+        assert_eq!(TraceFormat::from_name("Reference"), Some(TraceFormat::Reference));
+    }
+}
@@ -16,6 +16,7 @@ use crate::{
     dma_control::RequestType,
     events::{ApuEvent, DmaEvent, GbaEvent},
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 pub const APU_SAMPLING_FREQUENCY: usize = 32768; // Hz
 const SAMPLE_CYCLES: usize = CPU_CLOCK_SPEED as usize / APU_SAMPLING_FREQUENCY;
@@ -320,3 +321,37 @@ impl Apu {
         }
     }
 }
+
+impl Snapshot for Apu {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.ch1);
+        writer.save(&self.ch2);
+        writer.save(&self.ch3);
+        writer.save(&self.ch4);
+        writer.save(&self.fifo_a);
+        writer.save(&self.fifo_b);
+        writer.write(&self.psg_sound_control.into_bits());
+        writer.write(&self.dma_sound_control.into_bits());
+        writer.write(&self.sound_status.into_bits());
+        writer.write(&self.sound_bias.into_bits());
+        writer.write(&self.frame_sequencer_step);
+        writer.save(&self.high_pass);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.ch1)?;
+        reader.load(&mut self.ch2)?;
+        reader.load(&mut self.ch3)?;
+        reader.load(&mut self.ch4)?;
+        reader.load(&mut self.fifo_a)?;
+        reader.load(&mut self.fifo_b)?;
+        self.psg_sound_control = PsgSoundControl::from_bits(reader.read()?);
+        self.dma_sound_control = DmaSoundControl::from_bits(reader.read()?);
+        self.sound_status = SoundStatus::from_bits(reader.read()?);
+        self.sound_bias = SoundBias::from_bits(reader.read()?);
+        self.frame_sequencer_step = reader.read()?;
+        reader.load(&mut self.high_pass)?;
+        self.audio_buffer.clear();
+        Ok(())
+    }
+}
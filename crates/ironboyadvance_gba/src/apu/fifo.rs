@@ -1,6 +1,7 @@
 use std::collections::VecDeque;
 
 use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const FIFO_CAPACITY: usize = 32;
 const FIFO_REFILL_THRESHOLD: usize = 16;
@@ -50,6 +51,23 @@ impl FifoChannel {
     }
 }
 
+impl Snapshot for FifoChannel {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.queue);
+        writer.write(&self.current_sample);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        let queue: VecDeque<i8> = reader.read()?;
+        if queue.len() > FIFO_CAPACITY {
+            return Err(SnapshotError::InvalidValue("sound fifo"));
+        }
+        self.queue = queue;
+        self.current_sample = reader.read()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,3 +1,5 @@
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
+
 const HIGH_PASS_CHARGE_FACTOR: f32 = 0.999;
 
 #[derive(Debug)]
@@ -32,3 +34,20 @@ impl HighPassFilter {
         (left_output, right_output)
     }
 }
+
+impl Snapshot for HighPassFilter {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.previous_left_input);
+        writer.write(&self.previous_left_output);
+        writer.write(&self.previous_right_input);
+        writer.write(&self.previous_right_output);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.previous_left_input = reader.read()?;
+        self.previous_left_output = reader.read()?;
+        self.previous_right_input = reader.read()?;
+        self.previous_right_output = reader.read()?;
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@ use getset::{CopyGetters, Setters};
 use ironboyadvance_common::bits::BitOps;
 use ironboyadvance_common::memory::SystemMemoryAccess;
 use ironboyadvance_common::register_ops::RegisterOps;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[bitfield(u32)]
 #[derive(PartialEq, Eq)]
@@ -256,6 +257,33 @@ impl NoiseChannel {
     }
 }
 
+impl Snapshot for NoiseChannel {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.enabled);
+        writer.write(&self.dac_enabled);
+        writer.save(&self.length);
+        writer.save(&self.envelope);
+        writer.write(&self.lfsr);
+        writer.save(&self.period);
+        writer.write(&self.control.into_bits());
+        writer.write(&self.frequency.into_bits());
+        writer.write(&self.frame_sequencer_step);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.enabled = reader.read()?;
+        self.dac_enabled = reader.read()?;
+        reader.load(&mut self.length)?;
+        reader.load(&mut self.envelope)?;
+        self.lfsr = reader.read()?;
+        reader.load(&mut self.period)?;
+        self.control = NoiseControl::from_bits(reader.read()?);
+        self.frequency = NoiseFrequency::from_bits(reader.read()?);
+        self.frame_sequencer_step = reader.read()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
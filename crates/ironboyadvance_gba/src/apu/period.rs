@@ -1,3 +1,5 @@
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
+
 #[derive(Debug)]
 pub struct Period {
     accumulator: usize,
@@ -23,3 +25,14 @@ impl Period {
         self.accumulator = 0;
     }
 }
+
+impl Snapshot for Period {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.accumulator);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.accumulator = reader.read()?;
+        Ok(())
+    }
+}
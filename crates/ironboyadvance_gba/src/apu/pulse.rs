@@ -7,6 +7,7 @@ use bitfields::bitfield;
 use getset::{CopyGetters, Setters};
 use ironboyadvance_common::memory::SystemMemoryAccess;
 use ironboyadvance_common::register_ops::RegisterOps;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[bitfield(u16)]
 #[derive(PartialEq, Eq)]
@@ -274,6 +275,39 @@ impl PulseChannel {
     }
 }
 
+impl Snapshot for PulseChannel {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.enabled);
+        writer.write(&self.dac_enabled);
+        writer.write(&self.wave_duty_position);
+        if let Some(sweep) = &self.sweep {
+            writer.save(sweep);
+        }
+        writer.save(&self.length);
+        writer.save(&self.envelope);
+        writer.save(&self.period);
+        writer.write(&self.control.into_bits());
+        writer.write(&self.frequency.into_bits());
+        writer.write(&self.frame_sequencer_step);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.enabled = reader.read()?;
+        self.dac_enabled = reader.read()?;
+        self.wave_duty_position = reader.read()?;
+        if let Some(sweep) = &mut self.sweep {
+            reader.load(sweep)?;
+        }
+        reader.load(&mut self.length)?;
+        reader.load(&mut self.envelope)?;
+        reader.load(&mut self.period)?;
+        self.control = PulseControl::from_bits(reader.read()?);
+        self.frequency = PulseFrequency::from_bits(reader.read()?);
+        self.frame_sequencer_step = reader.read()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
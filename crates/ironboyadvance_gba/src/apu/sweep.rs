@@ -1,6 +1,7 @@
 use bitfields::{bitfield, bitflag};
 use getset::CopyGetters;
 use ironboyadvance_common::register_ops::RegisterOps;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const MAX_PERIOD: u16 = 2047;
 
@@ -136,3 +137,24 @@ impl Sweep {
         }
     }
 }
+
+impl Snapshot for Sweep {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.control.into_bits());
+        writer.write(&self.enabled);
+        writer.write(&self.shadow_period);
+        writer.write(&self.timer);
+        writer.write(&self.period_calculated);
+        writer.write(&self.disable_channel);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.control = SweepControl::from_bits(reader.read()?);
+        self.enabled = reader.read()?;
+        self.shadow_period = reader.read()?;
+        self.timer = reader.read()?;
+        self.period_calculated = reader.read()?;
+        self.disable_channel = reader.read()?;
+        Ok(())
+    }
+}
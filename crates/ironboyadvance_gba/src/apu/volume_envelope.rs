@@ -1,5 +1,6 @@
 use bitfields::bitflag;
 use getset::{CopyGetters, Setters};
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[bitflag(u8)]
 #[derive(Debug, PartialEq, Eq)]
@@ -58,3 +59,22 @@ impl VolumeEnvelope {
         self.initial_volume == 0 && self.direction == EnvelopeDirection::Decrease
     }
 }
+
+impl Snapshot for VolumeEnvelope {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.initial_volume);
+        writer.write(&self.direction.into_bits());
+        writer.write(&self.pace);
+        writer.write(&self.volume);
+        writer.write(&self.timer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.initial_volume = reader.read()?;
+        self.direction = EnvelopeDirection::from_bits(reader.read()?);
+        self.pace = reader.read()?;
+        self.volume = reader.read()?;
+        self.timer = reader.read()?;
+        Ok(())
+    }
+}
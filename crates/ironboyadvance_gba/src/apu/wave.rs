@@ -4,6 +4,7 @@ use crate::apu::{
 };
 use bitfields::{bitfield, bitflag};
 use getset::{CopyGetters, Setters};
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 use ironboyadvance_common::{memory::SystemMemoryAccess, register_ops::RegisterOps};
 
 #[bitflag(u8)]
@@ -270,6 +271,33 @@ impl WaveChannel {
     }
 }
 
+impl Snapshot for WaveChannel {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.enabled);
+        writer.save(&self.length);
+        writer.save(&self.period);
+        writer.write(&self.wave_position);
+        writer.write(&self.wave_ram);
+        writer.write(&self.control.into_bits());
+        writer.write(&self.volume.into_bits());
+        writer.write(&self.frequency.into_bits());
+        writer.write(&self.frame_sequencer_step);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.enabled = reader.read()?;
+        reader.load(&mut self.length)?;
+        reader.load(&mut self.period)?;
+        self.wave_position = reader.read()?;
+        self.wave_ram = reader.read()?;
+        self.control = WaveControl::from_bits(reader.read()?);
+        self.volume = WaveVolume::from_bits(reader.read()?);
+        self.frequency = WaveFrequency::from_bits(reader.read()?);
+        self.frame_sequencer_step = reader.read()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,10 +2,27 @@ use std::cell::Cell;
 
 use getset::CopyGetters;
 use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 use thiserror::Error;
 
 const BIOS_END: u32 = 0x3FFF;
 
+/// Stands in for the BIOS when none is loaded, the cpu emulating its functions instead. All it holds is the
+/// vectors and the interrupt handler, at the same addresses as in the real BIOS, which saves the registers,
+/// calls the game's handler at 0x03FFFFFC and returns to the interrupted code.
+const HLE_BIOS: [(usize, u32); 10] = [
+    (0x0000, 0xE3A0F302), // mov pc, #0x08000000
+    (0x0008, 0xEA00004C), // b 0x140
+    (0x0018, 0xEA000042), // b 0x128
+    (0x0128, 0xE92D500F), // stmfd sp!, {r0-r3, r12, lr}
+    (0x012C, 0xE3A00301), // mov r0, #0x04000000
+    (0x0130, 0xE28FE000), // add lr, pc, #0
+    (0x0134, 0xE510F004), // ldr pc, [r0, #-4]
+    (0x0138, 0xE8BD500F), // ldmfd sp!, {r0-r3, r12, lr}
+    (0x013C, 0xE25EF004), // subs pc, lr, #4
+    (0x0140, 0xE1B0F00E), // movs pc, lr
+];
+
 #[derive(Error, Debug)]
 pub enum BiosError {
     #[error("Invalid bios length: {0}")]
@@ -24,7 +41,13 @@ pub struct Bios {
 impl Bios {
     pub fn load(buffer: Vec<u8>) -> Result<Bios, BiosError> {
         let (data, loaded) = match buffer.is_empty() {
-            true => (vec![0; 0x4000], false),
+            true => {
+                let mut data = vec![0; 0x4000];
+                for (address, opcode) in HLE_BIOS {
+                    data[address..address + 4].copy_from_slice(&opcode.to_le_bytes());
+                }
+                (data, false)
+            }
             false => {
                 if buffer.len() != 0x4000 {
                     return Err(BiosError::InvalidBiosLength(buffer.len()));
@@ -44,6 +67,17 @@ impl Bios {
     pub fn set_pc(&self, pc: u32) {
         self.pc_in_bios.set(pc <= BIOS_END);
     }
+
+    /// The BIOS byte at `address` whatever the pc is, or `None` past its end.
+    pub fn peek_8(&self, address: u32) -> Option<u8> {
+        self.data.get(address as usize).copied()
+    }
+
+    pub fn poke_8(&mut self, address: u32, value: u8) {
+        if let Some(byte) = self.data.get_mut(address as usize) {
+            *byte = value;
+        }
+    }
 }
 
 impl SystemMemoryAccess for Bios {
@@ -74,3 +108,16 @@ impl SystemMemoryAccess for Bios {
 
     fn write_8(&mut self, _address: u32, _value: u8) {}
 }
+
+impl Snapshot for Bios {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.last_fetched.get());
+        writer.write(&self.pc_in_bios.get());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.last_fetched.set(reader.read()?);
+        self.pc_in_bios.set(reader.read()?);
+        Ok(())
+    }
+}
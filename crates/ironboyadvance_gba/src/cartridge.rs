@@ -1,8 +1,14 @@
-use std::{cell::RefCell, path::PathBuf, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use header::Header;
-use ironboyadvance_common::{memory::SystemMemoryAccess, scheduler::Scheduler};
+use ironboyadvance_common::{
+    memory::SystemMemoryAccess,
+    save_storage::{SaveStorage, SharedSaveStorage},
+    scheduler::Scheduler,
+    snapshot::{Snapshot, SnapshotError, StateReader, StateWriter},
+};
 use thiserror::Error;
+use tracing::warn;
 
 use crate::{
     cartridge::{
@@ -28,8 +34,14 @@ mod gpio;
 mod header;
 mod no_backup;
 mod rtc;
+mod save_format;
 mod sram;
 
+pub use save_format::{export_save, import_save};
+
+const SRAM_BASE: u32 = 0x0E000000;
+const ROM_PATCH_MASK: u32 = 0x01FFFFFE;
+
 #[derive(Error, Debug)]
 pub enum CartridgeError {
     #[error("Unsupported Cartridge type")]
@@ -40,11 +52,17 @@ pub enum CartridgeError {
     SaveIo(#[from] std::io::Error),
     #[error("Cartridge config has BackupType::Eeprom but no eeprom_size")]
     MissingEepromSize,
+    #[error("Invalid save file: {0}")]
+    InvalidSaveFile(&'static str),
+    #[error("Save file belongs to a different game")]
+    SaveRomMismatch,
 }
 
-pub trait CartridgeBackup: SystemMemoryAccess<Address = u32> {
+pub trait CartridgeBackup: SystemMemoryAccess<Address = u32> + Snapshot {
     fn rom(&self) -> &[u8];
 
+    fn rom_mut(&mut self) -> &mut [u8];
+
     fn backup_size(&self) -> usize {
         0
     }
@@ -58,44 +76,58 @@ pub trait CartridgeBackup: SystemMemoryAccess<Address = u32> {
         }
     }
 
+    /// Writes a byte of backup memory directly, bypassing any command protocol it has.
+    fn poke_backup(&mut self, address: u32, value: u8) {
+        self.write_8(address, value);
+    }
+
     fn handle_event(&mut self, _cartridge_event: CartridgeEvent) {}
+
+    fn flush_backup(&mut self) {}
 }
 
 pub struct Cartridge {
     backup: Box<dyn CartridgeBackup>,
     gpio: Option<Gpio>,
+    storage: SharedSaveStorage,
+    rom_patches: HashMap<u32, u16>,
 }
 
 impl Cartridge {
     pub fn load(
-        rom_path: PathBuf,
         buffer: Vec<u8>,
+        storage: Box<dyn SaveStorage>,
         base_unix_seconds: u64,
         scheduler: Rc<RefCell<Scheduler<GbaEvent>>>,
     ) -> Result<Cartridge, CartridgeError> {
         let header = Header::load(&buffer[0..228]);
         let config = determine_cartridge_config(&buffer, &header);
-        let save_file = rom_path.with_extension("sav");
+        let storage: SharedSaveStorage = Rc::new(RefCell::new(storage));
 
         let backup: Box<dyn CartridgeBackup> = match config.backup_type() {
             BackupType::None => Box::new(NoBackup::new(buffer)),
-            BackupType::Sram => Box::new(Sram::new(buffer, &save_file)?),
+            BackupType::Sram => Box::new(Sram::new(buffer, storage.clone())?),
             BackupType::Eeprom => Box::new(Eeprom::new(
                 buffer,
-                &save_file,
+                storage.clone(),
                 config.eeprom_size().ok_or(CartridgeError::MissingEepromSize)?,
                 scheduler.clone(),
             )?),
-            BackupType::Flash64KB => Box::new(Flash::new(buffer, &save_file, FlashSize::Small)?),
-            BackupType::Flash128KB => Box::new(Flash::new(buffer, &save_file, FlashSize::Large)?),
+            BackupType::Flash64KB => Box::new(Flash::new(buffer, storage.clone(), FlashSize::Small)?),
+            BackupType::Flash128KB => Box::new(Flash::new(buffer, storage.clone(), FlashSize::Large)?),
         };
 
         let rtc_offset = backup.backup_size();
         let gpio = CartridgeDevice::Rtc
             .is_set(config.device_pattern())
-            .then(|| Gpio::new(Some(Rtc::new(base_unix_seconds, save_file, rtc_offset, scheduler))));
+            .then(|| Gpio::new(Some(Rtc::new(base_unix_seconds, storage.clone(), rtc_offset, scheduler))));
 
-        Ok(Cartridge { backup, gpio })
+        Ok(Cartridge {
+            backup,
+            gpio,
+            storage,
+            rom_patches: HashMap::new(),
+        })
     }
 
     fn gpio_for_read(&self, address: u32) -> Option<&Gpio> {
@@ -106,32 +138,83 @@ impl Cartridge {
         self.gpio.as_mut().filter(|_| Gpio::in_range(address))
     }
 
+    /// Halfwords served in place of the rom, keyed by rom offset.
+    pub fn set_rom_patches(&mut self, rom_patches: HashMap<u32, u16>) {
+        self.rom_patches = rom_patches;
+    }
+
+    fn rom_patch(&self, address: u32) -> Option<u16> {
+        match self.rom_patches.is_empty() || address >= SRAM_BASE {
+            true => None,
+            false => self.rom_patches.get(&(address & ROM_PATCH_MASK)).copied(),
+        }
+    }
+
+    fn patched_read_16(&self, address: u32) -> u16 {
+        self.rom_patch(address).unwrap_or_else(|| self.backup.read_16(address))
+    }
+
+    pub fn rom_size(&self) -> usize {
+        self.backup.rom().len()
+    }
+
+    /// Reads rom, GPIO and backup memory without clocking the EEPROM's serial transfer.
+    pub fn peek_8(&self, address: u32) -> u8 {
+        match (self.gpio_for_read(address), self.rom_patch(address), address >= SRAM_BASE) {
+            (Some(gpio), _, _) => (gpio.read_16(address) >> ((address & 1) * 8)) as u8,
+            (None, Some(patch), _) => (patch >> ((address & 1) * 8)) as u8,
+            (None, None, true) => self.backup.read_8(address),
+            (None, None, false) => self.backup.rom_read(address),
+        }
+    }
+
+    /// Patches a byte of rom, or writes a byte of backup memory.
+    pub fn poke_8(&mut self, address: u32, value: u8) {
+        match address >= SRAM_BASE {
+            true => self.backup.poke_backup(address, value),
+            false => {
+                if let Some(byte) = self.backup.rom_mut().get_mut((address & 0x01FFFFFF) as usize) {
+                    *byte = value;
+                }
+            }
+        }
+    }
+
     pub fn handle_event(&mut self, cartridge_event: CartridgeEvent) {
         self.backup.handle_event(cartridge_event);
     }
+
+    pub fn flush_backup(&mut self) {
+        self.backup.flush_backup();
+        if let Err(e) = self.storage.borrow_mut().flush() {
+            warn!("backup flush failed: {}", e);
+        }
+    }
 }
 
 impl SystemMemoryAccess for Cartridge {
     type Address = u32;
 
     fn read_8(&self, address: u32) -> u8 {
-        match self.gpio_for_read(address) {
-            Some(gpio) => (gpio.read_16(address) >> ((address & 1) * 8)) as u8,
-            None => self.backup.read_8(address),
+        match (self.gpio_for_read(address), self.rom_patch(address)) {
+            (Some(gpio), _) => (gpio.read_16(address) >> ((address & 1) * 8)) as u8,
+            (None, Some(patch)) => (patch >> ((address & 1) * 8)) as u8,
+            (None, None) => self.backup.read_8(address),
         }
     }
 
     fn read_16(&self, address: u32) -> u16 {
         match self.gpio_for_read(address) {
             Some(gpio) => gpio.read_16(address),
-            None => self.backup.read_16(address),
+            None => self.patched_read_16(address),
         }
     }
 
     fn read_32(&self, address: u32) -> u32 {
-        match self.gpio_for_read(address) {
-            Some(gpio) => gpio.read_16(address) as u32 | (gpio.read_16(address + 2) as u32) << 16,
-            None => self.backup.read_32(address),
+        match (self.gpio_for_read(address), self.rom_patches.is_empty()) {
+            (Some(gpio), _) => gpio.read_16(address) as u32 | (gpio.read_16(address + 2) as u32) << 16,
+            (None, true) => self.backup.read_32(address),
+            (None, false) => self.patched_read_16(address) as u32 | (self.patched_read_16(address + 2) as u32) << 16,
         }
     }
 
@@ -159,3 +242,20 @@ impl SystemMemoryAccess for Cartridge {
         }
     }
 }
+
+impl Snapshot for Cartridge {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(self.backup.as_ref());
+        if let Some(gpio) = &self.gpio {
+            writer.save(gpio);
+        }
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(self.backup.as_mut())?;
+        if let Some(gpio) = &mut self.gpio {
+            reader.load(gpio)?;
+        }
+        Ok(())
+    }
+}
@@ -1,42 +1,39 @@
-use std::{
-    fs::{File, OpenOptions},
-    io::{Read, Seek, SeekFrom, Write},
-    path::Path,
-};
+use std::ops::Range;
 
+use ironboyadvance_common::{
+    save_storage::SharedSaveStorage,
+    snapshot::{Snapshot, SnapshotError, StateReader, StateWriter},
+};
 use tracing::warn;
 
 use crate::cartridge::{CartridgeError, rtc::RTC_SAVE_BYTES};
 
+/// Battery backed memory of the cartridge. Writes land in the buffer and widen a dirty range, which is
+/// handed to the save storage when the cartridge is flushed at a frame boundary.
 pub struct BackupFile {
     buffer: Vec<u8>,
-    file: File,
+    dirty: Option<Range<usize>>,
+    storage: SharedSaveStorage,
 }
 
 impl BackupFile {
-    pub fn open(path: &Path, size: usize, fill: u8) -> Result<Self, CartridgeError> {
-        let mut file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(path)?;
-
-        let buffer = match file.metadata()?.len() as usize {
+    pub fn open(storage: SharedSaveStorage, size: usize, fill: u8) -> Result<Self, CartridgeError> {
+        let saved = storage.borrow_mut().load()?;
+        let buffer = match saved.len() {
             0 => {
                 let buffer = vec![fill; size];
-                file.write_all(&buffer)?;
-                buffer
-            }
-            len if len == size || len == size + RTC_SAVE_BYTES => {
-                let mut buffer = vec![0; size];
-                file.read_exact(&mut buffer)?;
+                storage.borrow_mut().store(0, &buffer)?;
                 buffer
             }
+            len if len == size || len == size + RTC_SAVE_BYTES => saved[..size].to_vec(),
             _ => return Err(CartridgeError::SaveSizeMismatch),
         };
 
-        Ok(Self { buffer, file })
+        Ok(Self {
+            buffer,
+            dirty: None,
+            storage,
+        })
     }
 
     pub fn read(&self, offset: usize) -> u8 {
@@ -45,24 +42,118 @@ impl BackupFile {
 
     pub fn write(&mut self, offset: usize, value: u8) {
         self.buffer[offset] = value;
-        if let Err(e) = self
-            .file
-            .seek(SeekFrom::Start(offset as u64))
-            .and_then(|_| self.file.write_all(&[value]))
-        {
-            warn!("backup write failed at offset {:08X}: {}", offset, e);
-        }
+        self.mark_dirty(offset..offset + 1);
     }
 
     pub fn fill(&mut self, offset: usize, length: usize, value: u8) {
         let range = offset..offset + length;
         self.buffer[range.clone()].fill(value);
-        let result = self
-            .file
-            .seek(SeekFrom::Start(offset as u64))
-            .and_then(|_| self.file.write_all(&self.buffer[range]));
-        if let Err(e) = result {
-            warn!("backup fill failed at offset {:08X}: {}", offset, e);
+        self.mark_dirty(range);
+    }
+
+    fn mark_dirty(&mut self, range: Range<usize>) {
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+            None => range,
+        });
+    }
+
+    pub fn flush(&mut self) {
+        let Some(dirty) = self.dirty.take() else {
+            return;
+        };
+
+        if let Err(e) = self.storage.borrow_mut().store(dirty.start, &self.buffer[dirty.clone()]) {
+            warn!("backup write failed at offset {:08X}: {}", dirty.start, e);
+        }
+    }
+}
+
+impl Snapshot for BackupFile {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.buffer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        let mut loaded = vec![0; self.buffer.len()];
+        reader.read_bytes_into(&mut loaded)?;
+        // Only what the state changed goes back to the save file, so loading a state or rewinding over
+        // untouched backup memory does not rewrite it.
+        let changed = |(old, new): (&u8, &u8)| old != new;
+        if let Some(start) = self.buffer.iter().zip(&loaded).position(changed)
+            && let Some(end) = self.buffer.iter().zip(&loaded).rposition(changed)
+        {
+            self.buffer.copy_from_slice(&loaded);
+            self.mark_dirty(start..end + 1);
         }
+        Ok(())
+    }
+}
+
+impl Drop for BackupFile {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use ironboyadvance_common::save_storage::{MemorySaveStorage, SaveStorage};
+
+    use super::*;
+
+    fn open(storage: &MemorySaveStorage) -> BackupFile {
+        BackupFile::open(Rc::new(RefCell::new(Box::new(storage.clone()))), 16, 0xFF).unwrap()
+    }
+
+    #[test]
+    fn writes_are_held_until_flushed() {
+        let storage = MemorySaveStorage::new();
+        let mut backup_file = open(&storage);
+        assert_eq!(storage.data(), vec![0xFF; 16]);
+
+        backup_file.write(3, 0x12);
+        backup_file.write(9, 0x34);
+        assert_eq!(storage.data(), vec![0xFF; 16]);
+
+        backup_file.flush();
+        let mut expected = vec![0xFF; 16];
+        expected[3] = 0x12;
+        expected[9] = 0x34;
+        assert_eq!(storage.data(), expected);
+    }
+
+    #[test]
+    fn loading_a_state_only_writes_back_what_it_changed() {
+        let storage = MemorySaveStorage::new();
+        let mut backup_file = open(&storage);
+        let mut state = StateWriter::new();
+        state.save(&backup_file);
+        let unchanged = state.finish();
+
+        backup_file.write(2, 0x12);
+        backup_file.write(5, 0x34);
+        state = StateWriter::new();
+        state.save(&backup_file);
+        let changed = state.finish();
+        backup_file.flush();
+
+        StateReader::new(&changed).load(&mut backup_file).unwrap();
+        assert_eq!(backup_file.dirty, None, "the state matches the buffer");
+        StateReader::new(&unchanged).load(&mut backup_file).unwrap();
+        assert_eq!(backup_file.dirty, Some(2..6));
+        backup_file.flush();
+        assert_eq!(storage.data(), vec![0xFF; 16]);
+    }
+
+    #[test]
+    fn pending_writes_are_flushed_on_drop() {
+        let storage = MemorySaveStorage::new();
+        let mut backup_file = open(&storage);
+        backup_file.fill(4, 4, 0x00);
+        drop(backup_file);
+        assert_eq!(storage.clone().load().unwrap()[3..9], [0xFF, 0, 0, 0, 0, 0xFF]);
     }
 }
@@ -1,13 +1,14 @@
 use std::cell::RefCell;
-use std::path::Path;
 use std::{cell::Cell, rc::Rc};
 
 use ironboyadvance_common::bits::BitOps;
 use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::save_storage::SharedSaveStorage;
 use ironboyadvance_common::scheduler::Scheduler;
 
 use crate::cartridge::{CartridgeBackup, CartridgeError, backup_file::BackupFile};
 use crate::events::{CartridgeEvent, GbaEvent};
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateValue, StateWriter};
 
 const BLOCK_BYTES: u32 = 8;
 const DUMMY_BITS: u8 = 4;
@@ -28,7 +29,7 @@ impl EepromSize {
         }
     }
 
-    fn backup_size(self) -> usize {
+    pub(super) fn backup_size(self) -> usize {
         match self {
             EepromSize::Small => 0x200,
             EepromSize::Large => 0x2000,
@@ -78,13 +79,13 @@ pub struct Eeprom {
 impl Eeprom {
     pub fn new(
         rom: Vec<u8>,
-        save_file: &Path,
+        storage: SharedSaveStorage,
         size: EepromSize,
         scheduler: Rc<RefCell<Scheduler<GbaEvent>>>,
     ) -> Result<Self, CartridgeError> {
         Ok(Self {
             rom,
-            backup_file: BackupFile::open(save_file, size.backup_size(), 0xFF)?,
+            backup_file: BackupFile::open(storage, size.backup_size(), 0xFF)?,
             size,
             state: Cell::new(TransferState::Idle),
             scheduler,
@@ -266,6 +267,14 @@ impl CartridgeBackup for Eeprom {
         &self.rom
     }
 
+    fn rom_mut(&mut self) -> &mut [u8] {
+        &mut self.rom
+    }
+
+    fn flush_backup(&mut self) {
+        self.backup_file.flush();
+    }
+
     fn backup_size(&self) -> usize {
         self.size.backup_size()
     }
@@ -276,3 +285,94 @@ impl CartridgeBackup for Eeprom {
         }
     }
 }
+
+impl StateValue for TransferState {
+    fn write_state(&self, writer: &mut StateWriter) {
+        match *self {
+            TransferState::Idle => writer.write(&0u8),
+            TransferState::GetAccessMode => writer.write(&1u8),
+            TransferState::GetAddress {
+                access_mode,
+                shift,
+                bits_shifted,
+            } => {
+                writer.write(&2u8);
+                writer.write(&(access_mode == AccessMode::Write));
+                writer.write(&shift);
+                writer.write(&bits_shifted);
+            }
+            TransferState::GetData {
+                address,
+                shift,
+                bits_shifted,
+            } => {
+                writer.write(&3u8);
+                writer.write(&address);
+                writer.write(&shift);
+                writer.write(&bits_shifted);
+            }
+            TransferState::Stop { address, data } => {
+                writer.write(&4u8);
+                writer.write(&address);
+                writer.write(&data);
+            }
+            TransferState::Stream { data, position } => {
+                writer.write(&5u8);
+                writer.write(&data);
+                writer.write(&position);
+            }
+            TransferState::Busy => writer.write(&6u8),
+        }
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        let state = match reader.read::<u8>()? {
+            0 => TransferState::Idle,
+            1 => TransferState::GetAccessMode,
+            2 => TransferState::GetAddress {
+                access_mode: match reader.read::<bool>()? {
+                    true => AccessMode::Write,
+                    false => AccessMode::Read,
+                },
+                shift: reader.read()?,
+                bits_shifted: reader.read()?,
+            },
+            3 => TransferState::GetData {
+                address: reader.read()?,
+                shift: reader.read()?,
+                bits_shifted: reader.read()?,
+            },
+            4 => TransferState::Stop {
+                address: reader.read()?,
+                data: reader.read()?,
+            },
+            5 => TransferState::Stream {
+                data: reader.read()?,
+                position: reader.read()?,
+            },
+            6 => TransferState::Busy,
+            _ => return Err(SnapshotError::InvalidValue("eeprom transfer state")),
+        };
+        Ok(state)
+    }
+}
+
+impl Snapshot for Eeprom {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.backup_file);
+        writer.write(&self.state.get());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.backup_file)?;
+        let state: TransferState = reader.read()?;
+        let blocks = (self.size.backup_size() as u32) / BLOCK_BYTES;
+        if let TransferState::GetData { address, .. } | TransferState::Stop { address, .. } = state
+            && address >= blocks
+        {
+            return Err(SnapshotError::InvalidValue("eeprom address"));
+        }
+        self.state.set(state);
+        Ok(())
+    }
+}
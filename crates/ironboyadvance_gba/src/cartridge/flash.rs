@@ -1,8 +1,7 @@
-use std::path::Path;
-
-use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::{memory::SystemMemoryAccess, save_storage::SharedSaveStorage};
 
 use crate::cartridge::{CartridgeBackup, CartridgeError, backup_file::BackupFile};
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const BANK_SIZE: usize = 64 * 1024;
 const SECTOR_SIZE: usize = 4 * 1024;
@@ -30,7 +29,7 @@ pub enum FlashSize {
 }
 
 impl FlashSize {
-    fn backup_size(self) -> usize {
+    pub(super) fn backup_size(self) -> usize {
         match self {
             FlashSize::Small => BANK_SIZE,
             FlashSize::Large => BANK_SIZE * 2,
@@ -67,10 +66,10 @@ pub struct Flash {
 }
 
 impl Flash {
-    pub fn new(rom: Vec<u8>, save_file: &Path, size: FlashSize) -> Result<Self, CartridgeError> {
+    pub fn new(rom: Vec<u8>, storage: SharedSaveStorage, size: FlashSize) -> Result<Self, CartridgeError> {
         Ok(Self {
             rom,
-            backup_file: BackupFile::open(save_file, size.backup_size(), 0xFF)?,
+            backup_file: BackupFile::open(storage, size.backup_size(), 0xFF)?,
             size,
             state: FlashState::Ready,
             bank: 0,
@@ -207,7 +206,50 @@ impl CartridgeBackup for Flash {
         &self.rom
     }
 
+    fn rom_mut(&mut self) -> &mut [u8] {
+        &mut self.rom
+    }
+
+    fn poke_backup(&mut self, address: u32, value: u8) {
+        let backup_offset = self.backup_offset(address);
+        self.backup_file.write(backup_offset, value);
+    }
+
+    fn flush_backup(&mut self) {
+        self.backup_file.flush();
+    }
+
     fn backup_size(&self) -> usize {
         self.size.backup_size()
     }
 }
+
+impl Snapshot for Flash {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.backup_file);
+        writer.write(&(self.state as u8));
+        writer.write(&self.bank);
+        writer.write(&self.in_id_mode);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.backup_file)?;
+        self.state = match reader.read::<u8>()? {
+            0 => FlashState::Ready,
+            1 => FlashState::ReceivedFirstUnlock,
+            2 => FlashState::AwaitingCommand,
+            3 => FlashState::ErasePrepared,
+            4 => FlashState::EraseReceivedFirstUnlock,
+            5 => FlashState::AwaitingEraseCommand,
+            6 => FlashState::AwaitingProgramValue,
+            7 => FlashState::AwaitingBankNumber,
+            _ => return Err(SnapshotError::InvalidValue("flash state")),
+        };
+        self.bank = match reader.read::<usize>()? {
+            bank if bank * BANK_SIZE < self.size.backup_size() => bank,
+            _ => return Err(SnapshotError::InvalidValue("flash bank")),
+        };
+        self.in_id_mode = reader.read()?;
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use getset::CopyGetters;
 
 use crate::cartridge::rtc::Rtc;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[derive(CopyGetters)]
 pub struct Gpio {
@@ -65,3 +66,24 @@ impl Gpio {
         }
     }
 }
+
+impl Snapshot for Gpio {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.data);
+        writer.write(&self.direction);
+        writer.write(&self.readable);
+        if let Some(rtc) = &self.rtc {
+            writer.save(rtc);
+        }
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.data = reader.read()?;
+        self.direction = reader.read()?;
+        self.readable = reader.read()?;
+        if let Some(rtc) = &mut self.rtc {
+            reader.load(rtc)?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use ironboyadvance_common::memory::SystemMemoryAccess;
 
 use crate::cartridge::CartridgeBackup;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 pub struct NoBackup {
     rom: Vec<u8>,
@@ -30,4 +31,16 @@ impl CartridgeBackup for NoBackup {
     fn rom(&self) -> &[u8] {
         &self.rom
     }
+
+    fn rom_mut(&mut self) -> &mut [u8] {
+        &mut self.rom
+    }
+}
+
+impl Snapshot for NoBackup {
+    fn save_state(&self, _writer: &mut StateWriter) {}
+
+    fn load_state(&mut self, _reader: &mut StateReader) -> Result<(), SnapshotError> {
+        Ok(())
+    }
 }
@@ -1,15 +1,14 @@
 use std::cell::RefCell;
-use std::fs::OpenOptions;
-use std::io::{Read, Seek, SeekFrom, Write};
-use std::path::PathBuf;
 use std::rc::Rc;
 
 use bitfields::bitfield;
 use ironboyadvance_arm7tdmi::CPU_CLOCK_SPEED;
+use ironboyadvance_common::save_storage::SharedSaveStorage;
 use ironboyadvance_common::scheduler::Scheduler;
 use tracing::warn;
 
 use crate::events::GbaEvent;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateValue, StateWriter};
 
 const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
 pub(super) const RTC_SAVE_BYTES: usize = 16;
@@ -110,7 +109,7 @@ enum TransferState {
 
 pub struct Rtc {
     scheduler: Rc<RefCell<Scheduler<GbaEvent>>>,
-    save_file: PathBuf,
+    storage: SharedSaveStorage,
     save_offset: usize,
     base_unix_seconds: u64,
     offset_seconds: i64,
@@ -125,17 +124,17 @@ pub struct Rtc {
 impl Rtc {
     pub fn new(
         base_unix_seconds: u64,
-        save_file: PathBuf,
+        storage: SharedSaveStorage,
         save_offset: usize,
         scheduler: Rc<RefCell<Scheduler<GbaEvent>>>,
     ) -> Rtc {
         let mut control = RtcControl::from_bits(0);
         control.set_hour_mode_24(true);
-        let (offset_seconds, day_of_week_offset) = load_save(&save_file, save_offset);
+        let (offset_seconds, day_of_week_offset) = load_save(&storage, save_offset);
 
         Rtc {
             scheduler,
-            save_file,
+            storage,
             save_offset,
             base_unix_seconds,
             offset_seconds,
@@ -341,18 +340,8 @@ impl Rtc {
         bytes[0..8].copy_from_slice(&self.offset_seconds.to_le_bytes());
         bytes[8..16].copy_from_slice(&self.day_of_week_offset.to_le_bytes());
 
-        let result = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(&self.save_file)
-            .and_then(|mut file| {
-                file.seek(SeekFrom::Start(self.save_offset as u64))
-                    .and_then(|_| file.write_all(&bytes))
-            });
-
-        if let Err(error) = result {
-            warn!("rtc save failed at {:?}: {}", self.save_file, error);
+        if let Err(error) = self.storage.borrow_mut().store(self.save_offset, &bytes) {
+            warn!("rtc save failed at offset {:08X}: {}", self.save_offset, error);
         }
     }
 
@@ -422,22 +411,77 @@ fn next_position(register: RtcRegister, byte_index: usize, bits_transferred: u8)
     }
 }
 
-fn load_save(path: &PathBuf, offset: usize) -> (i64, i64) {
-    let mut bytes = [0u8; RTC_SAVE_BYTES];
-    let read = OpenOptions::new().read(true).open(path).and_then(|mut file| {
-        file.seek(SeekFrom::Start(offset as u64))
-            .and_then(|_| file.read_exact(&mut bytes))
-    });
-
-    match read {
-        Ok(()) => (
+fn load_save(storage: &SharedSaveStorage, offset: usize) -> (i64, i64) {
+    let saved = storage.borrow_mut().load().unwrap_or_default();
+    match saved.get(offset..offset + RTC_SAVE_BYTES) {
+        Some(bytes) => (
             i64::from_le_bytes(bytes[0..8].try_into().unwrap()),
             i64::from_le_bytes(bytes[8..16].try_into().unwrap()),
         ),
-        Err(_) => (0, DEFAULT_DAY_OF_WEEK_OFFSET),
+        None => (0, DEFAULT_DAY_OF_WEEK_OFFSET),
     }
 }
 
+/// Converts the RTC block VBA and mGBA append to a save, the seven BCD date time registers, the control
+/// register and the unix time the registers were latched at, into this emulator's footer. A footer that
+/// is already in the native layout is returned as is.
+pub(super) fn import_rtc_block(block: &[u8; RTC_SAVE_BYTES]) -> [u8; RTC_SAVE_BYTES] {
+    let latched_at = u64::from_le_bytes(block[8..16].try_into().unwrap());
+    if latched_at < 7 {
+        return *block;
+    }
+
+    let year = 2000 + from_binary_coded_decimal(block[0]) as i64;
+    let month = from_binary_coded_decimal(block[1]) as i64;
+    let day = from_binary_coded_decimal(block[2]) as i64;
+    let days = days_from_civil(year, month, day);
+    let control = RtcControl::from_bits(block[7]);
+    let register = HourRegister::from_bits(block[4]);
+    let hour = from_binary_coded_decimal(register.hour()) as i64;
+    let hour = match !control.hour_mode_24() && register.afternoon() {
+        true => hour % 12 + 12,
+        false => hour,
+    };
+    let seconds_of_day =
+        hour * 3600 + from_binary_coded_decimal(block[5]) as i64 * 60 + from_binary_coded_decimal(block[6]) as i64;
+
+    let offset_seconds = days * SECONDS_PER_DAY + seconds_of_day - latched_at as i64;
+    let day_of_week_offset = ((block[3] & 0x07) as i64 - days).rem_euclid(7);
+    let mut footer = [0u8; RTC_SAVE_BYTES];
+    footer[0..8].copy_from_slice(&offset_seconds.to_le_bytes());
+    footer[8..16].copy_from_slice(&day_of_week_offset.to_le_bytes());
+    footer
+}
+
+/// Converts a native footer into the VBA and mGBA RTC block, latched at `unix_seconds`.
+pub(super) fn export_rtc_block(footer: &[u8; RTC_SAVE_BYTES], unix_seconds: u64) -> [u8; RTC_SAVE_BYTES] {
+    let offset_seconds = i64::from_le_bytes(footer[0..8].try_into().unwrap());
+    let day_of_week_offset = i64::from_le_bytes(footer[8..16].try_into().unwrap());
+    let clock = unix_seconds as i64 + offset_seconds;
+    let days = clock.div_euclid(SECONDS_PER_DAY);
+    let seconds_of_day = clock.rem_euclid(SECONDS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    let hour = (seconds_of_day / 3600) as u8;
+
+    let mut control = RtcControl::from_bits(0);
+    control.set_hour_mode_24(true);
+    let mut register = HourRegister::from_bits(0);
+    register.set_hour(to_binary_coded_decimal(hour));
+    register.set_afternoon(hour >= 12);
+
+    let mut block = [0u8; RTC_SAVE_BYTES];
+    block[0] = to_binary_coded_decimal((year - 2000).rem_euclid(100) as u8);
+    block[1] = to_binary_coded_decimal(month as u8);
+    block[2] = to_binary_coded_decimal(day as u8);
+    block[3] = (days + day_of_week_offset).rem_euclid(7) as u8;
+    block[4] = register.into_bits();
+    block[5] = to_binary_coded_decimal((seconds_of_day / 60 % 60) as u8);
+    block[6] = to_binary_coded_decimal((seconds_of_day % 60) as u8);
+    block[7] = control.into_bits();
+    block[8..16].copy_from_slice(&unix_seconds.to_le_bytes());
+    block
+}
+
 fn to_binary_coded_decimal(value: u8) -> u8 {
     ((value / 10) << 4) | (value % 10)
 }
@@ -477,10 +521,120 @@ fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
     era * 146097 + day_of_era - 719468
 }
 
+impl StateValue for RtcRegister {
+    fn write_state(&self, writer: &mut StateWriter) {
+        writer.write(&(*self as u8));
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        match reader.read::<u8>()? {
+            0 => Ok(RtcRegister::ForceReset),
+            1 => Ok(RtcRegister::Control),
+            2 => Ok(RtcRegister::DateTime),
+            3 => Ok(RtcRegister::Time),
+            4 => Ok(RtcRegister::ForceIrq),
+            5 => Ok(RtcRegister::Unused),
+            _ => Err(SnapshotError::InvalidValue("rtc register")),
+        }
+    }
+}
+
+impl StateValue for TransferState {
+    fn write_state(&self, writer: &mut StateWriter) {
+        match *self {
+            TransferState::WaitingForChipSelectLow => writer.write(&0u8),
+            TransferState::WaitingForChipSelectHigh => writer.write(&1u8),
+            TransferState::ReceivingCommand { bits_transferred } => {
+                writer.write(&2u8);
+                writer.write(&bits_transferred);
+            }
+            TransferState::Reading {
+                register,
+                byte_index,
+                bits_transferred,
+            } => {
+                writer.write(&3u8);
+                writer.write(&register);
+                writer.write(&byte_index);
+                writer.write(&bits_transferred);
+            }
+            TransferState::Writing {
+                register,
+                byte_index,
+                bits_transferred,
+            } => {
+                writer.write(&4u8);
+                writer.write(&register);
+                writer.write(&byte_index);
+                writer.write(&bits_transferred);
+            }
+        }
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        let state = match reader.read::<u8>()? {
+            0 => TransferState::WaitingForChipSelectLow,
+            1 => TransferState::WaitingForChipSelectHigh,
+            2 => TransferState::ReceivingCommand {
+                bits_transferred: reader.read()?,
+            },
+            tag @ (3 | 4) => {
+                let register: RtcRegister = reader.read()?;
+                let byte_index: usize = reader.read()?;
+                let bits_transferred: u8 = reader.read()?;
+                if byte_index >= 7 || bits_transferred >= 8 {
+                    return Err(SnapshotError::InvalidValue("rtc transfer state"));
+                }
+                match tag {
+                    3 => TransferState::Reading {
+                        register,
+                        byte_index,
+                        bits_transferred,
+                    },
+                    _ => TransferState::Writing {
+                        register,
+                        byte_index,
+                        bits_transferred,
+                    },
+                }
+            }
+            _ => return Err(SnapshotError::InvalidValue("rtc transfer state")),
+        };
+        Ok(state)
+    }
+}
+
+impl Snapshot for Rtc {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.base_unix_seconds);
+        writer.write(&self.offset_seconds);
+        writer.write(&self.day_of_week_offset);
+        writer.write(&self.control.into_bits());
+        writer.write(&self.state);
+        writer.write(&self.previous_pins.into_bits());
+        writer.write(&self.bytes);
+        writer.write(&self.output_bit);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.base_unix_seconds = reader.read()?;
+        self.offset_seconds = reader.read()?;
+        self.day_of_week_offset = reader.read()?;
+        self.control = RtcControl::from_bits(reader.read()?);
+        self.state = reader.read()?;
+        self.previous_pins = RtcPins::from_bits(reader.read()?);
+        self.bytes = reader.read()?;
+        self.output_bit = reader.read()?;
+        self.save();
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::cartridge::gpio::Gpio;
+    use ironboyadvance_common::save_storage::MemorySaveStorage;
 
     const DATA_ADDRESS: u32 = 0x080000C4;
     const DIRECTION_ADDRESS: u32 = 0x080000C6;
@@ -506,21 +660,16 @@ mod tests {
         (0x60 | (index << 1) | read as u8).reverse_bits()
     }
 
-    fn save_path(name: &str) -> PathBuf {
-        let path = std::env::temp_dir().join(format!("ironboyadvance-rtc-{name}.sav"));
-        let _ = std::fs::remove_file(&path);
-        path
-    }
-
-    fn open(path: PathBuf, unix_seconds: u64) -> Gpio {
+    fn open(storage: MemorySaveStorage, unix_seconds: u64) -> Gpio {
         let scheduler = Rc::new(RefCell::new(Scheduler::new()));
-        let mut gpio = Gpio::new(Some(Rtc::new(unix_seconds, path, 0, scheduler)));
+        let storage: SharedSaveStorage = Rc::new(RefCell::new(Box::new(storage)));
+        let mut gpio = Gpio::new(Some(Rtc::new(unix_seconds, storage, 0, scheduler)));
         gpio.write_16(CONTROL_ADDRESS, 1);
         gpio
     }
 
-    fn harness(name: &str, unix_seconds: u64) -> Gpio {
-        open(save_path(name), unix_seconds)
+    fn harness(unix_seconds: u64) -> Gpio {
+        open(MemorySaveStorage::new(), unix_seconds)
     }
 
     fn begin_transfer(gpio: &mut Gpio) {
@@ -573,13 +722,13 @@ mod tests {
 
     #[test]
     fn control_boots_in_24_hour_mode_without_power_failure() {
-        let mut gpio = harness("boot_control", NEW_YEARS_DAY_2026);
+        let mut gpio = harness(NEW_YEARS_DAY_2026);
         assert_eq!(read_register(&mut gpio, command(RtcRegister::Control, true), 1), vec![0x40]);
     }
 
     #[test]
     fn date_time_reads_back_as_binary_coded_decimal() {
-        let mut gpio = harness("date_time", NEW_YEARS_DAY_2026 + AFTERNOON);
+        let mut gpio = harness(NEW_YEARS_DAY_2026 + AFTERNOON);
         let bytes = read_register(&mut gpio, command(RtcRegister::DateTime, true), 7);
 
         assert_eq!(bytes[0], 0x26, "year");
@@ -593,7 +742,7 @@ mod tests {
 
     #[test]
     fn time_register_matches_the_date_time_register() {
-        let mut gpio = harness("time", NEW_YEARS_DAY_2026 + AFTERNOON);
+        let mut gpio = harness(NEW_YEARS_DAY_2026 + AFTERNOON);
         let date_time = read_register(&mut gpio, command(RtcRegister::DateTime, true), 7);
         assert_eq!(
             read_register(&mut gpio, command(RtcRegister::Time, true), 3),
@@ -603,7 +752,7 @@ mod tests {
 
     #[test]
     fn twelve_hour_mode_folds_afternoon_hours() {
-        let mut gpio = harness("twelve_hour", NEW_YEARS_DAY_2026 + AFTERNOON);
+        let mut gpio = harness(NEW_YEARS_DAY_2026 + AFTERNOON);
         write_register(&mut gpio, command(RtcRegister::Control, false), &[0x00]);
         assert_eq!(
             read_register(&mut gpio, command(RtcRegister::DateTime, true), 7)[4],
@@ -614,7 +763,7 @@ mod tests {
 
     #[test]
     fn command_byte_accepts_either_bit_order() {
-        let mut gpio = harness("bit_order", NEW_YEARS_DAY_2026);
+        let mut gpio = harness(NEW_YEARS_DAY_2026);
         let reversed = read_register(&mut gpio, command(RtcRegister::Control, true), 1);
         let plain = read_register(&mut gpio, 0x63, 1);
         assert_eq!(reversed, plain, "0xC6 and 0x63 both select the control register");
@@ -622,7 +771,7 @@ mod tests {
 
     #[test]
     fn written_date_time_round_trips() {
-        let mut gpio = harness("round_trip", NEW_YEARS_DAY_2026);
+        let mut gpio = harness(NEW_YEARS_DAY_2026);
         let written = vec![0x99, 0x12, 0x25, 0x05, 0x08, 0x30, 0x15];
         write_register(&mut gpio, command(RtcRegister::DateTime, false), &written);
         assert_eq!(read_register(&mut gpio, command(RtcRegister::DateTime, true), 7), written);
@@ -630,14 +779,14 @@ mod tests {
 
     #[test]
     fn control_writes_cannot_set_the_read_only_power_failure_flag() {
-        let mut gpio = harness("read_only_power", NEW_YEARS_DAY_2026);
+        let mut gpio = harness(NEW_YEARS_DAY_2026);
         write_register(&mut gpio, command(RtcRegister::Control, false), &[0xFF]);
         assert_eq!(read_register(&mut gpio, command(RtcRegister::Control, true), 1), vec![0x6A]);
     }
 
     #[test]
     fn force_reset_zeroes_every_register() {
-        let mut gpio = harness("force_reset", NEW_YEARS_DAY_2026 + AFTERNOON);
+        let mut gpio = harness(NEW_YEARS_DAY_2026 + AFTERNOON);
         begin_transfer(&mut gpio);
         send_byte(&mut gpio, command(RtcRegister::ForceReset, false));
         end_transfer(&mut gpio);
@@ -650,14 +799,32 @@ mod tests {
 
     #[test]
     fn clock_survives_a_reload() {
-        let path = save_path("reload");
+        let storage = MemorySaveStorage::new();
         let written = vec![0x99, 0x12, 0x25, 0x05, 0x08, 0x30, 0x15];
 
-        let mut gpio = open(path.clone(), NEW_YEARS_DAY_2026);
+        let mut gpio = open(storage.clone(), NEW_YEARS_DAY_2026);
         write_register(&mut gpio, command(RtcRegister::DateTime, false), &written);
         drop(gpio);
 
-        let mut reloaded = open(path, NEW_YEARS_DAY_2026);
+        let mut reloaded = open(storage, NEW_YEARS_DAY_2026);
         assert_eq!(read_register(&mut reloaded, command(RtcRegister::DateTime, true), 7), written);
     }
+
+    #[test]
+    fn imported_rtc_block_keeps_the_clock_it_was_latched_with() {
+        let mut block = [0u8; RTC_SAVE_BYTES];
+        block[0..7].copy_from_slice(&[0x99, 0x12, 0x25, 0x05, 0x08, 0x30, 0x15]);
+        block[7] = 0x40;
+        block[8..16].copy_from_slice(&NEW_YEARS_DAY_2026.to_le_bytes());
+
+        let footer = import_rtc_block(&block);
+        assert_eq!(import_rtc_block(&footer), footer, "native footers pass through");
+        assert_eq!(export_rtc_block(&footer, NEW_YEARS_DAY_2026), block);
+
+        let mut gpio = open(MemorySaveStorage::with_data(footer.to_vec()), NEW_YEARS_DAY_2026 + 60);
+        assert_eq!(
+            read_register(&mut gpio, command(RtcRegister::DateTime, true), 7),
+            vec![0x99, 0x12, 0x25, 0x05, 0x08, 0x31, 0x15]
+        );
+    }
 }
@@ -0,0 +1,326 @@
+use std::ops::Range;
+
+use ironboyadvance_common::save_format::{
+    GAMESHARK_SP_MAGIC, GAMESHARK_SP_MAGIC_OFFSET, SHARKPORT_MAGIC, SaveConvertOptions, SaveFormat,
+};
+
+use crate::cartridge::{
+    CartridgeError,
+    config::{BackupType, CartridgeDevice, determine_cartridge_config},
+    flash::FlashSize,
+    header::Header,
+    rtc::{RTC_SAVE_BYTES, export_rtc_block, import_rtc_block},
+    sram::SRAM_SIZE,
+};
+
+const HEADER_LENGTH: usize = 228;
+const EEPROM_BLOCK_BYTES: usize = 8;
+const GAMESHARK_SP_PAYLOAD_OFFSET: usize = 0x430;
+const SHARKPORT_PLATFORM: u32 = 0x000F0000;
+const SHARKPORT_ROM_HEADER: Range<usize> = 0xA0..0xBC;
+const SHARKPORT_GAME_CODE: Range<usize> = 0x0C..0x10;
+const GAME_TITLE: Range<usize> = 0xA0..0xAC;
+
+/// What the cartridge keeps in its save: the backup memory, then the RTC footer when it has a clock.
+struct SaveLayout {
+    size: usize,
+    eeprom: bool,
+    rtc: bool,
+}
+
+impl SaveLayout {
+    fn detect(rom: &[u8]) -> Result<SaveLayout, CartridgeError> {
+        if rom.len() < HEADER_LENGTH {
+            return Err(CartridgeError::InvalidCatridgeType);
+        }
+
+        let header = Header::load(&rom[0..HEADER_LENGTH]);
+        let config = determine_cartridge_config(rom, &header);
+        let size = match config.backup_type() {
+            BackupType::None => 0,
+            BackupType::Sram => SRAM_SIZE,
+            BackupType::Eeprom => config.eeprom_size().ok_or(CartridgeError::MissingEepromSize)?.backup_size(),
+            BackupType::Flash64KB => FlashSize::Small.backup_size(),
+            BackupType::Flash128KB => FlashSize::Large.backup_size(),
+        };
+
+        Ok(SaveLayout {
+            size,
+            eeprom: matches!(config.backup_type(), BackupType::Eeprom),
+            rtc: CartridgeDevice::Rtc.is_set(config.device_pattern()),
+        })
+    }
+}
+
+/// Converts a save made by another emulator or a cheat device into the layout this emulator keeps for
+/// `rom`. Container formats are unwrapped, VBA and mGBA clock blocks become the native RTC footer and
+/// EEPROM saves are padded or trimmed to the chip the game uses.
+pub fn import_save(rom: &[u8], data: &[u8], options: &SaveConvertOptions) -> Result<Vec<u8>, CartridgeError> {
+    let layout = SaveLayout::detect(rom)?;
+    let (payload, from_container) = match SaveFormat::detect(data) {
+        SaveFormat::Raw => (data, false),
+        SaveFormat::GameSharkSp => (unwrap_gameshark_sp(data)?, true),
+        SaveFormat::SharkPort => (unwrap_sharkport(rom, data)?, true),
+    };
+
+    let (memory, rtc_block) = match payload.len() == layout.size + RTC_SAVE_BYTES {
+        true => (&payload[..layout.size], payload[layout.size..].try_into().ok()),
+        false => (payload, None),
+    };
+
+    let mut save = match (layout.eeprom, memory.len()) {
+        (true, _) => {
+            let mut memory = memory.to_vec();
+            memory.resize(layout.size, 0xFF);
+            memory
+        }
+        (false, length) if length == layout.size => memory.to_vec(),
+        (false, length) if from_container && length > layout.size => memory[..layout.size].to_vec(),
+        _ => return Err(CartridgeError::SaveSizeMismatch),
+    };
+
+    if layout.eeprom && options.swap_eeprom_blocks {
+        swap_eeprom_blocks(&mut save);
+    }
+    if let Some(block) = rtc_block.filter(|_| layout.rtc) {
+        save.extend_from_slice(&import_rtc_block(block));
+    }
+
+    Ok(save)
+}
+
+/// Converts a save in this emulator's layout for `rom` into `format`. Only raw saves carry the clock,
+/// as the VBA and mGBA RTC block.
+pub fn export_save(
+    rom: &[u8],
+    save: &[u8],
+    format: SaveFormat,
+    options: &SaveConvertOptions,
+) -> Result<Vec<u8>, CartridgeError> {
+    let layout = SaveLayout::detect(rom)?;
+    let mut memory = save.get(..layout.size).ok_or(CartridgeError::SaveSizeMismatch)?.to_vec();
+    if layout.eeprom && options.swap_eeprom_blocks {
+        swap_eeprom_blocks(&mut memory);
+    }
+
+    let footer = match layout.rtc {
+        true => save.get(layout.size..layout.size + RTC_SAVE_BYTES),
+        false => None,
+    };
+
+    match format {
+        SaveFormat::Raw => {
+            if let Some(footer) = footer {
+                memory.extend_from_slice(&export_rtc_block(footer.try_into().unwrap(), options.unix_seconds));
+            }
+            Ok(memory)
+        }
+        SaveFormat::GameSharkSp => {
+            let mut data = vec![0; GAMESHARK_SP_PAYLOAD_OFFSET];
+            data[GAMESHARK_SP_MAGIC_OFFSET..GAMESHARK_SP_MAGIC_OFFSET + GAMESHARK_SP_MAGIC.len()]
+                .copy_from_slice(GAMESHARK_SP_MAGIC);
+            data.extend_from_slice(&memory);
+            Ok(data)
+        }
+        SaveFormat::SharkPort => Ok(wrap_sharkport(rom, &memory)),
+    }
+}
+
+fn swap_eeprom_blocks(memory: &mut [u8]) {
+    for block in memory.chunks_exact_mut(EEPROM_BLOCK_BYTES) {
+        block.reverse();
+    }
+}
+
+fn unwrap_gameshark_sp(data: &[u8]) -> Result<&[u8], CartridgeError> {
+    data.get(GAMESHARK_SP_PAYLOAD_OFFSET..)
+        .ok_or(CartridgeError::InvalidSaveFile("GameShark SP save is truncated"))
+}
+
+struct SharkPortReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SharkPortReader<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], CartridgeError> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + length)
+            .ok_or(CartridgeError::InvalidSaveFile("SharkPort save is truncated"))?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, CartridgeError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn field(&mut self) -> Result<&'a [u8], CartridgeError> {
+        let length = self.u32()? as usize;
+        self.bytes(length)
+    }
+}
+
+fn sharkport_checksum(payload: &[u8]) -> u32 {
+    payload.iter().fold(0u32, |checksum, &byte| {
+        checksum.wrapping_add((byte as u32) << (checksum % 24))
+    })
+}
+
+fn unwrap_sharkport<'a>(rom: &[u8], data: &'a [u8]) -> Result<&'a [u8], CartridgeError> {
+    let mut reader = SharkPortReader { data, position: 0 };
+    if reader.field()? != SHARKPORT_MAGIC {
+        return Err(CartridgeError::InvalidSaveFile("missing SharkPort magic"));
+    }
+    if reader.u32()? != SHARKPORT_PLATFORM {
+        return Err(CartridgeError::InvalidSaveFile("SharkPort save is not for a GBA game"));
+    }
+
+    let _title = reader.field()?;
+    let _date = reader.field()?;
+    let _notes = reader.field()?;
+    let payload = reader.field()?;
+    if payload.len() < SHARKPORT_ROM_HEADER.len() {
+        return Err(CartridgeError::InvalidSaveFile("SharkPort save is truncated"));
+    }
+    if reader.u32()? != sharkport_checksum(payload) {
+        return Err(CartridgeError::InvalidSaveFile("SharkPort checksum mismatch"));
+    }
+
+    let game_code = &rom[SHARKPORT_ROM_HEADER][SHARKPORT_GAME_CODE];
+    if &payload[SHARKPORT_GAME_CODE] != game_code {
+        return Err(CartridgeError::SaveRomMismatch);
+    }
+
+    Ok(&payload[SHARKPORT_ROM_HEADER.len()..])
+}
+
+fn push_field(data: &mut Vec<u8>, field: &[u8]) {
+    data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+    data.extend_from_slice(field);
+}
+
+fn wrap_sharkport(rom: &[u8], memory: &[u8]) -> Vec<u8> {
+    let mut payload = rom[SHARKPORT_ROM_HEADER].to_vec();
+    payload.extend_from_slice(memory);
+    let title: Vec<u8> = rom[GAME_TITLE].iter().copied().take_while(|&byte| byte != 0).collect();
+
+    let mut data = Vec::new();
+    push_field(&mut data, SHARKPORT_MAGIC);
+    data.extend_from_slice(&SHARKPORT_PLATFORM.to_le_bytes());
+    push_field(&mut data, &title);
+    push_field(&mut data, b"");
+    push_field(&mut data, b"");
+    push_field(&mut data, &payload);
+    data.extend_from_slice(&sharkport_checksum(&payload).to_le_bytes());
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rom(backup: &[u8], game_code: &[u8; 4]) -> Vec<u8> {
+        let mut rom = vec![0; 0x400];
+        rom[GAME_TITLE][..4].copy_from_slice(b"TEST");
+        rom[0xAC..0xB0].copy_from_slice(game_code);
+        rom[0x200..0x200 + backup.len()].copy_from_slice(backup);
+        rom
+    }
+
+    fn pattern(length: usize) -> Vec<u8> {
+        (0..length).map(|index| (index * 7) as u8).collect()
+    }
+
+    #[test]
+    fn raw_sram_save_round_trips_and_rejects_wrong_sizes() {
+        let rom = rom(b"SRAM_V113", b"ATST");
+        let save = pattern(SRAM_SIZE);
+        let options = SaveConvertOptions::default();
+
+        assert_eq!(import_save(&rom, &save, &options).unwrap(), save);
+        assert_eq!(export_save(&rom, &save, SaveFormat::Raw, &options).unwrap(), save);
+        assert!(matches!(
+            import_save(&rom, &save[..0x1000], &options),
+            Err(CartridgeError::SaveSizeMismatch)
+        ));
+    }
+
+    #[test]
+    fn eeprom_saves_are_resized_and_optionally_swapped() {
+        let rom = rom(b"EEPROM_V124", b"ATST");
+        let mut block = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let options = SaveConvertOptions {
+            swap_eeprom_blocks: true,
+            ..Default::default()
+        };
+
+        let imported = import_save(&rom, &block, &options).unwrap();
+        assert_eq!(imported.len(), 0x200, "padded to the small chip");
+        assert_eq!(&imported[..8], &[8, 7, 6, 5, 4, 3, 2, 1]);
+        assert!(imported[8..].iter().all(|&byte| byte == 0xFF));
+
+        block.resize(0x2000, 0xFF);
+        let trimmed = import_save(&rom, &block, &SaveConvertOptions::default()).unwrap();
+        assert_eq!(trimmed.len(), 0x200, "trimmed to the small chip");
+        assert_eq!(export_save(&rom, &imported, SaveFormat::Raw, &options).unwrap(), trimmed);
+    }
+
+    #[test]
+    fn container_formats_round_trip() {
+        let rom = rom(b"FLASH_V126", b"ATST");
+        let save = pattern(FlashSize::Small.backup_size());
+        let options = SaveConvertOptions::default();
+
+        for format in [SaveFormat::GameSharkSp, SaveFormat::SharkPort] {
+            let exported = export_save(&rom, &save, format, &options).unwrap();
+            assert_eq!(SaveFormat::detect(&exported), format);
+            assert_eq!(import_save(&rom, &exported, &options).unwrap(), save);
+        }
+    }
+
+    #[test]
+    fn sharkport_rejects_other_games_and_corruption() {
+        let save = pattern(SRAM_SIZE);
+        let options = SaveConvertOptions::default();
+        let mut exported = export_save(&rom(b"SRAM_V113", b"ATST"), &save, SaveFormat::SharkPort, &options).unwrap();
+
+        assert!(matches!(
+            import_save(&rom(b"SRAM_V113", b"BXYZ"), &exported, &options),
+            Err(CartridgeError::SaveRomMismatch)
+        ));
+
+        let last_data_byte = exported.len() - 5;
+        exported[last_data_byte] ^= 0xFF;
+        assert!(matches!(
+            import_save(&rom(b"SRAM_V113", b"ATST"), &exported, &options),
+            Err(CartridgeError::InvalidSaveFile(_))
+        ));
+    }
+
+    #[test]
+    fn rtc_block_is_converted_for_games_with_a_clock() {
+        // Pokemon Ruby has a clock according to the overrides table.
+        let rom = rom(b"FLASH1M_V103", b"AXVE");
+        let options = SaveConvertOptions {
+            unix_seconds: 1767225600,
+            ..Default::default()
+        };
+        let mut native = pattern(FlashSize::Large.backup_size());
+        native.extend_from_slice(&60i64.to_le_bytes());
+        native.extend_from_slice(&3i64.to_le_bytes());
+
+        let exported = export_save(&rom, &native, SaveFormat::Raw, &options).unwrap();
+        assert_eq!(exported.len(), native.len());
+        assert_eq!(&exported[native.len() - 8..], &options.unix_seconds.to_le_bytes());
+        assert_eq!(import_save(&rom, &exported, &options).unwrap(), native);
+
+        let dropped = import_save(&rom, &exported[..FlashSize::Large.backup_size()], &options).unwrap();
+        assert_eq!(
+            dropped.len(),
+            FlashSize::Large.backup_size(),
+            "clock starts fresh without a block"
+        );
+    }
+}
@@ -1,10 +1,9 @@
-use std::path::Path;
-
-use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::{memory::SystemMemoryAccess, save_storage::SharedSaveStorage};
 
 use crate::cartridge::{CartridgeBackup, CartridgeError, backup_file::BackupFile};
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
-const SRAM_SIZE: usize = 32 * 1024;
+pub(super) const SRAM_SIZE: usize = 32 * 1024;
 
 pub struct Sram {
     rom: Vec<u8>,
@@ -12,8 +11,8 @@ pub struct Sram {
 }
 
 impl Sram {
-    pub fn new(rom: Vec<u8>, save_file: &Path) -> Result<Self, CartridgeError> {
-        let backup_file = BackupFile::open(save_file, SRAM_SIZE, 0xFF)?;
+    pub fn new(rom: Vec<u8>, storage: SharedSaveStorage) -> Result<Self, CartridgeError> {
+        let backup_file = BackupFile::open(storage, SRAM_SIZE, 0xFF)?;
         Ok(Self { rom, backup_file })
     }
 
@@ -73,7 +72,25 @@ impl CartridgeBackup for Sram {
         &self.rom
     }
 
+    fn rom_mut(&mut self) -> &mut [u8] {
+        &mut self.rom
+    }
+
+    fn flush_backup(&mut self) {
+        self.backup_file.flush();
+    }
+
     fn backup_size(&self) -> usize {
         SRAM_SIZE
     }
 }
+
+impl Snapshot for Sram {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.backup_file);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.backup_file)
+    }
+}
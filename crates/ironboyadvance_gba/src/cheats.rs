@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+
+use ironboyadvance_common::{
+    cheats::{Cheat, CheatError, CheatFormat, code_lines},
+    memory::{MemoryAccessWidth, SystemMemoryAccess},
+};
+
+use crate::system_bus::SystemBus;
+
+mod action_replay;
+mod codebreaker;
+mod gameshark;
+
+const TEA_DELTA: u32 = 0x9E3779B9;
+const TEA_ROUNDS: u32 = 32;
+/// Decrypted first word of the code that identifies the game on GameShark and Action Replay devices.
+const GAME_ID_CODE: u32 = 0x001DC0DE;
+/// Decrypted first word of the code that switches a GameShark or Action Replay to different seeds.
+const RESEED_CODE: u32 = 0xDEADFACE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessUnsigned,
+    GreaterUnsigned,
+    And,
+}
+
+/// What a conditional code does when its condition does not hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Skip {
+    /// Skips the ops decoded from this many code lines after the condition's.
+    Codes(usize),
+    /// Skips to the matching `Else` or `EndIf`.
+    Block,
+    /// Turns the whole cheat off.
+    Disable,
+}
+
+/// A decoded device code. Every device format is decoded into these, so one interpreter runs them all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatOp {
+    Write {
+        address: u32,
+        width: MemoryAccessWidth,
+        value: u32,
+    },
+    Add {
+        address: u32,
+        width: MemoryAccessWidth,
+        value: u32,
+    },
+    Or {
+        address: u32,
+        width: MemoryAccessWidth,
+        value: u32,
+    },
+    And {
+        address: u32,
+        width: MemoryAccessWidth,
+        value: u32,
+    },
+    /// Writes `value` at `offset` past the address stored at `pointer`.
+    WriteIndirect {
+        pointer: u32,
+        offset: u32,
+        width: MemoryAccessWidth,
+        value: u32,
+    },
+    /// Writes `count` values, stepping the address and the value after each one.
+    Slide {
+        address: u32,
+        width: MemoryAccessWidth,
+        value: u32,
+        count: u32,
+        address_step: u32,
+        value_step: u32,
+    },
+    If {
+        address: u32,
+        width: MemoryAccessWidth,
+        comparison: Comparison,
+        value: u32,
+        skip: Skip,
+    },
+    /// Holds while every key in `keys` is pressed, bit layout as KEYINPUT with 1 meaning pressed.
+    IfKeys {
+        keys: u16,
+        skip: Skip,
+    },
+    Else,
+    EndIf,
+    /// Replaces the halfword at `offset` into the rom for as long as the cheat is enabled.
+    RomPatch {
+        offset: u32,
+        value: u16,
+    },
+    /// Address the device would hook to run its codes. Codes run once per frame here, so hooks and master
+    /// codes are accepted and ignored.
+    Hook {
+        address: u32,
+    },
+}
+
+/// The ops a cheat decodes to. Each op keeps the index of the code line its code starts on, since a code
+/// line can decode to many ops or none, and conditionals skip by lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    ops: Vec<CheatOp>,
+    lines: Vec<usize>,
+}
+
+impl Program {
+    pub fn push(&mut self, line: usize, op: CheatOp) {
+        self.ops.push(op);
+        self.lines.push(line);
+    }
+
+    pub fn ops(&self) -> &[CheatOp] {
+        &self.ops
+    }
+}
+
+/// The cheats entered for a game, applied to memory once per frame.
+pub struct CheatEngine {
+    cheats: Vec<Cheat>,
+    programs: Vec<Program>,
+}
+
+impl CheatEngine {
+    pub fn new() -> Self {
+        CheatEngine {
+            cheats: Vec::new(),
+            programs: Vec::new(),
+        }
+    }
+
+    pub fn cheats(&self) -> &[Cheat] {
+        &self.cheats
+    }
+
+    pub fn add(&mut self, cheat: Cheat) -> Result<usize, CheatError> {
+        let program = parse(cheat.format, &cheat.code)?;
+        self.cheats.push(cheat);
+        self.programs.push(program);
+        Ok(self.cheats.len() - 1)
+    }
+
+    pub fn remove(&mut self, index: usize) -> Result<Cheat, CheatError> {
+        if index >= self.cheats.len() {
+            return Err(CheatError::UnknownCheat(index));
+        }
+        self.programs.remove(index);
+        Ok(self.cheats.remove(index))
+    }
+
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) -> Result<(), CheatError> {
+        let cheat = self.cheats.get_mut(index).ok_or(CheatError::UnknownCheat(index))?;
+        cheat.enabled = enabled;
+        Ok(())
+    }
+
+    /// Rom halfwords replaced by the enabled cheats, keyed by rom offset.
+    pub fn rom_patches(&self) -> HashMap<u32, u16> {
+        self.cheats
+            .iter()
+            .zip(&self.programs)
+            .filter(|(cheat, _)| cheat.enabled)
+            .flat_map(|(_, program)| program.ops())
+            .filter_map(|op| match *op {
+                CheatOp::RomPatch { offset, value } => Some((offset, value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Runs every enabled cheat once. Returns true when a cheat turned itself off, which changes the rom
+    /// patches.
+    pub fn apply(&mut self, memory: &mut impl SystemMemoryAccess<Address = u32>, pressed_keys: u16) -> bool {
+        let mut disabled = false;
+        for (cheat, program) in self.cheats.iter_mut().zip(&self.programs) {
+            if cheat.enabled && !run(program, memory, pressed_keys) {
+                cheat.enabled = false;
+                disabled = true;
+            }
+        }
+        disabled
+    }
+}
+
+/// The bus as cheats see it. Reads are peeks, so a condition on KEYINPUT is not the game polling input,
+/// and writes reach the bus at their own width like the cpu's.
+pub struct CheatBus<'a>(pub &'a mut SystemBus);
+
+impl SystemMemoryAccess for CheatBus<'_> {
+    type Address = u32;
+
+    fn read_8(&self, address: u32) -> u8 {
+        self.0.peek_8(address)
+    }
+
+    fn write_8(&mut self, address: u32, value: u8) {
+        self.0.write_8(address, value);
+    }
+
+    fn write_16(&mut self, address: u32, value: u16) {
+        self.0.write_16(address, value);
+    }
+
+    fn write_32(&mut self, address: u32, value: u32) {
+        self.0.write_32(address, value);
+    }
+}
+
+fn parse(format: CheatFormat, code: &str) -> Result<Program, CheatError> {
+    let lines: Vec<(usize, String)> = code_lines(code).collect();
+    match format {
+        CheatFormat::GameShark => gameshark::parse(&lines),
+        CheatFormat::ActionReplayV3 => action_replay::parse(&lines),
+        CheatFormat::CodeBreaker => codebreaker::parse(&lines),
+        CheatFormat::GameGenie => Err(CheatError::UnsupportedFormat(format)),
+    }
+}
+
+/// Decrypts a GameShark or Action Replay code line, both of which use TEA with device specific seeds.
+fn decrypt(mut address: u32, mut value: u32, seeds: &[u32; 4]) -> (u32, u32) {
+    let mut sum = TEA_DELTA.wrapping_mul(TEA_ROUNDS);
+    for _ in 0..TEA_ROUNDS {
+        value = value.wrapping_sub(
+            (address << 4).wrapping_add(seeds[2]) ^ address.wrapping_add(sum) ^ (address >> 5).wrapping_add(seeds[3]),
+        );
+        address = address.wrapping_sub(
+            (value << 4).wrapping_add(seeds[0]) ^ value.wrapping_add(sum) ^ (value >> 5).wrapping_add(seeds[1]),
+        );
+        sum = sum.wrapping_sub(TEA_DELTA);
+    }
+    (address, value)
+}
+
+fn width_mask(width: MemoryAccessWidth) -> u32 {
+    match width {
+        MemoryAccessWidth::Byte => 0xFF,
+        MemoryAccessWidth::HalfWord => 0xFFFF,
+        MemoryAccessWidth::Word => 0xFFFFFFFF,
+    }
+}
+
+fn read(memory: &impl SystemMemoryAccess<Address = u32>, address: u32, width: MemoryAccessWidth) -> u32 {
+    match width {
+        MemoryAccessWidth::Byte => memory.read_8(address) as u32,
+        MemoryAccessWidth::HalfWord => memory.read_16(address) as u32,
+        MemoryAccessWidth::Word => memory.read_32(address),
+    }
+}
+
+fn write(memory: &mut impl SystemMemoryAccess<Address = u32>, address: u32, width: MemoryAccessWidth, value: u32) {
+    match width {
+        MemoryAccessWidth::Byte => memory.write_8(address, value as u8),
+        MemoryAccessWidth::HalfWord => memory.write_16(address, value as u16),
+        MemoryAccessWidth::Word => memory.write_32(address, value),
+    }
+}
+
+fn sign_extend(value: u32, width: MemoryAccessWidth) -> i32 {
+    match width {
+        MemoryAccessWidth::Byte => value as u8 as i8 as i32,
+        MemoryAccessWidth::HalfWord => value as u16 as i16 as i32,
+        MemoryAccessWidth::Word => value as i32,
+    }
+}
+
+fn compare(current: u32, comparison: Comparison, value: u32, width: MemoryAccessWidth) -> bool {
+    match comparison {
+        Comparison::Equal => current == value,
+        Comparison::NotEqual => current != value,
+        Comparison::Less => sign_extend(current, width) < sign_extend(value, width),
+        Comparison::Greater => sign_extend(current, width) > sign_extend(value, width),
+        Comparison::LessUnsigned => current < value,
+        Comparison::GreaterUnsigned => current > value,
+        Comparison::And => current & value != 0,
+    }
+}
+
+/// Index of the `Else` or `EndIf` closing the block opened at `start`, or the end of the program.
+fn block_end(program: &[CheatOp], start: usize, stop_at_else: bool) -> usize {
+    let mut depth = 0;
+    for (index, op) in program.iter().enumerate().skip(start + 1) {
+        match op {
+            CheatOp::If { skip: Skip::Block, .. } | CheatOp::IfKeys { skip: Skip::Block, .. } => depth += 1,
+            CheatOp::Else if depth == 0 && stop_at_else => return index,
+            CheatOp::EndIf if depth == 0 => return index,
+            CheatOp::EndIf => depth -= 1,
+            _ => {}
+        }
+    }
+    program.len()
+}
+
+/// Runs a program once, returning false when it asked for the cheat to be disabled.
+fn run(program: &Program, memory: &mut impl SystemMemoryAccess<Address = u32>, pressed_keys: u16) -> bool {
+    let ops = program.ops();
+    let mut index = 0;
+    while index < ops.len() {
+        let (holds, skip) = match ops[index] {
+            CheatOp::Write { address, width, value } => {
+                write(memory, address, width, value);
+                (true, Skip::Codes(0))
+            }
+            CheatOp::Add { address, width, value } => {
+                let current = read(memory, address, width);
+                write(memory, address, width, current.wrapping_add(value) & width_mask(width));
+                (true, Skip::Codes(0))
+            }
+            CheatOp::Or { address, width, value } => {
+                write(memory, address, width, read(memory, address, width) | value);
+                (true, Skip::Codes(0))
+            }
+            CheatOp::And { address, width, value } => {
+                write(memory, address, width, read(memory, address, width) & value);
+                (true, Skip::Codes(0))
+            }
+            CheatOp::WriteIndirect {
+                pointer,
+                offset,
+                width,
+                value,
+            } => {
+                let target = memory.read_32(pointer).wrapping_add(offset);
+                write(memory, target, width, value);
+                (true, Skip::Codes(0))
+            }
+            CheatOp::Slide {
+                address,
+                width,
+                value,
+                count,
+                address_step,
+                value_step,
+            } => {
+                for step in 0..count {
+                    let value = value.wrapping_add(value_step.wrapping_mul(step)) & width_mask(width);
+                    write(memory, address.wrapping_add(address_step.wrapping_mul(step)), width, value);
+                }
+                (true, Skip::Codes(0))
+            }
+            CheatOp::If {
+                address,
+                width,
+                comparison,
+                value,
+                skip,
+            } => (compare(read(memory, address, width), comparison, value, width), skip),
+            CheatOp::IfKeys { keys, skip } => (pressed_keys & keys == keys, skip),
+            CheatOp::Else => {
+                index = block_end(ops, index, false);
+                (true, Skip::Codes(0))
+            }
+            CheatOp::EndIf | CheatOp::RomPatch { .. } | CheatOp::Hook { .. } => (true, Skip::Codes(0)),
+        };
+
+        index = match (holds, skip) {
+            (true, _) => index + 1,
+            (false, Skip::Codes(count)) => {
+                let last_skipped = program.lines[index] + count;
+                program.lines.partition_point(|&line| line <= last_skipped)
+            }
+            (false, Skip::Block) => block_end(ops, index, true) + 1,
+            (false, Skip::Disable) => return false,
+        };
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sparse byte addressed memory standing in for the bus.
+    #[derive(Default)]
+    pub struct TestMemory {
+        bytes: HashMap<u32, u8>,
+    }
+
+    impl SystemMemoryAccess for TestMemory {
+        type Address = u32;
+
+        fn read_8(&self, address: u32) -> u8 {
+            self.bytes.get(&address).copied().unwrap_or(0)
+        }
+
+        fn write_8(&mut self, address: u32, value: u8) {
+            self.bytes.insert(address, value);
+        }
+    }
+
+    /// Inverse of [`decrypt`], for building encrypted test codes.
+    pub fn encrypt(mut address: u32, mut value: u32, seeds: &[u32; 4]) -> (u32, u32) {
+        let mut sum = 0u32;
+        for _ in 0..TEA_ROUNDS {
+            sum = sum.wrapping_add(TEA_DELTA);
+            address = address.wrapping_add(
+                (value << 4).wrapping_add(seeds[0]) ^ value.wrapping_add(sum) ^ (value >> 5).wrapping_add(seeds[1]),
+            );
+            value = value.wrapping_add(
+                (address << 4).wrapping_add(seeds[2]) ^ address.wrapping_add(sum) ^ (address >> 5).wrapping_add(seeds[3]),
+            );
+        }
+        (address, value)
+    }
+
+    pub fn encrypted_code(lines: &[(u32, u32)], seeds: &[u32; 4]) -> String {
+        lines
+            .iter()
+            .map(|&(address, value)| {
+                let (address, value) = encrypt(address, value, seeds);
+                format!("{address:08X} {value:08X}\n")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decrypt_reverses_encrypt() {
+        let seeds = [1, 2, 3, 4];
+        let (address, value) = encrypt(0x12345678, 0x9ABCDEF0, &seeds);
+        assert_ne!((address, value), (0x12345678, 0x9ABCDEF0));
+        assert_eq!(decrypt(address, value, &seeds), (0x12345678, 0x9ABCDEF0));
+    }
+
+    #[test]
+    fn conditional_blocks_skip_to_else_and_end_if() {
+        let ops = [
+            CheatOp::If {
+                address: 0x02000000,
+                width: MemoryAccessWidth::Byte,
+                comparison: Comparison::Equal,
+                value: 1,
+                skip: Skip::Block,
+            },
+            CheatOp::Write {
+                address: 0x02000010,
+                width: MemoryAccessWidth::Byte,
+                value: 0xAA,
+            },
+            CheatOp::Else,
+            CheatOp::Write {
+                address: 0x02000010,
+                width: MemoryAccessWidth::Byte,
+                value: 0xBB,
+            },
+            CheatOp::EndIf,
+            CheatOp::Write {
+                address: 0x02000020,
+                width: MemoryAccessWidth::HalfWord,
+                value: 0x1234,
+            },
+        ];
+        let mut program = Program::default();
+        for (line, op) in ops.into_iter().enumerate() {
+            program.push(line, op);
+        }
+
+        let mut memory = TestMemory::default();
+        assert!(run(&program, &mut memory, 0));
+        assert_eq!(memory.read_8(0x02000010), 0xBB);
+        assert_eq!(memory.read_16(0x02000020), 0x1234);
+
+        memory.write_8(0x02000000, 1);
+        run(&program, &mut memory, 0);
+        assert_eq!(memory.read_8(0x02000010), 0xAA);
+    }
+
+    #[test]
+    fn engine_tracks_enabled_cheats_and_rom_patches() {
+        let mut engine = CheatEngine::new();
+        let index = engine
+            .add(Cheat::new("Fill", CheatFormat::CodeBreaker, "82000000 0063\n"))
+            .unwrap();
+
+        let mut memory = TestMemory::default();
+        engine.apply(&mut memory, 0);
+        assert_eq!(memory.read_16(0x02000000), 0x0063);
+
+        engine.set_enabled(index, false).unwrap();
+        memory.write_16(0x02000000, 0);
+        engine.apply(&mut memory, 0);
+        assert_eq!(memory.read_16(0x02000000), 0);
+
+        assert_eq!(engine.remove(index).unwrap().name, "Fill");
+        assert_eq!(engine.remove(index), Err(CheatError::UnknownCheat(index)));
+        assert!(engine.rom_patches().is_empty());
+    }
+}
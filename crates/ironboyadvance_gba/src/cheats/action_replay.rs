@@ -0,0 +1,242 @@
+use ironboyadvance_common::{
+    cheats::{CheatError, parse_code_line},
+    memory::MemoryAccessWidth,
+};
+
+use super::{CheatOp, Comparison, GAME_ID_CODE, Program, RESEED_CODE, Skip, decrypt, width_mask};
+
+pub const ACTION_REPLAY_SEEDS: [u32; 4] = [0x7AA9648F, 0x7FAE6994, 0xC0EFAAD5, 0x42712C57];
+
+const CONDITION_MASK: u32 = 0x38000000;
+const WIDTH_SHIFT: u32 = 25;
+const ACTION_MASK: u32 = 0xC0000000;
+const HOOK_MASK: u32 = 0xFF000000;
+const HOOK: u32 = 0xC4000000;
+
+/// Action Replay v3 packs the region nibble into bits 20-23 of the code address.
+fn code_address(address: u32) -> u32 {
+    ((address & 0x00F00000) << 4) | (address & 0x000FFFFF)
+}
+
+fn code_width(line: usize, address: u32) -> Result<MemoryAccessWidth, CheatError> {
+    match (address >> WIDTH_SHIFT) & 0x3 {
+        0 => Ok(MemoryAccessWidth::Byte),
+        1 => Ok(MemoryAccessWidth::HalfWord),
+        2 => Ok(MemoryAccessWidth::Word),
+        _ => Err(CheatError::UnsupportedCode {
+            line,
+            reason: "Action Replay always false conditions",
+        }),
+    }
+}
+
+/// Parses Action Replay v3 codes, `AAAAAAAA VVVVVVVV` encrypted with the v3 seeds.
+pub fn parse(lines: &[(usize, String)]) -> Result<Program, CheatError> {
+    let mut program = Program::default();
+    // A rom patch takes its value from the first word of the following line.
+    let mut patch_offset: Option<(u32, usize)> = None;
+
+    for (index, (line, digits)) in lines.iter().enumerate() {
+        let line = *line;
+        let (address, value) = parse_code_line(line, digits, 8, 16)?;
+        let (address, value) = decrypt(address, value, &ACTION_REPLAY_SEEDS);
+
+        if let Some((offset, start)) = patch_offset.take() {
+            let op = CheatOp::RomPatch {
+                offset,
+                value: address as u16,
+            };
+            program.push(start, op);
+            continue;
+        }
+
+        if address == RESEED_CODE {
+            return Err(CheatError::UnsupportedCode {
+                line,
+                reason: "Action Replay reseed codes",
+            });
+        }
+        if address == GAME_ID_CODE {
+            continue;
+        }
+
+        let op = match (address, address & CONDITION_MASK) {
+            (0, _) => match value >> 24 {
+                0x00 => continue,
+                0x18 | 0x1A | 0x1C | 0x1E => {
+                    patch_offset = Some(((value & 0x00FFFFFF) << 1, index));
+                    continue;
+                }
+                0x40 => CheatOp::EndIf,
+                0x60 => CheatOp::Else,
+                _ => {
+                    return Err(CheatError::UnsupportedCode {
+                        line,
+                        reason: "Action Replay special code",
+                    });
+                }
+            },
+            (address, 0) if address & HOOK_MASK == HOOK => CheatOp::Hook {
+                address: code_address(address),
+            },
+            (address, 0) => {
+                let width = code_width(line, address)?;
+                let target = code_address(address);
+                match address & ACTION_MASK {
+                    0x00000000 => match width {
+                        MemoryAccessWidth::Byte => CheatOp::Slide {
+                            address: target,
+                            width,
+                            value: value & 0xFF,
+                            count: (value >> 8) + 1,
+                            address_step: 1,
+                            value_step: 0,
+                        },
+                        MemoryAccessWidth::HalfWord => CheatOp::Slide {
+                            address: target,
+                            width,
+                            value: value & 0xFFFF,
+                            count: (value >> 16) + 1,
+                            address_step: 2,
+                            value_step: 0,
+                        },
+                        MemoryAccessWidth::Word => CheatOp::Write {
+                            address: target,
+                            width,
+                            value,
+                        },
+                    },
+                    0x40000000 => {
+                        let (offset, value) = match width {
+                            MemoryAccessWidth::Byte => (value >> 8, value & 0xFF),
+                            MemoryAccessWidth::HalfWord => ((value >> 16) << 1, value & 0xFFFF),
+                            MemoryAccessWidth::Word => (0, value),
+                        };
+                        CheatOp::WriteIndirect {
+                            pointer: target,
+                            offset,
+                            width,
+                            value,
+                        }
+                    }
+                    0x80000000 => CheatOp::Add {
+                        address: target,
+                        width,
+                        value,
+                    },
+                    _ => {
+                        return Err(CheatError::UnsupportedCode {
+                            line,
+                            reason: "Action Replay special write",
+                        });
+                    }
+                }
+            }
+            (address, condition) => {
+                let width = code_width(line, address)?;
+                let comparison = match condition {
+                    0x08000000 => Comparison::Equal,
+                    0x10000000 => Comparison::NotEqual,
+                    0x18000000 => Comparison::Less,
+                    0x20000000 => Comparison::Greater,
+                    0x28000000 => Comparison::LessUnsigned,
+                    0x30000000 => Comparison::GreaterUnsigned,
+                    _ => Comparison::And,
+                };
+                let skip = match address & ACTION_MASK {
+                    0x00000000 => Skip::Codes(1),
+                    0x40000000 => Skip::Codes(2),
+                    0x80000000 => Skip::Block,
+                    _ => Skip::Disable,
+                };
+                CheatOp::If {
+                    address: code_address(address),
+                    width,
+                    comparison,
+                    value: value & width_mask(width),
+                    skip,
+                }
+            }
+        };
+        program.push(index, op);
+    }
+
+    match patch_offset {
+        Some(_) => Err(CheatError::InvalidCode {
+            line: lines.last().map_or(0, |(line, _)| *line),
+            reason: "rom patch is missing its value",
+        }),
+        None => Ok(program),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cheats::{
+        run,
+        tests::{TestMemory, encrypted_code},
+    };
+    use ironboyadvance_common::memory::SystemMemoryAccess;
+
+    fn parse_code(lines: &[(u32, u32)]) -> Result<Program, CheatError> {
+        let code = encrypted_code(lines, &ACTION_REPLAY_SEEDS);
+        let lines: Vec<_> = ironboyadvance_common::cheats::code_lines(&code).collect();
+        parse(&lines)
+    }
+
+    #[test]
+    fn decodes_addresses_and_fills() {
+        let program = parse_code(&[(0x00200010, 0x000003FF), (0x02300020, 0x00001234), (0x04200030, 0x0BADF00D)]).unwrap();
+        let mut memory = TestMemory::default();
+        run(&program, &mut memory, 0);
+
+        assert_eq!(
+            (0x02000010..0x02000014)
+                .map(|address| memory.read_8(address))
+                .collect::<Vec<_>>(),
+            vec![0xFF; 4]
+        );
+        assert_eq!(memory.read_8(0x02000014), 0);
+        assert_eq!(memory.read_16(0x03000020), 0x1234);
+        assert_eq!(memory.read_32(0x02000030), 0x0BADF00D);
+    }
+
+    #[test]
+    fn block_conditions_and_rom_patches() {
+        let program = parse_code(&[
+            (0x8A200000, 0x00000007),
+            (0x00200004, 0x00000001),
+            (0x00000000, 0x60000000),
+            (0x00200004, 0x00000002),
+            (0x00000000, 0x40000000),
+            (0x00000000, 0x18000040),
+            (0x00004770, 0x00000000),
+        ])
+        .unwrap();
+        assert_eq!(
+            program.ops()[0],
+            CheatOp::If {
+                address: 0x02000000,
+                width: MemoryAccessWidth::HalfWord,
+                comparison: Comparison::Equal,
+                value: 7,
+                skip: Skip::Block,
+            }
+        );
+        assert_eq!(
+            program.ops().last(),
+            Some(&CheatOp::RomPatch {
+                offset: 0x80,
+                value: 0x4770,
+            })
+        );
+
+        let mut memory = TestMemory::default();
+        run(&program, &mut memory, 0);
+        assert_eq!(memory.read_8(0x02000004), 2);
+        memory.write_16(0x02000000, 7);
+        run(&program, &mut memory, 0);
+        assert_eq!(memory.read_8(0x02000004), 1);
+    }
+}
@@ -0,0 +1,160 @@
+use ironboyadvance_common::{
+    cheats::{CheatError, parse_code_line},
+    memory::MemoryAccessWidth,
+};
+
+use super::{CheatOp, Comparison, Program, Skip};
+
+const ADDRESS_MASK: u32 = 0x0FFFFFFF;
+
+/// Parses unencrypted CodeBreaker codes, `TAAAAAAA VVVV` where `T` is the code type.
+pub fn parse(lines: &[(usize, String)]) -> Result<Program, CheatError> {
+    let mut program = Program::default();
+    // A slide code takes its count and steps from the following line.
+    let mut slide: Option<(u32, u32, usize)> = None;
+
+    for (index, (line, digits)) in lines.iter().enumerate() {
+        let line = *line;
+        let (address, value) = parse_code_line(line, digits, 8, 12)?;
+
+        if let Some((start, first_value, start_line)) = slide.take() {
+            let op = CheatOp::Slide {
+                address: start,
+                width: MemoryAccessWidth::HalfWord,
+                value: first_value,
+                count: address & 0xFFFF,
+                address_step: value,
+                value_step: address >> 16,
+            };
+            program.push(start_line, op);
+            continue;
+        }
+
+        let target = address & ADDRESS_MASK;
+        let condition = |comparison| CheatOp::If {
+            address: target,
+            width: MemoryAccessWidth::HalfWord,
+            comparison,
+            value,
+            skip: Skip::Codes(1),
+        };
+
+        let op = match address >> 28 {
+            0x0 => CheatOp::Hook { address: target },
+            0x1 => continue,
+            0x2 => CheatOp::Or {
+                address: target,
+                width: MemoryAccessWidth::HalfWord,
+                value,
+            },
+            0x3 => CheatOp::Write {
+                address: target,
+                width: MemoryAccessWidth::Byte,
+                value: value & 0xFF,
+            },
+            0x4 => {
+                slide = Some((target, value, index));
+                continue;
+            }
+            0x6 => CheatOp::And {
+                address: target,
+                width: MemoryAccessWidth::HalfWord,
+                value,
+            },
+            0x7 => condition(Comparison::Equal),
+            0x8 => CheatOp::Write {
+                address: target,
+                width: MemoryAccessWidth::HalfWord,
+                value,
+            },
+            0x9 => {
+                return Err(CheatError::UnsupportedCode {
+                    line,
+                    reason: "encrypted CodeBreaker codes",
+                });
+            }
+            0xA => condition(Comparison::NotEqual),
+            0xB => condition(Comparison::GreaterUnsigned),
+            0xC => condition(Comparison::LessUnsigned),
+            0xD => CheatOp::IfKeys {
+                keys: value as u16,
+                skip: Skip::Codes(1),
+            },
+            0xE => CheatOp::Add {
+                address: target,
+                width: MemoryAccessWidth::HalfWord,
+                value,
+            },
+            0xF => condition(Comparison::And),
+            _ => {
+                return Err(CheatError::UnsupportedCode {
+                    line,
+                    reason: "CodeBreaker super codes",
+                });
+            }
+        };
+        program.push(index, op);
+    }
+
+    match slide {
+        Some(_) => Err(CheatError::InvalidCode {
+            line: lines.last().map_or(0, |(line, _)| *line),
+            reason: "slide code is missing its count",
+        }),
+        None => Ok(program),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cheats::{run, tests::TestMemory};
+    use ironboyadvance_common::{cheats::code_lines, memory::SystemMemoryAccess};
+
+    fn parse_code(code: &str) -> Result<Program, CheatError> {
+        let lines: Vec<_> = code_lines(code).collect();
+        parse(&lines)
+    }
+
+    #[test]
+    fn button_conditions_and_arithmetic() {
+        let program = parse_code(
+            "
+            0000101C 000A
+            D0000020 0009
+            32000000 0063
+            E2000002 0010
+            62000004 00F0
+            22000004 0001
+            ",
+        )
+        .unwrap();
+
+        let mut memory = TestMemory::default();
+        memory.write_16(0x02000002, 0x0005);
+        memory.write_16(0x02000004, 0x0F3C);
+        run(&program, &mut memory, 0);
+        assert_eq!(memory.read_8(0x02000000), 0, "A + Start not held");
+        assert_eq!(memory.read_16(0x02000002), 0x0015);
+        assert_eq!(memory.read_16(0x02000004), 0x0031);
+
+        run(&program, &mut memory, 0b1001);
+        assert_eq!(memory.read_8(0x02000000), 0x63);
+    }
+
+    #[test]
+    fn slide_codes_use_the_next_line() {
+        let program = parse_code("42000000 0100\n00010003 0004").unwrap();
+        let mut memory = TestMemory::default();
+        run(&program, &mut memory, 0);
+        assert_eq!(memory.read_16(0x02000000), 0x0100);
+        assert_eq!(memory.read_16(0x02000004), 0x0101);
+        assert_eq!(memory.read_16(0x02000008), 0x0102);
+
+        assert!(parse_code("42000000 0100").is_err());
+        assert!(matches!(
+            parse_code("9123ABCD 0001"),
+            Err(CheatError::UnsupportedCode { line: 1, .. })
+        ));
+    }
+}
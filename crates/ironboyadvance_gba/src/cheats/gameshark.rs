@@ -0,0 +1,217 @@
+use ironboyadvance_common::{
+    cheats::{CheatError, parse_code_line},
+    memory::MemoryAccessWidth,
+};
+
+use super::{CheatOp, Comparison, GAME_ID_CODE, Program, RESEED_CODE, Skip, decrypt};
+
+pub const GAMESHARK_SEEDS: [u32; 4] = [0x09F4FBBD, 0x9681884A, 0x352027E9, 0xF3DEE5A7];
+
+const ADDRESS_MASK: u32 = 0x0FFFFFFF;
+
+/// Parses GameShark and Action Replay v1/v2 codes, `AAAAAAAA VVVVVVVV` encrypted with the default seeds.
+pub fn parse(lines: &[(usize, String)]) -> Result<Program, CheatError> {
+    let mut program = Program::default();
+    // A list code writes its value to the addresses on the lines that follow it, two per line.
+    let mut list: Option<(u32, u32, usize)> = None;
+
+    for (index, (line, digits)) in lines.iter().enumerate() {
+        let line = *line;
+        let (address, value) = parse_code_line(line, digits, 8, 16)?;
+        let (address, value) = decrypt(address, value, &GAMESHARK_SEEDS);
+
+        if let Some((list_value, remaining, start)) = list.take() {
+            for target in [address, value].into_iter().take(remaining as usize) {
+                let op = CheatOp::Write {
+                    address: target & ADDRESS_MASK,
+                    width: MemoryAccessWidth::Word,
+                    value: list_value,
+                };
+                program.push(start, op);
+            }
+            list = (remaining > 2).then(|| (list_value, remaining - 2, start));
+            continue;
+        }
+
+        if address == RESEED_CODE {
+            return Err(CheatError::UnsupportedCode {
+                line,
+                reason: "GameShark reseed codes",
+            });
+        }
+        if address == GAME_ID_CODE {
+            continue;
+        }
+
+        let op = match address >> 28 {
+            0x0 => CheatOp::Write {
+                address: address & ADDRESS_MASK,
+                width: MemoryAccessWidth::Byte,
+                value: value & 0xFF,
+            },
+            0x1 => CheatOp::Write {
+                address: address & ADDRESS_MASK,
+                width: MemoryAccessWidth::HalfWord,
+                value: value & 0xFFFF,
+            },
+            0x2 => CheatOp::Write {
+                address: address & ADDRESS_MASK,
+                width: MemoryAccessWidth::Word,
+                value,
+            },
+            0x3 => {
+                let count = address & 0xFFFF;
+                if count > 0 {
+                    list = Some((value, count, index));
+                }
+                continue;
+            }
+            0x6 => CheatOp::RomPatch {
+                offset: (address & 0x00FFFFFF) << 1,
+                value: value as u16,
+            },
+            0x8 => {
+                return Err(CheatError::UnsupportedCode {
+                    line,
+                    reason: "GameShark button codes",
+                });
+            }
+            0xD => CheatOp::If {
+                address: address & ADDRESS_MASK,
+                width: MemoryAccessWidth::HalfWord,
+                comparison: Comparison::Equal,
+                value: value & 0xFFFF,
+                skip: Skip::Codes(1),
+            },
+            0xE => CheatOp::If {
+                address: value & ADDRESS_MASK,
+                width: MemoryAccessWidth::HalfWord,
+                comparison: Comparison::Equal,
+                value: address & 0xFFFF,
+                skip: Skip::Codes(((address >> 16) & 0xFF) as usize),
+            },
+            0xF => CheatOp::Hook {
+                address: address & ADDRESS_MASK,
+            },
+            _ => {
+                return Err(CheatError::InvalidCode {
+                    line,
+                    reason: "unknown GameShark code type",
+                });
+            }
+        };
+        program.push(index, op);
+    }
+
+    match list {
+        Some(_) => Err(CheatError::InvalidCode {
+            line: lines.last().map_or(0, |(line, _)| *line),
+            reason: "list code is missing addresses",
+        }),
+        None => Ok(program),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cheats::{
+        run,
+        tests::{TestMemory, encrypted_code},
+    };
+    use ironboyadvance_common::memory::SystemMemoryAccess;
+
+    fn parse_code(lines: &[(u32, u32)]) -> Result<Program, CheatError> {
+        let code = encrypted_code(lines, &GAMESHARK_SEEDS);
+        let lines: Vec<_> = ironboyadvance_common::cheats::code_lines(&code).collect();
+        parse(&lines)
+    }
+
+    #[test]
+    fn decodes_writes_conditions_and_patches() {
+        let program = parse_code(&[
+            (GAME_ID_CODE, 0x00001234),
+            (0xF8000400, 0x00000001),
+            (0x02000010, 0x000000FF),
+            (0xD3000100, 0x00000005),
+            (0x23000104, 0xDEADBEEF),
+            (0x60000080, 0x00004770),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            program.ops(),
+            [
+                CheatOp::Hook { address: 0x08000400 },
+                CheatOp::Write {
+                    address: 0x02000010,
+                    width: MemoryAccessWidth::Byte,
+                    value: 0xFF,
+                },
+                CheatOp::If {
+                    address: 0x03000100,
+                    width: MemoryAccessWidth::HalfWord,
+                    comparison: Comparison::Equal,
+                    value: 5,
+                    skip: Skip::Codes(1),
+                },
+                CheatOp::Write {
+                    address: 0x03000104,
+                    width: MemoryAccessWidth::Word,
+                    value: 0xDEADBEEF,
+                },
+                CheatOp::RomPatch {
+                    offset: 0x100,
+                    value: 0x4770,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn list_codes_write_every_listed_address() {
+        let program = parse_code(&[(0x30000003, 0x11223344), (0x02000000, 0x02000010), (0x02000020, 0)]).unwrap();
+        let addresses: Vec<u32> = program
+            .ops()
+            .iter()
+            .map(|op| match op {
+                CheatOp::Write { address, value, .. } => {
+                    assert_eq!(*value, 0x11223344);
+                    *address
+                }
+                _ => panic!("expected a write"),
+            })
+            .collect();
+        assert_eq!(addresses, vec![0x02000000, 0x02000010, 0x02000020]);
+
+        assert!(parse_code(&[(0x30000003, 0x11223344)]).is_err());
+        assert!(matches!(
+            parse_code(&[(RESEED_CODE, 0x1234)]),
+            Err(CheatError::UnsupportedCode { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn conditionals_skip_code_lines_rather_than_ops() {
+        // If the halfword at 0x03000100 is 5, run the next 3 lines: a list code writing 4 addresses.
+        let program = parse_code(&[
+            (0xE0030005, 0x03000100),
+            (0x30000004, 0x11223344),
+            (0x02000000, 0x02000004),
+            (0x02000008, 0x0200000C),
+            (0x02000010, 0x000000AA),
+        ])
+        .unwrap();
+        assert_eq!(program.ops().len(), 6);
+
+        let mut memory = TestMemory::default();
+        run(&program, &mut memory, 0);
+        assert_eq!(memory.read_32(0x0200000C), 0, "the whole list is skipped");
+        assert_eq!(memory.read_8(0x02000010), 0xAA);
+
+        memory.write_16(0x03000100, 5);
+        run(&program, &mut memory, 0);
+        assert_eq!(memory.read_32(0x02000000), 0x11223344);
+        assert_eq!(memory.read_32(0x0200000C), 0x11223344);
+    }
+}
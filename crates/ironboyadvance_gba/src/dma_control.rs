@@ -7,6 +7,7 @@ use ironboyadvance_common::scheduler::Scheduler;
 
 use crate::events::{DmaEvent, GbaEvent, InterruptEvent};
 use crate::system_bus::ROM_WS0_LO;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const DMA3_MAX_TRANSFER_COUNT: u32 = 0x10000;
 const DMA_MAX_TRANSFER_COUNT: u32 = 0x4000;
@@ -276,6 +277,10 @@ impl DmaController {
         self.runnable.iter().any(|&runnable| runnable)
     }
 
+    pub fn channel_enabled(&self, channel_id: usize) -> bool {
+        self.channels[channel_id].control.enabled()
+    }
+
     fn write_control(&mut self, channel_id: usize, address: u32, value: u8) {
         let started = self.channels[channel_id].write_control(address, value);
 
@@ -461,3 +466,36 @@ impl SystemMemoryAccess for DmaController {
         }
     }
 }
+
+impl Snapshot for DmaController {
+    fn save_state(&self, writer: &mut StateWriter) {
+        for channel in self.channels.iter() {
+            writer.write(&channel.source_address);
+            writer.write(&channel.current_source_address);
+            writer.write(&channel.destination_address);
+            writer.write(&channel.current_destination_address);
+            writer.write(&channel.count);
+            writer.write(&channel.current_count);
+            writer.write(&channel.control.into_bits());
+            writer.write(&channel.is_fifo);
+            writer.write(&channel.accessed_rom);
+        }
+        writer.write(&self.runnable);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        for channel in self.channels.iter_mut() {
+            channel.source_address = reader.read()?;
+            channel.current_source_address = reader.read()?;
+            channel.destination_address = reader.read()?;
+            channel.current_destination_address = reader.read()?;
+            channel.count = reader.read()?;
+            channel.current_count = reader.read()?;
+            channel.control = DmaControl::from_bits(reader.read()?);
+            channel.is_fifo = reader.read()?;
+            channel.accessed_rom = reader.read()?;
+        }
+        self.runnable = reader.read()?;
+        Ok(())
+    }
+}
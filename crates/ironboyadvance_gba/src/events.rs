@@ -1,4 +1,7 @@
-use ironboyadvance_common::scheduler::SystemEvent;
+use ironboyadvance_common::{
+    scheduler::SystemEvent,
+    snapshot::{SnapshotError, StateReader, StateValue, StateWriter},
+};
 
 use crate::dma_control::RequestType;
 
@@ -55,6 +58,12 @@ pub enum CartridgeEvent {
     EepromReady,
 }
 
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum SerialEvent {
+    TransferComplete,
+    Poll,
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum GbaEvent {
     Interrupt(InterruptEvent),
@@ -63,12 +72,13 @@ pub enum GbaEvent {
     Timer(TimerEvent),
     Dma(DmaEvent),
     Cartridge(CartridgeEvent),
+    Serial(SerialEvent),
 }
 
 impl SystemEvent for GbaEvent {
     fn priority(&self) -> u8 {
         match self {
-            GbaEvent::Interrupt(_) | GbaEvent::Ppu(_) | GbaEvent::Apu(_) | GbaEvent::Cartridge(_) => 0,
+            GbaEvent::Interrupt(_) | GbaEvent::Ppu(_) | GbaEvent::Apu(_) | GbaEvent::Cartridge(_) | GbaEvent::Serial(_) => 0,
             GbaEvent::Dma(dma_event) => match dma_event {
                 DmaEvent::Activate { .. } => 0,
                 DmaEvent::Request(_) => 1,
@@ -84,3 +94,157 @@ impl SystemEvent for GbaEvent {
 }
 
 pub type FutureGbaEvent = (GbaEvent, usize);
+
+const INTERRUPT_EVENTS: [InterruptEvent; 14] = [
+    InterruptEvent::LcdVBlank,
+    InterruptEvent::LcdHBlank,
+    InterruptEvent::LcdVCounterMatch,
+    InterruptEvent::Timer0Overflow,
+    InterruptEvent::Timer1Overflow,
+    InterruptEvent::Timer2Overflow,
+    InterruptEvent::Timer3Overflow,
+    InterruptEvent::SerialCommunication,
+    InterruptEvent::Dma0Overflow,
+    InterruptEvent::Dma1Overflow,
+    InterruptEvent::Dma2Overflow,
+    InterruptEvent::Dma3Overflow,
+    InterruptEvent::Keypad,
+    InterruptEvent::GamePak,
+];
+
+impl StateValue for GbaEvent {
+    fn write_state(&self, writer: &mut StateWriter) {
+        match *self {
+            GbaEvent::Interrupt(interrupt) => {
+                writer.write(&0u8);
+                writer.write(&(interrupt as u8));
+            }
+            GbaEvent::Ppu(ppu_event) => {
+                writer.write(&1u8);
+                writer.write(&(ppu_event as u8));
+            }
+            GbaEvent::Apu(apu_event) => {
+                writer.write(&2u8);
+                match apu_event {
+                    ApuEvent::Sample => writer.write(&0u8),
+                    ApuEvent::FrameSequence => writer.write(&1u8),
+                    ApuEvent::FifoStep { timer_id } => {
+                        writer.write(&2u8);
+                        writer.write(&timer_id);
+                    }
+                }
+            }
+            GbaEvent::Timer(timer_event) => {
+                writer.write(&3u8);
+                match timer_event {
+                    TimerEvent::Overflow { timer_id } => {
+                        writer.write(&0u8);
+                        writer.write(&timer_id);
+                    }
+                    TimerEvent::ControlWrite { timer_id, value } => {
+                        writer.write(&1u8);
+                        writer.write(&timer_id);
+                        writer.write(&value);
+                    }
+                    TimerEvent::ReloadWrite {
+                        timer_id,
+                        address,
+                        value,
+                    } => {
+                        writer.write(&2u8);
+                        writer.write(&timer_id);
+                        writer.write(&address);
+                        writer.write(&value);
+                    }
+                }
+            }
+            GbaEvent::Dma(dma_event) => {
+                writer.write(&4u8);
+                match dma_event {
+                    DmaEvent::Activate { channel_id } => {
+                        writer.write(&0u8);
+                        writer.write(&channel_id);
+                    }
+                    DmaEvent::Request(request_type) => {
+                        writer.write(&1u8);
+                        writer.write(&(request_type as u8));
+                    }
+                    DmaEvent::StopVideo => writer.write(&2u8),
+                }
+            }
+            GbaEvent::Cartridge(CartridgeEvent::EepromReady) => writer.write(&5u8),
+            GbaEvent::Serial(serial_event) => {
+                writer.write(&6u8);
+                writer.write(&(serial_event as u8));
+            }
+        }
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        let invalid = SnapshotError::InvalidValue("event");
+        let event = match reader.read::<u8>()? {
+            0 => GbaEvent::Interrupt(*INTERRUPT_EVENTS.get(reader.read::<u8>()? as usize).ok_or(invalid)?),
+            1 => GbaEvent::Ppu(match reader.read::<u8>()? {
+                0 => PpuEvent::HDraw,
+                1 => PpuEvent::HBlank,
+                2 => PpuEvent::VBlankHDraw,
+                3 => PpuEvent::VBlankHBlank,
+                _ => return Err(invalid),
+            }),
+            2 => GbaEvent::Apu(match reader.read::<u8>()? {
+                0 => ApuEvent::Sample,
+                1 => ApuEvent::FrameSequence,
+                2 => ApuEvent::FifoStep {
+                    timer_id: read_id(reader, 2)?,
+                },
+                _ => return Err(invalid),
+            }),
+            3 => GbaEvent::Timer(match reader.read::<u8>()? {
+                0 => TimerEvent::Overflow {
+                    timer_id: read_id(reader, 4)?,
+                },
+                1 => TimerEvent::ControlWrite {
+                    timer_id: read_id(reader, 4)?,
+                    value: reader.read()?,
+                },
+                2 => TimerEvent::ReloadWrite {
+                    timer_id: read_id(reader, 4)?,
+                    address: reader.read()?,
+                    value: reader.read()?,
+                },
+                _ => return Err(invalid),
+            }),
+            4 => GbaEvent::Dma(match reader.read::<u8>()? {
+                0 => DmaEvent::Activate {
+                    channel_id: read_id(reader, 4)?,
+                },
+                1 => DmaEvent::Request(match reader.read::<u8>()? {
+                    0 => RequestType::HBlank,
+                    1 => RequestType::VBlank,
+                    2 => RequestType::FifoA,
+                    3 => RequestType::FifoB,
+                    4 => RequestType::Video,
+                    _ => return Err(invalid),
+                }),
+                2 => DmaEvent::StopVideo,
+                _ => return Err(invalid),
+            }),
+            5 => GbaEvent::Cartridge(CartridgeEvent::EepromReady),
+            6 => GbaEvent::Serial(match reader.read::<u8>()? {
+                0 => SerialEvent::TransferComplete,
+                1 => SerialEvent::Poll,
+                _ => return Err(invalid),
+            }),
+            _ => return Err(invalid),
+        };
+        Ok(event)
+    }
+}
+
+fn read_id(reader: &mut StateReader, count: usize) -> Result<usize, SnapshotError> {
+    let id: usize = reader.read()?;
+    match id < count {
+        true => Ok(id),
+        false => Err(SnapshotError::InvalidValue("event")),
+    }
+}
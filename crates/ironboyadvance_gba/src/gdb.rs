@@ -0,0 +1,160 @@
+use ironboyadvance_arm7tdmi::CpuMode;
+use ironboyadvance_common::{emulator::SystemInspection, gdb::GdbTarget};
+
+use crate::GameBoyAdvance;
+
+const PC: usize = 15;
+const CPSR: usize = 16;
+const FIQ_REGISTERS: usize = 17;
+const BANKED_REGISTERS: usize = 24;
+const SPSRS: usize = 32;
+pub(crate) const REGISTER_COUNT: usize = 37;
+
+/// Register names in the order the target description numbers them.
+pub(crate) const REGISTER_NAMES: [&str; REGISTER_COUNT] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "sp", "lr", "pc", "cpsr", "r8_fiq",
+    "r9_fiq", "r10_fiq", "r11_fiq", "r12_fiq", "r13_fiq", "r14_fiq", "r13_svc", "r14_svc", "r13_abt", "r14_abt", "r13_irq",
+    "r14_irq", "r13_und", "r14_und", "spsr_fiq", "spsr_svc", "spsr_abt", "spsr_irq", "spsr_und",
+];
+
+/// r13 and r14 of each mode that banks only those two, in the order after the FIQ registers.
+const BANKED_MODES: [CpuMode; 4] = [CpuMode::Supervisor, CpuMode::Abort, CpuMode::Irq, CpuMode::Undefined];
+const SPSR_MODES: [CpuMode; 5] = [
+    CpuMode::Fiq,
+    CpuMode::Supervisor,
+    CpuMode::Abort,
+    CpuMode::Irq,
+    CpuMode::Undefined,
+];
+
+/// The core feature gdb requires for ARM, with the banked registers and SPSRs as an extra feature so
+/// `info registers` can show every mode.
+const TARGET_DESCRIPTION: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <architecture>armv4t</architecture>
+  <feature name="org.gnu.gdb.arm.core">
+    <reg name="r0" bitsize="32" type="uint32"/>
+    <reg name="r1" bitsize="32" type="uint32"/>
+    <reg name="r2" bitsize="32" type="uint32"/>
+    <reg name="r3" bitsize="32" type="uint32"/>
+    <reg name="r4" bitsize="32" type="uint32"/>
+    <reg name="r5" bitsize="32" type="uint32"/>
+    <reg name="r6" bitsize="32" type="uint32"/>
+    <reg name="r7" bitsize="32" type="uint32"/>
+    <reg name="r8" bitsize="32" type="uint32"/>
+    <reg name="r9" bitsize="32" type="uint32"/>
+    <reg name="r10" bitsize="32" type="uint32"/>
+    <reg name="r11" bitsize="32" type="uint32"/>
+    <reg name="r12" bitsize="32" type="uint32"/>
+    <reg name="sp" bitsize="32" type="data_ptr"/>
+    <reg name="lr" bitsize="32"/>
+    <reg name="pc" bitsize="32" type="code_ptr"/>
+    <reg name="cpsr" bitsize="32"/>
+  </feature>
+  <feature name="org.ironboyadvance.arm.banked">
+    <reg name="r8_fiq" bitsize="32" type="uint32" group="banked"/>
+    <reg name="r9_fiq" bitsize="32" type="uint32" group="banked"/>
+    <reg name="r10_fiq" bitsize="32" type="uint32" group="banked"/>
+    <reg name="r11_fiq" bitsize="32" type="uint32" group="banked"/>
+    <reg name="r12_fiq" bitsize="32" type="uint32" group="banked"/>
+    <reg name="r13_fiq" bitsize="32" type="data_ptr" group="banked"/>
+    <reg name="r14_fiq" bitsize="32" group="banked"/>
+    <reg name="r13_svc" bitsize="32" type="data_ptr" group="banked"/>
+    <reg name="r14_svc" bitsize="32" group="banked"/>
+    <reg name="r13_abt" bitsize="32" type="data_ptr" group="banked"/>
+    <reg name="r14_abt" bitsize="32" group="banked"/>
+    <reg name="r13_irq" bitsize="32" type="data_ptr" group="banked"/>
+    <reg name="r14_irq" bitsize="32" group="banked"/>
+    <reg name="r13_und" bitsize="32" type="data_ptr" group="banked"/>
+    <reg name="r14_und" bitsize="32" group="banked"/>
+    <reg name="spsr_fiq" bitsize="32" group="banked"/>
+    <reg name="spsr_svc" bitsize="32" group="banked"/>
+    <reg name="spsr_abt" bitsize="32" group="banked"/>
+    <reg name="spsr_irq" bitsize="32" group="banked"/>
+    <reg name="spsr_und" bitsize="32" group="banked"/>
+  </feature>
+</target>
+"#;
+
+/// Maps a banked register number to the mode owning it and its index within that mode.
+fn banked_register(index: usize) -> (CpuMode, usize) {
+    match index {
+        FIQ_REGISTERS..BANKED_REGISTERS => (CpuMode::Fiq, index - FIQ_REGISTERS + 8),
+        _ => {
+            let offset = index - BANKED_REGISTERS;
+            (BANKED_MODES[offset / 2], 13 + offset % 2)
+        }
+    }
+}
+
+impl GdbTarget for GameBoyAdvance {
+    fn target_description(&self) -> &'static str {
+        TARGET_DESCRIPTION
+    }
+
+    fn register_count(&self) -> usize {
+        REGISTER_COUNT
+    }
+
+    /// The pc reads as the instruction about to execute rather than r15, which runs two ahead of it.
+    fn read_register(&self, index: usize) -> u32 {
+        let cpu = &self.arm7tdmi;
+        match index {
+            0..PC => cpu.register_in_mode(cpu.mode(), index),
+            PC => cpu.instruction_address(),
+            CPSR => cpu.cpsr_bits(),
+            FIQ_REGISTERS..SPSRS => {
+                let (mode, index) = banked_register(index);
+                cpu.register_in_mode(mode, index)
+            }
+            _ => cpu.spsr_in_mode(SPSR_MODES[index - SPSRS]).unwrap_or_default(),
+        }
+    }
+
+    fn write_register(&mut self, index: usize, value: u32) {
+        let cpu = &mut self.arm7tdmi;
+        match index {
+            0..PC => cpu.set_register_in_mode(cpu.mode(), index, value),
+            PC => {
+                if value != cpu.instruction_address() {
+                    cpu.jump(value);
+                }
+            }
+            CPSR => cpu.set_cpsr_bits(value),
+            FIQ_REGISTERS..SPSRS => {
+                let (mode, index) = banked_register(index);
+                cpu.set_register_in_mode(mode, index, value);
+            }
+            _ => cpu.set_spsr_in_mode(SPSR_MODES[index - SPSRS], value),
+        }
+    }
+
+    fn write_memory(&mut self, address: u32, value: u8) {
+        self.poke_8(address, value);
+    }
+
+    fn jump(&mut self, address: u32) {
+        self.arm7tdmi.jump(address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banked_registers_follow_the_target_description() {
+        assert_eq!(banked_register(FIQ_REGISTERS), (CpuMode::Fiq, 8));
+        assert_eq!(banked_register(BANKED_REGISTERS - 1), (CpuMode::Fiq, 14));
+        assert_eq!(banked_register(BANKED_REGISTERS), (CpuMode::Supervisor, 13));
+        assert_eq!(banked_register(SPSRS - 1), (CpuMode::Undefined, 14));
+        assert_eq!(TARGET_DESCRIPTION.matches("<reg ").count(), REGISTER_COUNT);
+        for name in REGISTER_NAMES {
+            assert!(
+                TARGET_DESCRIPTION.contains(&format!("name=\"{name}\"")),
+                "{name} is not described"
+            );
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use ironboyadvance_common::{
+    call_stack::CallStack,
+    emulator::{MemoryRegion, Register, SystemInspection},
+    gdb::GdbTarget,
+};
+
+use crate::{
+    GameBoyAdvance,
+    gdb::REGISTER_NAMES,
+    system_bus::{
+        BIOS_BASE, IO_REGISTERS_BASE, OAM_BASE, PALETTE_RAM_BASE, ROM_WS0_LO, ROM_WS1_LO, ROM_WS2_LO, SRAM_LO, VRAM_BASE,
+        WRAM_BOARD_BASE, WRAM_CHIP_BASE,
+    },
+};
+
+/// The SPSR of whichever mode the cpu is in, next to the ones named after their mode.
+const CURRENT_SPSR: &str = "spsr";
+const REGION_SPAN: u32 = 0x0100_0000;
+const ROM_SPAN: u32 = 0x0200_0000;
+
+/// Finds a register by name, also taking r13-r15 for sp, lr and pc.
+fn register_index(name: &str) -> Option<usize> {
+    let name = match name.to_ascii_lowercase().as_str() {
+        "r13" => "sp",
+        "r14" => "lr",
+        "r15" => "pc",
+        _ => name,
+    };
+    REGISTER_NAMES.iter().position(|register| register.eq_ignore_ascii_case(name))
+}
+
+impl SystemInspection for GameBoyAdvance {
+    fn serial_output(&self) -> &[u8] {
+        self.arm7tdmi.bus().io_registers().serial_controller().output()
+    }
+
+    fn peek_8(&self, address: u32) -> u8 {
+        self.arm7tdmi.bus().peek_8(address)
+    }
+
+    fn poke_8(&mut self, address: u32, value: u8) {
+        self.arm7tdmi.bus_mut().poke_8(address, value);
+    }
+
+    /// The gdb register layout, with the pc at the instruction about to execute, followed by the SPSR of
+    /// the current mode when it has one.
+    fn registers(&self) -> Vec<Register> {
+        let mut registers: Vec<Register> = REGISTER_NAMES
+            .iter()
+            .enumerate()
+            .map(|(index, &name)| Register {
+                name,
+                value: self.read_register(index),
+                bits: 32,
+            })
+            .collect();
+        if let Some(spsr) = self.arm7tdmi.spsr_in_mode(self.arm7tdmi.mode()) {
+            registers.push(Register {
+                name: CURRENT_SPSR,
+                value: spsr,
+                bits: 32,
+            });
+        }
+        registers
+    }
+
+    fn register(&self, name: &str) -> Option<u32> {
+        match name.eq_ignore_ascii_case(CURRENT_SPSR) {
+            true => self.arm7tdmi.spsr_in_mode(self.arm7tdmi.mode()),
+            false => register_index(name).map(|index| self.read_register(index)),
+        }
+    }
+
+    fn set_register(&mut self, name: &str, value: u32) -> bool {
+        if name.eq_ignore_ascii_case(CURRENT_SPSR) {
+            let mode = self.arm7tdmi.mode();
+            let has_spsr = self.arm7tdmi.spsr_in_mode(mode).is_some();
+            self.arm7tdmi.set_spsr_in_mode(mode, value);
+            return has_spsr;
+        }
+        match register_index(name) {
+            Some(index) => {
+                self.write_register(index, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn memory_map(&self) -> Vec<MemoryRegion> {
+        let rom_size = self.arm7tdmi.bus().cartridge().rom_size() as u32;
+        vec![
+            MemoryRegion::new("BIOS", BIOS_BASE, 0x4000),
+            MemoryRegion::new("EWRAM", WRAM_BOARD_BASE, 0x40000).mirrored(REGION_SPAN),
+            MemoryRegion::new("IWRAM", WRAM_CHIP_BASE, 0x8000).mirrored(REGION_SPAN),
+            MemoryRegion::new("I/O", IO_REGISTERS_BASE, 0x400),
+            MemoryRegion::new("Palette RAM", PALETTE_RAM_BASE, 0x400).mirrored(REGION_SPAN),
+            MemoryRegion::new("VRAM", VRAM_BASE, 0x18000),
+            MemoryRegion::new("OAM", OAM_BASE, 0x400).mirrored(REGION_SPAN),
+            MemoryRegion::new("ROM (wait state 0)", ROM_WS0_LO, rom_size),
+            MemoryRegion::new("ROM (wait state 1)", ROM_WS1_LO, rom_size).mirror_of(ROM_WS0_LO),
+            MemoryRegion::new("ROM (wait state 2)", ROM_WS2_LO, rom_size).mirror_of(ROM_WS0_LO),
+            MemoryRegion::new("SRAM", SRAM_LO, 0x10000).mirrored(ROM_SPAN),
+        ]
+    }
+
+    fn ram_regions(&self) -> Vec<MemoryRegion> {
+        vec![
+            MemoryRegion::new("EWRAM", WRAM_BOARD_BASE, 0x40000),
+            MemoryRegion::new("IWRAM", WRAM_CHIP_BASE, 0x8000),
+        ]
+    }
+
+    fn call_stack(&self) -> Option<&CallStack> {
+        Some(self.arm7tdmi.call_stack())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ironboyadvance_common::{
+        emulator::Emulator,
+        memory::MemoryAccessWidth,
+        ram_search::{Comparison, RamSearch, SearchFilter, SearchOptions},
+        save_storage::MemorySaveStorage,
+    };
+
+    use super::*;
+
+    fn gba() -> GameBoyAdvance {
+        GameBoyAdvance::new(vec![0; 0x400], Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap()
+    }
+
+    #[test]
+    fn peeks_and_pokes_without_side_effects() {
+        let mut gba = gba();
+        gba.poke_8(0x06000001, 0xAB);
+        gba.poke_8(0x05000000, 0x12);
+        gba.poke_32(0x08000010, 0xDEADBEEF);
+        gba.poke_8(0x02040003, 0x77);
+
+        assert_eq!(gba.peek_16(0x06000000), 0xAB00);
+        assert_eq!(gba.peek_16(0x05000000), 0x0012);
+        assert_eq!(gba.peek_32(0x08000010), 0xDEADBEEF);
+        assert_eq!(gba.peek_32(0x0A000010), 0xDEADBEEF);
+        assert_eq!(gba.peek_8(0x02000003), 0x77, "EWRAM mirrors every 256KB");
+        assert_eq!(gba.peek_16(0x04000130), 0x03FF);
+        assert!(!gba.take_input_polled(), "peeking KEYINPUT is not polling it");
+
+        let map = gba.memory_map();
+        let ewram = map.iter().find(|region| region.name == "EWRAM").unwrap();
+        assert!(ewram.contains(0x02FFFFFF));
+        assert_eq!(ewram.mirrors(), 64);
+    }
+
+    #[test]
+    fn reads_and_writes_registers_by_name() {
+        let mut gba = gba();
+        assert!(gba.set_register("r0", 0x1234));
+        assert!(gba.set_register("R14", 0x08000100));
+        assert!(!gba.set_register("r16", 0));
+
+        assert_eq!(gba.register("r0"), Some(0x1234));
+        assert_eq!(gba.register("lr"), Some(0x08000100));
+        assert_eq!(gba.register("cpsr"), Some(gba.arm7tdmi.cpsr_bits()));
+        let registers = gba.registers();
+        let names: Vec<&str> = registers
+            .iter()
+            .map(|register| register.name)
+            .take(REGISTER_NAMES.len())
+            .collect();
+        assert_eq!(names, REGISTER_NAMES);
+        assert_eq!(
+            registers.iter().any(|register| register.name == CURRENT_SPSR),
+            gba.register("spsr").is_some()
+        );
+    }
+
+    #[test]
+    fn tracks_calls_and_returns() {
+        let mut rom = vec![0; 0x400];
+        // BL 0x08000010, then BX LR at the target.
+        rom[0x00..0x04].copy_from_slice(&0xEB000002u32.to_le_bytes());
+        rom[0x10..0x14].copy_from_slice(&0xE12FFF1Eu32.to_le_bytes());
+        let mut gba = GameBoyAdvance::new(rom, Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap();
+
+        while gba.register("pc") != Some(0x08000010) {
+            gba.arm7tdmi.cycle();
+        }
+        assert_eq!(gba.backtrace(None), vec!["#0  0x08000010", "#1  0x08000000"]);
+        gba.arm7tdmi.cycle();
+        assert_eq!(gba.register("pc"), Some(0x08000004));
+        assert_eq!(gba.call_stack().map(CallStack::depth), Some(0));
+    }
+
+    #[test]
+    fn ram_search_finds_a_counter_in_iwram() {
+        let mut gba = gba();
+        let options = SearchOptions {
+            width: MemoryAccessWidth::HalfWord,
+            ..SearchOptions::default()
+        };
+        let mut search = RamSearch::new(&gba, options);
+        assert_eq!(search.candidates().len(), (0x40000 + 0x8000) / 2);
+
+        gba.poke_16(0x03001000, 1000);
+        gba.poke_16(0x02000010, 7);
+        search.filter(&gba, SearchFilter::Previous(Comparison::NotEqual)).unwrap();
+        gba.poke_16(0x03001000, 999);
+        search.filter(&gba, SearchFilter::ChangedBy(-1)).unwrap();
+        assert_eq!(search.candidates(), &[0x03001000]);
+    }
+
+    #[test]
+    fn serial_output_collects_the_bytes_sent() {
+        let mut gba = gba();
+        for byte in *b"OK" {
+            gba.poke_16(0x0400012A, byte as u16);
+            gba.poke_16(0x04000128, 0x0081);
+            gba.run(1000, 0);
+        }
+        gba.poke_16(0x04000128, 0x3403);
+        gba.poke_8(0x0400012A, b'!');
+        gba.run(2000, 0);
+        assert_eq!(gba.serial_output(), b"OK!");
+    }
+}
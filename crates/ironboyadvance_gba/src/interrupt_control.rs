@@ -1,6 +1,11 @@
 use ironboyadvance_common::{memory::SystemMemoryAccess, register_ops::RegisterOps};
 
 use crate::events::InterruptEvent;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
+
+const STOP_WAKE_SOURCES: u16 = 1 << InterruptEvent::Keypad as u16
+    | 1 << InterruptEvent::GamePak as u16
+    | 1 << InterruptEvent::SerialCommunication as u16;
 
 pub struct InterruptController {
     interrupt_master_enabled: u32,
@@ -21,6 +26,11 @@ impl InterruptController {
         (self.interrupt_master_enabled & 0x1 != 0) && ((self.interrupt_flags & self.interrupt_enabled) != 0)
     }
 
+    /// Stop mode ends on a keypad, game pak or serial interrupt that IE enables, whatever IME is set to.
+    pub fn wakes_from_stop(&self) -> bool {
+        self.interrupt_flags & self.interrupt_enabled & STOP_WAKE_SOURCES != 0
+    }
+
     pub fn raise_interrupt(&mut self, interrupt_event: InterruptEvent) {
         self.interrupt_flags |= 1 << (interrupt_event as u8);
     }
@@ -56,3 +66,18 @@ impl SystemMemoryAccess for InterruptController {
         }
     }
 }
+
+impl Snapshot for InterruptController {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.interrupt_master_enabled);
+        writer.write(&self.interrupt_enabled);
+        writer.write(&self.interrupt_flags);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.interrupt_master_enabled = reader.read()?;
+        self.interrupt_enabled = reader.read()?;
+        self.interrupt_flags = reader.read()?;
+        Ok(())
+    }
+}
@@ -6,8 +6,9 @@ use tracing::debug;
 
 use crate::{
     apu::Apu, dma_control::DmaController, events::GbaEvent, interrupt_control::InterruptController, keypad::Keypad,
-    ppu::Ppu, system_control::SystemController, timer_control::TimerController,
+    ppu::Ppu, serial::SerialController, system_control::SystemController, timer_control::TimerController,
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[derive(Getters, MutGetters, Setters)]
 #[getset(get = "pub", get_mut = "pub")]
@@ -16,6 +17,7 @@ pub struct IoRegisters {
     apu: Apu,
     dma_controller: DmaController,
     timer_controller: TimerController,
+    serial_controller: SerialController,
     keypad: Keypad,
     interrupt_controller: InterruptController,
     system_controller: SystemController,
@@ -27,12 +29,21 @@ impl IoRegisters {
             ppu: Ppu::new(scheduler.clone()),
             apu: Apu::new(scheduler.clone()),
             dma_controller: DmaController::new(scheduler.clone()),
-            timer_controller: TimerController::new(scheduler),
+            timer_controller: TimerController::new(scheduler.clone()),
+            serial_controller: SerialController::new(scheduler),
             keypad: Keypad::new(),
             interrupt_controller: InterruptController::new(),
             system_controller: SystemController::new(),
         }
     }
+
+    /// Reads a register or video memory without side effects, for debuggers.
+    pub fn peek_8(&self, address: u32) -> u8 {
+        match address {
+            0x04000130..=0x04000133 => self.keypad.peek_8(address),
+            _ => self.read_8(address),
+        }
+    }
 }
 
 impl SystemMemoryAccess for IoRegisters {
@@ -48,6 +59,8 @@ impl SystemMemoryAccess for IoRegisters {
             0x040000B0..=0x040000DF => self.dma_controller.read_8(address),
             // Timer Control
             0x04000100..=0x0400010F => self.timer_controller.read_8(address),
+            // Serial Communication
+            0x04000120..=0x0400012F | 0x04000134..=0x0400015F => self.serial_controller.read_8(address),
             // Keypad
             0x04000130..=0x04000133 => self.keypad.read_8(address),
             // Interrupt Control
@@ -76,6 +89,8 @@ impl SystemMemoryAccess for IoRegisters {
             0x040000B0..=0x040000DF => self.dma_controller.write_8(address, value),
             // Timer Control
             0x04000100..=0x0400010F => self.timer_controller.write_8(address, value),
+            // Serial Communication
+            0x04000120..=0x0400012F | 0x04000134..=0x0400015F => self.serial_controller.write_8(address, value),
             // Keypad
             0x04000130..=0x04000133 => self.keypad.write_8(address, value),
             // Interrupt Control
@@ -96,6 +111,7 @@ impl SystemMemoryAccess for IoRegisters {
 
     fn write_16(&mut self, address: u32, value: u16) {
         match address {
+            0x04000120..=0x0400012F | 0x04000134..=0x0400015F => self.serial_controller.write_16(address, value),
             0x05000000..=0x07FFFFFF => self.ppu.write_16(address, value),
             _ => {
                 self.write_8(address, value as u8);
@@ -104,3 +120,27 @@ impl SystemMemoryAccess for IoRegisters {
         }
     }
 }
+
+impl Snapshot for IoRegisters {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.ppu);
+        writer.save(&self.apu);
+        writer.save(&self.dma_controller);
+        writer.save(&self.timer_controller);
+        writer.save(&self.serial_controller);
+        writer.save(&self.keypad);
+        writer.save(&self.interrupt_controller);
+        writer.save(&self.system_controller);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.ppu)?;
+        reader.load(&mut self.apu)?;
+        reader.load(&mut self.dma_controller)?;
+        reader.load(&mut self.timer_controller)?;
+        reader.load(&mut self.serial_controller)?;
+        reader.load(&mut self.keypad)?;
+        reader.load(&mut self.interrupt_controller)?;
+        reader.load(&mut self.system_controller)
+    }
+}
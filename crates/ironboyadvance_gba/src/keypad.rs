@@ -1,7 +1,10 @@
+use std::cell::Cell;
+
 use bitfields::bitfield;
 use ironboyadvance_common::memory::SystemMemoryAccess;
 
 use ironboyadvance_common::register_ops::RegisterOps;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[bitfield(u16)]
 #[derive(PartialEq, Eq)]
@@ -54,6 +57,7 @@ impl RegisterOps<u16> for KeyControl {
 pub struct Keypad {
     key_input: KeyInput,
     key_control: KeyControl,
+    input_polled: Cell<bool>,
 }
 
 impl Keypad {
@@ -61,9 +65,19 @@ impl Keypad {
         Self {
             key_input: KeyInput::from_bits(0x03FF),
             key_control: KeyControl::from_bits(0x0000),
+            input_polled: Cell::new(false),
         }
     }
 
+    pub fn take_input_polled(&self) -> bool {
+        self.input_polled.replace(false)
+    }
+
+    /// Keys held down, 1 meaning pressed. Unlike reading KEYINPUT this does not count as the game polling.
+    pub fn pressed_keys(&self) -> u16 {
+        !self.key_input.into_bits() & 0x03FF
+    }
+
     pub fn set_key_input(&mut self, input: u16) {
         self.key_input = KeyInput::from_bits(input);
     }
@@ -81,12 +95,9 @@ impl Keypad {
         }
         false
     }
-}
 
-impl SystemMemoryAccess for Keypad {
-    type Address = u32;
-
-    fn read_8(&self, address: u32) -> u8 {
+    /// Reads a register without counting as the game polling the keypad.
+    pub fn peek_8(&self, address: u32) -> u8 {
         match address {
             // KEYINPUT
             0x04000130..=0x04000131 => self.key_input.read_byte(address),
@@ -95,6 +106,17 @@ impl SystemMemoryAccess for Keypad {
             _ => panic!("Invalid byte read for Keypad: {:#010X}", address),
         }
     }
+}
+
+impl SystemMemoryAccess for Keypad {
+    type Address = u32;
+
+    fn read_8(&self, address: u32) -> u8 {
+        if let 0x04000130..=0x04000131 = address {
+            self.input_polled.set(true);
+        }
+        self.peek_8(address)
+    }
 
     fn write_8(&mut self, address: u32, value: u8) {
         match address {
@@ -106,3 +128,16 @@ impl SystemMemoryAccess for Keypad {
         }
     }
 }
+
+impl Snapshot for Keypad {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.key_input.into_bits());
+        writer.write(&self.key_control.into_bits());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.key_input = KeyInput::from_bits(reader.read()?);
+        self.key_control = KeyControl::from_bits(reader.read()?);
+        Ok(())
+    }
+}
@@ -1,15 +1,24 @@
-use std::{cell::RefCell, path::PathBuf, rc::Rc};
+use std::{cell::RefCell, rc::Rc};
 
-use ironboyadvance_arm7tdmi::{CPU_CLOCK_SPEED, cpu::Arm7tdmiCpu};
+use ironboyadvance_arm7tdmi::{CPU_CLOCK_SPEED, CpuState, cpu::Arm7tdmiCpu};
 use ironboyadvance_common::{
-    emulator::{Emulator, SystemInspection},
+    cheats::{Cheat, CheatError},
+    coverage::{Coverage, CoverageKind},
+    debugger::{BreakReason, Debugger, InstructionSet},
+    emulator::{Emulator, System},
+    gdb::GdbTarget,
+    profiler::Profiler,
+    save_storage::SaveStorage,
     scheduler::Scheduler,
+    snapshot::{self, SnapshotError, StateReader, StateWriter},
+    trace::Tracer,
 };
 use thiserror::Error;
 
 use crate::{
     bios::{Bios, BiosError},
-    cartridge::{Cartridge, CartridgeError},
+    cartridge::Cartridge,
+    cheats::{CheatBus, CheatEngine},
     events::{GbaEvent, InterruptEvent},
     system_bus::SystemBus,
     system_control::HaltMode,
@@ -18,13 +27,19 @@ use crate::{
 mod apu;
 mod bios;
 mod cartridge;
+mod cheats;
 mod dma_control;
 mod events;
+mod gdb;
+mod inspection;
 mod interrupt_control;
 mod io_registers;
 mod keypad;
+mod link;
 mod memory;
 mod ppu;
+mod prefetch;
+mod serial;
 mod system_bus;
 mod system_control;
 mod timer_control;
@@ -32,6 +47,8 @@ mod timer_control;
 pub const FPS: f32 = CPU_CLOCK_SPEED as f32 / CYCLES_PER_FRAME as f32;
 
 pub use apu::APU_SAMPLING_FREQUENCY;
+pub use cartridge::{CartridgeError, export_save, import_save};
+pub use link::{LINK_PLAYERS, LinkHub};
 
 pub use ppu::{CYCLES_PER_FRAME, VIEWPORT_HEIGHT, VIEWPORT_WIDTH};
 
@@ -46,30 +63,128 @@ pub enum GbaError {
 pub struct GameBoyAdvance {
     arm7tdmi: Arm7tdmiCpu<SystemBus>,
     scheduler: Rc<RefCell<Scheduler<GbaEvent>>>,
+    rom_hash: u64,
+    cheats: CheatEngine,
+    break_reason: Option<BreakReason>,
+    tracer: Option<Tracer>,
+    profiler: Option<Profiler>,
 }
 
 impl GameBoyAdvance {
     pub fn new(
-        rom_path: PathBuf,
         rom_buffer: Vec<u8>,
         bios_buffer: Vec<u8>,
+        save_storage: Box<dyn SaveStorage>,
         base_unix_seconds: u64,
         show_logs: bool,
     ) -> Result<GameBoyAdvance, GbaError> {
         let scheduler = Rc::new(RefCell::new(Scheduler::new()));
-        let cartridge = Cartridge::load(rom_path, rom_buffer, base_unix_seconds, scheduler.clone())?;
+        let rom_hash = snapshot::rom_hash(&rom_buffer);
+        let cartridge = Cartridge::load(rom_buffer, save_storage, base_unix_seconds, scheduler.clone())?;
         let bios = Bios::load(bios_buffer)?;
         let bios_loaded = bios.loaded();
         let gba = GameBoyAdvance {
             arm7tdmi: Arm7tdmiCpu::new(SystemBus::new(cartridge, bios, scheduler.clone()), show_logs, bios_loaded),
             scheduler,
+            rom_hash,
+            cheats: CheatEngine::new(),
+            break_reason: None,
+            tracer: None,
+            profiler: None,
         };
         Ok(gba)
     }
 
-    pub fn cycle(&mut self) {
-        match self.arm7tdmi.bus().halt_mode() {
-            HaltMode::Stopped => todo!(),
+    /// Plugs the serial port into `hub`, returning the multiplayer id it got, or None when all
+    /// [`LINK_PLAYERS`] places are taken. Replaces any cable already plugged in.
+    pub fn connect_link(&mut self, hub: &LinkHub) -> Option<usize> {
+        let port = hub.plug()?;
+        let serial_controller = self.arm7tdmi.bus_mut().io_registers_mut().serial_controller_mut();
+        serial_controller.set_link(Some(port));
+        serial_controller.link_id()
+    }
+
+    pub fn disconnect_link(&mut self) {
+        let serial_controller = self.arm7tdmi.bus_mut().io_registers_mut().serial_controller_mut();
+        serial_controller.set_link(None);
+    }
+
+    fn apply_cheats(&mut self) {
+        let bus = self.arm7tdmi.bus_mut();
+        let pressed_keys = bus.io_registers().keypad().pressed_keys();
+        if self.cheats.apply(&mut CheatBus(bus), pressed_keys) {
+            self.update_rom_patches();
+        }
+    }
+
+    fn update_rom_patches(&mut self) {
+        let rom_patches = self.cheats.rom_patches();
+        self.arm7tdmi.bus_mut().cartridge_mut().set_rom_patches(rom_patches);
+    }
+
+    fn check_breakpoints(&mut self) -> Option<BreakReason> {
+        let address = self.arm7tdmi.instruction_address();
+        let instruction_set = match self.arm7tdmi.cpu_state() {
+            CpuState::Arm => InstructionSet::Arm,
+            CpuState::Thumb => InstructionSet::Thumb,
+        };
+        let stack_pointer = self.arm7tdmi.stack_pointer();
+        self.arm7tdmi
+            .bus_mut()
+            .debugger_mut()
+            .before_instruction(address, Some(instruction_set), stack_pointer)
+    }
+
+    fn trace_instruction(&mut self) {
+        if let Some(tracer) = &mut self.tracer
+            && tracer.should_trace(self.arm7tdmi.instruction_address())
+        {
+            let timestamp = self.scheduler.borrow().timestamp() as u64;
+            let line = self.arm7tdmi.trace_line(tracer.format(), timestamp);
+            tracer.write_instruction(self.arm7tdmi.instruction_address(), &line);
+        }
+    }
+
+    fn cover_instruction(&mut self) {
+        let (kind, size) = match self.arm7tdmi.cpu_state() {
+            CpuState::Arm => (CoverageKind::Arm, 4),
+            CpuState::Thumb => (CoverageKind::Thumb, 2),
+        };
+        let address = self.arm7tdmi.instruction_address();
+        self.arm7tdmi.bus_mut().cover(address, size, kind);
+    }
+
+    /// Starts charging the step about to run to the current function, returning the timestamp and DMA
+    /// cycles the step starts at.
+    fn enter_profile(&mut self) -> Option<(usize, u64)> {
+        let profiler = self.profiler.as_mut()?;
+        profiler.enter(self.arm7tdmi.call_stack(), self.arm7tdmi.instruction_address());
+        Some((self.scheduler.borrow().timestamp(), self.arm7tdmi.bus().dma_cycles()))
+    }
+
+    fn record_profile(&mut self, start: Option<(usize, u64)>, halted: bool) {
+        if let Some(profiler) = &mut self.profiler
+            && let Some((timestamp, dma_cycles)) = start
+        {
+            let cycles = (self.scheduler.borrow().timestamp() - timestamp) as u64;
+            profiler.record(cycles, self.arm7tdmi.bus().dma_cycles() - dma_cycles, halted);
+        }
+    }
+
+    /// Runs one instruction, or one halted step. Returns why the debugger stopped the system, in which case
+    /// the instruction it stopped in front of has not executed yet.
+    pub fn cycle(&mut self) -> Option<BreakReason> {
+        let profile_start = self.enter_profile();
+        let executed_instruction = match self.arm7tdmi.bus().halt_mode() {
+            // Nothing is clocked, so no events can run and only input from outside, the keypad or a unit on
+            // the link cable, can wake the system.
+            HaltMode::Stopped => {
+                self.arm7tdmi.bus_mut().check_link();
+                if self.arm7tdmi.bus().wakes_from_stop() {
+                    self.arm7tdmi.bus_mut().un_halt();
+                }
+                false
+            }
             HaltMode::Halted => {
                 if self.arm7tdmi.bus().interrupt_pending() {
                     self.arm7tdmi.bus_mut().un_halt();
@@ -77,17 +192,25 @@ impl GameBoyAdvance {
                 } else if self.arm7tdmi.bus().dma_active() {
                     self.arm7tdmi.bus_mut().run_dma();
                 } else {
-                    self.scheduler.borrow_mut().step_to_next_event();
-                    self.arm7tdmi.bus_mut().handle_events();
+                    self.arm7tdmi.bus_mut().halt_until_next_event();
                 }
+                false
             }
             HaltMode::Running => {
                 if self.arm7tdmi.bus().interrupt_pending() {
                     self.arm7tdmi.irq();
                 }
+                if let Some(reason) = self.check_breakpoints() {
+                    return Some(reason);
+                }
+                self.trace_instruction();
+                self.cover_instruction();
                 self.arm7tdmi.cycle();
+                true
             }
-        }
+        };
+        self.record_profile(profile_start, !executed_instruction);
+        self.arm7tdmi.bus_mut().debugger_mut().after_cycle(executed_instruction)
     }
 }
 
@@ -98,9 +221,25 @@ impl Emulator for GameBoyAdvance {
         let end_time = start_time + target;
 
         while self.scheduler.borrow().timestamp() < end_time {
-            self.cycle();
+            if let Some(reason) = self.cycle() {
+                self.break_reason = Some(reason);
+                break;
+            }
+            // Time stands still in stop mode, so the frame ends here with the LCD off.
+            if self.arm7tdmi.bus().halt_mode() == HaltMode::Stopped {
+                self.arm7tdmi.bus_mut().io_registers_mut().ppu_mut().blank_screen();
+                break;
+            }
+        }
+        if self.scheduler.borrow().timestamp() >= end_time
+            && let Some(tracer) = &mut self.tracer
+        {
+            tracer.frame_completed();
         }
 
+        self.apply_cheats();
+        self.arm7tdmi.bus_mut().cartridge_mut().flush_backup();
+
         let elapsed = self.scheduler.borrow().timestamp() - start_time;
         elapsed.saturating_sub(target)
     }
@@ -124,6 +263,282 @@ impl Emulator for GameBoyAdvance {
             self.arm7tdmi.bus_mut().raise_interrupt(InterruptEvent::Keypad);
         }
     }
+
+    fn take_input_polled(&mut self) -> bool {
+        self.arm7tdmi.bus().io_registers().keypad().take_input_polled()
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::with_header(System::Gba, self.rom_hash);
+        writer.save(&self.arm7tdmi);
+        writer.finish()
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), SnapshotError> {
+        let mut reader = StateReader::with_header(state, System::Gba, self.rom_hash)?;
+        let previous_state = self.save_state();
+        let result = reader.load(&mut self.arm7tdmi).and_then(|_| reader.finish());
+        if result.is_err() {
+            let mut reader = StateReader::with_header(&previous_state, System::Gba, self.rom_hash)?;
+            reader.load(&mut self.arm7tdmi)?;
+        }
+        // The cable stays plugged in across snapshots, which only restore the registers.
+        let serial_controller = self.arm7tdmi.bus_mut().io_registers_mut().serial_controller_mut();
+        serial_controller.resume_polling();
+        result
+    }
+
+    fn cheats(&self) -> &[Cheat] {
+        self.cheats.cheats()
+    }
+
+    fn add_cheat(&mut self, cheat: Cheat) -> Result<usize, CheatError> {
+        let index = self.cheats.add(cheat)?;
+        self.update_rom_patches();
+        Ok(index)
+    }
+
+    fn remove_cheat(&mut self, index: usize) -> Result<Cheat, CheatError> {
+        let cheat = self.cheats.remove(index)?;
+        self.update_rom_patches();
+        Ok(cheat)
+    }
+
+    fn set_cheat_enabled(&mut self, index: usize, enabled: bool) -> Result<(), CheatError> {
+        self.cheats.set_enabled(index, enabled)?;
+        self.update_rom_patches();
+        Ok(())
+    }
+
+    fn debugger(&self) -> &Debugger {
+        self.arm7tdmi.bus().debugger()
+    }
+
+    fn debugger_mut(&mut self) -> &mut Debugger {
+        self.arm7tdmi.bus_mut().debugger_mut()
+    }
+
+    fn take_break_reason(&mut self) -> Option<BreakReason> {
+        self.break_reason.take()
+    }
+
+    fn step_over(&mut self) {
+        let return_address = self.arm7tdmi.call_return_address();
+        let stack_pointer = self.arm7tdmi.stack_pointer();
+        let debugger = self.arm7tdmi.bus_mut().debugger_mut();
+        match return_address {
+            Some(address) => debugger.step_until(address, Some(stack_pointer)),
+            None => debugger.step_instruction(),
+        }
+    }
+
+    /// Returns to the link register, so it finds the caller of functions that have not made a call
+    /// of their own yet.
+    fn step_out(&mut self) {
+        let return_address = self.arm7tdmi.link_register() & !1;
+        let stack_pointer = self.arm7tdmi.stack_pointer();
+        self.arm7tdmi
+            .bus_mut()
+            .debugger_mut()
+            .step_until(return_address, Some(stack_pointer));
+    }
+
+    fn gdb_target(&mut self) -> Option<&mut dyn GdbTarget> {
+        Some(self)
+    }
+
+    fn start_trace(&mut self, tracer: Tracer) -> Option<Tracer> {
+        self.tracer.replace(tracer)
+    }
+
+    fn stop_trace(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
+    fn start_profile(&mut self, profiler: Profiler) -> Option<Profiler> {
+        self.profiler.replace(profiler)
+    }
+
+    fn stop_profile(&mut self) -> Option<Profiler> {
+        self.profiler.take()
+    }
+
+    fn start_coverage(&mut self, coverage: Coverage) -> Option<Coverage> {
+        self.arm7tdmi.bus_mut().coverage_mut().replace(coverage)
+    }
+
+    fn stop_coverage(&mut self) -> Option<Coverage> {
+        self.arm7tdmi.bus_mut().coverage_mut().take()
+    }
 }
 
-impl SystemInspection for GameBoyAdvance {}
+#[cfg(test)]
+mod tests {
+    use ironboyadvance_arm7tdmi::CpuMode;
+    use ironboyadvance_common::{
+        cheats::CheatFormat, emulator::SystemInspection, keypad::KEYPAD_IDLE, save_storage::MemorySaveStorage,
+    };
+
+    use super::*;
+
+    #[test]
+    fn stop_mode_freezes_until_a_keypad_combination() {
+        let mut gba = GameBoyAdvance::new(vec![0; 0x400], Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap();
+        gba.run(1000, 0);
+        // Keypad interrupt enabled in IE, and KEYCNT asking for A and B together.
+        gba.poke_16(0x04000200, 1 << InterruptEvent::Keypad as u16);
+        gba.poke_16(0x04000132, 0xC003);
+        gba.poke_8(0x04000301, 0x80);
+
+        let stopped_at = gba.scheduler.borrow().timestamp();
+        gba.handle_pressed_buttons(KEYPAD_IDLE & !0x1);
+        gba.run(1000, 0);
+        assert_eq!(
+            gba.arm7tdmi.bus().halt_mode(),
+            HaltMode::Stopped,
+            "A alone does not match KEYCNT"
+        );
+        assert_eq!(gba.scheduler.borrow().timestamp(), stopped_at);
+        assert!(gba.frame_buffer().iter().all(|&pixel| pixel == 0));
+
+        gba.handle_pressed_buttons(KEYPAD_IDLE & !0x3);
+        gba.run(1000, 0);
+        assert_eq!(gba.arm7tdmi.bus().halt_mode(), HaltMode::Running);
+        assert!(gba.scheduler.borrow().timestamp() >= stopped_at + 1000);
+    }
+
+    #[test]
+    fn cheats_conditioned_on_keyinput_do_not_poll_input() {
+        let mut gba = GameBoyAdvance::new(vec![0; 0x400], Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap();
+        let cheat = Cheat::new("Keys", CheatFormat::CodeBreaker, "74000130 03FF\n82000000 0063\n");
+        gba.add_cheat(cheat).unwrap();
+
+        gba.apply_cheats();
+        assert_eq!(gba.peek_16(0x02000000), 0x0063);
+        assert!(!gba.take_input_polled());
+    }
+
+    #[test]
+    fn prefetch_buffer_speeds_up_code_with_internal_cycles() {
+        let cycles_for = |waitcnt: u16| {
+            // LDR r0, [r1] loading from IWRAM, which leaves the game pak bus free for its internal cycle.
+            let rom = 0xE5910000u32.to_le_bytes().repeat(0x100);
+            let mut gba = GameBoyAdvance::new(rom, Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap();
+            gba.poke_16(0x04000204, waitcnt);
+            gba.set_register("r1", 0x03000000);
+            for _ in 0..4 {
+                gba.arm7tdmi.cycle();
+            }
+            let start = gba.scheduler.borrow().timestamp();
+            for _ in 0..100 {
+                gba.arm7tdmi.cycle();
+            }
+            gba.scheduler.borrow().timestamp() - start
+        };
+
+        let without_prefetch = cycles_for(0);
+        let with_prefetch = cycles_for(0x4000);
+        assert!(
+            with_prefetch < without_prefetch,
+            "{with_prefetch} cycles with prefetch, {without_prefetch} without"
+        );
+        // A 1N opcode fetch of 5 + 3 cycles for its two halfwords, 1 for IWRAM and 1I. With prefetch, the load
+        // and internal cycle cover all but the last cycle of the first halfword, and the second takes its 3.
+        assert_eq!(without_prefetch, 100 * (8 + 1 + 1));
+        assert_eq!(with_prefetch, 100 * (1 + 3 + 1 + 1));
+    }
+
+    /// Cycles running `instructions` instructions of the Thumb `program`, repeated through rom, takes once
+    /// the pipeline is on it. r1 points into rom and r2 into IWRAM.
+    fn thumb_cycles(program: &[u16], waitcnt: u16, instructions: usize) -> usize {
+        // add r0, pc, #1 and bx r0, switching to the Thumb code right after.
+        let mut rom = [0xE28F0001u32, 0xE12FFF10].map(u32::to_le_bytes).concat();
+        rom.extend(program.iter().flat_map(|opcode| opcode.to_le_bytes()).cycle().take(0x400));
+        let mut gba = GameBoyAdvance::new(rom, Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap();
+        gba.poke_16(0x04000204, waitcnt);
+        gba.set_register("r1", 0x08000100);
+        gba.set_register("r2", 0x03000000);
+        for _ in 0..4 + program.len() {
+            gba.arm7tdmi.cycle();
+        }
+        let start = gba.scheduler.borrow().timestamp();
+        for _ in 0..instructions {
+            gba.arm7tdmi.cycle();
+        }
+        gba.scheduler.borrow().timestamp() - start
+    }
+
+    #[test]
+    fn prefetch_timings_match_the_documented_cycle_counts() {
+        // With WAITCNT's wait states at 0, a game pak halfword takes 5 cycles nonsequential and 3 sequential.
+        const N: usize = 5;
+        const S: usize = 3;
+
+        // mov r8, r8 is a 1S opcode fetch. The bus is never free, so the buffer never gets ahead.
+        assert_eq!(thumb_cycles(&[0x46C0], 0, 100), 100 * S);
+        assert_eq!(thumb_cycles(&[0x46C0], 0x4000, 100), 100 * S);
+
+        // ldr r0, [r2] from IWRAM then ldrh r3, [r1] from rom, each 1S + 1N + 1I with the fetch after a load
+        // being nonsequential. The ldrh empties the buffer, so the ldr's fetch goes to the game pak, and only
+        // the ldrh's fetch finds its halfword in flight, 1 cycle from done after the ldr's 2.
+        let program = [0x6810, 0x880B];
+        assert_eq!(thumb_cycles(&program, 0, 100), 50 * ((N + 1 + 1) + (N + N + 1)));
+        assert_eq!(thumb_cycles(&program, 0x4000, 100), 50 * ((N + 1 + 1) + (1 + N + 1)));
+
+        // Six ldr r0, [r2] then b back to the first, which is 2S + 1N and refills the pipeline from its target.
+        // The first ldr fetches sequentially after the refill, the rest nonsequentially after a load. With
+        // prefetch, the branch's own fetch is buffered but the target misses, and the halfword after it
+        // is a whole fetch away like the first ldr's.
+        let mut program = [0x6810; 7];
+        program[6] = 0xE7F8;
+        let without_prefetch = (S + 1 + 1) + 5 * (N + 1 + 1) + (N + N + S);
+        let with_prefetch = (S + 1 + 1) + 5 * (1 + 1 + 1) + (1 + N + S);
+        assert_eq!(thumb_cycles(&program, 0, 70), 10 * without_prefetch);
+        assert_eq!(thumb_cycles(&program, 0x4000, 70), 10 * with_prefetch);
+    }
+
+    #[test]
+    fn vblank_intr_wait_without_a_bios_runs_the_games_interrupt_handler() {
+        let program: [u32; 9] = [
+            0xE3A00301, // mov r0, #0x04000000
+            0xE3A01018, // mov r1, #0x18, VBlank and HBlank interrupts in DISPSTAT
+            0xE1C010B4, // strh r1, [r0, #4]
+            0xE3A01003, // mov r1, #3
+            0xE2802C02, // add r2, r0, #0x200
+            0xE1C210B0, // strh r1, [r2], IE
+            0xEF050000, // swi 0x05, VBlankIntrWait
+            0xE2855001, // add r5, r5, #1
+            0xEAFFFFFC, // b 0x08000018
+        ];
+        let handler: [u32; 10] = [
+            0xE3A00301, // mov r0, #0x04000000
+            0xE2800C02, // add r0, r0, #0x200
+            0xE1D010B2, // ldrh r1, [r0, #2]
+            0xE1C010B2, // strh r1, [r0, #2], acknowledging IF
+            0xE3A02301, // mov r2, #0x04000000
+            0xE15230B8, // ldrh r3, [r2, #-8]
+            0xE1833001, // orr r3, r3, r1
+            0xE14230B8, // strh r3, [r2, #-8], and the BIOS interrupt flags
+            0xE2866001, // add r6, r6, #1
+            0xE12FFF1E, // bx lr
+        ];
+        let mut rom: Vec<u8> = program.iter().flat_map(|opcode| opcode.to_le_bytes()).collect();
+        rom.resize(0x400, 0);
+        let mut gba = GameBoyAdvance::new(rom, Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap();
+        for (index, opcode) in handler.into_iter().enumerate() {
+            gba.poke_32(0x03000000 + index as u32 * 4, opcode);
+        }
+        gba.poke_32(0x03007FFC, 0x03000000);
+
+        gba.run(CYCLES_PER_FRAME * 3, 0);
+        let frames = gba.arm7tdmi.register_in_mode(CpuMode::System, 5);
+        let interrupts = gba.arm7tdmi.register_in_mode(CpuMode::System, 6);
+        assert!((2..=3).contains(&frames), "{frames} VBlankIntrWait returns in 3 frames");
+        assert!(interrupts > 600, "only {interrupts} interrupts handled");
+        assert_eq!(
+            gba.arm7tdmi.register_in_mode(CpuMode::Irq, 13),
+            0x03007FA0,
+            "the stack is balanced"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    cell::{RefCell, RefMut},
+    collections::VecDeque,
+    rc::Rc,
+};
+
+use crate::serial::SerialMode;
+
+/// Units one link cable chain connects, the most multiplayer mode supports.
+pub const LINK_PLAYERS: usize = 4;
+
+/// What a transfer another unit ran leaves for this one, picked up the next time it polls the hub.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Delivery {
+    /// The data the unit clocking a normal mode transfer sent.
+    Normal(u32),
+    /// SIOMULTI0-3 as every unit ends a multiplayer transfer with.
+    Multiplayer([u16; LINK_PLAYERS]),
+    Uart(u8),
+}
+
+/// A unit's serial port as the others on the cable see it.
+#[derive(Debug, Clone)]
+pub(crate) struct PortState {
+    pub mode: SerialMode,
+    /// Armed with an external clock for a normal mode transfer, waiting for another unit to clock it.
+    pub ready: bool,
+    /// SIODATA8 or SIODATA32 in normal mode, SIOMLT_SEND in multiplayer mode.
+    pub send: u32,
+    /// The SO line, what normal mode drives between transfers.
+    pub idle_so: bool,
+    /// RCNT's data lines in bits 0-3 and their directions in bits 4-7, for general purpose mode.
+    pub lines: u8,
+    /// UART mode with its receiver enabled.
+    pub receiving: bool,
+    pub inbox: VecDeque<Delivery>,
+}
+
+impl PortState {
+    fn new() -> Self {
+        PortState {
+            mode: SerialMode::Normal8,
+            ready: false,
+            send: 0,
+            idle_so: false,
+            lines: 0,
+            receiving: false,
+            inbox: VecDeque::new(),
+        }
+    }
+}
+
+type Ports = [Option<PortState>; LINK_PLAYERS];
+
+/// Connects the serial ports of several [`GameBoyAdvance`](crate::GameBoyAdvance)s in one process, the way
+/// link cables do. The instances run side by side on one thread, transfers reaching the other units the next
+/// time they poll the hub.
+#[derive(Debug, Clone, Default)]
+pub struct LinkHub {
+    ports: Rc<RefCell<Ports>>,
+}
+
+impl LinkHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many units are plugged in.
+    pub fn connected(&self) -> usize {
+        self.ports().iter().flatten().count()
+    }
+
+    /// Takes the first free place on the cable, which becomes the unit's multiplayer id.
+    pub(crate) fn plug(&self) -> Option<LinkPort> {
+        let mut ports = self.ports();
+        let id = ports.iter().position(Option::is_none)?;
+        ports[id] = Some(PortState::new());
+        Some(LinkPort { hub: self.clone(), id })
+    }
+
+    fn ports(&self) -> RefMut<'_, Ports> {
+        self.ports.borrow_mut()
+    }
+}
+
+/// One unit's end of the cable, freeing its place on the hub when dropped.
+#[derive(Debug)]
+pub(crate) struct LinkPort {
+    hub: LinkHub,
+    id: usize,
+}
+
+impl LinkPort {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Every place on the cable, this unit's included.
+    pub fn ports(&self) -> RefMut<'_, Ports> {
+        self.hub.ports()
+    }
+
+    pub fn update(&self, update: impl FnOnce(&mut PortState)) {
+        if let Some(port) = &mut self.ports()[self.id] {
+            update(port);
+        }
+    }
+
+    /// The other units plugged in, with their ids.
+    pub fn others(&self) -> Vec<(usize, PortState)> {
+        self.ports()
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| *id != self.id)
+            .filter_map(|(id, port)| port.clone().map(|port| (id, port)))
+            .collect()
+    }
+
+    pub fn deliver(&self, id: usize, delivery: Delivery) {
+        if let Some(port) = &mut self.ports()[id] {
+            port.inbox.push_back(delivery);
+        }
+    }
+
+    pub fn take_inbox(&self) -> VecDeque<Delivery> {
+        self.ports()[self.id]
+            .as_mut()
+            .map(|port| std::mem::take(&mut port.inbox))
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for LinkPort {
+    fn drop(&mut self) {
+        self.hub.ports()[self.id] = None;
+    }
+}
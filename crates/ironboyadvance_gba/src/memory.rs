@@ -1,4 +1,5 @@
 use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 pub struct Memory {
     wram_board: Vec<u8>,
@@ -33,3 +34,15 @@ impl SystemMemoryAccess for Memory {
         }
     }
 }
+
+impl Snapshot for Memory {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.wram_board);
+        writer.write_bytes(&self.wram_chip);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.read_bytes_into(&mut self.wram_board)?;
+        reader.read_bytes_into(&mut self.wram_chip)
+    }
+}
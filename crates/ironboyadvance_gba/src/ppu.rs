@@ -10,6 +10,7 @@ use crate::{
         background::Background, color::bgr555_to_rgb888, effects::Effects, lcd::*, mosaic::Mosaic, object::Object, window::*,
     },
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const CYCLES_PER_PIXEL: usize = 4;
 
@@ -263,6 +264,11 @@ impl Ppu {
         }
     }
 
+    /// Stop mode powers the LCD off, leaving the screen black until the PPU draws again.
+    pub fn blank_screen(&mut self) {
+        self.frame_buffer.fill(0);
+    }
+
     pub fn handle_event(&mut self, event: PpuEvent, timestamp: usize) {
         match event {
             PpuEvent::HDraw => self.handle_hdraw_complete(),
@@ -485,3 +491,40 @@ impl Ppu {
         self.background.advance_affine_points(should_advance);
     }
 }
+
+impl Snapshot for Ppu {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.lcd_control.into_bits());
+        writer.write(&self.green_swap);
+        writer.write(&self.lcd_status.into_bits());
+        writer.write(&self.v_count);
+        writer.save(&self.background);
+        writer.save(&self.window);
+        writer.save(&self.mosaic);
+        writer.save(&self.effects);
+        writer.write_bytes(&self.palette_ram);
+        writer.write_bytes(&self.vram);
+        writer.write_bytes(&self.oam);
+        writer.write(&self.frame_buffer);
+        writer.write(&self.scanline_forced_blank);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.lcd_control = LcdControl::from_bits(reader.read()?);
+        self.green_swap = reader.read()?;
+        self.lcd_status = LcdStatus::from_bits(reader.read()?);
+        self.v_count = reader.read()?;
+        reader.load(&mut self.background)?;
+        reader.load(&mut self.window)?;
+        reader.load(&mut self.mosaic)?;
+        reader.load(&mut self.effects)?;
+        reader.read_bytes_into(&mut self.palette_ram)?;
+        reader.read_bytes_into(&mut self.vram)?;
+        reader.read_bytes_into(&mut self.oam)?;
+        for pixel in self.frame_buffer.iter_mut() {
+            *pixel = reader.read()?;
+        }
+        self.scanline_forced_blank = reader.read()?;
+        Ok(())
+    }
+}
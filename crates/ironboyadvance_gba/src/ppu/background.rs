@@ -2,6 +2,7 @@ use bitfields::{bitfield, bitflag};
 use ironboyadvance_common::{bits::SignExtend, memory::SystemMemoryAccess, register_ops::RegisterOps};
 
 use crate::ppu::{SB_ENTRIES, SB_SIDE, color::ColorMode};
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[bitflag(u8)]
 #[derive(Debug, PartialEq, Eq)]
@@ -349,3 +350,34 @@ impl SystemMemoryAccess for Background {
         }
     }
 }
+
+impl Snapshot for Background {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.bg_controls.map(BgControl::into_bits));
+        writer.write(&self.bg_x_offsets.map(BgOffset::into_bits));
+        writer.write(&self.bg_y_offsets.map(BgOffset::into_bits));
+        writer.write(&self.bg_x_references.map(BgReferencePoint::into_bits));
+        writer.write(&self.bg_y_references.map(BgReferencePoint::into_bits));
+        writer.write(&self.bg_x_affine);
+        writer.write(&self.bg_y_affine);
+        writer.write(&self.bg_pa.map(BgAffineParameter::into_bits));
+        writer.write(&self.bg_pb.map(BgAffineParameter::into_bits));
+        writer.write(&self.bg_pc.map(BgAffineParameter::into_bits));
+        writer.write(&self.bg_pd.map(BgAffineParameter::into_bits));
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.bg_controls = reader.read::<[u16; 4]>()?.map(BgControl::from_bits);
+        self.bg_x_offsets = reader.read::<[u16; 4]>()?.map(BgOffset::from_bits);
+        self.bg_y_offsets = reader.read::<[u16; 4]>()?.map(BgOffset::from_bits);
+        self.bg_x_references = reader.read::<[u32; 2]>()?.map(BgReferencePoint::from_bits);
+        self.bg_y_references = reader.read::<[u32; 2]>()?.map(BgReferencePoint::from_bits);
+        self.bg_x_affine = reader.read()?;
+        self.bg_y_affine = reader.read()?;
+        self.bg_pa = reader.read::<[u16; 2]>()?.map(BgAffineParameter::from_bits);
+        self.bg_pb = reader.read::<[u16; 2]>()?.map(BgAffineParameter::from_bits);
+        self.bg_pc = reader.read::<[u16; 2]>()?.map(BgAffineParameter::from_bits);
+        self.bg_pd = reader.read::<[u16; 2]>()?.map(BgAffineParameter::from_bits);
+        Ok(())
+    }
+}
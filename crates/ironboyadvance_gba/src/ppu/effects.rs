@@ -5,6 +5,7 @@ use crate::ppu::{
     Layer, Pixel,
     color::{bgr555_to_channels, channels_to_bgr555},
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[bitflag(u8)]
 #[derive(Debug, PartialEq, Eq)]
@@ -224,3 +225,18 @@ fn darken(color: u16, evy: u8) -> u16 {
     let decrease = |color: u16| -> u16 { color - (color as u32 * evy as u32 / 16) as u16 };
     channels_to_bgr555(decrease(red), decrease(green), decrease(blue))
 }
+
+impl Snapshot for Effects {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.special_effect_control.into_bits());
+        writer.write(&self.alpha_blending.into_bits());
+        writer.write(&self.brightness.into_bits());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.special_effect_control = SpecialEffectsControl::from_bits(reader.read()?);
+        self.alpha_blending = AlphaBlending::from_bits(reader.read()?);
+        self.brightness = Brightness::from_bits(reader.read()?);
+        Ok(())
+    }
+}
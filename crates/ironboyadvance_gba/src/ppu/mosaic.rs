@@ -3,6 +3,7 @@ use getset::CopyGetters;
 use ironboyadvance_common::memory::SystemMemoryAccess;
 
 use ironboyadvance_common::register_ops::RegisterOps;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[bitfield(u32)]
 #[derive(PartialEq, Eq)]
@@ -82,3 +83,18 @@ impl SystemMemoryAccess for Mosaic {
         }
     }
 }
+
+impl Snapshot for Mosaic {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.size.into_bits());
+        writer.write(&self.bg_source_y);
+        writer.write(&self.obj_source_y);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.size = MosaicSize::from_bits(reader.read()?);
+        self.bg_source_y = reader.read()?;
+        self.obj_source_y = reader.read()?;
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@ use getset::CopyGetters;
 use ironboyadvance_common::{memory::SystemMemoryAccess, register_ops::RegisterOps};
 
 use crate::ppu::{ScanlineContext, VIEWPORT_WIDTH};
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[bitfield(u16)]
 #[derive(PartialEq, Eq)]
@@ -236,3 +237,20 @@ impl SystemMemoryAccess for Window {
         }
     }
 }
+
+impl Snapshot for Window {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.win_x_dimensions.map(WindowDimension::into_bits));
+        writer.write(&self.win_y_dimensions.map(WindowDimension::into_bits));
+        writer.write(&self.win_inside.into_bits());
+        writer.write(&self.win_outside.into_bits());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.win_x_dimensions = reader.read::<[u16; 2]>()?.map(WindowDimension::from_bits);
+        self.win_y_dimensions = reader.read::<[u16; 2]>()?.map(WindowDimension::from_bits);
+        self.win_inside = WindowInside::from_bits(reader.read()?);
+        self.win_outside = WindowOutside::from_bits(reader.read()?);
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
+
+/// Halfwords the game pak prefetch buffer holds.
+pub const PREFETCH_CAPACITY: u32 = 8;
+
+/// The game pak prefetch unit. While the cpu is busy elsewhere, running internal cycles or touching other
+/// memory, it keeps reading the halfwords after the last opcode fetched from rom, so a sequential opcode
+/// fetch the buffer already holds takes a single cycle.
+pub struct Prefetcher {
+    active: bool,
+    /// Address of the oldest halfword in the buffer, the one the next opcode fetch has to be at.
+    head: u32,
+    /// Halfwords fetched and waiting in the buffer.
+    count: u32,
+    /// Cycles left until the halfword being fetched lands in the buffer.
+    countdown: usize,
+    /// Cycles a sequential halfword read takes in the wait state region being prefetched.
+    fetch_cycles: usize,
+}
+
+impl Prefetcher {
+    pub fn new() -> Self {
+        Prefetcher {
+            active: false,
+            head: 0,
+            count: 0,
+            countdown: 0,
+            fetch_cycles: 0,
+        }
+    }
+
+    /// Serves an opcode fetch of `halfwords` halfwords at `address`, returning the cycles it takes, or None
+    /// when the buffer is not prefetching that address and the fetch has to go to the game pak.
+    pub fn fetch(&mut self, address: u32, halfwords: u32) -> Option<usize> {
+        if !self.active || address != self.head {
+            return None;
+        }
+
+        let cycles = match self.count >= halfwords {
+            true => {
+                self.consume(halfwords);
+                self.advance(1);
+                1
+            }
+            // The rest of the opcode is still on its way, so the cpu waits for it.
+            false => {
+                let mut cycles = 0;
+                while self.count < halfwords {
+                    cycles += self.countdown;
+                    self.count += 1;
+                    self.countdown = self.fetch_cycles;
+                }
+                self.consume(halfwords);
+                cycles
+            }
+        };
+        Some(cycles)
+    }
+
+    fn consume(&mut self, halfwords: u32) {
+        self.count -= halfwords;
+        self.head = self.head.wrapping_add(halfwords * 2);
+    }
+
+    /// Lets `cycles` pass with the game pak bus free, filling the buffer.
+    pub fn advance(&mut self, mut cycles: usize) {
+        if !self.active {
+            return;
+        }
+        while cycles > 0 && self.count < PREFETCH_CAPACITY {
+            let step = cycles.min(self.countdown);
+            self.countdown -= step;
+            cycles -= step;
+            if self.countdown == 0 {
+                self.count += 1;
+                self.countdown = self.fetch_cycles;
+            }
+        }
+    }
+
+    /// Starts prefetching from `address`, after an opcode fetch the buffer could not serve.
+    pub fn restart(&mut self, address: u32, fetch_cycles: usize) {
+        self.active = true;
+        self.head = address;
+        self.count = 0;
+        self.fetch_cycles = fetch_cycles;
+        self.countdown = fetch_cycles;
+    }
+
+    /// Empties the buffer, for data accesses to the game pak and for turning prefetch off.
+    pub fn stop(&mut self) {
+        self.active = false;
+        self.count = 0;
+    }
+}
+
+impl Snapshot for Prefetcher {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.active);
+        writer.write(&self.head);
+        writer.write(&self.count);
+        writer.write(&(self.countdown as u32));
+        writer.write(&(self.fetch_cycles as u32));
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.active = reader.read()?;
+        self.head = reader.read()?;
+        self.count = reader.read()?;
+        if self.count > PREFETCH_CAPACITY {
+            return Err(SnapshotError::InvalidValue("prefetch buffer count"));
+        }
+        self.countdown = reader.read::<u32>()? as usize;
+        self.fetch_cycles = reader.read::<u32>()? as usize;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_while_idle_and_serves_sequential_fetches() {
+        let mut prefetcher = Prefetcher::new();
+        assert_eq!(prefetcher.fetch(0x08000000, 1), None);
+
+        prefetcher.restart(0x08000002, 3);
+        prefetcher.advance(7);
+        assert_eq!(prefetcher.count, 2);
+        assert_eq!(prefetcher.fetch(0x08000002, 1), Some(1));
+        assert_eq!(prefetcher.fetch(0x08000004, 1), Some(1));
+        // The third halfword landed during the second hit, and the fourth is a whole fetch away.
+        assert_eq!(
+            prefetcher.fetch(0x08000006, 2),
+            Some(3),
+            "waits for both halfwords of an ARM opcode"
+        );
+        assert_eq!(prefetcher.fetch(0x08000020, 1), None, "a branch misses");
+
+        prefetcher.advance(100);
+        assert_eq!(prefetcher.count, PREFETCH_CAPACITY);
+        prefetcher.stop();
+        assert_eq!(prefetcher.fetch(0x0800000A, 1), None);
+    }
+}
@@ -0,0 +1,774 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use ironboyadvance_arm7tdmi::CPU_CLOCK_SPEED;
+use ironboyadvance_common::{
+    bits::BitOps,
+    memory::SystemMemoryAccess,
+    register_ops::RegisterOps,
+    scheduler::Scheduler,
+    snapshot::{Snapshot, SnapshotError, StateReader, StateWriter},
+};
+
+use crate::{
+    events::{GbaEvent, InterruptEvent, SerialEvent},
+    link::{Delivery, LINK_PLAYERS, LinkPort},
+};
+
+pub const SIODATA8: u32 = 0x0400012A;
+/// Cycles between checks of the link hub for transfers the other units ran.
+const POLL_CYCLES: usize = 256;
+const BAUD_RATES: [usize; 4] = [9600, 38400, 57600, 115200];
+/// A start bit, 16 data bits and a stop bit for each unit.
+const MULTIPLAYER_BITS_PER_UNIT: usize = 18;
+/// A start bit, 8 data bits and a stop bit.
+const UART_BITS: usize = 10;
+const UART_FIFO_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialMode {
+    Normal8,
+    Normal32,
+    Multiplayer,
+    Uart,
+    GeneralPurpose,
+    JoyBus,
+}
+
+/// A transfer this unit clocks, with what it receives latched when it started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transfer {
+    Normal { received: u32, partner: Option<usize> },
+    Multiplayer([u16; LINK_PLAYERS]),
+    Uart(u8),
+}
+
+/// The serial port, SIOCNT picking between normal, multiplayer and UART mode unless RCNT turns it into
+/// general purpose pins or the JOY bus. Without a link cable the lines read high, so a normal transfer
+/// receives all ones and multiplayer 0xFFFF from the missing units.
+pub struct SerialController {
+    /// SIOMULTI0-3, the first two also being SIODATA32.
+    data: [u16; LINK_PLAYERS],
+    /// SIOMLT_SEND, also SIODATA8.
+    send: u16,
+    control: u16,
+    rcnt: u16,
+    joy_control: u16,
+    joy_receive: u32,
+    joy_transmit: u32,
+    joy_status: u16,
+    uart_received: VecDeque<u8>,
+    transfer: Option<Transfer>,
+    last_si: bool,
+    /// Bytes sent by the 8-bit normal and UART transfers this unit clocked, for test roms that print their
+    /// results over the serial port.
+    output: Vec<u8>,
+    link: Option<LinkPort>,
+    scheduler: Rc<RefCell<Scheduler<GbaEvent>>>,
+}
+
+impl SerialController {
+    pub fn new(scheduler: Rc<RefCell<Scheduler<GbaEvent>>>) -> Self {
+        Self {
+            data: [0; LINK_PLAYERS],
+            send: 0,
+            control: 0,
+            rcnt: 0,
+            joy_control: 0,
+            joy_receive: 0,
+            joy_transmit: 0,
+            joy_status: 0,
+            uart_received: VecDeque::new(),
+            transfer: None,
+            last_si: true,
+            output: Vec::new(),
+            link: None,
+            scheduler,
+        }
+    }
+
+    pub fn mode(&self) -> SerialMode {
+        match (self.rcnt.bit(15), self.rcnt.bit(14)) {
+            (false, _) => match self.control.bits(12..=13) {
+                0 => SerialMode::Normal8,
+                1 => SerialMode::Normal32,
+                2 => SerialMode::Multiplayer,
+                _ => SerialMode::Uart,
+            },
+            (true, false) => SerialMode::GeneralPurpose,
+            (true, true) => SerialMode::JoyBus,
+        }
+    }
+
+    /// Plugs the port into a link cable, or unplugs it with None.
+    pub fn set_link(&mut self, link: Option<LinkPort>) {
+        self.link = link;
+        self.resume_polling();
+    }
+
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    pub fn link_id(&self) -> Option<usize> {
+        self.link.as_ref().map(LinkPort::id)
+    }
+
+    /// Starts polling the hub again while linked, for after a snapshot replaced the scheduled events.
+    pub fn resume_polling(&mut self) {
+        self.scheduler.borrow_mut().cancel_events(GbaEvent::Serial(SerialEvent::Poll));
+        if self.link.is_some() {
+            self.publish();
+            self.schedule(SerialEvent::Poll, POLL_CYCLES);
+        }
+    }
+
+    pub fn handle_event(&mut self, event: SerialEvent) {
+        match event {
+            SerialEvent::TransferComplete => self.complete_transfer(),
+            SerialEvent::Poll => self.poll(),
+        }
+    }
+
+    /// Takes the byte SIODATA8 shows off the UART receive buffer, once the cpu or a DMA has loaded it.
+    pub fn uart_data_loaded(&mut self) {
+        if self.mode() == SerialMode::Uart {
+            self.uart_received.pop_front();
+        }
+    }
+
+    fn schedule(&self, event: SerialEvent, cycles: usize) {
+        self.scheduler.borrow_mut().schedule((GbaEvent::Serial(event), cycles));
+    }
+
+    fn raise_interrupt(&self) {
+        self.scheduler
+            .borrow_mut()
+            .schedule((GbaEvent::Interrupt(InterruptEvent::SerialCommunication), 0));
+    }
+
+    fn cycles_per_bit(&self) -> usize {
+        CPU_CLOCK_SPEED as usize / BAUD_RATES[self.control.bits(0..=1) as usize]
+    }
+
+    /// SIOCNT with the bits the lines and the transfer state drive.
+    fn read_control(&self) -> u16 {
+        let mut control = self.control;
+        match self.mode() {
+            SerialMode::Normal8 | SerialMode::Normal32 => control.set_bit(2, self.partner_so()),
+            SerialMode::Multiplayer => {
+                let id = self.link_id().unwrap_or(0);
+                control.set_bit(2, id != 0);
+                control.set_bit(3, self.all_multiplayer());
+                control = (control & !0x30) | (id as u16) << 4;
+            }
+            SerialMode::Uart => {
+                control.set_bit(4, matches!(self.transfer, Some(Transfer::Uart(_))));
+                control.set_bit(5, self.uart_received.is_empty());
+            }
+            SerialMode::GeneralPurpose | SerialMode::JoyBus => {}
+        }
+        control
+    }
+
+    /// Bits of SIOCNT the lines or the transfer state drive rather than the cpu.
+    fn control_read_only(&self) -> u16 {
+        match self.mode() {
+            SerialMode::Normal8 | SerialMode::Normal32 => 0x0004,
+            SerialMode::Multiplayer => 0x007C,
+            SerialMode::Uart => 0x0074,
+            SerialMode::GeneralPurpose | SerialMode::JoyBus => 0,
+        }
+    }
+
+    /// RCNT, with the lines general purpose mode uses as inputs reading what the other end drives.
+    fn read_rcnt(&self) -> u16 {
+        match self.mode() {
+            SerialMode::GeneralPurpose => {
+                let outputs = self.rcnt.bits(4..=7);
+                (self.rcnt & !0xF) | (self.rcnt & outputs) | (self.partner_lines() & !outputs & 0xF)
+            }
+            _ => self.rcnt,
+        }
+    }
+
+    /// The level the other units hold each general purpose line at, high unless one drives it low. SC and SD
+    /// connect straight through the cable while SI and SO cross over.
+    fn partner_lines(&self) -> u16 {
+        let mut lines = 0xF;
+        let Some(link) = &self.link else {
+            return lines;
+        };
+        for (_, port) in link.others() {
+            if port.mode != SerialMode::GeneralPurpose {
+                continue;
+            }
+            let (levels, outputs) = (port.lines as u16 & 0xF, (port.lines >> 4) as u16);
+            for (line, from) in [(0, 0), (1, 1), (2, 3), (3, 2)] {
+                if outputs.bit(from) && !levels.bit(from) {
+                    lines.set_bit(line, false);
+                }
+            }
+        }
+        lines
+    }
+
+    /// SI in normal mode, the SO the other unit holds between transfers.
+    fn partner_so(&self) -> bool {
+        self.link
+            .as_ref()
+            .and_then(|link| {
+                link.others()
+                    .into_iter()
+                    .find(|(_, port)| matches!(port.mode, SerialMode::Normal8 | SerialMode::Normal32))
+            })
+            .is_none_or(|(_, port)| port.idle_so)
+    }
+
+    fn all_multiplayer(&self) -> bool {
+        self.link
+            .as_ref()
+            .is_some_and(|link| link.others().iter().all(|(_, port)| port.mode == SerialMode::Multiplayer))
+    }
+
+    fn normal_data(&self) -> u32 {
+        match self.mode() {
+            SerialMode::Normal32 => self.data[0] as u32 | (self.data[1] as u32) << 16,
+            _ => self.send as u32 & 0xFF,
+        }
+    }
+
+    fn set_normal_data(&mut self, value: u32) {
+        match self.mode() {
+            SerialMode::Normal32 => self.data[..2].copy_from_slice(&[value as u16, (value >> 16) as u16]),
+            _ => self.send = (self.send & 0xFF00) | (value as u16 & 0xFF),
+        }
+    }
+
+    /// Shows the other units what this one is set up to do.
+    fn publish(&self) {
+        let Some(link) = &self.link else {
+            return;
+        };
+        let mode = self.mode();
+        let normal = matches!(mode, SerialMode::Normal8 | SerialMode::Normal32);
+        let send = match normal {
+            true => self.normal_data(),
+            false => self.send as u32,
+        };
+        link.update(|port| {
+            port.mode = mode;
+            port.ready = normal && self.control.bit(7) && !self.control.bit(0);
+            port.send = send;
+            port.idle_so = self.control.bit(3);
+            port.lines = self.rcnt as u8;
+            port.receiving = mode == SerialMode::Uart && self.control.bit(11);
+        });
+    }
+
+    fn control_written(&mut self) {
+        if !self.control.bit(7) || self.transfer.is_some() {
+            return;
+        }
+        match self.mode() {
+            SerialMode::Normal8 | SerialMode::Normal32 if self.control.bit(0) => self.start_normal(),
+            SerialMode::Multiplayer if self.link_id().unwrap_or(0) == 0 => self.start_multiplayer(),
+            _ => {}
+        }
+    }
+
+    /// Clocks a normal mode transfer at 256KHz, or 2MHz with bit 1 set, exchanging data with a unit waiting
+    /// on an external clock.
+    fn start_normal(&mut self) {
+        let bits = match self.mode() {
+            SerialMode::Normal32 => 32,
+            _ => 8,
+        };
+        let cycles_per_bit = match self.control.bit(1) {
+            true => 8,
+            false => 64,
+        };
+        let partner = self.link.as_ref().and_then(|link| {
+            link.others()
+                .into_iter()
+                .find(|(_, port)| port.ready && port.mode == self.mode())
+        });
+        let (received, partner) = match partner {
+            Some((id, port)) => (port.send, Some(id)),
+            None => (u32::MAX, None),
+        };
+        self.transfer = Some(Transfer::Normal { received, partner });
+        self.schedule(SerialEvent::TransferComplete, bits * cycles_per_bit);
+    }
+
+    /// Sends every unit's SIOMLT_SEND to all of them, which the parent starts.
+    fn start_multiplayer(&mut self) {
+        let mut values = [0xFFFF; LINK_PLAYERS];
+        values[0] = self.send;
+        let mut units = 1;
+        if let Some(link) = &self.link {
+            for (id, port) in link.others() {
+                if port.mode == SerialMode::Multiplayer {
+                    values[id] = port.send as u16;
+                    units += 1;
+                }
+            }
+        }
+        self.transfer = Some(Transfer::Multiplayer(values));
+        let cycles = units * MULTIPLAYER_BITS_PER_UNIT * self.cycles_per_bit();
+        self.schedule(SerialEvent::TransferComplete, cycles);
+    }
+
+    fn send_uart(&mut self) {
+        if !self.control.bit(10) || self.transfer.is_some() {
+            return;
+        }
+        self.transfer = Some(Transfer::Uart(self.send as u8));
+        self.schedule(SerialEvent::TransferComplete, UART_BITS * self.cycles_per_bit());
+    }
+
+    fn complete_transfer(&mut self) {
+        let Some(transfer) = self.transfer.take() else {
+            return;
+        };
+        match transfer {
+            Transfer::Normal { received, partner } => {
+                let sent = self.normal_data();
+                if self.mode() == SerialMode::Normal8 {
+                    self.output.push(sent as u8);
+                }
+                self.set_normal_data(received);
+                if let (Some(link), Some(partner)) = (&self.link, partner) {
+                    link.deliver(partner, Delivery::Normal(sent));
+                }
+                self.finish();
+            }
+            Transfer::Multiplayer(values) => {
+                self.data = values;
+                if let Some(link) = &self.link {
+                    for (id, port) in link.others() {
+                        if port.mode == SerialMode::Multiplayer {
+                            link.deliver(id, Delivery::Multiplayer(values));
+                        }
+                    }
+                }
+                self.finish();
+            }
+            Transfer::Uart(byte) => {
+                self.output.push(byte);
+                if let Some(link) = &self.link
+                    && let Some((id, _)) = link.others().into_iter().find(|(_, port)| port.receiving)
+                {
+                    link.deliver(id, Delivery::Uart(byte));
+                }
+                if self.control.bit(14) {
+                    self.raise_interrupt();
+                }
+            }
+        }
+        self.publish();
+    }
+
+    /// Ends a normal or multiplayer transfer, clearing the start bit.
+    fn finish(&mut self) {
+        self.control.set_bit(7, false);
+        if self.control.bit(14) {
+            self.raise_interrupt();
+        }
+    }
+
+    fn receive(&mut self, delivery: Delivery) {
+        let mode = self.mode();
+        match delivery {
+            Delivery::Normal(value) => {
+                if matches!(mode, SerialMode::Normal8 | SerialMode::Normal32) && self.control.bit(7) && !self.control.bit(0)
+                {
+                    self.set_normal_data(value);
+                    self.finish();
+                }
+            }
+            Delivery::Multiplayer(values) => {
+                if mode == SerialMode::Multiplayer {
+                    self.data = values;
+                    self.finish();
+                }
+            }
+            Delivery::Uart(byte) => {
+                if mode == SerialMode::Uart && self.control.bit(11) {
+                    let capacity = match self.control.bit(8) {
+                        true => UART_FIFO_SIZE,
+                        false => 1,
+                    };
+                    match self.uart_received.len() < capacity {
+                        true => self.uart_received.push_back(byte),
+                        false => self.control.set_bit(6, true),
+                    }
+                    if self.control.bit(14) {
+                        self.raise_interrupt();
+                    }
+                }
+            }
+        }
+    }
+
+    fn poll(&mut self) {
+        if self.link.is_some() {
+            self.check_link();
+            self.schedule(SerialEvent::Poll, POLL_CYCLES);
+        }
+    }
+
+    /// Picks up what the other units sent, and in general purpose mode watches SI fall for its interrupt.
+    /// Polling does this while the system runs, stop mode checks directly since it clocks no events.
+    pub fn check_link(&mut self) {
+        let Some(link) = &self.link else {
+            return;
+        };
+        for delivery in link.take_inbox() {
+            self.receive(delivery);
+        }
+        if self.mode() == SerialMode::GeneralPurpose {
+            let si = self.read_rcnt().bit(2);
+            if self.last_si && !si && self.rcnt.bit(8) {
+                self.raise_interrupt();
+            }
+            self.last_si = si;
+        }
+        self.publish();
+    }
+
+    fn store_8(&mut self, address: u32, value: u8) {
+        match address {
+            // SIOMULTI0-3, SIODATA32
+            0x04000120..=0x04000127 => self.data[(address as usize - 0x04000120) / 2].write_byte(address, value),
+            // SIOCNT
+            0x04000128..=0x04000129 => {
+                let read_only = self.control_read_only();
+                let previous = self.control;
+                self.control.write_byte(address, value);
+                self.control = (previous & read_only) | (self.control & !read_only);
+            }
+            // SIOMLT_SEND, SIODATA8
+            0x0400012A..=0x0400012B => self.send.write_byte(address, value),
+            // RCNT
+            0x04000134..=0x04000135 => self.rcnt.write_byte(address, value),
+            // JOYCNT, whose flags are cleared by writing 1
+            0x04000140 => {
+                self.joy_control &= !(value as u16 & 0x07);
+                self.joy_control = (self.joy_control & !0x40) | (value as u16 & 0x40);
+            }
+            // JOY_RECV
+            0x04000150..=0x04000153 => self.joy_receive.write_byte(address, value),
+            // JOY_TRANS
+            0x04000154..=0x04000157 => {
+                self.joy_transmit.write_byte(address, value);
+                self.joy_status.set_bit(3, true);
+            }
+            // JOYSTAT, with only the general purpose flags writable
+            0x04000158 => self.joy_status = (self.joy_status & !0x30) | (value as u16 & 0x30),
+            _ => {}
+        }
+    }
+
+    fn written(&mut self, address: u32) {
+        match address {
+            0x04000128..=0x04000129 => self.control_written(),
+            SIODATA8 if self.mode() == SerialMode::Uart => self.send_uart(),
+            _ => {}
+        }
+        self.publish();
+    }
+}
+
+impl SystemMemoryAccess for SerialController {
+    type Address = u32;
+
+    fn read_8(&self, address: u32) -> u8 {
+        match address {
+            // SIOMULTI0-3, SIODATA32
+            0x04000120..=0x04000127 => self.data[(address as usize - 0x04000120) / 2].read_byte(address),
+            // SIOCNT
+            0x04000128..=0x04000129 => self.read_control().read_byte(address),
+            // SIODATA8, the oldest byte in the UART receive buffer
+            SIODATA8 if self.mode() == SerialMode::Uart => self.uart_received.front().copied().unwrap_or(0),
+            // SIOMLT_SEND, SIODATA8
+            0x0400012A..=0x0400012B => self.send.read_byte(address),
+            // RCNT
+            0x04000134..=0x04000135 => self.read_rcnt().read_byte(address),
+            // JOYCNT
+            0x04000140..=0x04000141 => self.joy_control.read_byte(address),
+            // JOY_RECV
+            0x04000150..=0x04000153 => self.joy_receive.read_byte(address),
+            // JOY_TRANS
+            0x04000154..=0x04000157 => self.joy_transmit.read_byte(address),
+            // JOYSTAT
+            0x04000158..=0x04000159 => self.joy_status.read_byte(address),
+            _ => 0,
+        }
+    }
+
+    fn write_8(&mut self, address: u32, value: u8) {
+        self.store_8(address, value);
+        self.written(address);
+    }
+
+    /// Writes both bytes before acting on them, so a SIOCNT write that sets the mode and the start bit
+    /// starts a transfer in the new mode.
+    fn write_16(&mut self, address: u32, value: u16) {
+        self.store_8(address, value as u8);
+        self.store_8(address + 1, (value >> 8) as u8);
+        self.written(address);
+    }
+}
+
+impl Snapshot for SerialController {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.data);
+        writer.write(&self.send);
+        writer.write(&self.control);
+        writer.write(&self.rcnt);
+        writer.write(&self.joy_control);
+        writer.write(&self.joy_receive);
+        writer.write(&self.joy_transmit);
+        writer.write(&self.joy_status);
+        writer.write(&self.uart_received);
+        match self.transfer {
+            None => writer.write(&0u8),
+            Some(Transfer::Normal { received, partner }) => {
+                writer.write(&1u8);
+                writer.write(&received);
+                writer.write(&partner);
+            }
+            Some(Transfer::Multiplayer(values)) => {
+                writer.write(&2u8);
+                writer.write(&values);
+            }
+            Some(Transfer::Uart(byte)) => {
+                writer.write(&3u8);
+                writer.write(&byte);
+            }
+        }
+        writer.write(&self.last_si);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.data = reader.read()?;
+        self.send = reader.read()?;
+        self.control = reader.read()?;
+        self.rcnt = reader.read()?;
+        self.joy_control = reader.read()?;
+        self.joy_receive = reader.read()?;
+        self.joy_transmit = reader.read()?;
+        self.joy_status = reader.read()?;
+        let received: VecDeque<u8> = reader.read()?;
+        if received.len() > UART_FIFO_SIZE {
+            return Err(SnapshotError::InvalidValue("UART receive buffer"));
+        }
+        self.uart_received = received;
+        self.transfer = match reader.read::<u8>()? {
+            0 => None,
+            1 => Some(Transfer::Normal {
+                received: reader.read()?,
+                partner: reader.read()?,
+            }),
+            2 => Some(Transfer::Multiplayer(reader.read()?)),
+            3 => Some(Transfer::Uart(reader.read()?)),
+            _ => return Err(SnapshotError::InvalidValue("serial transfer")),
+        };
+        self.last_si = reader.read()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ironboyadvance_arm7tdmi::memory::MemoryInterface;
+    use ironboyadvance_common::{emulator::Emulator, memory::MemoryAccess, save_storage::MemorySaveStorage};
+
+    use crate::{GameBoyAdvance, link::LinkHub, system_control::HaltMode};
+
+    use super::*;
+
+    struct Unit {
+        serial: SerialController,
+        scheduler: Rc<RefCell<Scheduler<GbaEvent>>>,
+        interrupts: usize,
+    }
+
+    impl Unit {
+        fn new(hub: Option<&LinkHub>) -> Self {
+            let scheduler = Rc::new(RefCell::new(Scheduler::new()));
+            let mut serial = SerialController::new(scheduler.clone());
+            serial.set_link(hub.and_then(LinkHub::plug));
+            Unit {
+                serial,
+                scheduler,
+                interrupts: 0,
+            }
+        }
+
+        fn run(&mut self, cycles: usize) {
+            self.scheduler.borrow_mut().step(cycles);
+            loop {
+                let Some((event, _)) = self.scheduler.borrow_mut().pop() else {
+                    break;
+                };
+                match event {
+                    GbaEvent::Serial(serial_event) => self.serial.handle_event(serial_event),
+                    GbaEvent::Interrupt(InterruptEvent::SerialCommunication) => self.interrupts += 1,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Runs the units side by side, the way a frontend steps linked instances.
+    fn run_all(units: &mut [&mut Unit], cycles: usize) {
+        for _ in 0..cycles / POLL_CYCLES {
+            for unit in units.iter_mut() {
+                unit.run(POLL_CYCLES);
+            }
+        }
+    }
+
+    #[test]
+    fn normal_transfer_without_a_cable_receives_all_ones() {
+        let mut unit = Unit::new(None);
+        unit.serial.write_32(0x04000120, 0x12345678);
+        unit.serial.write_16(0x04000128, 0x1000);
+        unit.serial.write_16(0x04000128, 0x5081);
+        assert!(unit.serial.read_16(0x04000128).bit(7), "the transfer is running");
+
+        unit.run(32 * 64);
+        assert!(!unit.serial.read_16(0x04000128).bit(7));
+        assert_eq!(unit.serial.read_32(0x04000120), 0xFFFFFFFF);
+        assert_eq!(unit.interrupts, 1);
+    }
+
+    #[test]
+    fn linked_units_exchange_normal_transfers() {
+        let hub = LinkHub::new();
+        let (mut master, mut slave) = (Unit::new(Some(&hub)), Unit::new(Some(&hub)));
+        slave.serial.write_32(0x04000120, 0xCAFEF00D);
+        slave.serial.write_16(0x04000128, 0x1000);
+        slave.serial.write_16(0x04000128, 0x5080);
+        master.serial.write_32(0x04000120, 0x12345678);
+        master.serial.write_16(0x04000128, 0x1000);
+        master.serial.write_16(0x04000128, 0x5081);
+
+        run_all(&mut [&mut master, &mut slave], 32 * 64 + 2 * POLL_CYCLES);
+        assert_eq!(master.serial.read_32(0x04000120), 0xCAFEF00D);
+        assert_eq!(slave.serial.read_32(0x04000120), 0x12345678);
+        assert!(
+            !slave.serial.read_16(0x04000128).bit(7),
+            "the slave's transfer ended with the master's"
+        );
+        assert_eq!((master.interrupts, slave.interrupts), (1, 1));
+    }
+
+    #[test]
+    fn multiplayer_transfer_shares_every_units_data() {
+        let hub = LinkHub::new();
+        let mut units: Vec<Unit> = (0..3).map(|_| Unit::new(Some(&hub))).collect();
+        for (id, unit) in units.iter_mut().enumerate() {
+            unit.serial.write_16(0x0400012A, 0x1000 + id as u16);
+            unit.serial.write_16(0x04000128, 0x6003);
+        }
+        assert_eq!(
+            units[1].serial.read_16(0x04000128) & 0x3C,
+            0x1C,
+            "a child with id 1, all units ready"
+        );
+
+        units[0].serial.write_16(0x04000128, 0x6083);
+        let [parent, first, second] = &mut units[..] else {
+            unreachable!();
+        };
+        run_all(
+            &mut [parent, first, second],
+            3 * MULTIPLAYER_BITS_PER_UNIT * 146 + 2 * POLL_CYCLES,
+        );
+        for unit in &units {
+            let data: Vec<u16> = (0..4).map(|index| unit.serial.read_16(0x04000120 + index * 2)).collect();
+            assert_eq!(data, [0x1000, 0x1001, 0x1002, 0xFFFF]);
+            assert_eq!(unit.interrupts, 1);
+        }
+    }
+
+    #[test]
+    fn uart_bytes_land_in_the_receive_fifo() {
+        let hub = LinkHub::new();
+        let (mut sender, mut receiver) = (Unit::new(Some(&hub)), Unit::new(Some(&hub)));
+        receiver.serial.write_16(0x04000128, 0x3903);
+        sender.serial.write_16(0x04000128, 0x3403);
+        for byte in [0x41, 0x42] {
+            sender.serial.write_8(0x0400012A, byte);
+            run_all(&mut [&mut sender, &mut receiver], UART_BITS * 146 + 2 * POLL_CYCLES);
+        }
+
+        assert!(!receiver.serial.read_16(0x04000128).bit(5), "the receive FIFO holds data");
+        assert_eq!(receiver.serial.read_8(SIODATA8), 0x41);
+        assert_eq!(receiver.serial.read_8(SIODATA8), 0x41, "reading alone leaves the byte there");
+        receiver.serial.uart_data_loaded();
+        assert_eq!(receiver.serial.read_8(SIODATA8), 0x42);
+        receiver.serial.uart_data_loaded();
+        assert!(receiver.serial.read_16(0x04000128).bit(5));
+    }
+
+    #[test]
+    fn general_purpose_mode_reads_the_other_units_lines() {
+        let hub = LinkHub::new();
+        let (mut first, mut second) = (Unit::new(Some(&hub)), Unit::new(Some(&hub)));
+        first.serial.write_16(0x04000134, 0x8100);
+        assert_eq!(first.serial.read_16(0x04000134) & 0xF, 0xF, "undriven lines float high");
+
+        // SO driven low, which the other end of the cable sees on SI.
+        second.serial.write_16(0x04000134, 0x8080);
+        run_all(&mut [&mut first, &mut second], 2 * POLL_CYCLES);
+        assert_eq!(first.serial.read_16(0x04000134) & 0xF, 0xB);
+        assert_eq!(first.interrupts, 1, "SI falling raises the serial interrupt");
+    }
+
+    #[test]
+    fn only_loads_take_bytes_off_the_uart_receive_buffer() {
+        let mut gba = GameBoyAdvance::new(vec![0; 0x400], Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap();
+        let bus = gba.arm7tdmi.bus_mut();
+        bus.write_16(0x04000128, 0x3903);
+        let serial = bus.io_registers_mut().serial_controller_mut();
+        serial.uart_received.extend([0x41, 0x42]);
+
+        assert_eq!(bus.read_8(SIODATA8), 0x41, "cheats and debuggers read without side effects");
+        assert_eq!(bus.read_16(SIODATA8), 0x41);
+        assert_eq!(bus.load_16(SIODATA8, MemoryAccess::NonSequential as u8), 0x41);
+        assert_eq!(bus.load_8(SIODATA8, MemoryAccess::NonSequential as u8), 0x42);
+        assert!(bus.read_16(0x04000128).bit(5), "the buffer is empty");
+    }
+
+    #[test]
+    fn a_transfer_from_another_unit_wakes_a_stopped_one() {
+        let boot = || GameBoyAdvance::new(vec![0; 0x400], Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap();
+        let hub = LinkHub::new();
+        let (mut master, mut slave) = (boot(), boot());
+        master.connect_link(&hub);
+        slave.connect_link(&hub);
+
+        let bus = slave.arm7tdmi.bus_mut();
+        bus.write_32(0x04000120, 0xCAFEF00D);
+        bus.write_16(0x04000128, 0x1000);
+        bus.write_16(0x04000128, 0x5080);
+        bus.write_16(0x04000200, 1 << InterruptEvent::SerialCommunication as u16);
+        bus.write_8(0x04000301, 0x80);
+        slave.run(1000, 0);
+        assert_eq!(slave.arm7tdmi.bus().halt_mode(), HaltMode::Stopped);
+
+        let bus = master.arm7tdmi.bus_mut();
+        bus.write_32(0x04000120, 0x12345678);
+        bus.write_16(0x04000128, 0x1000);
+        bus.write_16(0x04000128, 0x5081);
+        master.run(4000, 0);
+
+        slave.run(1000, 0);
+        assert_eq!(slave.arm7tdmi.bus().halt_mode(), HaltMode::Running);
+        assert_eq!(slave.arm7tdmi.bus().read_32(0x04000120), 0x12345678);
+        assert_eq!(master.arm7tdmi.bus().read_32(0x04000120), 0xCAFEF00D);
+    }
+}
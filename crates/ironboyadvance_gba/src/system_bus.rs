@@ -6,6 +6,8 @@ use ironboyadvance_arm7tdmi::{
     memory::{CpuContext, MemoryInterface},
 };
 use ironboyadvance_common::{
+    coverage::{Coverage, CoverageKind},
+    debugger::{AccessKind, Debugger},
     memory::{MemoryAccess, MemoryAccessWidth, SystemMemoryAccess},
     scheduler::Scheduler,
 };
@@ -18,8 +20,11 @@ use crate::{
     events::{ApuEvent, CartridgeEvent, DmaEvent, GbaEvent, InterruptEvent, PpuEvent, TimerEvent},
     io_registers::IoRegisters,
     memory::Memory,
+    prefetch::Prefetcher,
+    serial::SIODATA8,
     system_control::HaltMode,
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 pub const BIOS_BASE: u32 = 0x0000_0000;
 pub const WRAM_BOARD_BASE: u32 = 0x0200_0000;
@@ -43,11 +48,19 @@ pub struct SystemBus {
     memory: Memory,
     #[getset(get = "pub", get_mut = "pub")]
     io_registers: IoRegisters,
+    #[getset(get = "pub", get_mut = "pub")]
     cartridge: Cartridge,
     scheduler: Rc<RefCell<Scheduler<GbaEvent>>>,
     cpu_context: CpuContext,
     dma_open_bus_value: u32,
     last_access: u8,
+    #[getset(get = "pub", get_mut = "pub")]
+    debugger: Debugger,
+    /// Cycles DMA transfers kept the cpu off the bus, for profiling.
+    dma_cycles: u64,
+    #[getset(get = "pub", get_mut = "pub")]
+    coverage: Option<Coverage>,
+    prefetcher: Prefetcher,
 }
 
 impl MemoryInterface for SystemBus {
@@ -55,6 +68,9 @@ impl MemoryInterface for SystemBus {
         self.cycle(address, access, MemoryAccessWidth::Byte);
         let value = self.read_8(address) as u32;
         self.latch_dma_open_bus(access, value);
+        self.watch(address, 1, access, AccessKind::Read, value);
+        self.cover_read(address, 1, access);
+        self.serial_loaded(address, 1);
         value
     }
 
@@ -62,6 +78,9 @@ impl MemoryInterface for SystemBus {
         self.cycle(address, access, MemoryAccessWidth::HalfWord);
         let value = self.read_16(address) as u32;
         self.latch_dma_open_bus(access, (value << 16) | value);
+        self.watch(address, 2, access, AccessKind::Read, value);
+        self.cover_read(address, 2, access);
+        self.serial_loaded(address, 2);
         value
     }
 
@@ -69,6 +88,9 @@ impl MemoryInterface for SystemBus {
         self.cycle(address, access, MemoryAccessWidth::Word);
         let value = self.read_32(address);
         self.latch_dma_open_bus(access, value);
+        self.watch(address, 4, access, AccessKind::Read, value);
+        self.cover_read(address, 4, access);
+        self.serial_loaded(address, 4);
         value
     }
 
@@ -76,6 +98,7 @@ impl MemoryInterface for SystemBus {
         self.cycle(address, access, MemoryAccessWidth::Byte);
         self.write_8(address, value);
         self.latch_dma_open_bus(access, u32::from(value));
+        self.watch(address, 1, access, AccessKind::Write, u32::from(value));
     }
 
     fn store_16(&mut self, address: u32, value: u16, access: u8) {
@@ -83,12 +106,14 @@ impl MemoryInterface for SystemBus {
         self.write_16(address, value);
         let value = u32::from(value);
         self.latch_dma_open_bus(access, (value << 16) | value);
+        self.watch(address, 2, access, AccessKind::Write, value);
     }
 
     fn store_32(&mut self, address: u32, value: u32, access: u8) {
         self.cycle(address, access, MemoryAccessWidth::Word);
         self.write_32(address, value);
         self.latch_dma_open_bus(access, value);
+        self.watch(address, 4, access, AccessKind::Write, value);
     }
 
     fn idle_cycle(&mut self) {
@@ -96,6 +121,7 @@ impl MemoryInterface for SystemBus {
             self.run_dma();
         }
         self.scheduler.borrow_mut().step(1);
+        self.prefetcher.advance(1);
         self.handle_events();
     }
 
@@ -234,6 +260,75 @@ impl SystemBus {
             cpu_context: CpuContext::default(),
             dma_open_bus_value: 0,
             last_access: 0,
+            debugger: Debugger::new(),
+            dma_cycles: 0,
+            coverage: None,
+            prefetcher: Prefetcher::new(),
+        }
+    }
+
+    /// Reads what the cpu would see at `address` without latching anything, for debuggers. The BIOS reads
+    /// back its contents even while the pc is outside it.
+    pub fn peek_8(&self, address: u32) -> u8 {
+        match address & 0xFF000000 {
+            BIOS_BASE => match self.bios.peek_8(address) {
+                Some(value) => value,
+                None => self.open_bus_read(address, MemoryAccessWidth::Byte) as u8,
+            },
+            WRAM_BOARD_BASE | WRAM_CHIP_BASE => self.memory.read_8(address),
+            IO_REGISTERS_BASE | PALETTE_RAM_BASE | VRAM_BASE | OAM_BASE => self.io_registers.peek_8(address),
+            ROM_WS0_LO..=SRAM_HI => self.cartridge.peek_8(address),
+            _ => self.open_bus_read(address, MemoryAccessWidth::Byte) as u8,
+        }
+    }
+
+    /// Writes a single byte for debuggers. Video memory ignores byte stores from the cpu, so its bytes are
+    /// written as part of their halfword, and rom and the BIOS are patched.
+    pub fn poke_8(&mut self, address: u32, value: u8) {
+        match address & 0xFF000000 {
+            BIOS_BASE => self.bios.poke_8(address, value),
+            PALETTE_RAM_BASE | VRAM_BASE | OAM_BASE => {
+                let shift = (address & 1) * 8;
+                let halfword =
+                    self.io_registers.peek_8(address & !1) as u16 | (self.io_registers.peek_8(address | 1) as u16) << 8;
+                let halfword = (halfword & !(0xFF << shift)) | (value as u16) << shift;
+                self.io_registers.write_16(address & !1, halfword);
+            }
+            ROM_WS0_LO..=SRAM_HI => self.cartridge.poke_8(address, value),
+            _ => self.write_8(address, value),
+        }
+    }
+
+    /// Reports data accesses to the debugger. Opcode fetches never trigger watchpoints.
+    #[inline]
+    fn watch(&mut self, address: u32, width: u32, access_pattern: u8, access: AccessKind, value: u32) {
+        if !MemoryAccess::Instruction.is_set(access_pattern) {
+            self.debugger.memory_access(address, width, access, value);
+        }
+    }
+
+    /// Marks `length` bytes of rom at `address` in the coverage map, when one is being recorded.
+    pub fn cover(&mut self, address: u32, length: u32, kind: CoverageKind) {
+        if let Some(coverage) = &mut self.coverage
+            && (ROM_WS0_LO..SRAM_LO).contains(&address)
+        {
+            coverage.mark((address & 0x01FFFFFF) as usize, length as usize, kind);
+        }
+    }
+
+    /// Marks rom the cpu or DMA reads as data. Instruction fetches run ahead of execution, so the system
+    /// marks code as it executes instead.
+    fn cover_read(&mut self, address: u32, width: u32, access_pattern: u8) {
+        if !MemoryAccess::Instruction.is_set(access_pattern) {
+            self.cover(address & !(width - 1), width, CoverageKind::Data);
+        }
+    }
+
+    /// Reading SIODATA8 in UART mode takes the byte it showed off the receive buffer. Only loads do, so
+    /// debuggers and cheats can read it freely.
+    fn serial_loaded(&mut self, address: u32, width: u32) {
+        if SIODATA8.wrapping_sub(address & !(width - 1)) < width {
+            self.io_registers.serial_controller_mut().uart_data_loaded();
         }
     }
 
@@ -303,10 +398,56 @@ impl SystemBus {
 
         let index = ((address >> 24) & 0xF) as usize;
         let cycles = self.io_registers.system_controller().cycles(index, width, access);
+        let cycles = self.prefetch(address, index, access_pattern, width, cycles);
         self.scheduler.borrow_mut().step(cycles);
         self.handle_events();
     }
 
+    /// Runs an access past the game pak prefetch buffer, returning the cycles it takes instead of the
+    /// `cycles` the wait states alone would charge. Any other game pak access, DMA included, empties the
+    /// buffer, and the buffer fills while the bus is busy anywhere else.
+    fn prefetch(
+        &mut self,
+        address: u32,
+        index: usize,
+        access_pattern: u8,
+        width: MemoryAccessWidth,
+        cycles: usize,
+    ) -> usize {
+        let game_pak = address >= ROM_WS0_LO;
+        let rom = address < SRAM_LO;
+        if !game_pak {
+            self.prefetcher.advance(cycles);
+            return cycles;
+        }
+
+        let system_controller = self.io_registers.system_controller();
+        let opcode = rom && MemoryAccess::Instruction.is_set(access_pattern);
+        if !opcode || !system_controller.prefetch_enabled() {
+            self.prefetcher.stop();
+            return cycles;
+        }
+
+        let halfwords = match width {
+            MemoryAccessWidth::Word => 2,
+            MemoryAccessWidth::Byte | MemoryAccessWidth::HalfWord => 1,
+        };
+        if let Some(cycles) = self.prefetcher.fetch(address, halfwords) {
+            return cycles;
+        }
+        let fetch_cycles = system_controller.cycles(index, MemoryAccessWidth::HalfWord, MemoryAccess::Sequential);
+        self.prefetcher.restart(address.wrapping_add(halfwords * 2), fetch_cycles);
+        cycles
+    }
+
+    /// Skips ahead to the next event while the cpu is halted, with the prefetch buffer filling meanwhile.
+    pub fn halt_until_next_event(&mut self) {
+        let cycles = self.scheduler.borrow().cycles_until_next_event();
+        self.scheduler.borrow_mut().step(cycles);
+        self.prefetcher.advance(cycles);
+        self.handle_events();
+    }
+
     pub fn handle_events(&mut self) {
         loop {
             let Some((event, timestamp)) = self.scheduler.borrow_mut().pop() else {
@@ -320,16 +461,28 @@ impl SystemBus {
                 GbaEvent::Apu(apu_event) => self.handle_apu_event(apu_event),
                 GbaEvent::Dma(dma_event) => self.handle_dma_event(dma_event),
                 GbaEvent::Cartridge(cartridge_event) => self.handle_cartridge_event(cartridge_event),
+                GbaEvent::Serial(serial_event) => self.io_registers.serial_controller_mut().handle_event(serial_event),
             }
         }
     }
 
+    /// Lets a transfer another unit on the link cable clocked raise the serial interrupt while stopped.
+    pub fn check_link(&mut self) {
+        self.io_registers.serial_controller_mut().check_link();
+        self.handle_events();
+    }
+
     pub fn interrupt_pending(&self) -> bool {
         self.io_registers.interrupt_controller().interrupt_pending()
     }
 
     pub fn raise_interrupt(&mut self, interrupt_event: InterruptEvent) {
         self.io_registers.interrupt_controller_mut().raise_interrupt(interrupt_event);
+        self.debugger.interrupt_raised(interrupt_event as u8);
+    }
+
+    pub fn wakes_from_stop(&self) -> bool {
+        self.io_registers.interrupt_controller().wakes_from_stop()
     }
 
     pub fn halt_mode(&self) -> HaltMode {
@@ -353,6 +506,11 @@ impl SystemBus {
     }
 
     pub fn handle_dma_event(&mut self, dma_event: DmaEvent) {
+        if let DmaEvent::Activate { channel_id } = dma_event
+            && self.io_registers.dma_controller().channel_enabled(channel_id)
+        {
+            self.debugger.dma_started(channel_id as u8);
+        }
         self.io_registers.dma_controller_mut().handle_event(dma_event);
     }
 
@@ -361,7 +519,9 @@ impl SystemBus {
     }
 
     pub fn run_dma(&mut self) {
+        let start = self.scheduler.borrow().timestamp();
         self.scheduler.borrow_mut().step(1);
+        self.prefetcher.advance(1);
 
         while let Some(transfer) = self.io_registers.dma_controller().next_transfer() {
             match transfer.chunk_size {
@@ -378,16 +538,51 @@ impl SystemBus {
             self.io_registers.dma_controller_mut().complete_transfer();
 
             while let Some(channel_id) = self.io_registers.dma_controller().pending_dma_request() {
-                self.io_registers
-                    .dma_controller_mut()
-                    .handle_event(DmaEvent::Activate { channel_id });
+                self.handle_dma_event(DmaEvent::Activate { channel_id });
             }
         }
 
         self.scheduler.borrow_mut().step(1);
+        self.prefetcher.advance(1);
+        self.dma_cycles += (self.scheduler.borrow().timestamp() - start) as u64;
     }
 
     pub fn dma_active(&self) -> bool {
         self.io_registers.dma_controller().is_active()
     }
+
+    pub fn dma_cycles(&self) -> u64 {
+        self.dma_cycles
+    }
+}
+
+impl Snapshot for SystemBus {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&*self.scheduler.borrow());
+        writer.save(&self.bios);
+        writer.save(&self.memory);
+        writer.save(&self.io_registers);
+        writer.save(&self.cartridge);
+        writer.write(&self.cpu_context.pc);
+        writer.write(&self.cpu_context.cpu_state.into_bits());
+        writer.write(&self.cpu_context.pipeline);
+        writer.write(&self.dma_open_bus_value);
+        writer.write(&self.last_access);
+        writer.save(&self.prefetcher);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut *self.scheduler.borrow_mut())?;
+        reader.load(&mut self.bios)?;
+        reader.load(&mut self.memory)?;
+        reader.load(&mut self.io_registers)?;
+        reader.load(&mut self.cartridge)?;
+        self.cpu_context.pc = reader.read()?;
+        self.cpu_context.cpu_state = CpuState::from_bits(reader.read()?);
+        self.cpu_context.pipeline = reader.read()?;
+        self.dma_open_bus_value = reader.read()?;
+        self.last_access = reader.read()?;
+        reader.load(&mut self.prefetcher)?;
+        Ok(())
+    }
 }
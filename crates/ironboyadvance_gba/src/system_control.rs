@@ -7,6 +7,7 @@ use ironboyadvance_common::{
 use tracing::debug;
 
 use crate::system_bus::{PALETTE_RAM_BASE, ROM_WS0_LO, ROM_WS1_LO, ROM_WS2_LO, SRAM_LO, VRAM_BASE, WRAM_BOARD_BASE};
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const INDEX_WRAM_BOARD: usize = (WRAM_BOARD_BASE >> 24) as usize;
 const INDEX_PALETTE_RAM: usize = (PALETTE_RAM_BASE >> 24) as usize;
@@ -186,8 +187,7 @@ impl RegisterOps<u32> for InternalMemoryControl {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
-#[allow(unused)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum HaltMode {
     Halted,
     Stopped,
@@ -216,6 +216,10 @@ impl SystemController {
         }
     }
 
+    pub fn prefetch_enabled(&self) -> bool {
+        self.waitstate_control.game_pak_prefetch_buffer_enabled()
+    }
+
     pub fn cycles(&self, lut_index: usize, width: MemoryAccessWidth, access: MemoryAccess) -> usize {
         match width {
             MemoryAccessWidth::Byte | MemoryAccessWidth::HalfWord => match access {
@@ -271,7 +275,7 @@ impl SystemMemoryAccess for SystemController {
             0x04000300 => self.post_flag = value & 0x1 != 0,
             // HALTCNT
             0x04000301 => match value & 0x80 != 0 {
-                true => todo!("Stopped"),
+                true => self.halt_mode = HaltMode::Stopped,
                 false => self.halt_mode = HaltMode::Halted,
             },
             // Undocumented - Purpose Unknown
@@ -293,6 +297,33 @@ impl SystemMemoryAccess for SystemController {
     }
 }
 
+impl Snapshot for SystemController {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.waitstate_control.into_bits());
+        writer.write(&self.post_flag);
+        writer.write(&(self.halt_mode as u8));
+        writer.write(&self.internal_memory_control.into_bits());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.waitstate_control = WaitStateControl::from_bits(reader.read()?);
+        self.post_flag = reader.read()?;
+        self.halt_mode = match reader.read::<u8>()? {
+            0 => HaltMode::Halted,
+            1 => HaltMode::Stopped,
+            2 => HaltMode::Running,
+            _ => return Err(SnapshotError::InvalidValue("halt mode")),
+        };
+        self.internal_memory_control = InternalMemoryControl::from_bits(reader.read()?);
+
+        self.cycle_luts = ClockCycleLuts::new();
+        self.cycle_luts.update_gamepak_wait_states(&self.waitstate_control);
+        self.cycle_luts
+            .update_wram_wait_states(self.internal_memory_control.wait_control_wram());
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4,6 +4,7 @@ use bitfields::bitfield;
 use ironboyadvance_common::{memory::SystemMemoryAccess, register_ops::RegisterOps, scheduler::Scheduler};
 
 use crate::events::{ApuEvent, GbaEvent, InterruptEvent, TimerEvent};
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const PRESCALER_SELECTIONS: [usize; 4] = [1, 64, 256, 1024];
 
@@ -257,3 +258,26 @@ impl SystemMemoryAccess for TimerController {
         }
     }
 }
+
+impl Snapshot for TimerController {
+    fn save_state(&self, writer: &mut StateWriter) {
+        for timer in self.timers.iter() {
+            writer.write(&timer.counter);
+            writer.write(&timer.reload);
+            writer.write(&timer.control.into_bits());
+            writer.write(&timer.start_time);
+            writer.write(&timer.active);
+        }
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        for timer in self.timers.iter_mut() {
+            timer.counter = reader.read()?;
+            timer.reload = reader.read()?;
+            timer.control = TimerControl::from_bits(reader.read()?);
+            timer.start_time = reader.read()?;
+            timer.active = reader.read()?;
+        }
+        Ok(())
+    }
+}
@@ -14,6 +14,7 @@ use crate::{
     },
     events::{ApuEvent, GbcEvent},
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 mod high_pass_filter;
 mod length;
@@ -247,3 +248,31 @@ impl SystemMemoryAccess for Apu {
         }
     }
 }
+
+impl Snapshot for Apu {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.ch1);
+        writer.save(&self.ch2);
+        writer.save(&self.ch3);
+        writer.save(&self.ch4);
+        writer.write(&self.master_volume.into_bits());
+        writer.write(&self.panning.into_bits());
+        writer.write(&self.enabled);
+        writer.write(&self.frame_sequencer_step);
+        writer.save(&self.high_pass);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.ch1)?;
+        reader.load(&mut self.ch2)?;
+        reader.load(&mut self.ch3)?;
+        reader.load(&mut self.ch4)?;
+        self.master_volume = MasterVolume::from_bits(reader.read()?);
+        self.panning = SoundPanning::from_bits(reader.read()?);
+        self.enabled = reader.read()?;
+        self.frame_sequencer_step = reader.read::<usize>()? % FRAME_SEQUENCER_STEPS;
+        reader.load(&mut self.high_pass)?;
+        self.audio_buffer.clear();
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use getset::{CopyGetters, Setters};
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 pub const WAVE_MAX_LENGTH: u16 = 256;
 pub const DEFAULT_MAX_LENGTH: u16 = 64;
@@ -53,3 +54,16 @@ impl Length {
         self.time == self.max_length
     }
 }
+
+impl Snapshot for Length {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.initial_time);
+        writer.write(&self.time);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.initial_time = reader.read()?;
+        self.time = reader.read()?;
+        Ok(())
+    }
+}
@@ -7,6 +7,7 @@ use crate::apu::{
     period::Period,
     volume_envelope::VolumeEnvelope,
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const CONTROL_UNUSED_BITS: u8 = 0xBF;
 const WRITE_ONLY: u8 = 0xFF;
@@ -210,3 +211,30 @@ impl NoiseChannel {
         }
     }
 }
+
+impl Snapshot for NoiseChannel {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.enabled);
+        writer.write(&self.dac_enabled);
+        writer.save(&self.length);
+        writer.save(&self.envelope);
+        writer.save(&self.period);
+        writer.write(&self.lfsr);
+        writer.write(&self.polynomial.into_bits());
+        writer.write(&self.control.into_bits());
+        writer.write(&self.frame_sequencer_step);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.enabled = reader.read()?;
+        self.dac_enabled = reader.read()?;
+        reader.load(&mut self.length)?;
+        reader.load(&mut self.envelope)?;
+        reader.load(&mut self.period)?;
+        self.lfsr = reader.read()?;
+        self.polynomial = Polynomial::from_bits(reader.read()?);
+        self.control = NoiseControl::from_bits(reader.read()?);
+        self.frame_sequencer_step = reader.read()?;
+        Ok(())
+    }
+}
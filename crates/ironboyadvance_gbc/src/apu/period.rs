@@ -1,4 +1,5 @@
 use getset::CopyGetters;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 pub const WAVE_TRIGGER_DELAY: u16 = 6;
 
@@ -47,3 +48,16 @@ impl Period {
         self.timer += WAVE_TRIGGER_DELAY;
     }
 }
+
+impl Snapshot for Period {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.timer);
+        writer.write(&self.reloaded);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.timer = reader.read()?;
+        self.reloaded = reader.read()?;
+        Ok(())
+    }
+}
@@ -8,6 +8,7 @@ use crate::apu::{
     sweep::Sweep,
     volume_envelope::VolumeEnvelope,
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const LENGTH_UNUSED_BITS: u8 = 0x3F;
 const PERIOD_HIGH_UNUSED_BITS: u8 = 0xBF;
@@ -244,3 +245,38 @@ impl PulseChannel {
         }
     }
 }
+
+impl Snapshot for PulseChannel {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.enabled);
+        writer.write(&self.dac_enabled);
+        writer.write(&self.wave_duty_position);
+        if let Some(sweep) = &self.sweep {
+            writer.save(sweep);
+        }
+        writer.save(&self.length);
+        writer.save(&self.envelope);
+        writer.save(&self.period);
+        writer.write(&self.control.into_bits());
+        writer.write(&self.period_low);
+        writer.write(&self.period_high.into_bits());
+        writer.write(&self.frame_sequencer_step);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.enabled = reader.read()?;
+        self.dac_enabled = reader.read()?;
+        self.wave_duty_position = reader.read()?;
+        if let Some(sweep) = &mut self.sweep {
+            reader.load(sweep)?;
+        }
+        reader.load(&mut self.length)?;
+        reader.load(&mut self.envelope)?;
+        reader.load(&mut self.period)?;
+        self.control = PulseLength::from_bits(reader.read()?);
+        self.period_low = reader.read()?;
+        self.period_high = PeriodHigh::from_bits(reader.read()?);
+        self.frame_sequencer_step = reader.read()?;
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use bitfields::{bitfield, bitflag};
 use getset::CopyGetters;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[bitflag(u8)]
 #[derive(Debug, PartialEq, Eq)]
@@ -74,3 +75,18 @@ impl VolumeEnvelope {
         self.control.initial_volume() == 0 && self.control.direction() == EnvelopeDirection::Decrease
     }
 }
+
+impl Snapshot for VolumeEnvelope {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.control.into_bits());
+        writer.write(&self.volume);
+        writer.write(&self.timer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.control = EnvelopeControl::from_bits(reader.read()?);
+        self.volume = reader.read()?;
+        self.timer = reader.read()?;
+        Ok(())
+    }
+}
@@ -7,6 +7,7 @@ use crate::apu::{
     length::{Length, WAVE_MAX_LENGTH},
     period::Period,
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const DAC_UNUSED_BITS: u8 = 0x7F;
 const VOLUME_UNUSED_BITS: u8 = 0x9F;
@@ -261,3 +262,32 @@ impl WaveChannel {
         }
     }
 }
+
+impl Snapshot for WaveChannel {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.enabled);
+        writer.write(&self.dac_control.into_bits());
+        writer.save(&self.length);
+        writer.save(&self.period);
+        writer.write(&self.wave_position);
+        writer.write_bytes(&self.wave_ram);
+        writer.write(&self.volume.into_bits());
+        writer.write(&self.period_low);
+        writer.write(&self.period_high.into_bits());
+        writer.write(&self.frame_sequencer_step);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.enabled = reader.read()?;
+        self.dac_control = DacControl::from_bits(reader.read()?);
+        reader.load(&mut self.length)?;
+        reader.load(&mut self.period)?;
+        self.wave_position = reader.read::<u8>()? % WAVE_SAMPLES;
+        reader.read_bytes_into(&mut self.wave_ram)?;
+        self.volume = WaveVolume::from_bits(reader.read()?);
+        self.period_low = reader.read()?;
+        self.period_high = PeriodHigh::from_bits(reader.read()?);
+        self.frame_sequencer_step = reader.read()?;
+        Ok(())
+    }
+}
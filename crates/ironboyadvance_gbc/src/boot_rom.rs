@@ -1,5 +1,6 @@
 use getset::CopyGetters;
 use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 use thiserror::Error;
 
 const DMG_BOOT_ROM_SIZE: usize = 0x0100;
@@ -65,3 +66,14 @@ impl SystemMemoryAccess for BootRom {
         }
     }
 }
+
+impl Snapshot for BootRom {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.mapped);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.mapped = reader.read::<bool>()? && self.loaded;
+        Ok(())
+    }
+}
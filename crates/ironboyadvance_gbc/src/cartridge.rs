@@ -1,17 +1,28 @@
-use std::path::PathBuf;
+use std::{cell::RefCell, rc::Rc};
 
 use getset::CopyGetters;
-use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::{
+    memory::SystemMemoryAccess,
+    save_storage::SaveStorage,
+    scheduler::Scheduler,
+    snapshot::{Snapshot, SnapshotError, StateReader, StateWriter},
+};
 use ironboyadvance_sm83::GbMode;
 use thiserror::Error;
 
-use crate::cartridge::{
-    header::{CartridgeType, Header},
-    mbc1::Mbc1,
-    mbc2::Mbc2,
-    mbc3::Mbc3,
-    mbc5::Mbc5,
-    no_mbc::NoMbc,
+use crate::{
+    cartridge::{
+        backup_file::BackupFile,
+        header::{CartridgeType, Header},
+        mbc1::Mbc1,
+        mbc2::Mbc2,
+        mbc3::Mbc3,
+        mbc5::Mbc5,
+        no_mbc::NoMbc,
+        rtc::RealTimeClock,
+    },
+    cheats::RomPatch,
+    events::GbcEvent,
 };
 
 mod backup_file;
@@ -22,6 +33,9 @@ mod mbc3;
 mod mbc5;
 mod no_mbc;
 mod rtc;
+mod save_format;
+
+pub use save_format::{export_save, import_save};
 
 const ROM_BANK_SIZE: usize = 0x4000;
 
@@ -33,15 +47,41 @@ pub enum CartridgeError {
     SaveSizeMismatch,
     #[error("Save file I/O failed: {0}")]
     SaveIo(#[from] std::io::Error),
+    #[error("Invalid save file: {0}")]
+    InvalidSaveFile(&'static str),
 }
 
-pub trait MemoryBankController: SystemMemoryAccess<Address = u16> {
+pub trait MemoryBankController: SystemMemoryAccess<Address = u16> + Snapshot {
     fn rom(&self) -> &[u8];
 
+    fn rom_patches(&self) -> &[RomPatch];
+
+    fn set_rom_patches(&mut self, rom_patches: Vec<RomPatch>);
+
+    /// The rom bank mapped in at `address`, which is below 0x8000.
+    fn rom_bank(&self, address: u16) -> usize;
+
+    /// Reads `address` from rom `bank`, as patched by any Game Genie codes.
     fn rom_read(&self, bank: usize, address: u16) -> u8 {
         let offset = (bank * ROM_BANK_SIZE) | (address as usize & (ROM_BANK_SIZE - 1));
-        *self.rom().get(offset).unwrap_or(&0xFF)
+        let value = *self.rom().get(offset).unwrap_or(&0xFF);
+        self.rom_patches()
+            .iter()
+            .find_map(|patch| patch.apply(address, value))
+            .unwrap_or(value)
+    }
+
+    /// Cartridge RAM, its banks laid out one after another.
+    fn ram(&self) -> &BackupFile;
+
+    fn ram_mut(&mut self) -> &mut BackupFile;
+
+    /// Bytes of [`MemoryBankController::ram`] the banks cover, leaving out state kept after them like a clock's.
+    fn ram_size(&self) -> usize {
+        self.ram().len()
     }
+
+    fn flush_backup(&mut self) {}
 }
 
 #[derive(CopyGetters)]
@@ -52,23 +92,28 @@ pub struct Cartridge {
 }
 
 impl Cartridge {
-    pub fn load(rom_file: PathBuf, buffer: Vec<u8>) -> Result<Cartridge, CartridgeError> {
+    pub fn load(
+        buffer: Vec<u8>,
+        save_storage: Box<dyn SaveStorage>,
+        base_unix_seconds: u64,
+        scheduler: Rc<RefCell<Scheduler<GbcEvent>>>,
+    ) -> Result<Cartridge, CartridgeError> {
         let header = Header::load(&buffer[0x000..=0x014F])?;
-        let save_file = rom_file.with_extension("sav");
         let rom_banks = header.rom_banks();
         let ram_banks = header.ram_banks();
 
         let mbc: Box<dyn MemoryBankController> = match header.cartridge_type() {
-            CartridgeType::NoMbc { ram, battery } => Box::new(NoMbc::new(buffer, ram, battery, &save_file)?),
+            CartridgeType::NoMbc { ram, battery } => Box::new(NoMbc::new(buffer, ram, battery, save_storage)?),
             CartridgeType::Mbc1 { ram, battery } => {
-                Box::new(Mbc1::new(buffer, rom_banks, ram_banks, ram, battery, &save_file)?)
+                Box::new(Mbc1::new(buffer, rom_banks, ram_banks, ram, battery, save_storage)?)
             }
-            CartridgeType::Mbc2 { battery } => Box::new(Mbc2::new(buffer, rom_banks, battery, &save_file)?),
+            CartridgeType::Mbc2 { battery } => Box::new(Mbc2::new(buffer, rom_banks, battery, save_storage)?),
             CartridgeType::Mbc3 { ram, battery, timer } => {
-                Box::new(Mbc3::new(buffer, ram_banks, ram, battery, timer, &save_file)?)
+                let rtc = RealTimeClock::new(timer, base_unix_seconds, scheduler);
+                Box::new(Mbc3::new(buffer, ram_banks, ram, battery, save_storage, rtc)?)
             }
             CartridgeType::Mbc5 { ram, battery, .. } => {
-                Box::new(Mbc5::new(buffer, rom_banks, ram_banks, ram, battery, &save_file)?)
+                Box::new(Mbc5::new(buffer, rom_banks, ram_banks, ram, battery, save_storage)?)
             }
         };
 
@@ -77,6 +122,37 @@ impl Cartridge {
             mode: header.mode(),
         })
     }
+
+    pub fn flush_backup(&mut self) {
+        self.mbc.flush_backup();
+    }
+
+    pub fn set_rom_patches(&mut self, rom_patches: Vec<RomPatch>) {
+        self.mbc.set_rom_patches(rom_patches);
+    }
+
+    pub fn ram_size(&self) -> usize {
+        self.mbc.ram_size()
+    }
+
+    /// Reads cartridge RAM at `offset` into its banks, whether or not the bank is mapped in or RAM is enabled.
+    pub fn peek_ram(&self, offset: usize) -> u8 {
+        match offset < self.mbc.ram_size() {
+            true => self.mbc.ram().read(offset),
+            false => 0xFF,
+        }
+    }
+
+    pub fn poke_ram(&mut self, offset: usize, value: u8) {
+        if offset < self.mbc.ram_size() {
+            self.mbc.ram_mut().write(offset, value);
+        }
+    }
+
+    /// Where in the rom file `address`, which is below 0x8000, reads from with the banks mapped in now.
+    pub fn rom_offset(&self, address: u16) -> usize {
+        (self.mbc.rom_bank(address) * ROM_BANK_SIZE) | (address as usize & (ROM_BANK_SIZE - 1))
+    }
 }
 
 impl SystemMemoryAccess for Cartridge {
@@ -90,3 +166,13 @@ impl SystemMemoryAccess for Cartridge {
         self.mbc.write_8(address, value)
     }
 }
+
+impl Snapshot for Cartridge {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(self.mbc.as_ref());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(self.mbc.as_mut())
+    }
+}
@@ -1,51 +1,46 @@
-use std::{
-    fs::{File, OpenOptions},
-    io::{Read, Seek, SeekFrom, Write},
-    path::Path,
-};
+use std::ops::Range;
 
+use ironboyadvance_common::{
+    save_storage::SaveStorage,
+    snapshot::{Snapshot, SnapshotError, StateReader, StateWriter},
+};
 use tracing::warn;
 
 use crate::cartridge::CartridgeError;
 
+/// Cartridge RAM, persisted when the cartridge has a battery. Writes only touch the buffer and widen a
+/// dirty range until the cartridge is flushed at a frame boundary.
 pub struct BackupFile {
     buffer: Vec<u8>,
-    file: Option<File>,
+    dirty: Option<Range<usize>>,
+    storage: Option<Box<dyn SaveStorage>>,
 }
 
 impl BackupFile {
-    pub fn open(path: &Path, size: usize, fill: u8) -> Result<Self, CartridgeError> {
-        let mut file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(path)?;
-
-        let buffer = match file.metadata()?.len() as usize {
+    pub fn open(mut storage: Box<dyn SaveStorage>, size: usize, fill: u8) -> Result<Self, CartridgeError> {
+        let saved = storage.load()?;
+        let buffer = match saved.len() {
             0 => {
                 let buffer = vec![fill; size];
-                file.write_all(&buffer)?;
-                buffer
-            }
-            length if length == size => {
-                let mut buffer = vec![0; size];
-                file.read_exact(&mut buffer)?;
+                storage.store(0, &buffer)?;
                 buffer
             }
+            length if length == size => saved,
             _ => return Err(CartridgeError::SaveSizeMismatch),
         };
 
         Ok(Self {
             buffer,
-            file: Some(file),
+            dirty: None,
+            storage: Some(storage),
         })
     }
 
     pub fn memory(size: usize, fill: u8) -> Self {
         Self {
             buffer: vec![fill; size],
-            file: None,
+            dirty: None,
+            storage: None,
         }
     }
 
@@ -59,16 +54,59 @@ impl BackupFile {
 
     pub fn write(&mut self, offset: usize, value: u8) {
         self.buffer[offset] = value;
+        self.mark_dirty(offset..offset + 1);
+    }
 
-        let Some(file) = self.file.as_mut() else {
+    fn mark_dirty(&mut self, range: Range<usize>) {
+        if self.storage.is_none() {
+            return;
+        }
+
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+            None => range,
+        });
+    }
+
+    pub fn flush(&mut self) {
+        let Some(storage) = self.storage.as_mut() else {
             return;
         };
 
-        if let Err(error) = file
-            .seek(SeekFrom::Start(offset as u64))
-            .and_then(|_| file.write_all(&[value]))
+        let result = match self.dirty.take() {
+            Some(dirty) => storage.store(dirty.start, &self.buffer[dirty]),
+            None => Ok(()),
+        };
+
+        if let Err(error) = result.and_then(|_| storage.flush()) {
+            warn!("backup flush failed: {}", error);
+        }
+    }
+}
+
+impl Snapshot for BackupFile {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.buffer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        let mut loaded = vec![0; self.buffer.len()];
+        reader.read_bytes_into(&mut loaded)?;
+        // Only what the state changed goes back to the save file, so loading a state or rewinding over
+        // untouched cartridge RAM does not rewrite it.
+        let changed = |(old, new): (&u8, &u8)| old != new;
+        if let Some(start) = self.buffer.iter().zip(&loaded).position(changed)
+            && let Some(end) = self.buffer.iter().zip(&loaded).rposition(changed)
         {
-            warn!("backup write failed at offset {:#06X}: {}", offset, error);
+            self.buffer.copy_from_slice(&loaded);
+            self.mark_dirty(start..end + 1);
         }
+        Ok(())
+    }
+}
+
+impl Drop for BackupFile {
+    fn drop(&mut self) {
+        self.flush();
     }
 }
@@ -1,13 +1,14 @@
-use std::path::Path;
-
-use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::{memory::SystemMemoryAccess, save_storage::SaveStorage};
 
 use super::{CartridgeError, MemoryBankController, backup_file::BackupFile};
+use crate::cheats::RomPatch;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const RAM_BANK_SIZE: usize = 0x2000;
 
 pub struct Mbc1 {
     rom: Vec<u8>,
+    rom_patches: Vec<RomPatch>,
     ram: BackupFile,
     ram_enabled: bool,
     banking_mode: u8,
@@ -24,7 +25,7 @@ impl Mbc1 {
         ram_banks: usize,
         has_ram: bool,
         has_battery: bool,
-        save_file: &Path,
+        save_storage: Box<dyn SaveStorage>,
     ) -> Result<Mbc1, CartridgeError> {
         let ram_banks = match has_ram {
             true => ram_banks,
@@ -32,12 +33,13 @@ impl Mbc1 {
         };
 
         let ram = match has_battery {
-            true => BackupFile::open(save_file, ram_banks * RAM_BANK_SIZE, 0x00)?,
+            true => BackupFile::open(save_storage, ram_banks * RAM_BANK_SIZE, 0x00)?,
             false => BackupFile::memory(ram_banks * RAM_BANK_SIZE, 0x00),
         };
 
         Ok(Mbc1 {
             rom: buffer,
+            rom_patches: Vec::new(),
             ram,
             ram_enabled: false,
             banking_mode: 0,
@@ -63,14 +65,7 @@ impl SystemMemoryAccess for Mbc1 {
 
     fn read_8(&self, address: u16) -> u8 {
         match address {
-            0x0000..=0x3FFF => {
-                let bank = match self.banking_mode {
-                    0 => 0,
-                    _ => self.current_rom_bank & 0xE0,
-                };
-                self.rom_read(bank, address)
-            }
-            0x4000..=0x7FFF => self.rom_read(self.current_rom_bank, address),
+            0x0000..=0x7FFF => self.rom_read(self.rom_bank(address), address),
             0xA000..=0xBFFF => {
                 let offset = self.ram_offset(address);
                 match self.ram_enabled && offset < self.ram.len() {
@@ -114,7 +109,54 @@ impl SystemMemoryAccess for Mbc1 {
 }
 
 impl MemoryBankController for Mbc1 {
+    fn ram(&self) -> &BackupFile {
+        &self.ram
+    }
+
+    fn ram_mut(&mut self) -> &mut BackupFile {
+        &mut self.ram
+    }
+
     fn rom(&self) -> &[u8] {
         &self.rom
     }
+
+    fn rom_patches(&self) -> &[RomPatch] {
+        &self.rom_patches
+    }
+
+    fn set_rom_patches(&mut self, rom_patches: Vec<RomPatch>) {
+        self.rom_patches = rom_patches;
+    }
+
+    fn rom_bank(&self, address: u16) -> usize {
+        match (address, self.banking_mode) {
+            (0x4000..=0x7FFF, _) => self.current_rom_bank,
+            (_, 0) => 0,
+            _ => self.current_rom_bank & 0xE0,
+        }
+    }
+
+    fn flush_backup(&mut self) {
+        self.ram.flush();
+    }
+}
+
+impl Snapshot for Mbc1 {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.ram);
+        writer.write(&self.ram_enabled);
+        writer.write(&self.banking_mode);
+        writer.write(&self.current_rom_bank);
+        writer.write(&self.current_ram_bank);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.ram)?;
+        self.ram_enabled = reader.read()?;
+        self.banking_mode = reader.read::<u8>()? & 0x01;
+        self.current_rom_bank = reader.read::<usize>()? % self.rom_banks;
+        self.current_ram_bank = reader.read::<usize>()? & 0x03;
+        Ok(())
+    }
 }
@@ -1,14 +1,15 @@
-use std::path::Path;
-
-use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::{memory::SystemMemoryAccess, save_storage::SaveStorage};
 
 use super::{CartridgeError, MemoryBankController, backup_file::BackupFile};
+use crate::cheats::RomPatch;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
-const BUILT_IN_RAM_SIZE: usize = 512;
+pub(super) const BUILT_IN_RAM_SIZE: usize = 512;
 const UNUSED_RAM_BITS: u8 = 0xF0;
 
 pub struct Mbc2 {
     rom: Vec<u8>,
+    rom_patches: Vec<RomPatch>,
     ram: BackupFile,
     ram_enabled: bool,
     current_rom_bank: usize,
@@ -16,14 +17,20 @@ pub struct Mbc2 {
 }
 
 impl Mbc2 {
-    pub fn new(buffer: Vec<u8>, rom_banks: usize, has_battery: bool, save_file: &Path) -> Result<Mbc2, CartridgeError> {
+    pub fn new(
+        buffer: Vec<u8>,
+        rom_banks: usize,
+        has_battery: bool,
+        save_storage: Box<dyn SaveStorage>,
+    ) -> Result<Mbc2, CartridgeError> {
         let ram = match has_battery {
-            true => BackupFile::open(save_file, BUILT_IN_RAM_SIZE, UNUSED_RAM_BITS)?,
+            true => BackupFile::open(save_storage, BUILT_IN_RAM_SIZE, UNUSED_RAM_BITS)?,
             false => BackupFile::memory(BUILT_IN_RAM_SIZE, UNUSED_RAM_BITS),
         };
 
         Ok(Mbc2 {
             rom: buffer,
+            rom_patches: Vec::new(),
             ram,
             ram_enabled: false,
             current_rom_bank: 1,
@@ -37,8 +44,7 @@ impl SystemMemoryAccess for Mbc2 {
 
     fn read_8(&self, address: u16) -> u8 {
         match address {
-            0x0000..=0x3FFF => self.rom_read(0, address),
-            0x4000..=0x7FFF => self.rom_read(self.current_rom_bank, address),
+            0x0000..=0x7FFF => self.rom_read(self.rom_bank(address), address),
             0xA000..=0xBFFF => match self.ram_enabled {
                 true => self.ram.read(address as usize & (BUILT_IN_RAM_SIZE - 1)) | UNUSED_RAM_BITS,
                 false => 0xFF,
@@ -71,7 +77,49 @@ impl SystemMemoryAccess for Mbc2 {
 }
 
 impl MemoryBankController for Mbc2 {
+    fn ram(&self) -> &BackupFile {
+        &self.ram
+    }
+
+    fn ram_mut(&mut self) -> &mut BackupFile {
+        &mut self.ram
+    }
+
     fn rom(&self) -> &[u8] {
         &self.rom
     }
+
+    fn rom_patches(&self) -> &[RomPatch] {
+        &self.rom_patches
+    }
+
+    fn set_rom_patches(&mut self, rom_patches: Vec<RomPatch>) {
+        self.rom_patches = rom_patches;
+    }
+
+    fn rom_bank(&self, address: u16) -> usize {
+        match address {
+            0x4000..=0x7FFF => self.current_rom_bank,
+            _ => 0,
+        }
+    }
+
+    fn flush_backup(&mut self) {
+        self.ram.flush();
+    }
+}
+
+impl Snapshot for Mbc2 {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.ram);
+        writer.write(&self.ram_enabled);
+        writer.write(&self.current_rom_bank);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.ram)?;
+        self.ram_enabled = reader.read()?;
+        self.current_rom_bank = reader.read::<usize>()? % self.rom_banks;
+        Ok(())
+    }
 }
@@ -1,16 +1,17 @@
-use std::path::Path;
-
-use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::{memory::SystemMemoryAccess, save_storage::SaveStorage};
 
 use super::rtc::RealTimeClock;
 use super::{CartridgeError, MemoryBankController, backup_file::BackupFile};
+use crate::cheats::RomPatch;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
-const RAM_BANK_SIZE: usize = 0x2000;
-const CLOCK_BYTES: usize = 8;
+pub(super) const RAM_BANK_SIZE: usize = 0x2000;
+pub(super) const CLOCK_BYTES: usize = 8;
 const CLOCK_REGISTERS: usize = 5;
 
 pub struct Mbc3 {
     rom: Vec<u8>,
+    rom_patches: Vec<RomPatch>,
     ram: BackupFile,
     ram_enabled: bool,
     current_rom_bank: usize,
@@ -26,9 +27,10 @@ impl Mbc3 {
         ram_banks: usize,
         has_ram: bool,
         has_battery: bool,
-        has_real_time_clock: bool,
-        save_file: &Path,
+        save_storage: Box<dyn SaveStorage>,
+        mut rtc: RealTimeClock,
     ) -> Result<Mbc3, CartridgeError> {
+        let has_real_time_clock = rtc.time().is_some();
         let ram_banks = match has_ram {
             true => ram_banks,
             false => 0,
@@ -41,12 +43,11 @@ impl Mbc3 {
 
         let size = ram_banks * RAM_BANK_SIZE + clock_bytes;
         let ram = match has_battery {
-            true => BackupFile::open(save_file, size, 0x00)?,
+            true => BackupFile::open(save_storage, size, 0x00)?,
             false => BackupFile::memory(size, 0x00),
         };
 
-        let mut rtc = RealTimeClock::new(has_real_time_clock);
-        if has_real_time_clock && rtc.time().is_some() {
+        if has_real_time_clock {
             let mut clock = [0; CLOCK_BYTES];
             for (offset, byte) in clock.iter_mut().enumerate() {
                 *byte = ram.read(ram_banks * RAM_BANK_SIZE + offset);
@@ -56,6 +57,7 @@ impl Mbc3 {
 
         Ok(Mbc3 {
             rom: buffer,
+            rom_patches: Vec::new(),
             ram,
             ram_enabled: false,
             current_rom_bank: 1,
@@ -87,8 +89,7 @@ impl SystemMemoryAccess for Mbc3 {
 
     fn read_8(&self, address: u16) -> u8 {
         match address {
-            0x0000..=0x3FFF => self.rom_read(0, address),
-            0x4000..=0x7FFF => self.rom_read(self.current_rom_bank, address),
+            0x0000..=0x7FFF => self.rom_read(self.rom_bank(address), address),
             0xA000..=0xBFFF => match (self.ram_enabled, self.select_rtc_register) {
                 (false, _) => 0xFF,
                 (true, false) if self.current_ram_bank < self.ram_banks => self.ram.read(self.ram_offset(address)),
@@ -139,7 +140,58 @@ impl SystemMemoryAccess for Mbc3 {
 }
 
 impl MemoryBankController for Mbc3 {
+    fn ram(&self) -> &BackupFile {
+        &self.ram
+    }
+
+    fn ram_mut(&mut self) -> &mut BackupFile {
+        &mut self.ram
+    }
+
+    fn ram_size(&self) -> usize {
+        self.ram_banks * RAM_BANK_SIZE
+    }
+
     fn rom(&self) -> &[u8] {
         &self.rom
     }
+
+    fn rom_patches(&self) -> &[RomPatch] {
+        &self.rom_patches
+    }
+
+    fn set_rom_patches(&mut self, rom_patches: Vec<RomPatch>) {
+        self.rom_patches = rom_patches;
+    }
+
+    fn rom_bank(&self, address: u16) -> usize {
+        match address {
+            0x4000..=0x7FFF => self.current_rom_bank,
+            _ => 0,
+        }
+    }
+
+    fn flush_backup(&mut self) {
+        self.ram.flush();
+    }
+}
+
+impl Snapshot for Mbc3 {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.ram);
+        writer.write(&self.ram_enabled);
+        writer.write(&self.current_rom_bank);
+        writer.write(&self.current_ram_bank);
+        writer.write(&self.select_rtc_register);
+        writer.save(&self.rtc);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.ram)?;
+        self.ram_enabled = reader.read()?;
+        self.current_rom_bank = reader.read::<usize>()? & 0x7F;
+        self.current_ram_bank = reader.read::<usize>()? & 0x07;
+        self.select_rtc_register = reader.read()?;
+        reader.load(&mut self.rtc)
+    }
 }
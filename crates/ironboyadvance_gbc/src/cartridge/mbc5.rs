@@ -1,13 +1,14 @@
-use std::path::Path;
-
-use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::{memory::SystemMemoryAccess, save_storage::SaveStorage};
 
 use super::{CartridgeError, MemoryBankController, backup_file::BackupFile};
+use crate::cheats::RomPatch;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const RAM_BANK_SIZE: usize = 0x2000;
 
 pub struct Mbc5 {
     rom: Vec<u8>,
+    rom_patches: Vec<RomPatch>,
     ram: BackupFile,
     ram_enabled: bool,
     current_rom_bank: usize,
@@ -23,7 +24,7 @@ impl Mbc5 {
         ram_banks: usize,
         has_ram: bool,
         has_battery: bool,
-        save_file: &Path,
+        save_storage: Box<dyn SaveStorage>,
     ) -> Result<Mbc5, CartridgeError> {
         let ram_banks = match has_ram {
             true => ram_banks,
@@ -31,12 +32,13 @@ impl Mbc5 {
         };
 
         let ram = match has_battery {
-            true => BackupFile::open(save_file, ram_banks * RAM_BANK_SIZE, 0x00)?,
+            true => BackupFile::open(save_storage, ram_banks * RAM_BANK_SIZE, 0x00)?,
             false => BackupFile::memory(ram_banks * RAM_BANK_SIZE, 0x00),
         };
 
         Ok(Mbc5 {
             rom: buffer,
+            rom_patches: Vec::new(),
             ram,
             ram_enabled: false,
             current_rom_bank: 1,
@@ -56,8 +58,7 @@ impl SystemMemoryAccess for Mbc5 {
 
     fn read_8(&self, address: u16) -> u8 {
         match address {
-            0x0000..=0x3FFF => self.rom_read(0, address),
-            0x4000..=0x7FFF => self.rom_read(self.current_rom_bank, address),
+            0x0000..=0x7FFF => self.rom_read(self.rom_bank(address), address),
             0xA000..=0xBFFF => {
                 let offset = self.ram_offset(address);
                 match self.ram_enabled && offset < self.ram.len() {
@@ -94,7 +95,54 @@ impl SystemMemoryAccess for Mbc5 {
 }
 
 impl MemoryBankController for Mbc5 {
+    fn ram(&self) -> &BackupFile {
+        &self.ram
+    }
+
+    fn ram_mut(&mut self) -> &mut BackupFile {
+        &mut self.ram
+    }
+
     fn rom(&self) -> &[u8] {
         &self.rom
     }
+
+    fn rom_patches(&self) -> &[RomPatch] {
+        &self.rom_patches
+    }
+
+    fn set_rom_patches(&mut self, rom_patches: Vec<RomPatch>) {
+        self.rom_patches = rom_patches;
+    }
+
+    fn rom_bank(&self, address: u16) -> usize {
+        match address {
+            0x4000..=0x7FFF => self.current_rom_bank,
+            _ => 0,
+        }
+    }
+
+    fn flush_backup(&mut self) {
+        self.ram.flush();
+    }
+}
+
+impl Snapshot for Mbc5 {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.ram);
+        writer.write(&self.ram_enabled);
+        writer.write(&self.current_rom_bank);
+        writer.write(&self.current_ram_bank);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.ram)?;
+        self.ram_enabled = reader.read()?;
+        self.current_rom_bank = reader.read::<usize>()? % self.rom_banks;
+        self.current_ram_bank = match reader.read::<usize>()? {
+            bank if bank < self.ram_banks.max(1) => bank,
+            _ => return Err(SnapshotError::InvalidValue("ram bank")),
+        };
+        Ok(())
+    }
 }
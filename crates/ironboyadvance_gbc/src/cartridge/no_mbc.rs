@@ -1,29 +1,39 @@
-use std::path::Path;
+use ironboyadvance_common::{memory::SystemMemoryAccess, save_storage::SaveStorage};
 
-use ironboyadvance_common::memory::SystemMemoryAccess;
-
-use super::{CartridgeError, MemoryBankController, backup_file::BackupFile};
+use super::{CartridgeError, MemoryBankController, ROM_BANK_SIZE, backup_file::BackupFile};
+use crate::cheats::RomPatch;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const RAM_BANK_SIZE: usize = 0x2000;
 
 pub struct NoMbc {
     rom: Vec<u8>,
+    rom_patches: Vec<RomPatch>,
     ram: BackupFile,
 }
 
 impl NoMbc {
-    pub fn new(buffer: Vec<u8>, has_ram: bool, has_battery: bool, save_file: &Path) -> Result<NoMbc, CartridgeError> {
+    pub fn new(
+        buffer: Vec<u8>,
+        has_ram: bool,
+        has_battery: bool,
+        save_storage: Box<dyn SaveStorage>,
+    ) -> Result<NoMbc, CartridgeError> {
         let ram_size = match has_ram {
             true => RAM_BANK_SIZE,
             false => 0,
         };
 
         let ram = match has_battery {
-            true => BackupFile::open(save_file, ram_size, 0x00)?,
+            true => BackupFile::open(save_storage, ram_size, 0x00)?,
             false => BackupFile::memory(ram_size, 0x00),
         };
 
-        Ok(NoMbc { rom: buffer, ram })
+        Ok(NoMbc {
+            rom: buffer,
+            rom_patches: Vec::new(),
+            ram,
+        })
     }
 }
 
@@ -32,7 +42,7 @@ impl SystemMemoryAccess for NoMbc {
 
     fn read_8(&self, address: u16) -> u8 {
         match address {
-            0x0000..=0x7FFF => *self.rom.get(address as usize).unwrap_or(&0xFF),
+            0x0000..=0x7FFF => self.rom_read(self.rom_bank(address), address),
             0xA000..=0xBFFF => match self.ram.len() {
                 0 => 0xFF,
                 _ => self.ram.read(address as usize & (RAM_BANK_SIZE - 1)),
@@ -54,7 +64,41 @@ impl SystemMemoryAccess for NoMbc {
 }
 
 impl MemoryBankController for NoMbc {
+    fn ram(&self) -> &BackupFile {
+        &self.ram
+    }
+
+    fn ram_mut(&mut self) -> &mut BackupFile {
+        &mut self.ram
+    }
+
     fn rom(&self) -> &[u8] {
         &self.rom
     }
+
+    fn rom_patches(&self) -> &[RomPatch] {
+        &self.rom_patches
+    }
+
+    fn set_rom_patches(&mut self, rom_patches: Vec<RomPatch>) {
+        self.rom_patches = rom_patches;
+    }
+
+    fn rom_bank(&self, address: u16) -> usize {
+        address as usize / ROM_BANK_SIZE
+    }
+
+    fn flush_backup(&mut self) {
+        self.ram.flush();
+    }
+}
+
+impl Snapshot for NoMbc {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.ram);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.ram)
+    }
 }
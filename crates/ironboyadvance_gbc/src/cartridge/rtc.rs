@@ -1,21 +1,40 @@
-use std::time;
+use std::{cell::RefCell, rc::Rc};
 
+use ironboyadvance_common::{
+    scheduler::Scheduler,
+    snapshot::{Snapshot, SnapshotError, StateReader, StateWriter},
+};
+use ironboyadvance_sm83::CPU_CLOCK_SPEED;
+
+use crate::events::GbcEvent;
+
+/// The MBC3 clock. `time` is the unix time the counter started from, and the current time is
+/// `base_unix_seconds` advanced by the emulated cycles, so the clock is the same on every run from the same
+/// start, e.g. when replaying a movie.
 pub struct RealTimeClock {
+    scheduler: Rc<RefCell<Scheduler<GbcEvent>>>,
+    base_unix_seconds: u64,
     registers: [u8; 5],
     latch_registers: [u8; 5],
     time: Option<u64>,
 }
 
 impl RealTimeClock {
-    pub fn new(has_real_time_clock: bool) -> Self {
+    pub fn new(has_real_time_clock: bool, base_unix_seconds: u64, scheduler: Rc<RefCell<Scheduler<GbcEvent>>>) -> Self {
         let time = if has_real_time_clock { Some(0) } else { None };
         RealTimeClock {
+            scheduler,
+            base_unix_seconds,
             registers: [0u8; 5],
             latch_registers: [0u8; 5],
             time,
         }
     }
 
+    fn current_unix_seconds(&self) -> u64 {
+        self.base_unix_seconds + (self.scheduler.borrow().timestamp() / CPU_CLOCK_SPEED as usize) as u64
+    }
+
     pub fn set_register(&mut self, address: usize, value: u8) {
         self.registers[address] = value;
     }
@@ -34,19 +53,17 @@ impl RealTimeClock {
             return;
         }
 
-        let current_time = match self.time {
-            Some(t) => time::UNIX_EPOCH + time::Duration::from_secs(t),
-            None => return,
+        let Some(start) = self.time else {
+            return;
         };
 
         if self.calculate_time() == self.time {
             return;
         }
 
-        let time = match time::SystemTime::now().duration_since(current_time) {
-            Ok(n) => n.as_secs(),
-            _ => 0,
-        };
+        // The counter can start before the unix epoch when the game sets it ahead of an early base time,
+        // so the arithmetic wraps rather than overflowing.
+        let time = self.current_unix_seconds().wrapping_sub(start);
         self.registers[0] = (time % 60) as u8;
         self.registers[1] = ((time / 60) % 60) as u8;
         self.registers[2] = ((time / 3600) % 24) as u8;
@@ -65,16 +82,7 @@ impl RealTimeClock {
 
     fn calculate_time(&self) -> Option<u64> {
         self.time?;
-        let mut time = match time::SystemTime::now().duration_since(time::UNIX_EPOCH) {
-            Ok(t) => t.as_secs(),
-            Err(_) => panic!("System clock is set to a time before the unix epoch (1970-01-01)"),
-        };
-        time -= self.registers[0] as u64;
-        time -= (self.registers[1] as u64) * 60;
-        time -= (self.registers[2] as u64) * 3600;
-        let days = ((self.registers[4] as u64 & 0x1) << 8) | (self.registers[3] as u64);
-        time -= days * 3600 * 24;
-        Some(time)
+        Some(self.current_unix_seconds().wrapping_sub(counter_seconds(&self.registers)))
     }
 
     pub fn time(&self) -> Option<u64> {
@@ -85,3 +93,67 @@ impl RealTimeClock {
         self.time = value;
     }
 }
+
+/// Seconds counted by the clock registers: seconds, minutes, hours and the 9 bit day counter.
+pub fn counter_seconds(registers: &[u8; 5]) -> u64 {
+    let days = ((registers[4] as u64 & 0x1) << 8) | (registers[3] as u64);
+    registers[0] as u64 + (registers[1] as u64) * 60 + (registers[2] as u64) * 3600 + days * 3600 * 24
+}
+
+/// Clock registers after `seconds` have been counted, with the day carry set once the counter overflows.
+pub fn counter_registers(seconds: u64) -> [u8; 5] {
+    let days = seconds / (3600 * 24);
+    let carry = match days >= 512 {
+        true => 0x80,
+        false => 0x00,
+    };
+    [
+        (seconds % 60) as u8,
+        ((seconds / 60) % 60) as u8,
+        ((seconds / 3600) % 24) as u8,
+        days as u8,
+        carry | ((days >> 8) & 0x01) as u8,
+    ]
+}
+
+impl Snapshot for RealTimeClock {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.base_unix_seconds);
+        writer.write(&self.registers);
+        writer.write(&self.latch_registers);
+        writer.write(&self.time);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.base_unix_seconds = reader.read()?;
+        self.registers = reader.read()?;
+        self.latch_registers = reader.read()?;
+        let time: Option<u64> = reader.read()?;
+        if time.is_some() != self.time.is_some() {
+            return Err(SnapshotError::InvalidValue("real time clock"));
+        }
+        self.time = time;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_emulated_time_from_the_base_time() {
+        let scheduler = Rc::new(RefCell::new(Scheduler::new()));
+        let mut rtc = RealTimeClock::new(true, 1_700_000_000, scheduler.clone());
+        for (register, value) in [(0, 50), (1, 59), (2, 23), (3, 0xFF), (4, 0x00)] {
+            rtc.set_register(register, value);
+        }
+        rtc.set_time();
+        assert_eq!(rtc.time(), Some(1_700_000_000 - counter_seconds(&rtc.registers)));
+
+        scheduler.borrow_mut().step(CPU_CLOCK_SPEED as usize * 15);
+        rtc.set_latch_registers();
+        let latched: Vec<u8> = (0..5).map(|register| rtc.latch_register(register)).collect();
+        assert_eq!(latched, [5, 0, 0, 0x00, 0x01]);
+    }
+}
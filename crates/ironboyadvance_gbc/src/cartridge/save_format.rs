@@ -0,0 +1,188 @@
+use ironboyadvance_common::save_format::{SaveConvertOptions, SaveFormat};
+
+use crate::cartridge::{
+    CartridgeError,
+    header::{CartridgeType, Header},
+    mbc2::BUILT_IN_RAM_SIZE,
+    mbc3::{CLOCK_BYTES, RAM_BANK_SIZE},
+    rtc::{counter_registers, counter_seconds},
+};
+
+const HEADER_END: usize = 0x014F;
+const CLOCK_REGISTERS: usize = 5;
+/// BGB and VBA footer: the clock registers and their latched copies as 32 bit words, then the unix time
+/// they were saved at as a 64 bit word. Older VBA builds write the time as a 32 bit word instead.
+const CLOCK_FOOTER_BYTES: usize = CLOCK_REGISTERS * 2 * 4 + 8;
+const SHORT_CLOCK_FOOTER_BYTES: usize = CLOCK_REGISTERS * 2 * 4 + 4;
+
+/// What the cartridge keeps in its save: the battery backed RAM, then the clock when it has one.
+struct SaveLayout {
+    ram_size: usize,
+    clock: bool,
+}
+
+impl SaveLayout {
+    fn detect(rom: &[u8]) -> Result<SaveLayout, CartridgeError> {
+        if rom.len() <= HEADER_END {
+            return Err(CartridgeError::InvalidCatridgeType);
+        }
+
+        let header = Header::load(&rom[0x000..=HEADER_END])?;
+        let banked_ram = |ram: bool, battery: bool| match ram && battery {
+            true => header.ram_banks() * RAM_BANK_SIZE,
+            false => 0,
+        };
+
+        let (ram_size, clock) = match header.cartridge_type() {
+            CartridgeType::NoMbc { ram, battery } => (
+                match ram && battery {
+                    true => RAM_BANK_SIZE,
+                    false => 0,
+                },
+                false,
+            ),
+            CartridgeType::Mbc1 { ram, battery } | CartridgeType::Mbc5 { ram, battery, .. } => {
+                (banked_ram(ram, battery), false)
+            }
+            CartridgeType::Mbc2 { battery } => (
+                match battery {
+                    true => BUILT_IN_RAM_SIZE,
+                    false => 0,
+                },
+                false,
+            ),
+            CartridgeType::Mbc3 { ram, battery, timer } => (banked_ram(ram, battery), battery && timer),
+        };
+
+        Ok(SaveLayout { ram_size, clock })
+    }
+}
+
+/// Converts a raw save made by another emulator into the layout this emulator keeps for `rom`. BGB and
+/// VBA clock footers become the native clock, and a save without one starts the clock at zero.
+pub fn import_save(rom: &[u8], data: &[u8], options: &SaveConvertOptions) -> Result<Vec<u8>, CartridgeError> {
+    if SaveFormat::detect(data) != SaveFormat::Raw {
+        return Err(CartridgeError::InvalidSaveFile("cheat device saves only hold GBA games"));
+    }
+
+    let layout = SaveLayout::detect(rom)?;
+    let footer = match data.len().checked_sub(layout.ram_size) {
+        Some(0) => None,
+        Some(CLOCK_BYTES) if layout.clock => return Ok(data.to_vec()),
+        Some(length @ (SHORT_CLOCK_FOOTER_BYTES | CLOCK_FOOTER_BYTES)) => Some(&data[data.len() - length..]),
+        _ => return Err(CartridgeError::SaveSizeMismatch),
+    };
+
+    let mut save = data[..layout.ram_size].to_vec();
+    if layout.clock {
+        let time = match footer {
+            Some(footer) => {
+                let mut registers = [0u8; CLOCK_REGISTERS];
+                for (index, register) in registers.iter_mut().enumerate() {
+                    *register = footer[index * 4];
+                }
+                let saved_at = match footer.len() {
+                    CLOCK_FOOTER_BYTES => u64::from_le_bytes(footer[40..48].try_into().unwrap()),
+                    _ => u32::from_le_bytes(footer[40..44].try_into().unwrap()) as u64,
+                };
+                saved_at.saturating_sub(counter_seconds(&registers))
+            }
+            None => options.unix_seconds,
+        };
+        save.extend_from_slice(&time.to_be_bytes());
+    }
+
+    Ok(save)
+}
+
+/// Converts a save in this emulator's layout for `rom` into a raw save, with the clock as a 48 byte BGB
+/// footer.
+pub fn export_save(
+    rom: &[u8],
+    save: &[u8],
+    format: SaveFormat,
+    options: &SaveConvertOptions,
+) -> Result<Vec<u8>, CartridgeError> {
+    if format != SaveFormat::Raw {
+        return Err(CartridgeError::InvalidSaveFile("cheat device saves only hold GBA games"));
+    }
+
+    let layout = SaveLayout::detect(rom)?;
+    let clock_bytes = match layout.clock {
+        true => CLOCK_BYTES,
+        false => 0,
+    };
+    if save.len() != layout.ram_size + clock_bytes {
+        return Err(CartridgeError::SaveSizeMismatch);
+    }
+
+    let mut data = save[..layout.ram_size].to_vec();
+    if layout.clock {
+        let time = u64::from_be_bytes(save[layout.ram_size..].try_into().unwrap());
+        let registers = counter_registers(options.unix_seconds.saturating_sub(time));
+        for _copy in 0..2 {
+            for register in registers {
+                data.extend_from_slice(&(register as u32).to_le_bytes());
+            }
+        }
+        data.extend_from_slice(&options.unix_seconds.to_le_bytes());
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNIX_SECONDS: u64 = 1767225600;
+
+    fn rom(cartridge_type: u8, ram_size: u8) -> Vec<u8> {
+        let mut rom = vec![0; 0x8000];
+        rom[0x0147] = cartridge_type;
+        rom[0x0149] = ram_size;
+        rom
+    }
+
+    #[test]
+    fn bgb_clock_footer_becomes_the_native_clock() {
+        let rom = rom(0x10, 0x03);
+        let ram: Vec<u8> = (0..4 * RAM_BANK_SIZE).map(|index| index as u8).collect();
+        let options = SaveConvertOptions {
+            unix_seconds: UNIX_SECONDS,
+            ..Default::default()
+        };
+        let mut native = ram.clone();
+        native.extend_from_slice(&(UNIX_SECONDS - 90_061).to_be_bytes());
+
+        let exported = export_save(&rom, &native, SaveFormat::Raw, &options).unwrap();
+        assert_eq!(exported.len(), ram.len() + CLOCK_FOOTER_BYTES);
+        assert_eq!(
+            &exported[ram.len()..ram.len() + 20],
+            &[1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(import_save(&rom, &exported, &options).unwrap(), native);
+
+        let mut short = exported[..exported.len() - 8].to_vec();
+        short.extend_from_slice(&(UNIX_SECONDS as u32).to_le_bytes());
+        assert_eq!(import_save(&rom, &short, &options).unwrap(), native);
+    }
+
+    #[test]
+    fn plain_saves_pass_through_and_wrong_sizes_are_rejected() {
+        let rom = rom(0x03, 0x02);
+        let ram = vec![0x5A; RAM_BANK_SIZE];
+        let options = SaveConvertOptions::default();
+
+        assert_eq!(import_save(&rom, &ram, &options).unwrap(), ram);
+        assert_eq!(export_save(&rom, &ram, SaveFormat::Raw, &options).unwrap(), ram);
+        assert!(matches!(
+            import_save(&rom, &ram[..0x800], &options),
+            Err(CartridgeError::SaveSizeMismatch)
+        ));
+        assert!(matches!(
+            export_save(&rom, &ram, SaveFormat::SharkPort, &options),
+            Err(CartridgeError::InvalidSaveFile(_))
+        ));
+    }
+}
@@ -0,0 +1,253 @@
+use ironboyadvance_common::{
+    cheats::{Cheat, CheatError, CheatFormat, code_lines, parse_code_line},
+    memory::SystemMemoryAccess,
+};
+
+/// Game Genie stores the address with its top nibble inverted and the compare byte scrambled.
+const GAME_GENIE_ADDRESS_XOR: u16 = 0xF000;
+const GAME_GENIE_COMPARE_XOR: u8 = 0xBA;
+const ROM_END: u16 = 0x7FFF;
+
+/// A Game Genie patch of the byte the cpu reads at `address`. With a compare byte the patch only applies
+/// while the mapped rom bank holds that byte, which is how a code targets one bank out of many.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RomPatch {
+    pub address: u16,
+    pub value: u8,
+    pub compare: Option<u8>,
+}
+
+impl RomPatch {
+    pub fn apply(&self, address: u16, original: u8) -> Option<u8> {
+        match address == self.address && self.compare.is_none_or(|compare| compare == original) {
+            true => Some(self.value),
+            false => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatOp {
+    RomPatch(RomPatch),
+    /// GameShark write to whichever WRAM or SRAM bank is mapped at `address`.
+    Write {
+        address: u16,
+        value: u8,
+    },
+}
+
+/// The cheats entered for a game. Game Genie codes patch rom reads, GameShark codes write memory once per
+/// frame.
+pub struct CheatEngine {
+    cheats: Vec<Cheat>,
+    programs: Vec<Vec<CheatOp>>,
+}
+
+impl CheatEngine {
+    pub fn new() -> Self {
+        CheatEngine {
+            cheats: Vec::new(),
+            programs: Vec::new(),
+        }
+    }
+
+    pub fn cheats(&self) -> &[Cheat] {
+        &self.cheats
+    }
+
+    pub fn add(&mut self, cheat: Cheat) -> Result<usize, CheatError> {
+        let program = parse(cheat.format, &cheat.code)?;
+        self.cheats.push(cheat);
+        self.programs.push(program);
+        Ok(self.cheats.len() - 1)
+    }
+
+    pub fn remove(&mut self, index: usize) -> Result<Cheat, CheatError> {
+        if index >= self.cheats.len() {
+            return Err(CheatError::UnknownCheat(index));
+        }
+        self.programs.remove(index);
+        Ok(self.cheats.remove(index))
+    }
+
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) -> Result<(), CheatError> {
+        let cheat = self.cheats.get_mut(index).ok_or(CheatError::UnknownCheat(index))?;
+        cheat.enabled = enabled;
+        Ok(())
+    }
+
+    /// Rom patches of the enabled cheats, earlier cheats taking precedence.
+    pub fn rom_patches(&self) -> Vec<RomPatch> {
+        self.enabled_ops()
+            .filter_map(|op| match op {
+                CheatOp::RomPatch(patch) => Some(patch),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn apply(&self, memory: &mut impl SystemMemoryAccess<Address = u16>) {
+        for op in self.enabled_ops() {
+            if let CheatOp::Write { address, value } = op {
+                memory.write_8(address, value);
+            }
+        }
+    }
+
+    fn enabled_ops(&self) -> impl Iterator<Item = CheatOp> + '_ {
+        self.cheats
+            .iter()
+            .zip(&self.programs)
+            .filter(|(cheat, _)| cheat.enabled)
+            .flat_map(|(_, program)| program.iter().copied())
+    }
+}
+
+fn parse(format: CheatFormat, code: &str) -> Result<Vec<CheatOp>, CheatError> {
+    let parse_line = match format {
+        CheatFormat::GameGenie => parse_game_genie,
+        CheatFormat::GameShark => parse_gameshark,
+        _ => return Err(CheatError::UnsupportedFormat(format)),
+    };
+    code_lines(code).map(|(line, digits)| parse_line(line, &digits)).collect()
+}
+
+/// Parses `VVA-AAA` or `VVA-AAA-CXC`, where the address digits are ordered `-ADE-F` for address `FADE`.
+fn parse_game_genie(line: usize, digits: &str) -> Result<CheatOp, CheatError> {
+    let (value, address, compare) = match digits.len() {
+        6 => {
+            let (value, address) = parse_code_line(line, digits, 2, 6)?;
+            (value, address, None)
+        }
+        9 => {
+            let (value, rest) = parse_code_line(line, digits, 2, 9)?;
+            (value, rest >> 12, Some(rest & 0xFFF))
+        }
+        _ => {
+            return Err(CheatError::InvalidCode {
+                line,
+                reason: "Game Genie codes have 6 or 9 digits",
+            });
+        }
+    };
+
+    let address = (((address & 0xF) << 12) | (address >> 4)) as u16 ^ GAME_GENIE_ADDRESS_XOR;
+    if address > ROM_END {
+        return Err(CheatError::InvalidCode {
+            line,
+            reason: "Game Genie address is outside the rom",
+        });
+    }
+
+    // The middle compare digit only checks the code; the outer two hold the byte.
+    let compare = compare.map(|compare| (((compare >> 4) & 0xF0) | (compare & 0xF)) as u8);
+    Ok(CheatOp::RomPatch(RomPatch {
+        address,
+        value: value as u8,
+        compare: compare.map(|compare| compare.rotate_right(2) ^ GAME_GENIE_COMPARE_XOR),
+    }))
+}
+
+/// Parses `TTVVLLHH`, writing `VV` to `HHLL`. Types `00` and `01` write to the banks currently mapped.
+fn parse_gameshark(line: usize, digits: &str) -> Result<CheatOp, CheatError> {
+    let (kind_value, address) = parse_code_line(line, digits, 4, 8)?;
+    let address = (address as u16).swap_bytes();
+
+    match kind_value >> 8 {
+        0x00 | 0x01 if address > ROM_END => Ok(CheatOp::Write {
+            address,
+            value: kind_value as u8,
+        }),
+        0x00 | 0x01 => Err(CheatError::InvalidCode {
+            line,
+            reason: "GameShark codes cannot write to the rom",
+        }),
+        0x80..=0x87 | 0x90..=0x97 => Err(CheatError::UnsupportedCode {
+            line,
+            reason: "GameShark codes for a fixed WRAM bank",
+        }),
+        _ => Err(CheatError::InvalidCode {
+            line,
+            reason: "unknown GameShark code type",
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_genie_decodes_address_value_and_compare() {
+        assert_eq!(
+            parse(CheatFormat::GameGenie, "00A-17B-C49").unwrap(),
+            vec![CheatOp::RomPatch(RomPatch {
+                address: 0x4A17,
+                value: 0x00,
+                compare: Some(0xC8),
+            })]
+        );
+        assert_eq!(
+            parse(CheatFormat::GameGenie, "3E1-0AF").unwrap(),
+            vec![CheatOp::RomPatch(RomPatch {
+                address: 0x010A,
+                value: 0x3E,
+                compare: None,
+            })]
+        );
+        assert!(parse(CheatFormat::GameGenie, "3E1-0A7").is_err(), "address 0x810A is not rom");
+        assert!(parse(CheatFormat::GameGenie, "3E1-0A").is_err());
+
+        let patch = RomPatch {
+            address: 0x4A17,
+            value: 0x00,
+            compare: Some(0xC8),
+        };
+        assert_eq!(patch.apply(0x4A17, 0xC8), Some(0x00));
+        assert_eq!(patch.apply(0x4A17, 0xC7), None, "another bank is mapped");
+        assert_eq!(patch.apply(0x4A18, 0xC8), None);
+    }
+
+    #[test]
+    fn gameshark_writes_every_frame_while_enabled() {
+        #[derive(Default)]
+        struct TestMemory(Vec<(u16, u8)>);
+
+        impl SystemMemoryAccess for TestMemory {
+            type Address = u16;
+
+            fn read_8(&self, _address: u16) -> u8 {
+                0
+            }
+
+            fn write_8(&mut self, address: u16, value: u8) {
+                self.0.push((address, value));
+            }
+        }
+
+        let mut engine = CheatEngine::new();
+        engine
+            .add(Cheat::new("lives", CheatFormat::GameShark, "010238CD\n0163A0A0"))
+            .unwrap();
+        engine.add(Cheat::new("patch", CheatFormat::GameGenie, "3E1-0AF")).unwrap();
+        assert_eq!(engine.rom_patches().len(), 1);
+
+        let mut memory = TestMemory::default();
+        engine.apply(&mut memory);
+        engine.apply(&mut memory);
+        assert_eq!(memory.0, vec![(0xCD38, 0x02), (0xA0A0, 0x63), (0xCD38, 0x02), (0xA0A0, 0x63)]);
+
+        engine.set_enabled(0, false).unwrap();
+        engine.apply(&mut memory);
+        assert_eq!(memory.0.len(), 4);
+
+        assert!(matches!(
+            engine.add(Cheat::new("rom", CheatFormat::GameShark, "01020040")),
+            Err(CheatError::InvalidCode { line: 1, .. })
+        ));
+        assert_eq!(
+            engine.add(Cheat::new("gba", CheatFormat::CodeBreaker, "82000000 0001")),
+            Err(CheatError::UnsupportedFormat(CheatFormat::CodeBreaker))
+        );
+    }
+}
@@ -7,9 +7,14 @@ use crate::{
     events::{DmaEvent, GbcEvent},
     speed_control::{DOUBLE_SPEED_T_CYCLES, NORMAL_SPEED_T_CYCLES},
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateValue, StateWriter};
+
+/// Channel numbers the debugger uses for the two DMA units.
+pub const OAM_DMA_CHANNEL: u8 = 0;
+pub const VRAM_DMA_CHANNEL: u8 = 1;
 
 const OAM_DMA_LENGTH: u16 = 0x00A0;
-const OAM_DMA_DESTINATION: u16 = 0xFE00;
+pub const OAM_DMA_DESTINATION: u16 = 0xFE00;
 const VRAM_DMA_DESTINATION: u16 = 0x8000;
 const VRAM_DMA_BLOCK_LENGTH: u8 = 16;
 
@@ -21,6 +26,32 @@ enum VramDmaMode {
     GdmaActive,
 }
 
+impl StateValue for VramDmaMode {
+    fn write_state(&self, writer: &mut StateWriter) {
+        match self {
+            VramDmaMode::Stopped => writer.write(&0u8),
+            VramDmaMode::HdmaPending => writer.write(&1u8),
+            VramDmaMode::HdmaActive { block_bytes_remaining } => {
+                writer.write(&2u8);
+                writer.write(block_bytes_remaining);
+            }
+            VramDmaMode::GdmaActive => writer.write(&3u8),
+        }
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        match reader.read::<u8>()? {
+            0 => Ok(VramDmaMode::Stopped),
+            1 => Ok(VramDmaMode::HdmaPending),
+            2 => Ok(VramDmaMode::HdmaActive {
+                block_bytes_remaining: reader.read()?,
+            }),
+            3 => Ok(VramDmaMode::GdmaActive),
+            _ => Err(SnapshotError::InvalidValue("vram dma mode")),
+        }
+    }
+}
+
 pub struct DmaTransfer {
     pub source: u16,
     pub destination: u16,
@@ -216,3 +247,30 @@ impl SystemMemoryAccess for DmaController {
         }
     }
 }
+
+impl Snapshot for DmaController {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.oam_source);
+        writer.write(&self.oam_index);
+        writer.write(&self.oam_pending);
+        writer.write(&self.oam_active);
+        writer.write(&self.vram_mode);
+        writer.write(&self.vram_source);
+        writer.write(&self.vram_destination);
+        writer.write(&self.vram_length);
+        writer.write(&self.speed);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.oam_source = reader.read()?;
+        self.oam_index = reader.read()?;
+        self.oam_pending = reader.read()?;
+        self.oam_active = reader.read()?;
+        self.vram_mode = reader.read()?;
+        self.vram_source = reader.read()?;
+        self.vram_destination = reader.read()?;
+        self.vram_length = reader.read()?;
+        self.speed = reader.read()?;
+        Ok(())
+    }
+}
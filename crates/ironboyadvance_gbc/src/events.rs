@@ -1,4 +1,7 @@
-use ironboyadvance_common::scheduler::SystemEvent;
+use ironboyadvance_common::{
+    scheduler::SystemEvent,
+    snapshot::{SnapshotError, StateReader, StateValue, StateWriter},
+};
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum InterruptEvent {
@@ -58,3 +61,41 @@ impl SystemEvent for GbcEvent {
 }
 
 pub type FutureGbcEvent = (GbcEvent, usize);
+
+impl StateValue for GbcEvent {
+    fn write_state(&self, writer: &mut StateWriter) {
+        let (tag, value) = match *self {
+            GbcEvent::Interrupt(interrupt_event) => (0u8, interrupt_event as u8),
+            GbcEvent::Ppu(ppu_event) => (1, ppu_event as u8),
+            GbcEvent::Apu(apu_event) => (2, apu_event as u8),
+            GbcEvent::Timer(timer_event) => (3, timer_event as u8),
+            GbcEvent::Dma(dma_event) => (4, dma_event as u8),
+            GbcEvent::Serial(serial_event) => (5, serial_event as u8),
+        };
+        writer.write(&tag);
+        writer.write(&value);
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        let tag: u8 = reader.read()?;
+        let value: u8 = reader.read()?;
+        let event = match (tag, value) {
+            (0, 0) => GbcEvent::Interrupt(InterruptEvent::VBlank),
+            (0, 1) => GbcEvent::Interrupt(InterruptEvent::Lcd),
+            (0, 2) => GbcEvent::Interrupt(InterruptEvent::Timer),
+            (0, 3) => GbcEvent::Interrupt(InterruptEvent::Serial),
+            (0, 4) => GbcEvent::Interrupt(InterruptEvent::Joypad),
+            (1, 0) => GbcEvent::Ppu(PpuEvent::OamScan),
+            (1, 1) => GbcEvent::Ppu(PpuEvent::DrawingPixels),
+            (1, 2) => GbcEvent::Ppu(PpuEvent::HBlank),
+            (1, 3) => GbcEvent::Ppu(PpuEvent::VBlank),
+            (2, 0) => GbcEvent::Apu(ApuEvent::Sample),
+            (2, 1) => GbcEvent::Apu(ApuEvent::FrameSequence),
+            (3, 0) => GbcEvent::Timer(TimerEvent::Overflow),
+            (4, 0) => GbcEvent::Dma(DmaEvent::OamTransfer),
+            (5, 0) => GbcEvent::Serial(SerialEvent::TransferBit),
+            _ => return Err(SnapshotError::InvalidValue("event")),
+        };
+        Ok(event)
+    }
+}
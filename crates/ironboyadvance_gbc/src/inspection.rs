@@ -0,0 +1,238 @@
+use ironboyadvance_common::{
+    call_stack::CallStack,
+    emulator::{MemoryRegion, Register, System, SystemInspection},
+};
+use ironboyadvance_sm83::{GbMode, memory::MemoryInterface};
+
+use crate::GameBoyColor;
+
+const MEMORY_MAP: [MemoryRegion; 11] = [
+    MemoryRegion::new("ROM bank 0", 0x0000, 0x4000),
+    MemoryRegion::new("ROM bank N", 0x4000, 0x4000),
+    MemoryRegion::new("VRAM", 0x8000, 0x2000),
+    MemoryRegion::new("External RAM", 0xA000, 0x2000),
+    MemoryRegion::new("WRAM bank 0", 0xC000, 0x1000),
+    MemoryRegion::new("WRAM bank N", 0xD000, 0x1000),
+    MemoryRegion::new("Echo RAM", 0xE000, 0x1E00).mirror_of(0xC000),
+    MemoryRegion::new("OAM", 0xFE00, 0xA0),
+    MemoryRegion::new("I/O", 0xFF00, 0x80),
+    MemoryRegion::new("HRAM", 0xFF80, 0x7F),
+    MemoryRegion::new("IE", 0xFFFF, 1),
+];
+
+/// Addresses from here on reach RAM banks through their backing store, `0x01BBAAAA` being address `AAAA` in
+/// bank `BB`, so the banks that are not mapped in can be searched too.
+const BANKED: u32 = 0x0100_0000;
+
+const WRAM_BANKS: [&str; 8] = [
+    "WRAM bank 0",
+    "WRAM bank 1",
+    "WRAM bank 2",
+    "WRAM bank 3",
+    "WRAM bank 4",
+    "WRAM bank 5",
+    "WRAM bank 6",
+    "WRAM bank 7",
+];
+
+const EXTERNAL_RAM_BANKS: [&str; 16] = [
+    "External RAM bank 0",
+    "External RAM bank 1",
+    "External RAM bank 2",
+    "External RAM bank 3",
+    "External RAM bank 4",
+    "External RAM bank 5",
+    "External RAM bank 6",
+    "External RAM bank 7",
+    "External RAM bank 8",
+    "External RAM bank 9",
+    "External RAM bank A",
+    "External RAM bank B",
+    "External RAM bank C",
+    "External RAM bank D",
+    "External RAM bank E",
+    "External RAM bank F",
+];
+
+const fn banked(bank: usize, address: u32) -> u32 {
+    BANKED | (bank as u32) << 16 | address
+}
+
+impl SystemInspection for GameBoyColor {
+    fn serial_output(&self) -> &[u8] {
+        self.sm83.bus().io_registers().serial_transfer().output()
+    }
+
+    fn peek_8(&self, address: u32) -> u8 {
+        match address >= BANKED {
+            true => self.sm83.bus().peek_banked((address >> 16 & 0xFF) as usize, address as u16),
+            false => self.sm83.bus().peek_8(address as u16),
+        }
+    }
+
+    fn poke_8(&mut self, address: u32, value: u8) {
+        match address >= BANKED {
+            true => self
+                .sm83
+                .bus_mut()
+                .poke_banked((address >> 16 & 0xFF) as usize, address as u16, value),
+            false => self.sm83.bus_mut().poke_8(address as u16, value),
+        }
+    }
+
+    fn registers(&self) -> Vec<Register> {
+        self.sm83.register_file()
+    }
+
+    fn register(&self, name: &str) -> Option<u32> {
+        self.sm83.register(name)
+    }
+
+    fn set_register(&mut self, name: &str, value: u32) -> bool {
+        self.sm83.set_register(name, value)
+    }
+
+    /// Banked regions show whichever bank is mapped in.
+    fn memory_map(&self) -> Vec<MemoryRegion> {
+        MEMORY_MAP.to_vec()
+    }
+
+    /// Every WRAM bank the mode has and every external RAM bank of the cartridge, the switchable ones at their
+    /// [`BANKED`] addresses.
+    fn ram_regions(&self) -> Vec<MemoryRegion> {
+        let cartridge = self.sm83.bus().cartridge();
+        let wram_banks = match self.system == System::Gbc && cartridge.mode() == GbMode::Color {
+            true => WRAM_BANKS.len(),
+            false => 2,
+        };
+        let ram_size = cartridge.ram_size() as u32;
+
+        let mut regions = vec![MemoryRegion::new(WRAM_BANKS[0], 0xC000, 0x1000)];
+        regions.extend((1..wram_banks).map(|bank| MemoryRegion::new(WRAM_BANKS[bank], banked(bank, 0xD000), 0x1000)));
+        regions.extend(
+            EXTERNAL_RAM_BANKS
+                .into_iter()
+                .enumerate()
+                .take(ram_size.div_ceil(0x2000) as usize)
+                .map(|(bank, name)| {
+                    let size = (ram_size - bank as u32 * 0x2000).min(0x2000);
+                    MemoryRegion::new(name, banked(bank, 0xA000), size)
+                }),
+        );
+        regions.push(MemoryRegion::new("HRAM", 0xFF80, 0x7F));
+        regions
+    }
+
+    fn call_stack(&self) -> Option<&CallStack> {
+        Some(self.sm83.call_stack())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ironboyadvance_common::{
+        debugger::{AccessKind, BreakReason, WatchCondition, WatchKind, Watchpoint},
+        emulator::{Emulator, System},
+        memory::SystemMemoryAccess,
+        ram_search::{Comparison, RamSearch, SearchFilter, SearchOptions},
+        save_storage::MemorySaveStorage,
+    };
+
+    use super::*;
+
+    #[test]
+    fn peeks_pokes_and_sets_registers() {
+        let mut rom = vec![0; 0x8000];
+        rom[0x0150] = 0x3C;
+        let mut gb = GameBoyColor::new(System::Gb, rom, Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap();
+
+        gb.poke_8(0xC010, 0x42);
+        gb.poke_8(0x0150, 0xFF);
+        assert_eq!(gb.peek_8(0xE010), 0x42, "echo RAM shows WRAM");
+        assert_eq!(gb.peek_8(0x0150), 0x3C, "rom is not writable");
+        gb.peek_8(0xFF00);
+        assert!(!gb.take_input_polled());
+
+        assert!(gb.set_register("hl", 0xC010));
+        assert!(gb.set_register("f", 0xFF));
+        assert_eq!(gb.register("h"), Some(0xC0));
+        assert_eq!(gb.register("af").map(|af| af & 0xFF), Some(0xF0));
+        assert!(!gb.set_register("ix", 0));
+        assert_eq!(gb.registers().len(), 11);
+    }
+
+    #[test]
+    fn tracks_calls_and_returns() {
+        let mut rom = vec![0; 0x8000];
+        rom[0x0150..0x0153].copy_from_slice(&[0xCD, 0x00, 0x02]);
+        rom[0x0201] = 0xC9;
+        let mut gb = GameBoyColor::new(System::Gb, rom, Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap();
+        gb.set_register("pc", 0x0150);
+
+        gb.sm83.cycle();
+        gb.sm83.cycle();
+        assert_eq!(gb.backtrace(None), vec!["#0  0x00000201", "#1  0x00000150"]);
+        gb.sm83.cycle();
+        assert_eq!(gb.register("pc"), Some(0x0153));
+        assert_eq!(gb.call_stack().map(CallStack::depth), Some(0));
+    }
+
+    #[test]
+    fn read_watchpoints_skip_instruction_fetches() {
+        let mut rom = vec![0; 0x8000];
+        // ld a, (0x0200) twice
+        rom[0x0150..0x0156].copy_from_slice(&[0xFA, 0x00, 0x02, 0xFA, 0x00, 0x02]);
+        rom[0x0200] = 0x42;
+        let mut gb = GameBoyColor::new(System::Gb, rom, Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap();
+        gb.set_register("pc", 0x0150);
+        let watch = |address, length| Watchpoint {
+            address,
+            length,
+            kind: WatchKind::Read,
+            condition: WatchCondition::Any,
+        };
+
+        gb.debugger_mut().add_watchpoint(watch(0x0150, 6));
+        assert_eq!(gb.cycle(), None, "executing watched code does not break");
+        gb.debugger_mut().add_watchpoint(watch(0x0200, 1));
+        assert_eq!(
+            gb.cycle(),
+            Some(BreakReason::Watchpoint {
+                address: 0x0200,
+                access: AccessKind::Read,
+                value: 0x42,
+            })
+        );
+    }
+
+    #[test]
+    fn searches_every_ram_bank_through_the_backing_store() {
+        let mut rom = vec![0; 0x8000];
+        rom[0x0143] = 0x80;
+        rom[0x0147] = 0x1A;
+        rom[0x0149] = 0x03;
+        let mut gbc = GameBoyColor::new(System::Gbc, rom, Vec::new(), Box::new(MemorySaveStorage::new()), 0, false).unwrap();
+        let bus = gbc.sm83.bus_mut();
+        bus.write_8(0xFF70, 3);
+        bus.write_8(0xD010, 0x42);
+        bus.write_8(0xFF70, 1);
+        bus.write_8(0x0000, 0x0A);
+        bus.write_8(0x4000, 2);
+        bus.write_8(0xA005, 0x42);
+        bus.write_8(0x0000, 0x00);
+
+        let regions = gbc.ram_regions();
+        assert_eq!(regions.len(), 1 + 7 + 4 + 1);
+        assert!(regions.contains(&MemoryRegion::new("WRAM bank 3", 0x0103_D000, 0x1000)));
+        assert!(regions.contains(&MemoryRegion::new("External RAM bank 2", 0x0102_A000, 0x2000)));
+        assert_eq!(gbc.peek_8(0xA005), 0xFF, "external RAM is disabled");
+
+        let mut search = RamSearch::new(&gbc, SearchOptions::default());
+        assert_eq!(search.filter(&gbc, SearchFilter::Value(Comparison::Equal, 0x42)), Ok(2));
+        assert_eq!(search.candidates(), &[0x0103_D010, 0x0102_A005]);
+
+        gbc.poke_8(0x0102_A005, 0x43);
+        assert_eq!(gbc.peek_8(0x0102_A005), 0x43);
+        assert_eq!(gbc.peek_8(0x0105_A000), 0xFF, "the cartridge has 4 banks");
+    }
+}
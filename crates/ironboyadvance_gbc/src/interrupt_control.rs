@@ -2,6 +2,7 @@ use ironboyadvance_common::memory::SystemMemoryAccess;
 use ironboyadvance_sm83::memory::InterruptContext;
 
 use crate::events::InterruptEvent;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const INTERRUPT_MASK: u8 = 0x1F;
 const INTERRUPT_FLAGS_UNUSED_BITS: u8 = 0xE0;
@@ -53,3 +54,16 @@ impl SystemMemoryAccess for InterruptController {
         };
     }
 }
+
+impl Snapshot for InterruptController {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.interrupt_context.interrupt_enabled());
+        writer.write(&self.interrupt_context.interrupt_flags());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.interrupt_context.set_interrupt_enabled(reader.read()?);
+        self.interrupt_context.set_interrupt_flags(reader.read()?);
+        Ok(())
+    }
+}
@@ -16,6 +16,7 @@ use crate::{
     speed_control::SpeedController,
     timer::Timer,
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[derive(Getters, MutGetters)]
 #[getset(get = "pub", get_mut = "pub")]
@@ -43,6 +44,14 @@ impl IoRegisters {
             dma_controller: DmaController::new(scheduler),
         }
     }
+
+    /// Reads a register or video memory without side effects, for debuggers.
+    pub fn peek_8(&self, address: u16) -> u8 {
+        match address {
+            0xFF00 => self.joypad.peek_8(address),
+            _ => self.read_8(address),
+        }
+    }
 }
 
 impl SystemMemoryAccess for IoRegisters {
@@ -103,3 +112,27 @@ impl SystemMemoryAccess for IoRegisters {
         }
     }
 }
+
+impl Snapshot for IoRegisters {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.ppu);
+        writer.save(&self.apu);
+        writer.save(&self.joypad);
+        writer.save(&self.interrupt_controller);
+        writer.save(&self.serial_transfer);
+        writer.save(&self.speed_controller);
+        writer.save(&self.timer);
+        writer.save(&self.dma_controller);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.ppu)?;
+        reader.load(&mut self.apu)?;
+        reader.load(&mut self.joypad)?;
+        reader.load(&mut self.interrupt_controller)?;
+        reader.load(&mut self.serial_transfer)?;
+        reader.load(&mut self.speed_controller)?;
+        reader.load(&mut self.timer)?;
+        reader.load(&mut self.dma_controller)
+    }
+}
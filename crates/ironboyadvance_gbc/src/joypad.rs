@@ -1,8 +1,12 @@
+use std::cell::Cell;
+
 use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 pub struct Joypad {
     button_input: u16,
     select: u8,
+    input_polled: Cell<bool>,
 }
 
 impl Joypad {
@@ -10,6 +14,19 @@ impl Joypad {
         Joypad {
             button_input: 0x03FF,
             select: 0x30,
+            input_polled: Cell::new(false),
+        }
+    }
+
+    pub fn take_input_polled(&self) -> bool {
+        self.input_polled.replace(false)
+    }
+
+    /// Reads P1 without counting as the game polling the joypad.
+    pub fn peek_8(&self, address: u16) -> u8 {
+        match address {
+            0xFF00 => 0xC0 | self.select | self.selected_input(),
+            _ => panic!("Invalid byte read for Joypad: {:#06X}", address),
         }
     }
 
@@ -43,10 +60,8 @@ impl SystemMemoryAccess for Joypad {
     type Address = u16;
 
     fn read_8(&self, address: u16) -> u8 {
-        match address {
-            0xFF00 => 0xC0 | self.select | self.selected_input(),
-            _ => panic!("Invalid byte read for Joypad: {:#06X}", address),
-        }
+        self.input_polled.set(true);
+        self.peek_8(address)
     }
 
     fn write_8(&mut self, address: u16, value: u8) {
@@ -56,3 +71,16 @@ impl SystemMemoryAccess for Joypad {
         }
     }
 }
+
+impl Snapshot for Joypad {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.button_input);
+        writer.write(&self.select);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.button_input = reader.read()?;
+        self.select = reader.read()?;
+        Ok(())
+    }
+}
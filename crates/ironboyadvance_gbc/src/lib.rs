@@ -1,16 +1,24 @@
-use std::{cell::RefCell, path::PathBuf, rc::Rc};
+use std::{cell::RefCell, rc::Rc};
 
 use ironboyadvance_common::{
-    emulator::{Emulator, System, SystemInspection},
+    cheats::{Cheat, CheatError},
+    coverage::Coverage,
+    debugger::{BreakReason, Debugger},
+    emulator::{Emulator, System},
     memory::SystemMemoryAccess,
+    profiler::Profiler,
+    save_storage::SaveStorage,
     scheduler::Scheduler,
+    snapshot::{self, SnapshotError, StateReader, StateWriter},
+    trace::Tracer,
 };
 use ironboyadvance_sm83::{CPU_CLOCK_SPEED, GbMode, HaltMode, cpu::Sm83, memory::MemoryInterface};
 use thiserror::Error;
 
 use crate::{
     boot_rom::{BootRom, BootRomError},
-    cartridge::{Cartridge, CartridgeError},
+    cartridge::Cartridge,
+    cheats::CheatEngine,
     events::{GbcEvent, InterruptEvent},
     system_bus::SystemBus,
 };
@@ -18,8 +26,10 @@ use crate::{
 mod apu;
 mod boot_rom;
 mod cartridge;
+mod cheats;
 mod dma_control;
 mod events;
+mod inspection;
 mod interrupt_control;
 mod io_registers;
 mod joypad;
@@ -33,6 +43,7 @@ mod timer;
 pub const FPS: f32 = CPU_CLOCK_SPEED as f32 / CYCLES_PER_FRAME as f32;
 
 pub use apu::SAMPLE_RATE;
+pub use cartridge::{CartridgeError, export_save, import_save};
 
 pub use ppu::{CYCLES_PER_FRAME, VIEWPORT_HEIGHT, VIEWPORT_WIDTH};
 
@@ -47,23 +58,31 @@ pub enum GbcError {
 pub struct GameBoyColor {
     sm83: Sm83<SystemBus>,
     scheduler: Rc<RefCell<Scheduler<GbcEvent>>>,
+    system: System,
+    rom_hash: u64,
+    cheats: CheatEngine,
+    break_reason: Option<BreakReason>,
+    tracer: Option<Tracer>,
+    profiler: Option<Profiler>,
 }
 
 impl GameBoyColor {
     pub fn new(
         kind: System,
-        rom_path: PathBuf,
         rom_buffer: Vec<u8>,
         boot_rom_buffer: Vec<u8>,
+        save_storage: Box<dyn SaveStorage>,
+        base_unix_seconds: u64,
         show_logs: bool,
     ) -> Result<GameBoyColor, GbcError> {
         let scheduler = Rc::new(RefCell::new(Scheduler::new()));
-        let cartridge = Cartridge::load(rom_path, rom_buffer)?;
+        let rom_hash = snapshot::rom_hash(&rom_buffer);
+        let cartridge = Cartridge::load(rom_buffer, save_storage, base_unix_seconds, scheduler.clone())?;
         let boot_rom = BootRom::load(boot_rom_buffer)?;
         let skip_boot = !boot_rom.loaded();
-        let mode = match kind {
-            System::Gb => GbMode::ColorAsMonochrome,
-            _ => cartridge.mode(),
+        let (system, mode) = match kind {
+            System::Gb => (System::Gb, GbMode::ColorAsMonochrome),
+            _ => (System::Gbc, cartridge.mode()),
         };
 
         let gbc = GameBoyColor {
@@ -74,27 +93,88 @@ impl GameBoyColor {
                 mode,
             ),
             scheduler,
+            system,
+            rom_hash,
+            cheats: CheatEngine::new(),
+            break_reason: None,
+            tracer: None,
+            profiler: None,
         };
         Ok(gbc)
     }
 
-    pub fn cycle(&mut self) {
-        match self.sm83.halt_mode() {
-            HaltMode::Stopped => self.sm83.bus_mut().idle_cycle(),
-            HaltMode::Halted => match self.sm83.bus().interrupts_pending() {
-                true => {
-                    self.sm83.set_halt_mode(HaltMode::Running);
-                    self.sm83.irq();
+    fn update_rom_patches(&mut self) {
+        let rom_patches = self.cheats.rom_patches();
+        self.sm83.bus_mut().cartridge_mut().set_rom_patches(rom_patches);
+    }
+
+    fn check_breakpoints(&mut self) -> Option<BreakReason> {
+        let address = self.sm83.pc() as u32;
+        let stack_pointer = self.sm83.sp() as u32;
+        self.sm83
+            .bus_mut()
+            .debugger_mut()
+            .before_instruction(address, None, stack_pointer)
+    }
+
+    fn trace_instruction(&mut self) {
+        if let Some(tracer) = &mut self.tracer
+            && tracer.should_trace(self.sm83.pc() as u32)
+        {
+            let timestamp = self.scheduler.borrow().timestamp() as u64;
+            tracer.write_instruction(self.sm83.pc() as u32, &self.sm83.trace_line(tracer.format(), timestamp));
+        }
+    }
+
+    /// Starts charging the step about to run to the current function, returning the timestamp it starts at.
+    fn enter_profile(&mut self) -> Option<usize> {
+        let profiler = self.profiler.as_mut()?;
+        profiler.enter(self.sm83.call_stack(), self.sm83.pc() as u32);
+        Some(self.scheduler.borrow().timestamp())
+    }
+
+    /// VRAM DMA copies without taking time, so every cycle goes to the cpu or to halt.
+    fn record_profile(&mut self, start: Option<usize>, halted: bool) {
+        if let Some(profiler) = &mut self.profiler
+            && let Some(timestamp) = start
+        {
+            profiler.record((self.scheduler.borrow().timestamp() - timestamp) as u64, 0, halted);
+        }
+    }
+
+    /// Runs one instruction, or one halted or stopped step. Returns why the debugger stopped the system, in
+    /// which case the instruction it stopped in front of has not executed yet.
+    pub fn cycle(&mut self) -> Option<BreakReason> {
+        let profile_start = self.enter_profile();
+        let executed_instruction = match self.sm83.halt_mode() {
+            HaltMode::Stopped => {
+                self.sm83.bus_mut().idle_cycle();
+                false
+            }
+            HaltMode::Halted => {
+                match self.sm83.bus().interrupts_pending() {
+                    true => {
+                        self.sm83.set_halt_mode(HaltMode::Running);
+                        self.sm83.irq();
+                    }
+                    false => self.sm83.bus_mut().idle_cycle(),
                 }
-                false => self.sm83.bus_mut().idle_cycle(),
-            },
+                false
+            }
             HaltMode::Running => {
                 if self.sm83.bus().interrupts_pending() {
                     self.sm83.irq();
                 }
+                if let Some(reason) = self.check_breakpoints() {
+                    return Some(reason);
+                }
+                self.trace_instruction();
                 self.sm83.cycle();
+                true
             }
-        }
+        };
+        self.record_profile(profile_start, !executed_instruction);
+        self.sm83.bus_mut().debugger_mut().after_cycle(executed_instruction)
     }
 }
 
@@ -105,9 +185,20 @@ impl Emulator for GameBoyColor {
         let end_time = start_time + target;
 
         while self.scheduler.borrow().timestamp() < end_time {
-            self.cycle();
+            if let Some(reason) = self.cycle() {
+                self.break_reason = Some(reason);
+                break;
+            }
+        }
+        if self.scheduler.borrow().timestamp() >= end_time
+            && let Some(tracer) = &mut self.tracer
+        {
+            tracer.frame_completed();
         }
 
+        self.cheats.apply(self.sm83.bus_mut());
+        self.sm83.bus_mut().cartridge_mut().flush_backup();
+
         let elapsed = self.scheduler.borrow().timestamp() - start_time;
         elapsed.saturating_sub(target)
     }
@@ -137,14 +228,105 @@ impl Emulator for GameBoyColor {
             self.sm83.set_halt_mode(HaltMode::Running);
         }
     }
-}
 
-impl SystemInspection for GameBoyColor {
-    fn serial_output(&self) -> &[u8] {
-        self.sm83.bus().io_registers().serial_transfer().output()
+    fn take_input_polled(&mut self) -> bool {
+        self.sm83.bus().io_registers().joypad().take_input_polled()
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::with_header(self.system, self.rom_hash);
+        writer.save(&self.sm83);
+        writer.finish()
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), SnapshotError> {
+        let mut reader = StateReader::with_header(state, self.system, self.rom_hash)?;
+        let previous_state = self.save_state();
+        let result = reader.load(&mut self.sm83).and_then(|_| reader.finish());
+        if result.is_err() {
+            let mut reader = StateReader::with_header(&previous_state, self.system, self.rom_hash)?;
+            reader.load(&mut self.sm83)?;
+        }
+        result
+    }
+
+    fn cheats(&self) -> &[Cheat] {
+        self.cheats.cheats()
+    }
+
+    fn add_cheat(&mut self, cheat: Cheat) -> Result<usize, CheatError> {
+        let index = self.cheats.add(cheat)?;
+        self.update_rom_patches();
+        Ok(index)
+    }
+
+    fn remove_cheat(&mut self, index: usize) -> Result<Cheat, CheatError> {
+        let cheat = self.cheats.remove(index)?;
+        self.update_rom_patches();
+        Ok(cheat)
+    }
+
+    fn set_cheat_enabled(&mut self, index: usize, enabled: bool) -> Result<(), CheatError> {
+        self.cheats.set_enabled(index, enabled)?;
+        self.update_rom_patches();
+        Ok(())
+    }
+
+    fn debugger(&self) -> &Debugger {
+        self.sm83.bus().debugger()
+    }
+
+    fn debugger_mut(&mut self) -> &mut Debugger {
+        self.sm83.bus_mut().debugger_mut()
+    }
+
+    fn take_break_reason(&mut self) -> Option<BreakReason> {
+        self.break_reason.take()
+    }
+
+    fn step_over(&mut self) {
+        let pc = self.sm83.pc();
+        let stack_pointer = self.sm83.sp() as u32;
+        let opcode = self.sm83.bus().read_8(pc);
+        let debugger = self.sm83.bus_mut().debugger_mut();
+        match Sm83::<SystemBus>::call_return_address(opcode, pc) {
+            Some(address) => debugger.step_until(address as u32, Some(stack_pointer)),
+            None => debugger.step_instruction(),
+        }
+    }
+
+    /// Returns to the address on top of the stack, so it finds the caller of functions that have not
+    /// pushed anything themselves.
+    fn step_out(&mut self) {
+        let stack_pointer = self.sm83.sp();
+        let return_address = self.sm83.bus().read_16(stack_pointer) as u32;
+        self.sm83
+            .bus_mut()
+            .debugger_mut()
+            .step_until(return_address, Some(stack_pointer as u32 + 2));
+    }
+
+    fn start_trace(&mut self, tracer: Tracer) -> Option<Tracer> {
+        self.tracer.replace(tracer)
+    }
+
+    fn stop_trace(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
+    fn start_profile(&mut self, profiler: Profiler) -> Option<Profiler> {
+        self.profiler.replace(profiler)
+    }
+
+    fn stop_profile(&mut self) -> Option<Profiler> {
+        self.profiler.take()
+    }
+
+    fn start_coverage(&mut self, coverage: Coverage) -> Option<Coverage> {
+        self.sm83.bus_mut().coverage_mut().replace(coverage)
     }
 
-    fn read_memory(&self, address: u32) -> u8 {
-        self.sm83.bus().read_8(address as u16)
+    fn stop_coverage(&mut self) -> Option<Coverage> {
+        self.sm83.bus_mut().coverage_mut().take()
     }
 }
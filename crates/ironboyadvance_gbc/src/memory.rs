@@ -1,4 +1,5 @@
 use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const WRAM_SIZE: usize = 0x8000;
 const HRAM_SIZE: usize = 0x007F;
@@ -46,4 +47,33 @@ impl Memory {
             hram: [0; HRAM_SIZE],
         }
     }
+
+    /// Reads work RAM at `offset` into its banks, whichever bank is mapped in.
+    pub fn peek_wram(&self, offset: usize) -> u8 {
+        self.wram.get(offset).copied().unwrap_or(0xFF)
+    }
+
+    pub fn poke_wram(&mut self, offset: usize, value: u8) {
+        if let Some(byte) = self.wram.get_mut(offset) {
+            *byte = value;
+        }
+    }
+}
+
+impl Snapshot for Memory {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.wram_bank);
+        writer.write_bytes(&self.wram);
+        writer.write_bytes(&self.hram);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.wram_bank = match reader.read()? {
+            bank @ 1..=7 => bank,
+            _ => return Err(SnapshotError::InvalidValue("wram bank")),
+        };
+        reader.read_bytes_into(&mut self.wram)?;
+        reader.read_bytes_into(&mut self.hram)?;
+        Ok(())
+    }
 }
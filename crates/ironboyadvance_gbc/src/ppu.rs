@@ -16,6 +16,7 @@ use crate::{
         oam::{OAM_SIZE, Oam},
     },
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 mod background;
 mod oam;
@@ -498,3 +499,55 @@ impl Ppu {
         self.vram[VRAM_BANK_SIZE + address as usize - 0x8000]
     }
 }
+
+impl Snapshot for Ppu {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.ly);
+        writer.write(&self.lyc);
+        writer.write(&self.lcd_control.into_bits());
+        writer.write(&self.lcd_status.into_bits());
+        writer.save(&self.background);
+        writer.save(&self.window);
+        writer.save(&self.bg_palette);
+        writer.save(&self.obj0_palette);
+        writer.save(&self.obj1_palette);
+        writer.save(&self.cgb_bg_palette);
+        writer.save(&self.cgb_obj_palette);
+        writer.write_bytes(&self.vram);
+        writer.save(&self.oam);
+        writer.write(&self.frame_buffer);
+        writer.write(&self.vram_bank);
+        writer.write(&self.interrupt_line);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.ly = reader.read()?;
+        self.lyc = reader.read()?;
+        self.lcd_control = LcdControl::from_bits(reader.read()?);
+        self.lcd_status = LcdStatus::from_bits(reader.read()?);
+        reader.load(&mut self.background)?;
+        reader.load(&mut self.window)?;
+        reader.load(&mut self.bg_palette)?;
+        reader.load(&mut self.obj0_palette)?;
+        reader.load(&mut self.obj1_palette)?;
+        reader.load(&mut self.cgb_bg_palette)?;
+        reader.load(&mut self.cgb_obj_palette)?;
+        reader.read_bytes_into(&mut self.vram)?;
+        reader.load(&mut self.oam)?;
+        let frame_buffer: Vec<u32> = reader.read()?;
+        if frame_buffer.len() != self.frame_buffer.len() {
+            return Err(SnapshotError::LengthMismatch {
+                expected: self.frame_buffer.len(),
+                found: frame_buffer.len(),
+            });
+        }
+        self.frame_buffer = frame_buffer;
+        self.vram_bank = match reader.read()? {
+            bank @ 0..=1 => bank,
+            _ => return Err(SnapshotError::InvalidValue("vram bank")),
+        };
+        self.interrupt_line = reader.read()?;
+        self.events.clear();
+        Ok(())
+    }
+}
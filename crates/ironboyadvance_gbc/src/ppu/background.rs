@@ -3,6 +3,7 @@ use ironboyadvance_common::memory::SystemMemoryAccess;
 use super::tile::{TILE_HEIGHT, TILE_WIDTH};
 
 use bitfields::bitfield;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[bitfield(u8, order = msb)]
 pub struct BgMapAttributes {
@@ -57,3 +58,16 @@ impl SystemMemoryAccess for Background {
         }
     }
 }
+
+impl Snapshot for Background {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.scx);
+        writer.write(&self.scy);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.scx = reader.read()?;
+        self.scy = reader.read()?;
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use bitfields::bitfield;
 
 use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 pub const OAM_SIZE: usize = 40;
 
@@ -80,3 +81,14 @@ impl Oam {
         self.data[index]
     }
 }
+
+impl Snapshot for Oam {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.data.map(OamEntry::into_bits));
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.data = reader.read::<[u32; OAM_SIZE]>()?.map(OamEntry::from_bits);
+        Ok(())
+    }
+}
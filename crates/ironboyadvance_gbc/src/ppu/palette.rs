@@ -1,3 +1,5 @@
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
+
 // Converting RGB 555 to RGB 888 [round(255 * i / 31) for i in range(32)]
 const GBC_COLOR_LUT: &[u8; 32] = &[
     0, 8, 16, 25, 33, 41, 49, 58, 66, 74, 82, 90, 99, 107, 115, 123, 132, 140, 148, 156, 165, 173, 181, 189, 197, 206, 214,
@@ -110,3 +112,29 @@ impl CgbPalette {
         }
     }
 }
+
+impl Snapshot for Palette {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.read());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.write(reader.read()?);
+        Ok(())
+    }
+}
+
+impl Snapshot for CgbPalette {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.increment);
+        writer.write(&self.address);
+        writer.write(&self.data);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.increment = reader.read()?;
+        self.address = reader.read::<u8>()? & 0x3F;
+        self.data = reader.read()?;
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use crate::{
     ppu::tile::{TILE_HEIGHT, TILE_WIDTH},
     ppu::{VIEWPORT_HEIGHT, VIEWPORT_WIDTH},
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 pub struct Window {
     wx: u8,
@@ -20,6 +21,7 @@ impl Window {
         }
     }
 
+    #[allow(clippy::nonminimal_bool)]
     pub fn inside_window(&self, window_enabled: bool, lx: u8, ly: u8) -> bool {
         (window_enabled && lx >= self.wx.wrapping_sub(7)) && (window_enabled && ly >= self.wy)
     }
@@ -71,3 +73,18 @@ impl SystemMemoryAccess for Window {
         }
     }
 }
+
+impl Snapshot for Window {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.wx);
+        writer.write(&self.wy);
+        writer.write(&self.line_counter);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.wx = reader.read()?;
+        self.wy = reader.read()?;
+        self.line_counter = reader.read()?;
+        Ok(())
+    }
+}
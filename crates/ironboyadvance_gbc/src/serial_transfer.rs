@@ -10,6 +10,7 @@ use ironboyadvance_common::{memory::SystemMemoryAccess, scheduler::Scheduler};
 use ironboyadvance_sm83::{CPU_CLOCK_SPEED, GbSpeed};
 
 use crate::events::{GbcEvent, InterruptEvent, SerialEvent};
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const NORMAL_CLOCK_FREQUENCY: usize = 8192;
 const FAST_CLOCK_FREQUENCY: usize = 262144;
@@ -144,3 +145,22 @@ impl SystemMemoryAccess for SerialTransfer {
         }
     }
 }
+
+impl Snapshot for SerialTransfer {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.serial_transfer_data);
+        writer.write(&self.serial_transfer_control.into_bits());
+        writer.write(&self.bits_remaining);
+        writer.write(&self.transferred_byte);
+        writer.write(&self.speed);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.serial_transfer_data = reader.read()?;
+        self.serial_transfer_control = SerialTransferControl::from_bits(reader.read()?);
+        self.bits_remaining = reader.read()?;
+        self.transferred_byte = reader.read()?;
+        self.speed = reader.read()?;
+        Ok(())
+    }
+}
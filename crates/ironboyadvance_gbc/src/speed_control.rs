@@ -1,5 +1,6 @@
 use bitfields::bitfield;
 use ironboyadvance_common::memory::SystemMemoryAccess;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 use ironboyadvance_sm83::GbSpeed;
 
 const SPEED_SWITCH_UNUSED_BITS: u8 = 0x7E;
@@ -62,3 +63,14 @@ impl SystemMemoryAccess for SpeedController {
         }
     }
 }
+
+impl Snapshot for SpeedController {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.speed_switch.into_bits());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.speed_switch = SpeedSwitch::from_bits(reader.read()?);
+        Ok(())
+    }
+}
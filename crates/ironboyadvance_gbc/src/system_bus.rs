@@ -1,7 +1,12 @@
 use std::{cell::RefCell, rc::Rc};
 
 use getset::{Getters, MutGetters};
-use ironboyadvance_common::{memory::SystemMemoryAccess, scheduler::Scheduler};
+use ironboyadvance_common::{
+    coverage::{Coverage, CoverageKind},
+    debugger::{AccessKind, Debugger},
+    memory::SystemMemoryAccess,
+    scheduler::Scheduler,
+};
 use ironboyadvance_sm83::{
     GbMode, GbSpeed,
     memory::{InterruptContext, MemoryInterface},
@@ -11,11 +16,13 @@ use tracing::debug;
 use crate::{
     boot_rom::BootRom,
     cartridge::Cartridge,
+    dma_control::{OAM_DMA_CHANNEL, OAM_DMA_DESTINATION, VRAM_DMA_CHANNEL},
     events::{ApuEvent, DmaEvent, GbcEvent, InterruptEvent, PpuEvent, SerialEvent, TimerEvent},
     io_registers::IoRegisters,
     memory::Memory,
     speed_control::{DOUBLE_SPEED_T_CYCLES, NORMAL_SPEED_T_CYCLES},
 };
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const SPEED_SWITCH_T_CYCLES: usize = 8200;
 
@@ -29,9 +36,39 @@ pub struct SystemBus {
     scheduler: Rc<RefCell<Scheduler<GbcEvent>>>,
     #[getset(skip)]
     cpu_halted: bool,
+    debugger: Debugger,
+    coverage: Option<Coverage>,
 }
 
 impl SystemBus {
+    /// Writes a byte for debuggers. Rom writes are dropped rather than reaching the memory bank controller,
+    /// where they would switch banks.
+    pub fn poke_8(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x7FFF => {}
+            _ => self.write_8(address, value),
+        }
+    }
+
+    /// Reads `address` from RAM `bank` through the backing store, so banks that are not mapped in and
+    /// disabled cartridge RAM can be looked at too. Only work RAM at 0xD000 and cartridge RAM at 0xA000 are
+    /// banked.
+    pub fn peek_banked(&self, bank: usize, address: u16) -> u8 {
+        match address {
+            0xA000..=0xBFFF => self.cartridge.peek_ram((bank * 0x2000) | (address as usize & 0x1FFF)),
+            0xD000..=0xDFFF => self.memory.peek_wram((bank * 0x1000) | (address as usize & 0x0FFF)),
+            _ => 0xFF,
+        }
+    }
+
+    pub fn poke_banked(&mut self, bank: usize, address: u16, value: u8) {
+        match address {
+            0xA000..=0xBFFF => self.cartridge.poke_ram((bank * 0x2000) | (address as usize & 0x1FFF), value),
+            0xD000..=0xDFFF => self.memory.poke_wram((bank * 0x1000) | (address as usize & 0x0FFF), value),
+            _ => {}
+        }
+    }
+
     pub fn new(cartridge: Cartridge, boot_rom: BootRom, mode: GbMode, scheduler: Rc<RefCell<Scheduler<GbcEvent>>>) -> Self {
         let skip_boot = !boot_rom.loaded();
 
@@ -42,6 +79,8 @@ impl SystemBus {
             io_registers: IoRegisters::new(mode, skip_boot, scheduler.clone()),
             scheduler,
             cpu_halted: false,
+            debugger: Debugger::new(),
+            coverage: None,
         }
     }
 
@@ -83,6 +122,7 @@ impl SystemBus {
 
     pub fn raise_interrupt(&mut self, interrupt_event: InterruptEvent) {
         self.io_registers.interrupt_controller_mut().raise_interrupt(interrupt_event);
+        self.debugger.interrupt_raised(interrupt_event as u8);
     }
 
     pub fn handle_ppu_event(&mut self, ppu_event: PpuEvent, timestamp: usize) {
@@ -122,20 +162,45 @@ impl SystemBus {
         let Some(transfer) = self.io_registers.dma_controller().next_oam_transfer() else {
             return;
         };
+        if transfer.destination == OAM_DMA_DESTINATION {
+            self.debugger.dma_started(OAM_DMA_CHANNEL);
+        }
 
         let value = self.read_8(transfer.source);
+        self.cover(transfer.source, CoverageKind::Data);
         self.write_8(transfer.destination, value);
         self.io_registers.dma_controller_mut().complete_oam_transfer(timestamp);
     }
 
     fn run_vram_dma(&mut self) {
+        self.debugger.dma_started(VRAM_DMA_CHANNEL);
         while let Some(transfer) = self.io_registers.dma_controller().next_vram_transfer() {
             let value = self.read_8(transfer.source);
+            self.cover(transfer.source, CoverageKind::Data);
             self.write_8(transfer.destination, value);
             self.io_registers.dma_controller_mut().complete_vram_transfer();
         }
     }
 
+    /// Marks the rom byte at `address` in the coverage map, when one is being recorded and the boot rom
+    /// does not cover it.
+    fn cover(&mut self, address: u16, kind: CoverageKind) {
+        if let Some(coverage) = &mut self.coverage
+            && address < 0x8000
+            && !self.boot_rom.contains(address)
+        {
+            coverage.mark(self.cartridge.rom_offset(address), 1, kind);
+        }
+    }
+
+    fn load(&mut self, address: u16) -> u8 {
+        self.cycle();
+        match self.io_registers.dma_controller().oam_dma_conflict(address) {
+            true => 0xFF,
+            false => self.read_8(address),
+        }
+    }
+
     pub fn speed(&self) -> GbSpeed {
         self.io_registers.speed_controller().speed()
     }
@@ -170,13 +235,12 @@ impl SystemMemoryAccess for SystemBus {
 }
 
 impl MemoryInterface for SystemBus {
+    /// Data loads only, so read watchpoints do not fire on code, as on the GBA.
     fn load_8(&mut self, address: u16) -> u8 {
-        self.cycle();
-
-        match self.io_registers.dma_controller().oam_dma_conflict(address) {
-            true => 0xFF,
-            false => self.read_8(address),
-        }
+        let value = self.load(address);
+        self.cover(address, CoverageKind::Data);
+        self.debugger.memory_access(address as u32, 1, AccessKind::Read, value as u32);
+        value
     }
 
     fn load_16(&mut self, address: u16) -> u16 {
@@ -185,12 +249,26 @@ impl MemoryInterface for SystemBus {
         high << 8 | low
     }
 
+    fn fetch_8(&mut self, address: u16) -> u8 {
+        let value = self.load(address);
+        self.cover(address, CoverageKind::Sm83);
+        value
+    }
+
+    fn fetch_16(&mut self, address: u16) -> u16 {
+        let low = self.fetch_8(address) as u16;
+        let high = self.fetch_8(address.wrapping_add(1)) as u16;
+        high << 8 | low
+    }
+
     fn store_8(&mut self, address: u16, value: u8) {
         self.cycle();
 
         if !self.io_registers.dma_controller().oam_dma_conflict(address) {
             self.write_8(address, value);
         }
+        self.debugger
+            .memory_access(address as u32, 1, AccessKind::Write, value as u32);
     }
 
     fn store_16(&mut self, address: u16, value: u16) {
@@ -198,6 +276,13 @@ impl MemoryInterface for SystemBus {
         self.store_8(address.wrapping_add(1), (value >> 8) as u8);
     }
 
+    fn peek_8(&self, address: u16) -> u8 {
+        match address {
+            0xFF00 => self.io_registers.peek_8(address),
+            _ => self.read_8(address),
+        }
+    }
+
     fn idle_cycle(&mut self) {
         self.cycle();
     }
@@ -229,3 +314,24 @@ impl MemoryInterface for SystemBus {
         self.io_registers.interrupt_controller_mut().interrupt_context_mut()
     }
 }
+
+impl Snapshot for SystemBus {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&*self.scheduler.borrow());
+        writer.save(&self.boot_rom);
+        writer.save(&self.cartridge);
+        writer.save(&self.memory);
+        writer.save(&self.io_registers);
+        writer.write(&self.cpu_halted);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut *self.scheduler.borrow_mut())?;
+        reader.load(&mut self.boot_rom)?;
+        reader.load(&mut self.cartridge)?;
+        reader.load(&mut self.memory)?;
+        reader.load(&mut self.io_registers)?;
+        self.cpu_halted = reader.read()?;
+        Ok(())
+    }
+}
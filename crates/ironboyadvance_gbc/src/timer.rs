@@ -5,6 +5,7 @@ use ironboyadvance_common::{memory::SystemMemoryAccess, scheduler::Scheduler};
 use ironboyadvance_sm83::GbSpeed;
 
 use crate::events::{ApuEvent, GbcEvent, InterruptEvent, TimerEvent};
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 const DIV_APU_T_CYCLES: usize = 8192;
 const DIV_APU_BIT: usize = 12;
@@ -190,3 +191,24 @@ impl SystemMemoryAccess for Timer {
         }
     }
 }
+
+impl Snapshot for Timer {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.divider);
+        writer.write(&self.counter);
+        writer.write(&self.counter_cycles);
+        writer.write(&self.modulo);
+        writer.write(&self.control.into_bits());
+        writer.write(&self.speed);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.divider = reader.read()?;
+        self.counter = reader.read()?;
+        self.counter_cycles = reader.read()?;
+        self.modulo = reader.read()?;
+        self.control = TimerControl::from_bits(reader.read()?);
+        self.speed = reader.read()?;
+        Ok(())
+    }
+}
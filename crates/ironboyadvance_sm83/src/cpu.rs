@@ -3,14 +3,22 @@ use tracing::debug;
 
 use crate::{
     Condition, GbMode, HaltMode, R16Memory, Register8, Register16, Register16Stack,
-    instruction::{Instruction, SharpSm83InstructionFactory, generate_lut},
+    instruction::{Instruction, Operands, SharpSm83InstructionFactory, generate_lut},
     memory::{InterruptContext, MemoryInterface},
     registers::Registers,
 };
+use ironboyadvance_common::{
+    call_stack::{CallStack, Frame, FrameKind},
+    emulator::Register,
+    snapshot::{Snapshot, SnapshotError, StateReader, StateWriter},
+    trace::TraceFormat,
+};
 
 const INTERRUPT_VECTOR_BASE: u16 = 0x0040;
 const INTERRUPT_VECTOR_SIZE: u16 = 0x0008;
 const CANCELLED_INTERRUPT_VECTOR: u16 = 0x0000;
+/// RET, RETI, the conditional RETs and JP HL, which some code returns through.
+const RETURN_OPCODES: [u8; 7] = [0xC9, 0xD9, 0xC0, 0xC8, 0xD0, 0xD8, 0xE9];
 
 #[derive(Getters, MutGetters, Setters)]
 pub struct Sm83<I: MemoryInterface> {
@@ -24,6 +32,8 @@ pub struct Sm83<I: MemoryInterface> {
     interrupt_master_enable: bool,
     enable_interrupt_delay: u8,
     lut: [SharpSm83InstructionFactory; 256],
+    #[getset(get = "pub")]
+    call_stack: CallStack,
 }
 
 impl<I: MemoryInterface> MemoryInterface for Sm83<I> {
@@ -43,6 +53,10 @@ impl<I: MemoryInterface> MemoryInterface for Sm83<I> {
         self.bus.store_16(address, value);
     }
 
+    fn peek_8(&self, address: u16) -> u8 {
+        self.bus.peek_8(address)
+    }
+
     fn idle_cycle(&mut self) {
         self.bus.idle_cycle();
     }
@@ -75,23 +89,44 @@ impl<I: MemoryInterface> Sm83<I> {
             interrupt_master_enable: false,
             enable_interrupt_delay: 0,
             lut: generate_lut(),
+            call_stack: CallStack::new(),
         }
     }
 
     pub fn cycle(&mut self) {
-        let opcode = self.bus.load_8(self.registers.pc());
+        let address = self.registers.pc();
+        let opcode = self.bus.fetch_8(address);
         let instruction = (self.lut[opcode as usize])(opcode);
         if self.show_logs {
-            debug!("{}", instruction.disassemble(self));
+            debug!("{}", self.disassemble_next());
         }
         match self.halt_bug {
             true => self.halt_bug = false,
             false => self.advance_pc(),
         }
         instruction.execute(self);
+        self.track_call(opcode, address);
         self.step_enable_interrupt_delay();
     }
 
+    /// Keeps the call stack in step with a CALL or RST that was taken, or a return that landed on one of
+    /// the frames' return addresses.
+    fn track_call(&mut self, opcode: u8, address: u16) {
+        let pc = self.registers.pc();
+        if let Some(return_address) = Self::call_return_address(opcode, address)
+            && pc != return_address
+        {
+            self.call_stack.push(Frame {
+                kind: FrameKind::Call,
+                call_site: address as u32,
+                target: pc as u32,
+                return_address: return_address as u32,
+            });
+        } else if RETURN_OPCODES.contains(&opcode) && pc != address.wrapping_add(1) {
+            self.call_stack.returned_to(pc as u32);
+        }
+    }
+
     pub fn irq(&mut self) {
         if !self.interrupt_master_enable {
             return;
@@ -100,7 +135,8 @@ impl<I: MemoryInterface> Sm83<I> {
         self.interrupt_master_enable = false;
         self.enable_interrupt_delay = 0;
         self.bus.idle_cycle();
-        self.push_stack(self.registers.pc());
+        let return_address = self.registers.pc();
+        self.push_stack(return_address);
 
         let interrupt_context = self.bus.interrupt_context_mut();
         let vector = match interrupt_context.pending_interrupt() {
@@ -114,6 +150,12 @@ impl<I: MemoryInterface> Sm83<I> {
 
         self.bus.idle_cycle();
         self.registers.set_pc(vector);
+        self.call_stack.push(Frame {
+            kind: FrameKind::Interrupt,
+            call_site: return_address as u32,
+            target: vector as u32,
+            return_address: return_address as u32,
+        });
     }
 
     pub fn halt_mode(&self) -> HaltMode {
@@ -164,10 +206,157 @@ impl<I: MemoryInterface> Sm83<I> {
         }
     }
 
-    pub(crate) fn pc(&self) -> u16 {
+    pub fn pc(&self) -> u16 {
         self.registers.pc()
     }
 
+    pub fn sp(&self) -> u16 {
+        self.registers.sp()
+    }
+
+    /// The register file as debuggers list it, the 8 bit registers first, then sp, pc and the interrupt
+    /// master enable.
+    pub fn register_file(&self) -> Vec<Register> {
+        let registers = &self.registers;
+        [
+            ("a", registers.a() as u32, 8),
+            ("f", registers.f().into_bits() as u32, 8),
+            ("b", registers.b() as u32, 8),
+            ("c", registers.c() as u32, 8),
+            ("d", registers.d() as u32, 8),
+            ("e", registers.e() as u32, 8),
+            ("h", registers.h() as u32, 8),
+            ("l", registers.l() as u32, 8),
+            ("sp", registers.sp() as u32, 16),
+            ("pc", registers.pc() as u32, 16),
+            ("ime", self.interrupt_master_enable as u32, 1),
+        ]
+        .map(|(name, value, bits)| Register { name, value, bits })
+        .to_vec()
+    }
+
+    /// Reads a register by name, also taking the pairs af, bc, de and hl.
+    pub fn register(&self, name: &str) -> Option<u32> {
+        let registers = &self.registers;
+        let pair = match name.to_ascii_lowercase().as_str() {
+            "af" => registers.af(),
+            "bc" => registers.bc(),
+            "de" => registers.de(),
+            "hl" => registers.hl(),
+            _ => {
+                return self
+                    .register_file()
+                    .into_iter()
+                    .find(|register| register.name.eq_ignore_ascii_case(name))
+                    .map(|register| register.value);
+            }
+        };
+        Some(pair as u32)
+    }
+
+    /// Writes a register by name, returning false when there is none. The low nibble of f always reads 0.
+    pub fn set_register(&mut self, name: &str, value: u32) -> bool {
+        let registers = &mut self.registers;
+        match name.to_ascii_lowercase().as_str() {
+            "a" => {
+                registers.set_a(value as u8);
+            }
+            "f" => registers.set_af(registers.af() & 0xFF00 | value as u16 & 0xFF),
+            "b" => {
+                registers.set_b(value as u8);
+            }
+            "c" => {
+                registers.set_c(value as u8);
+            }
+            "d" => {
+                registers.set_d(value as u8);
+            }
+            "e" => {
+                registers.set_e(value as u8);
+            }
+            "h" => {
+                registers.set_h(value as u8);
+            }
+            "l" => {
+                registers.set_l(value as u8);
+            }
+            "af" => registers.set_af(value as u16),
+            "bc" => registers.set_bc(value as u16),
+            "de" => registers.set_de(value as u16),
+            "hl" => registers.set_hl(value as u16),
+            "sp" => {
+                registers.set_sp(value as u16);
+            }
+            "pc" => {
+                registers.set_pc(value as u16);
+            }
+            "ime" => self.interrupt_master_enable = value != 0,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Where execution continues once the instruction with `opcode` at `pc` returns, when it is a CALL or
+    /// RST.
+    pub fn call_return_address(opcode: u8, pc: u16) -> Option<u16> {
+        match opcode {
+            0xCD | 0xC4 | 0xCC | 0xD4 | 0xDC => Some(pc.wrapping_add(3)),
+            _ if opcode & 0xC7 == 0xC7 => Some(pc.wrapping_add(1)),
+            _ => None,
+        }
+    }
+
+    /// Disassembles the instruction at the pc, reading its bytes without touching the bus.
+    pub fn disassemble_next(&self) -> String {
+        let pc = self.registers.pc();
+        let opcode = self.bus.peek_8(pc);
+        let operands = [self.bus.peek_8(pc.wrapping_add(1)), self.bus.peek_8(pc.wrapping_add(2))];
+        (self.lut[opcode as usize])(opcode).disassemble(&mut Operands::new(pc, operands))
+    }
+
+    /// Formats the instruction about to execute for an instruction trace. The reference format is the one
+    /// Gameboy Doctor compares against.
+    pub fn trace_line(&self, format: TraceFormat, timestamp: u64) -> String {
+        let registers = &self.registers;
+        let pc = registers.pc();
+        let memory: [u8; 4] = std::array::from_fn(|offset| self.bus.peek_8(pc.wrapping_add(offset as u16)));
+        match format {
+            TraceFormat::Reference => format!(
+                "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+                registers.a(),
+                registers.af() as u8,
+                registers.b(),
+                registers.c(),
+                registers.d(),
+                registers.e(),
+                registers.h(),
+                registers.l(),
+                registers.sp(),
+                pc,
+                memory[0],
+                memory[1],
+                memory[2],
+                memory[3],
+            ),
+            TraceFormat::Full => {
+                let opcode = match memory[0] {
+                    0xCB => format!("CB{:02X}", memory[1]),
+                    opcode => format!("{opcode:02X}"),
+                };
+                format!(
+                    "{timestamp:>12} {pc:04X} IME:{} {opcode:<4} {:<16} AF:{:04X} BC:{:04X} DE:{:04X} HL:{:04X} SP:{:04X}",
+                    self.interrupt_master_enable as u8,
+                    self.disassemble_next(),
+                    registers.af(),
+                    registers.bc(),
+                    registers.de(),
+                    registers.hl(),
+                    registers.sp(),
+                )
+            }
+        }
+    }
+
     pub(crate) fn set_pc(&mut self, value: u16) {
         self.registers.set_pc(value);
     }
@@ -264,13 +453,13 @@ impl<I: MemoryInterface> Sm83<I> {
     }
 
     pub(crate) fn fetch_byte(&mut self) -> u8 {
-        let byte = self.bus.load_8(self.registers.pc());
+        let byte = self.bus.fetch_8(self.registers.pc());
         self.registers.set_pc(self.registers.pc().wrapping_add(1));
         byte
     }
 
     pub(crate) fn fetch_word(&mut self) -> u16 {
-        let word = self.bus.load_16(self.registers.pc());
+        let word = self.bus.fetch_16(self.registers.pc());
         self.registers.set_pc(self.registers.pc().wrapping_add(2));
         word
     }
@@ -289,3 +478,24 @@ impl<I: MemoryInterface> Sm83<I> {
         self.bus.store_8(self.registers.sp(), value as u8);
     }
 }
+
+impl<I: MemoryInterface + Snapshot> Snapshot for Sm83<I> {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.save(&self.registers);
+        writer.write(&self.halt_mode);
+        writer.write(&self.halt_bug);
+        writer.write(&self.interrupt_master_enable);
+        writer.write(&self.enable_interrupt_delay);
+        writer.save(&self.bus);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        reader.load(&mut self.registers)?;
+        self.halt_mode = reader.read()?;
+        self.halt_bug = reader.read()?;
+        self.interrupt_master_enable = reader.read()?;
+        self.enable_interrupt_delay = reader.read()?;
+        self.call_stack.clear();
+        reader.load(&mut self.bus)
+    }
+}
@@ -0,0 +1,59 @@
+use ironboyadvance_common::disassembly::Disassembly;
+
+use crate::instruction::{Instruction, Operands, decode};
+
+/// Disassembles the instruction at the start of `bytes` as if it sat at `address`. Missing operand bytes
+/// read as zero.
+pub fn disassemble_sm83(bytes: &[u8], address: u16) -> Disassembly {
+    let byte = |index: usize| bytes.get(index).copied().unwrap_or_default();
+    let opcode = byte(0);
+    let instruction = decode(opcode)(opcode);
+    let operand_bytes = [byte(1), byte(2)];
+
+    let mut operands = Operands::new(address, operand_bytes);
+    let text = instruction.disassemble(&mut operands);
+    let size = operands.instruction_length() as u32;
+    Disassembly {
+        address: address as u32,
+        size,
+        text,
+        flow: instruction.flow(&mut Operands::new(address, operand_bytes)),
+    }
+}
+
+/// [`disassemble_sm83`] in the shape the sweeps in [`disassembly`](ironboyadvance_common::disassembly)
+/// take. Addresses past the 16 bit bus wrap around.
+pub fn decode_sm83_bytes(bytes: &[u8], address: u32) -> Disassembly {
+    let mut disassembly = disassemble_sm83(bytes, address as u16);
+    disassembly.address = address;
+    disassembly
+}
+
+#[cfg(test)]
+mod tests {
+    use ironboyadvance_common::disassembly::Flow;
+
+    use super::*;
+
+    #[test]
+    fn resolves_jump_targets_and_lengths() {
+        let relative = disassemble_sm83(&[0x20, 0xFE], 0x0150);
+        assert_eq!(relative.text, "JR NZ,0x0150");
+        assert_eq!(relative.size, 2);
+        assert_eq!(
+            relative.flow,
+            Flow::Jump {
+                target: 0x0150,
+                conditional: true
+            }
+        );
+
+        let call = disassemble_sm83(&[0xCD, 0x34, 0x12], 0x0100);
+        assert_eq!((call.text.as_str(), call.size), ("CALL 0x1234", 3));
+        assert_eq!(call.flow.target(), Some(0x1234));
+
+        assert_eq!(disassemble_sm83(&[0xCB, 0x37], 0).size, 2);
+        assert_eq!(disassemble_sm83(&[0xC9], 0).flow, Flow::Indirect { conditional: false });
+        assert_eq!(disassemble_sm83(&[0xD3], 0).flow, Flow::Invalid);
+    }
+}
@@ -20,6 +20,7 @@ use crate::instruction::stack_pointer_load::StackPointerLoad;
 use crate::instruction::stop::Stop;
 use crate::instruction::undefined::Undefined;
 use crate::memory::MemoryInterface;
+use ironboyadvance_common::disassembly::Flow;
 
 mod accumulator_operations;
 mod alu_16;
@@ -45,7 +46,50 @@ mod undefined;
 
 pub(crate) trait Instruction {
     fn execute<I: MemoryInterface>(&self, cpu: &mut Sm83<I>);
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Sm83<I>) -> String;
+    fn disassemble(&self, operands: &mut Operands) -> String;
+
+    /// Where execution can go after the instruction, reading any target from `operands`.
+    fn flow(&self, _operands: &mut Operands) -> Flow {
+        Flow::Next
+    }
+}
+
+/// The bytes after an opcode. Disassembly reads immediates from here rather than through the bus, so it
+/// never moves the pc or spends cycles.
+pub(crate) struct Operands {
+    address: u16,
+    bytes: [u8; 2],
+    position: usize,
+}
+
+impl Operands {
+    /// `address` is where the opcode itself sits.
+    pub(crate) fn new(address: u16, bytes: [u8; 2]) -> Self {
+        Operands {
+            address,
+            bytes,
+            position: 0,
+        }
+    }
+
+    pub(crate) fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// Length of the instruction, counting the opcode and the operands fetched so far.
+    pub(crate) fn instruction_length(&self) -> u16 {
+        1 + self.position as u16
+    }
+
+    pub(crate) fn fetch_byte(&mut self) -> u8 {
+        let byte = self.bytes.get(self.position).copied().unwrap_or_default();
+        self.position += 1;
+        byte
+    }
+
+    pub(crate) fn fetch_word(&mut self) -> u16 {
+        u16::from_le_bytes([self.fetch_byte(), self.fetch_byte()])
+    }
 }
 
 pub(crate) type SharpSm83InstructionFactory = fn(u8) -> SharpSm83Instruction;
@@ -100,28 +144,53 @@ impl Instruction for SharpSm83Instruction {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, operands: &mut Operands) -> String {
+        match self {
+            Self::Nop(i) => i.disassemble(operands),
+            Self::Undefined(i) => i.disassemble(operands),
+            Self::Stop(i) => i.disassemble(operands),
+            Self::Halt(i) => i.disassemble(operands),
+            Self::Prefix(i) => i.disassemble(operands),
+            Self::LoadRegister(i) => i.disassemble(operands),
+            Self::LoadRegister16(i) => i.disassemble(operands),
+            Self::LoadAccumulator(i) => i.disassemble(operands),
+            Self::Alu16(i) => i.disassemble(operands),
+            Self::IncrementDecrement(i) => i.disassemble(operands),
+            Self::AccumulatorOperations(i) => i.disassemble(operands),
+            Self::Alu8(i) => i.disassemble(operands),
+            Self::RelativeJump(i) => i.disassemble(operands),
+            Self::Jump(i) => i.disassemble(operands),
+            Self::Call(i) => i.disassemble(operands),
+            Self::Ret(i) => i.disassemble(operands),
+            Self::Restart(i) => i.disassemble(operands),
+            Self::StackOperations(i) => i.disassemble(operands),
+            Self::StackPointerLoad(i) => i.disassemble(operands),
+            Self::InterruptEnable(i) => i.disassemble(operands),
+        }
+    }
+
+    fn flow(&self, operands: &mut Operands) -> Flow {
         match self {
-            Self::Nop(i) => i.disassemble(cpu),
-            Self::Undefined(i) => i.disassemble(cpu),
-            Self::Stop(i) => i.disassemble(cpu),
-            Self::Halt(i) => i.disassemble(cpu),
-            Self::Prefix(i) => i.disassemble(cpu),
-            Self::LoadRegister(i) => i.disassemble(cpu),
-            Self::LoadRegister16(i) => i.disassemble(cpu),
-            Self::LoadAccumulator(i) => i.disassemble(cpu),
-            Self::Alu16(i) => i.disassemble(cpu),
-            Self::IncrementDecrement(i) => i.disassemble(cpu),
-            Self::AccumulatorOperations(i) => i.disassemble(cpu),
-            Self::Alu8(i) => i.disassemble(cpu),
-            Self::RelativeJump(i) => i.disassemble(cpu),
-            Self::Jump(i) => i.disassemble(cpu),
-            Self::Call(i) => i.disassemble(cpu),
-            Self::Ret(i) => i.disassemble(cpu),
-            Self::Restart(i) => i.disassemble(cpu),
-            Self::StackOperations(i) => i.disassemble(cpu),
-            Self::StackPointerLoad(i) => i.disassemble(cpu),
-            Self::InterruptEnable(i) => i.disassemble(cpu),
+            Self::Nop(i) => i.flow(operands),
+            Self::Undefined(i) => i.flow(operands),
+            Self::Stop(i) => i.flow(operands),
+            Self::Halt(i) => i.flow(operands),
+            Self::Prefix(i) => i.flow(operands),
+            Self::LoadRegister(i) => i.flow(operands),
+            Self::LoadRegister16(i) => i.flow(operands),
+            Self::LoadAccumulator(i) => i.flow(operands),
+            Self::Alu16(i) => i.flow(operands),
+            Self::IncrementDecrement(i) => i.flow(operands),
+            Self::AccumulatorOperations(i) => i.flow(operands),
+            Self::Alu8(i) => i.flow(operands),
+            Self::RelativeJump(i) => i.flow(operands),
+            Self::Jump(i) => i.flow(operands),
+            Self::Call(i) => i.flow(operands),
+            Self::Ret(i) => i.flow(operands),
+            Self::Restart(i) => i.flow(operands),
+            Self::StackOperations(i) => i.flow(operands),
+            Self::StackPointerLoad(i) => i.flow(operands),
+            Self::InterruptEnable(i) => i.flow(operands),
         }
     }
 }
@@ -135,7 +204,7 @@ pub(crate) fn generate_lut() -> [SharpSm83InstructionFactory; 256] {
     lut
 }
 
-fn decode(opcode: u8) -> SharpSm83InstructionFactory {
+pub(crate) fn decode(opcode: u8) -> SharpSm83InstructionFactory {
     match opcode {
         0x00 => |opcode| SharpSm83Instruction::Nop(Nop::new(opcode)),
         0x10 => |opcode| SharpSm83Instruction::Stop(Stop::new(opcode)),
@@ -2,7 +2,7 @@ use ironboyadvance_common::bits::BitOps;
 
 use crate::AccumulatorOpcode;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy)]
@@ -103,7 +103,7 @@ impl Instruction for AccumulatorOperations {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, _operands: &mut Operands) -> String {
         self.opcode.to_string()
     }
 }
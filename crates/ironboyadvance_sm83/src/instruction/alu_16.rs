@@ -3,7 +3,7 @@ use ironboyadvance_common::bits::BitOps;
 use crate::Register16;
 use crate::alu;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 const ADD_SP_SIGNED_IMM8: u8 = 0xE8;
@@ -75,7 +75,7 @@ impl Instruction for Alu16 {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, _operands: &mut Operands) -> String {
         match self.opcode {
             Alu16Opcode::Increment(r16) => format!("INC {}", r16),
             Alu16Opcode::Decrement(r16) => format!("DEC {}", r16),
@@ -2,7 +2,7 @@ use ironboyadvance_common::bits::BitOps;
 
 use crate::alu;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 use crate::{AluOpcode, Register8};
 
@@ -49,10 +49,10 @@ impl Instruction for Alu8 {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, operands: &mut Operands) -> String {
         match self.operand {
             AluOperand::Register(r8) => format!("{} A,{}", self.opcode, r8),
-            AluOperand::Immediate => format!("{} A,{:#04X}", self.opcode, cpu.fetch_byte()),
+            AluOperand::Immediate => format!("{} A,{:#04X}", self.opcode, operands.fetch_byte()),
         }
     }
 }
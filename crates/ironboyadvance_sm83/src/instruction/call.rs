@@ -1,8 +1,8 @@
-use ironboyadvance_common::bits::BitOps;
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow};
 
 use crate::Condition;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 const CALL_IMM16: u8 = 0xCD;
@@ -38,11 +38,18 @@ impl Instruction for Call {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Sm83<I>) -> String {
-        let value = cpu.fetch_word();
+    fn disassemble(&self, operands: &mut Operands) -> String {
+        let value = operands.fetch_word();
         match self.condition {
-            Some(condition) => format!("CALL {},{:#04X}", condition, value),
-            None => format!("CALL {:#04X}", value),
+            Some(condition) => format!("CALL {},{:#06X}", condition, value),
+            None => format!("CALL {:#06X}", value),
+        }
+    }
+
+    fn flow(&self, operands: &mut Operands) -> Flow {
+        Flow::Call {
+            target: operands.fetch_word() as u32,
+            conditional: self.condition.is_some(),
         }
     }
 }
@@ -1,9 +1,9 @@
 use ironboyadvance_common::bits::BitOps;
 
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
 use crate::instruction::cb::bit_op::BitOp;
 use crate::instruction::cb::rotate_shift_r8::RotateShiftR8;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 mod bit_op;
@@ -25,10 +25,10 @@ impl Instruction for CbInstruction {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, operands: &mut Operands) -> String {
         match self {
-            Self::RotateShiftR8(i) => i.disassemble(cpu),
-            Self::BitOp(i) => i.disassemble(cpu),
+            Self::RotateShiftR8(i) => i.disassemble(operands),
+            Self::BitOp(i) => i.disassemble(operands),
         }
     }
 }
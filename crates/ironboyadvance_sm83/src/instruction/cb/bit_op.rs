@@ -1,7 +1,7 @@
 use ironboyadvance_common::bits::BitOps;
 
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 use crate::{BitOpcode, Register8};
 
@@ -38,7 +38,7 @@ impl Instruction for BitOp {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, _operands: &mut Operands) -> String {
         format!("{} {},{}", self.opcode, self.bit_index, self.r8)
     }
 }
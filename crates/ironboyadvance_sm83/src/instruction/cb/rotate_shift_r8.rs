@@ -1,7 +1,7 @@
 use ironboyadvance_common::bits::BitOps;
 
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 use crate::{Register8, RotateShiftOpcode};
 
@@ -54,7 +54,7 @@ impl Instruction for RotateShiftR8 {
         cpu.registers_mut().f_mut().set_carry(carry);
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, _operands: &mut Operands) -> String {
         format!("{} {}", self.opcode, self.r8)
     }
 }
@@ -1,6 +1,6 @@
 use crate::HaltMode;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy)]
@@ -20,7 +20,7 @@ impl Instruction for Halt {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, _operands: &mut Operands) -> String {
         "HALT".to_string()
     }
 }
@@ -2,7 +2,7 @@ use ironboyadvance_common::bits::BitOps;
 
 use crate::Register8;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy)]
@@ -37,7 +37,7 @@ impl Instruction for IncrementDecrement {
         });
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, _operands: &mut Operands) -> String {
         match self.is_decrement {
             true => format!("DEC {}", self.r8),
             false => format!("INC {}", self.r8),
@@ -1,7 +1,7 @@
 use ironboyadvance_common::bits::BitOps;
 
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy)]
@@ -25,7 +25,7 @@ impl Instruction for InterruptEnable {
         };
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, _operands: &mut Operands) -> String {
         match self.enables_interrupts {
             true => "EI".to_string(),
             false => "DI".to_string(),
@@ -1,8 +1,8 @@
-use ironboyadvance_common::bits::BitOps;
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow};
 
 use crate::Condition;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 const JP_IMM16: u8 = 0xC3;
@@ -53,16 +53,26 @@ impl Instruction for Jump {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, operands: &mut Operands) -> String {
         match self.target {
             JumpTarget::Hl => "JP HL".to_string(),
             JumpTarget::Immediate(condition) => {
-                let value = cpu.fetch_word();
+                let value = operands.fetch_word();
                 match condition {
-                    Some(condition) => format!("JP {},{:#04X}", condition, value),
-                    None => format!("JP {:#04X}", value),
+                    Some(condition) => format!("JP {},{:#06X}", condition, value),
+                    None => format!("JP {:#06X}", value),
                 }
             }
         }
     }
+
+    fn flow(&self, operands: &mut Operands) -> Flow {
+        match self.target {
+            JumpTarget::Hl => Flow::Indirect { conditional: false },
+            JumpTarget::Immediate(condition) => Flow::Jump {
+                target: operands.fetch_word() as u32,
+                conditional: condition.is_some(),
+            },
+        }
+    }
 }
@@ -2,7 +2,7 @@ use ironboyadvance_common::bits::BitOps;
 
 use crate::R16Memory;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -59,11 +59,11 @@ impl Instruction for LoadAccumulator {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, operands: &mut Operands) -> String {
         let location = match self.addressing {
             AccumulatorAddressing::Indirect(r16_memory) => format!("[{}]", r16_memory),
-            AccumulatorAddressing::Direct => format!("[{:#04X}]", cpu.fetch_word()),
-            AccumulatorAddressing::DirectHighPage => format!("[FF00+{:#04X}]", cpu.fetch_byte()),
+            AccumulatorAddressing::Direct => format!("[{:#04X}]", operands.fetch_word()),
+            AccumulatorAddressing::DirectHighPage => format!("[FF00+{:#04X}]", operands.fetch_byte()),
             AccumulatorAddressing::IndirectHighPageC => "[FF00+C]".to_string(),
         };
 
@@ -2,7 +2,7 @@ use ironboyadvance_common::bits::BitOps;
 
 use crate::Register8;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy)]
@@ -38,10 +38,10 @@ impl Instruction for LoadRegister {
         cpu.set_register_8(self.destination, value);
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, operands: &mut Operands) -> String {
         match self.source {
             LoadRegisterSource::Register(r8) => format!("LD {},{}", self.destination, r8),
-            LoadRegisterSource::Immediate => format!("LD {},{:#04X}", self.destination, cpu.fetch_byte()),
+            LoadRegisterSource::Immediate => format!("LD {},{:#04X}", self.destination, operands.fetch_byte()),
         }
     }
 }
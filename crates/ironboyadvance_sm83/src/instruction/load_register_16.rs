@@ -2,7 +2,7 @@ use ironboyadvance_common::bits::BitOps;
 
 use crate::Register16;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy)]
@@ -24,8 +24,8 @@ impl Instruction for LoadRegister16 {
         cpu.set_register_16(self.r16, value);
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Sm83<I>) -> String {
-        let value = cpu.fetch_word();
+    fn disassemble(&self, operands: &mut Operands) -> String {
+        let value = operands.fetch_word();
         format!("LD {},{:#04X}", self.r16, value)
     }
 }
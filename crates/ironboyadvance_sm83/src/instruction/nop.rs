@@ -1,5 +1,5 @@
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy)]
@@ -14,7 +14,7 @@ impl Nop {
 impl Instruction for Nop {
     fn execute<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) {}
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, _operands: &mut Operands) -> String {
         "NOP".to_string()
     }
 }
@@ -1,6 +1,6 @@
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
 use crate::instruction::cb::generate_cb_lut;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy)]
@@ -20,10 +20,10 @@ impl Instruction for Prefix {
         instruction.execute(cpu);
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Sm83<I>) -> String {
-        let opcode = cpu.fetch_byte();
+    fn disassemble(&self, operands: &mut Operands) -> String {
+        let opcode = operands.fetch_byte();
         let cb_lut = generate_cb_lut();
         let instruction = (cb_lut[opcode as usize])(opcode);
-        instruction.disassemble(cpu)
+        instruction.disassemble(operands)
     }
 }
@@ -1,8 +1,8 @@
-use ironboyadvance_common::bits::BitOps;
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow};
 
 use crate::Condition;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy)]
@@ -37,11 +37,24 @@ impl Instruction for RelativeJump {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Sm83<I>) -> String {
-        let value = cpu.fetch_byte();
+    fn disassemble(&self, operands: &mut Operands) -> String {
+        let target = relative_target(operands);
         match self.condition {
-            Some(condition) => format!("JR {},{:#04X}", condition, value),
-            None => format!("JR {:#04X}", value),
+            Some(condition) => format!("JR {},{:#06X}", condition, target),
+            None => format!("JR {:#06X}", target),
         }
     }
+
+    fn flow(&self, operands: &mut Operands) -> Flow {
+        Flow::Jump {
+            target: relative_target(operands) as u32,
+            conditional: self.condition.is_some(),
+        }
+    }
+}
+
+/// The offset is relative to the pc after the operand, two bytes past the opcode.
+fn relative_target(operands: &mut Operands) -> u16 {
+    let offset = operands.fetch_byte() as i8;
+    operands.address().wrapping_add(2).wrapping_add_signed(offset as i16)
 }
@@ -1,7 +1,7 @@
-use ironboyadvance_common::bits::BitOps;
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow};
 
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy)]
@@ -24,7 +24,14 @@ impl Instruction for Restart {
         cpu.set_pc(self.target);
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, _operands: &mut Operands) -> String {
         format!("RST {:02X}H", self.target)
     }
+
+    fn flow(&self, _operands: &mut Operands) -> Flow {
+        Flow::Call {
+            target: self.target as u32,
+            conditional: false,
+        }
+    }
 }
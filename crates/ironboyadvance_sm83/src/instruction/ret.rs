@@ -1,8 +1,8 @@
-use ironboyadvance_common::bits::BitOps;
+use ironboyadvance_common::{bits::BitOps, disassembly::Flow};
 
 use crate::Condition;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 const RET: u8 = 0xC9;
@@ -51,13 +51,19 @@ impl Instruction for Ret {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, _operands: &mut Operands) -> String {
         match (self.condition, self.enables_interrupts) {
             (Some(condition), _) => format!("RET {}", condition),
             (None, true) => "RETI".to_string(),
             (None, false) => "RET".to_string(),
         }
     }
+
+    fn flow(&self, _operands: &mut Operands) -> Flow {
+        Flow::Indirect {
+            conditional: self.condition.is_some(),
+        }
+    }
 }
 
 fn return_to_caller<I: MemoryInterface>(cpu: &mut Sm83<I>) {
@@ -2,7 +2,7 @@ use ironboyadvance_common::bits::BitOps;
 
 use crate::Register16Stack;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy)]
@@ -34,7 +34,7 @@ impl Instruction for StackOperations {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, _operands: &mut Operands) -> String {
         match self.is_push {
             true => format!("PUSH {}", self.r16_stack),
             false => format!("POP {}", self.r16_stack),
@@ -1,6 +1,6 @@
 use crate::alu;
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 const LD_IMM16_SP: u8 = 0x08;
@@ -52,10 +52,10 @@ impl Instruction for StackPointerLoad {
         }
     }
 
-    fn disassemble<I: MemoryInterface>(&self, cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, operands: &mut Operands) -> String {
         match self.opcode {
-            StackPointerLoadOpcode::DirectFromStackPointer => format!("LD {:#04X},SP", cpu.fetch_word()),
-            StackPointerLoadOpcode::HlFromAdjustedStackPointer => format!("LD HL,SP+{:#04X}", cpu.fetch_byte()),
+            StackPointerLoadOpcode::DirectFromStackPointer => format!("LD {:#04X},SP", operands.fetch_word()),
+            StackPointerLoadOpcode::HlFromAdjustedStackPointer => format!("LD HL,SP+{:#04X}", operands.fetch_byte()),
             StackPointerLoadOpcode::StackPointerFromHl => "LD SP,HL".to_string(),
         }
     }
@@ -1,5 +1,7 @@
+use ironboyadvance_common::disassembly::Flow;
+
 use crate::cpu::Sm83;
-use crate::instruction::Instruction;
+use crate::instruction::{Instruction, Operands};
 use crate::memory::MemoryInterface;
 
 #[derive(Debug, Clone, Copy)]
@@ -14,7 +16,11 @@ impl Undefined {
 impl Instruction for Undefined {
     fn execute<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) {}
 
-    fn disassemble<I: MemoryInterface>(&self, _cpu: &mut Sm83<I>) -> String {
+    fn disassemble(&self, _operands: &mut Operands) -> String {
         "Undefined".into()
     }
+
+    fn flow(&self, _operands: &mut Operands) -> Flow {
+        Flow::Invalid
+    }
 }
@@ -1,7 +1,10 @@
 use std::fmt;
 
+use ironboyadvance_common::snapshot::{SnapshotError, StateReader, StateValue, StateWriter};
+
 mod alu;
 pub mod cpu;
+pub mod disassembler;
 mod instruction;
 pub mod memory;
 mod registers;
@@ -29,6 +32,34 @@ pub enum HaltMode {
     Stopped,
 }
 
+impl StateValue for GbSpeed {
+    fn write_state(&self, writer: &mut StateWriter) {
+        writer.write(&(*self == GbSpeed::Double));
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        match reader.read()? {
+            true => Ok(GbSpeed::Double),
+            false => Ok(GbSpeed::Normal),
+        }
+    }
+}
+
+impl StateValue for HaltMode {
+    fn write_state(&self, writer: &mut StateWriter) {
+        writer.write(&(*self as u8));
+    }
+
+    fn read_state(reader: &mut StateReader) -> Result<Self, SnapshotError> {
+        match reader.read::<u8>()? {
+            0 => Ok(HaltMode::Running),
+            1 => Ok(HaltMode::Halted),
+            2 => Ok(HaltMode::Stopped),
+            _ => Err(SnapshotError::InvalidValue("halt mode")),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Register8 {
     B = 0b000,
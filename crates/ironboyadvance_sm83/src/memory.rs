@@ -28,10 +28,22 @@ pub trait MemoryInterface {
 
     fn load_16(&mut self, address: u16) -> u16;
 
+    /// Reads a byte of the instruction stream, an opcode or an operand, rather than data.
+    fn fetch_8(&mut self, address: u16) -> u8 {
+        self.load_8(address)
+    }
+
+    fn fetch_16(&mut self, address: u16) -> u16 {
+        self.load_16(address)
+    }
+
     fn store_8(&mut self, address: u16, value: u8);
 
     fn store_16(&mut self, address: u16, value: u16);
 
+    /// Reads without spending a cycle or triggering watchpoints, for disassembly and tracing.
+    fn peek_8(&self, address: u16) -> u8;
+
     fn idle_cycle(&mut self);
 
     fn change_speed(&mut self) -> bool;
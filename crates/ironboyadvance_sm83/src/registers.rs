@@ -2,6 +2,7 @@ use bitfields::bitfield;
 use getset::{CopyGetters, MutGetters, Setters};
 
 use crate::GbMode;
+use ironboyadvance_common::snapshot::{Snapshot, SnapshotError, StateReader, StateWriter};
 
 #[bitfield(u8)]
 #[derive(PartialEq, Eq)]
@@ -136,6 +137,35 @@ impl Registers {
     }
 }
 
+impl Snapshot for Registers {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write(&self.a);
+        writer.write(&self.f.into_bits());
+        writer.write(&self.b);
+        writer.write(&self.c);
+        writer.write(&self.d);
+        writer.write(&self.e);
+        writer.write(&self.h);
+        writer.write(&self.l);
+        writer.write(&self.pc);
+        writer.write(&self.sp);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SnapshotError> {
+        self.a = reader.read()?;
+        self.f = Flags::from_bits(reader.read()?);
+        self.b = reader.read()?;
+        self.c = reader.read()?;
+        self.d = reader.read()?;
+        self.e = reader.read()?;
+        self.h = reader.read()?;
+        self.l = reader.read()?;
+        self.pc = reader.read()?;
+        self.sp = reader.read()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -124,6 +124,10 @@ mod tests {
             self.store_8(address.wrapping_add(1), (value >> 8) as u8);
         }
 
+        fn peek_8(&self, address: u16) -> u8 {
+            self.read_untimed(address)
+        }
+
         fn idle_cycle(&mut self) {
             self.check_bus_state(BusKind::Internal, None, None);
         }